@@ -0,0 +1,621 @@
+// 音频输出设备：专用线程持有 OutputStream，Sink 的队列输出通过共享播放器接入当前设备。
+// 切换设备只重建输出流，曲目与播放进度留在 Sink 中不受影响；设备消失时自动回退
+
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::cpal::{self, Device};
+use rodio::queue::SourcesQueueOutput;
+use rodio::{OutputStream, Source};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::broadcast::Sender;
+
+use crate::music::{ChannelOptions, ChannelOptionsState, MusicState};
+use crate::storage;
+
+const AUDIO_OUTPUT_SETTINGS_FILE: &str = "audio-output.json";
+const OUTPUT_WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+/// 回退到其他设备后，每隔多少次巡检尝试切回用户选择的设备
+const PREFERRED_DEVICE_RETRY_TICKS: u32 = 5;
+/// 没有可用设备时以这个间隔空转消费队列，保证 seek 等控制操作不会卡住
+const NULL_OUTPUT_TICK: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioOutputSettings {
+    /// 用户选择的设备名；为空表示跟随系统默认设备
+    pub device: Option<String>,
+    /// 平衡、单声道、左右互换
+    pub channels: ChannelOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutputConfigRange {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub default_sample_rate: Option<u32>,
+    pub default_channels: Option<u16>,
+    pub configs: Vec<OutputConfigRange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OutputDeviceStatus {
+    pub selected: Option<String>,
+    /// 正在使用的设备；为空表示没有可用设备
+    pub active: Option<String>,
+    /// 所选设备不可用，已回退到默认设备或静音输出
+    pub fallback: bool,
+}
+
+/// Sink 队列输出的读取位置；frame_offset 记录当前帧已读出的声道数
+struct QueueCursor {
+    output: SourcesQueueOutput<f32>,
+    frame_offset: usize,
+    frame_channels: usize,
+}
+
+impl QueueCursor {
+    fn pull(&mut self) -> Option<f32> {
+        let sample = self.output.next()?;
+        // 帧的第一个采样读出后队列才切到对应的音源，此时再取声道数
+        if self.frame_offset == 0 {
+            self.frame_channels = (self.output.channels() as usize).max(1);
+        }
+        self.frame_offset = (self.frame_offset + 1) % self.frame_channels;
+        Some(sample)
+    }
+}
+
+struct SharedQueue {
+    cursor: StdMutex<QueueCursor>,
+    /// 每接入一次设备递增，旧设备上的播放器随之退出
+    generation: AtomicU64,
+    /// 累计读出的采样数，用于检测设备停止回调
+    pulled: AtomicU64,
+}
+
+impl SharedQueue {
+    /// 切到新一代播放器；丢弃旧设备没读完的半帧，保证新设备从帧边界开始
+    fn next_generation(&self) -> u64 {
+        let Ok(mut cursor) = self.cursor.lock() else {
+            return self.generation.load(Ordering::SeqCst);
+        };
+        while cursor.frame_offset != 0 && cursor.pull().is_some() {}
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+struct DevicePlayer {
+    shared: Arc<SharedQueue>,
+    generation: u64,
+}
+
+impl DevicePlayer {
+    fn new(shared: &Arc<SharedQueue>) -> Self {
+        Self {
+            generation: shared.next_generation(),
+            shared: Arc::clone(shared),
+        }
+    }
+}
+
+impl Iterator for DevicePlayer {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let mut cursor = self.shared.cursor.lock().ok()?;
+        if self.shared.generation.load(Ordering::SeqCst) != self.generation {
+            return None;
+        }
+        self.shared.pulled.fetch_add(1, Ordering::Relaxed);
+        cursor.pull()
+    }
+}
+
+impl Source for DevicePlayer {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.shared
+            .cursor
+            .lock()
+            .ok()
+            .and_then(|cursor| cursor.output.current_frame_len())
+    }
+
+    fn channels(&self) -> u16 {
+        self.shared
+            .cursor
+            .lock()
+            .map(|cursor| cursor.output.channels())
+            .unwrap_or(2)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.shared
+            .cursor
+            .lock()
+            .map(|cursor| cursor.output.sample_rate())
+            .unwrap_or(44_100)
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+enum OutputCommand {
+    /// 打开指定设备（为空时打开系统默认设备），回复实际使用的设备名
+    Open {
+        device: Option<String>,
+        reply: mpsc::Sender<Result<String, String>>,
+    },
+    /// 关闭输出流，改为静音空转
+    Close { reply: mpsc::Sender<()> },
+}
+
+#[derive(Clone)]
+pub struct AudioOutput {
+    commands: mpsc::Sender<OutputCommand>,
+    shared: Arc<SharedQueue>,
+    status: Arc<StdMutex<OutputDeviceStatus>>,
+}
+
+fn find_device(name: Option<&str>) -> Result<Device, String> {
+    let host = cpal::default_host();
+    match name {
+        None => host
+            .default_output_device()
+            .ok_or_else(|| "No default output device".to_string()),
+        Some(name) => host
+            .output_devices()
+            .map_err(|e| format!("list output devices: {}", e))?
+            .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+            .ok_or_else(|| format!("Output device not found: {}", name)),
+    }
+}
+
+fn open_stream(
+    shared: &Arc<SharedQueue>,
+    name: Option<&str>,
+) -> Result<(OutputStream, String), String> {
+    let device = find_device(name)?;
+    let device_name = device
+        .name()
+        .unwrap_or_else(|_| "Unknown device".to_string());
+    let (stream, handle) = OutputStream::try_from_device(&device)
+        .map_err(|e| format!("open output device {}: {}", device_name, e))?;
+    handle
+        .play_raw(DevicePlayer::new(shared))
+        .map_err(|e| format!("play on output device {}: {}", device_name, e))?;
+    Ok((stream, device_name))
+}
+
+fn run_output_thread(shared: Arc<SharedQueue>, commands: Receiver<OutputCommand>) {
+    let mut stream: Option<OutputStream> = None;
+    let mut null_player: Option<DevicePlayer> = None;
+    loop {
+        let command = if stream.is_some() {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            }
+        } else {
+            match commands.recv_timeout(NULL_OUTPUT_TICK) {
+                Ok(command) => Some(command),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        };
+
+        match command {
+            Some(OutputCommand::Open { device, reply }) => {
+                let result = open_stream(&shared, device.as_deref()).map(|(new_stream, name)| {
+                    // 新流接管之后再释放旧流
+                    stream = Some(new_stream);
+                    null_player = None;
+                    name
+                });
+                let _ = reply.send(result);
+            }
+            Some(OutputCommand::Close { reply }) => {
+                stream = None;
+                null_player = Some(DevicePlayer::new(&shared));
+                let _ = reply.send(());
+            }
+            None => {
+                // 按实时速率消费队列，效果等同于输出到空设备
+                let player = null_player.get_or_insert_with(|| DevicePlayer::new(&shared));
+                let samples = (player.sample_rate() as u64 * player.channels() as u64)
+                    * NULL_OUTPUT_TICK.as_millis() as u64
+                    / 1000;
+                for _ in 0..samples {
+                    player.next();
+                }
+            }
+        }
+    }
+}
+
+impl AudioOutput {
+    /// 启动输出线程并接入系统默认设备；没有可用设备时静音空转，之后由巡检重试
+    pub fn start(queue: SourcesQueueOutput<f32>) -> Result<Self, String> {
+        let shared = Arc::new(SharedQueue {
+            cursor: StdMutex::new(QueueCursor {
+                output: queue,
+                frame_offset: 0,
+                frame_channels: 1,
+            }),
+            generation: AtomicU64::new(0),
+            pulled: AtomicU64::new(0),
+        });
+        let (commands, receiver) = mpsc::channel();
+        let thread_shared = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("rmusic-audio-output".to_string())
+            .spawn(move || run_output_thread(thread_shared, receiver))
+            .map_err(|e| format!("spawn audio output thread: {}", e))?;
+
+        let output = Self {
+            commands,
+            shared,
+            status: Arc::default(),
+        };
+        output.connect(None);
+        Ok(output)
+    }
+
+    fn open(&self, device: Option<&str>) -> Result<String, String> {
+        let (reply, result) = mpsc::channel();
+        self.commands
+            .send(OutputCommand::Open {
+                device: device.map(ToOwned::to_owned),
+                reply,
+            })
+            .map_err(|_| "audio output thread stopped".to_string())?;
+        result
+            .recv()
+            .map_err(|_| "audio output thread stopped".to_string())?
+    }
+
+    fn close(&self) {
+        let (reply, done) = mpsc::channel();
+        if self.commands.send(OutputCommand::Close { reply }).is_ok() {
+            let _ = done.recv();
+        }
+    }
+
+    pub fn status(&self) -> OutputDeviceStatus {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+
+    fn set_status(&self, status: OutputDeviceStatus) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+
+    /// 依次尝试所选设备、系统默认设备，都不可用时静音空转
+    fn connect(&self, selected: Option<String>) -> OutputDeviceStatus {
+        let mut errors = Vec::new();
+        let mut candidates = vec![selected.clone()];
+        if selected.is_some() {
+            candidates.push(None);
+        }
+        for candidate in candidates {
+            match self.open(candidate.as_deref()) {
+                Ok(name) => {
+                    let status = OutputDeviceStatus {
+                        fallback: selected.is_some() && candidate.is_none(),
+                        selected,
+                        active: Some(name),
+                    };
+                    self.set_status(status.clone());
+                    return status;
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        eprintln!("No audio output device available: {}", errors.join("; "));
+        self.close();
+        let status = OutputDeviceStatus {
+            selected,
+            active: None,
+            fallback: true,
+        };
+        self.set_status(status.clone());
+        status
+    }
+}
+
+pub fn list_devices() -> Result<Vec<OutputDeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_name = host
+        .default_output_device()
+        .and_then(|device| device.name().ok());
+    let devices = host
+        .output_devices()
+        .map_err(|e| format!("list output devices: {}", e))?;
+
+    Ok(devices
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let default_config = device.default_output_config().ok();
+            let mut configs: Vec<OutputConfigRange> = Vec::new();
+            if let Ok(supported) = device.supported_output_configs() {
+                for config in supported {
+                    let range = OutputConfigRange {
+                        channels: config.channels(),
+                        min_sample_rate: config.min_sample_rate().0,
+                        max_sample_rate: config.max_sample_rate().0,
+                    };
+                    // 不同采样格式会给出相同的范围
+                    if !configs.contains(&range) {
+                        configs.push(range);
+                    }
+                }
+            }
+            Some(OutputDeviceInfo {
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                default_sample_rate: default_config.as_ref().map(|config| config.sample_rate().0),
+                default_channels: default_config.as_ref().map(|config| config.channels()),
+                configs,
+            })
+        })
+        .collect())
+}
+
+fn settings_path(app_handle: &AppHandle) -> Result<std::path::PathBuf, String> {
+    storage::app_data_file(app_handle, AUDIO_OUTPUT_SETTINGS_FILE)
+}
+
+fn load_settings(app_handle: &AppHandle) -> Result<AudioOutputSettings, String> {
+    Ok(storage::read_json_or_default(&settings_path(app_handle)?))
+}
+
+fn emit_status(app_handle: &AppHandle, status: &OutputDeviceStatus) {
+    let _ = app_handle.emit("output-device-changed", status);
+}
+
+/// 启动时恢复声道设置并切到上次保存的设备
+pub fn restore_output_device(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let Ok(settings) = load_settings(&app_handle) else {
+            return;
+        };
+        if let Some(channel_options) = app_handle.try_state::<ChannelOptionsState>() {
+            channel_options.set(settings.channels);
+        }
+        let Some(device) = settings.device else {
+            return;
+        };
+        let Some(output) = app_handle.try_state::<AudioOutput>() else {
+            return;
+        };
+        let status = output.connect(Some(device));
+        emit_status(&app_handle, &status);
+    });
+}
+
+/// 设备停止回调（被拔出、驱动出错）时暂停播放并重新接入；回退后定期尝试切回所选设备
+pub fn start_output_watchdog(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut last_pulled = 0u64;
+        let mut ticks = 0u32;
+        loop {
+            tokio::time::sleep(OUTPUT_WATCHDOG_INTERVAL).await;
+            let Some(output) = app_handle
+                .try_state::<AudioOutput>()
+                .map(|output| output.inner().clone())
+            else {
+                return;
+            };
+            let pulled = output.shared.pulled.load(Ordering::Relaxed);
+            let stalled = pulled == last_pulled;
+            last_pulled = pulled;
+            ticks = ticks.wrapping_add(1);
+
+            let status = output.status();
+            let lost = status.active.is_some() && stalled;
+            let retry = status.fallback && ticks.is_multiple_of(PREFERRED_DEVICE_RETRY_TICKS);
+            if !lost && !retry {
+                continue;
+            }
+            if lost {
+                eprintln!(
+                    "Audio output device stopped: {}",
+                    status.active.as_deref().unwrap_or_default()
+                );
+                if let Some(sender) = app_handle.try_state::<Sender<MusicState>>() {
+                    let _ = sender.send(MusicState::Pause);
+                }
+                let _ = app_handle.emit("output-device-lost", &status);
+            }
+
+            let selected = status.selected.clone();
+            let new_status = tauri::async_runtime::spawn_blocking(move || output.connect(selected))
+                .await
+                .unwrap_or(status.clone());
+            if lost || new_status.active != status.active {
+                emit_status(&app_handle, &new_status);
+            }
+            last_pulled = app_handle
+                .try_state::<AudioOutput>()
+                .map(|output| output.shared.pulled.load(Ordering::Relaxed))
+                .unwrap_or(last_pulled);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn list_output_devices() -> Result<Vec<OutputDeviceInfo>, String> {
+    tauri::async_runtime::spawn_blocking(list_devices)
+        .await
+        .map_err(|e| format!("Failed to join output device task: {}", e))?
+}
+
+#[tauri::command]
+pub fn get_output_device(output: tauri::State<'_, AudioOutput>) -> OutputDeviceStatus {
+    output.status()
+}
+
+/// 切换输出设备并保存选择；device 为空表示跟随系统默认设备
+#[tauri::command]
+pub async fn set_output_device(
+    app_handle: AppHandle,
+    output: tauri::State<'_, AudioOutput>,
+    device: Option<String>,
+) -> Result<OutputDeviceStatus, String> {
+    let output = output.inner().clone();
+    let task_output = output.clone();
+    let requested = device.clone();
+    let opened =
+        tauri::async_runtime::spawn_blocking(move || task_output.open(requested.as_deref()))
+            .await
+            .map_err(|e| format!("Failed to join output device task: {}", e))??;
+
+    let status = OutputDeviceStatus {
+        selected: device.clone(),
+        active: Some(opened),
+        fallback: false,
+    };
+    output.set_status(status.clone());
+    let mut settings = load_settings(&app_handle)?;
+    settings.device = device;
+    storage::write_json(&settings_path(&app_handle)?, &settings)?;
+    emit_status(&app_handle, &status);
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn get_channel_options(
+    channel_options: tauri::State<'_, ChannelOptionsState>,
+) -> ChannelOptions {
+    channel_options.get()
+}
+
+/// 修改声道处理选项并保存；未传入的字段保持不变
+#[tauri::command]
+pub fn set_channel_options(
+    app_handle: AppHandle,
+    sender: tauri::State<'_, Sender<MusicState>>,
+    channel_options: tauri::State<'_, ChannelOptionsState>,
+    balance: Option<f32>,
+    mono: Option<bool>,
+    swap_channels: Option<bool>,
+) -> Result<ChannelOptions, String> {
+    if balance.is_some_and(|balance| !balance.is_finite()) {
+        return Err("Invalid balance".to_string());
+    }
+    let mut events = Vec::new();
+    if let Some(balance) = balance {
+        events.push(MusicState::Balance(balance));
+    }
+    if let Some(mono) = mono {
+        events.push(MusicState::MonoDownmix(mono));
+    }
+    if let Some(swap_channels) = swap_channels {
+        events.push(MusicState::SwapChannels(swap_channels));
+    }
+    for event in events {
+        sender
+            .send(event)
+            .map_err(|e| format!("Send music event error: {}", e))?;
+    }
+
+    // 事件在播放任务中异步应用，这里按同样的规则算出保存值
+    let current = channel_options.get();
+    let options = ChannelOptions {
+        balance: balance
+            .map(|balance| balance.clamp(-1.0, 1.0))
+            .unwrap_or(current.balance),
+        mono: mono.unwrap_or(current.mono),
+        swap_channels: swap_channels.unwrap_or(current.swap_channels),
+    };
+    let mut settings = load_settings(&app_handle)?;
+    settings.channels = options;
+    storage::write_json(&settings_path(&app_handle)?, &settings)?;
+    let _ = app_handle.emit("channel-options-changed", options);
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+    use rodio::Sink;
+
+    fn shared_queue(queue: SourcesQueueOutput<f32>) -> Arc<SharedQueue> {
+        Arc::new(SharedQueue {
+            cursor: StdMutex::new(QueueCursor {
+                output: queue,
+                frame_offset: 0,
+                frame_channels: 1,
+            }),
+            generation: AtomicU64::new(0),
+            pulled: AtomicU64::new(0),
+        })
+    }
+
+    #[test]
+    fn switching_players_keeps_channels_aligned_and_position() {
+        let (sink, queue) = Sink::new_idle();
+        // 左声道为正的帧序号，右声道为负
+        let samples: Vec<f32> = (1..=100)
+            .flat_map(|frame| [frame as f32, -(frame as f32)])
+            .collect();
+        sink.append(SamplesBuffer::new(2, 44_100, samples));
+        let shared = shared_queue(queue);
+
+        let mut first = DevicePlayer::new(&shared);
+        let played: Vec<f32> = (0..5).filter_map(|_| first.next()).collect();
+        assert_eq!(played, [1.0, -1.0, 2.0, -2.0, 3.0]);
+
+        // 旧设备停在半帧处，新设备跳过剩余声道，从下一帧的左声道继续
+        let mut second = DevicePlayer::new(&shared);
+        assert_eq!(first.next(), None);
+        let played: Vec<f32> = (0..4).filter_map(|_| second.next()).collect();
+        assert_eq!(played, [4.0, -4.0, 5.0, -5.0]);
+        assert_eq!(shared.pulled.load(Ordering::Relaxed), 9);
+    }
+
+    /// 需要 ALSA 运行库：cargo test -- --ignored plays_through_alsa_null_device
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "requires an ALSA runtime"]
+    fn plays_through_alsa_null_device() {
+        let (sink, queue) = Sink::new_idle();
+        let output = AudioOutput::start(queue).unwrap();
+        let samples = vec![0.0f32; 44_100 * 2 * 5];
+        sink.append(SamplesBuffer::new(2, 44_100, samples));
+
+        let name = output.open(Some("null")).unwrap();
+        assert_eq!(name, "null");
+        thread::sleep(Duration::from_millis(300));
+        let pulled = output.shared.pulled.load(Ordering::Relaxed);
+        assert!(pulled > 0);
+
+        // 热切换：重新打开设备后曲目继续播放
+        output.open(Some("null")).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        assert!(output.shared.pulled.load(Ordering::Relaxed) > pulled);
+        assert!(!sink.empty());
+        assert!(output.open(Some("no-such-device")).is_err());
+    }
+}
@@ -0,0 +1,577 @@
+// A-B 循环与曲目书签：循环由播放结束监控轮询位置并跳回 A 点；书签按曲目键保存在 app_data_dir/bookmarks.json
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+use crate::music::{
+    self, NowPlayingState, PlaybackDurationState, PlaybackTrackIdState, SeekResult,
+};
+use crate::storage;
+use crate::time_stretch::PlaybackRateState;
+use crate::timestamp::now_ms;
+use crate::user_meta::{online_key, remote_key, TrackRef};
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+/// 循环生效时监控的轮询间隔，越短越接近 B 点
+pub const AB_LOOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// A、B 两点的最小间隔
+const MIN_LOOP_LENGTH_MS: u64 = 200;
+/// B 点距曲目结尾至少保留这么久，保证在 sink 播完之前跳回 A 点
+const LOOP_END_GUARD_MS: u64 = 300;
+const MAX_BOOKMARK_NAME_LEN: usize = 100;
+
+static BOOKMARKS_LOCK: StdMutex<()> = StdMutex::new(());
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopPoint {
+    A,
+    B,
+}
+
+/// 循环只对设置时的曲目生效，切歌后自动失效
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AbLoop {
+    pub track_id: u64,
+    pub a_ms: Option<u64>,
+    pub b_ms: Option<u64>,
+}
+
+#[derive(Clone, Default)]
+pub struct AbLoopState(pub Arc<StdMutex<AbLoop>>);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AbLoopStatus {
+    pub track_id: u64,
+    pub a_ms: Option<u64>,
+    pub b_ms: Option<u64>,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: String,
+    pub name: String,
+    pub position_ms: u64,
+    pub created_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct BookmarkStore {
+    local: BTreeMap<String, Vec<Bookmark>>,
+    online: BTreeMap<String, Vec<Bookmark>>,
+    remote: BTreeMap<String, Vec<Bookmark>>,
+}
+
+#[derive(Clone, Serialize)]
+struct BookmarksChangedEvent {
+    track: TrackRef,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl AbLoop {
+    pub fn is_active(&self) -> bool {
+        matches!((self.a_ms, self.b_ms), (Some(a), Some(b)) if a < b)
+    }
+
+    /// 设置一个端点；与另一端点冲突（顺序颠倒或间隔过短）时清除另一端点
+    fn set_point(&mut self, point: LoopPoint, position_ms: u64, duration_ms: u64) {
+        let position_ms = if duration_ms > LOOP_END_GUARD_MS {
+            position_ms.min(duration_ms - LOOP_END_GUARD_MS)
+        } else {
+            position_ms
+        };
+        match point {
+            LoopPoint::A => {
+                self.a_ms = Some(position_ms);
+                if self
+                    .b_ms
+                    .is_some_and(|b| b < position_ms + MIN_LOOP_LENGTH_MS)
+                {
+                    self.b_ms = None;
+                }
+            }
+            LoopPoint::B => {
+                self.b_ms = Some(position_ms);
+                if self
+                    .a_ms
+                    .is_some_and(|a| position_ms < a + MIN_LOOP_LENGTH_MS)
+                {
+                    self.a_ms = None;
+                }
+            }
+        }
+    }
+
+    /// 播放到 B 点之后返回需要跳回的 A 点
+    fn restart_position(&self, track_id: u64, position_ms: u64) -> Option<u64> {
+        if self.track_id != track_id || !self.is_active() {
+            return None;
+        }
+        match (self.a_ms, self.b_ms) {
+            (Some(a), Some(b)) if position_ms >= b => Some(a),
+            _ => None,
+        }
+    }
+
+    fn status(&self, current_track_id: u64) -> AbLoopStatus {
+        if self.track_id != current_track_id {
+            return AbLoopStatus {
+                track_id: current_track_id,
+                a_ms: None,
+                b_ms: None,
+                active: false,
+            };
+        }
+        AbLoopStatus {
+            track_id: self.track_id,
+            a_ms: self.a_ms,
+            b_ms: self.b_ms,
+            active: self.is_active(),
+        }
+    }
+}
+
+pub fn is_ab_loop_active(app_handle: &AppHandle, track_id: u64) -> bool {
+    app_handle
+        .try_state::<AbLoopState>()
+        .and_then(|state| {
+            state
+                .0
+                .lock()
+                .ok()
+                .map(|ab| ab.track_id == track_id && ab.is_active())
+        })
+        .unwrap_or(false)
+}
+
+/// 由播放结束监控调用：到达 B 点时返回 A 点位置
+pub fn ab_loop_restart_position(
+    app_handle: &AppHandle,
+    track_id: u64,
+    position_ms: u64,
+) -> Option<u64> {
+    let state = app_handle.try_state::<AbLoopState>()?;
+    let ab = state.0.lock().ok()?;
+    ab.restart_position(track_id, position_ms)
+}
+
+fn emit_ab_loop(app_handle: &AppHandle, status: &AbLoopStatus) {
+    let _ = app_handle.emit("ab-loop-changed", status);
+}
+
+/// 未指定位置时使用当前播放位置
+#[tauri::command]
+pub async fn set_loop_point(
+    app_handle: AppHandle,
+    state: tauri::State<'_, AbLoopState>,
+    track_id: tauri::State<'_, PlaybackTrackIdState>,
+    duration: tauri::State<'_, PlaybackDurationState>,
+    playback_rate: tauri::State<'_, PlaybackRateState>,
+    point: LoopPoint,
+    position_ms: Option<u64>,
+) -> Result<AbLoopStatus, String> {
+    let current_track_id = *track_id.0.lock().await;
+    if current_track_id == 0 {
+        return Err("No track is loaded".to_string());
+    }
+    let duration_ms = *duration.0.lock().await;
+    let position_ms = position_ms.unwrap_or_else(|| playback_rate.position().as_millis() as u64);
+
+    let status = {
+        let mut ab = state
+            .0
+            .lock()
+            .map_err(|_| "A-B loop state poisoned".to_string())?;
+        if ab.track_id != current_track_id {
+            *ab = AbLoop {
+                track_id: current_track_id,
+                ..AbLoop::default()
+            };
+        }
+        ab.set_point(point, position_ms, duration_ms);
+        ab.status(current_track_id)
+    };
+    emit_ab_loop(&app_handle, &status);
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn clear_ab_loop(
+    app_handle: AppHandle,
+    state: tauri::State<'_, AbLoopState>,
+    track_id: tauri::State<'_, PlaybackTrackIdState>,
+) -> Result<AbLoopStatus, String> {
+    let current_track_id = *track_id.0.lock().await;
+    let status = {
+        let mut ab = state
+            .0
+            .lock()
+            .map_err(|_| "A-B loop state poisoned".to_string())?;
+        *ab = AbLoop::default();
+        ab.status(current_track_id)
+    };
+    emit_ab_loop(&app_handle, &status);
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn get_ab_loop(
+    state: tauri::State<'_, AbLoopState>,
+    track_id: tauri::State<'_, PlaybackTrackIdState>,
+) -> Result<AbLoopStatus, String> {
+    let current_track_id = *track_id.0.lock().await;
+    let ab = state
+        .0
+        .lock()
+        .map_err(|_| "A-B loop state poisoned".to_string())?;
+    Ok(ab.status(current_track_id))
+}
+
+impl BookmarkStore {
+    fn bookmarks(&self, track: &TrackRef) -> Vec<Bookmark> {
+        let entry = match track {
+            TrackRef::Local { key, .. } => self.local.get(key),
+            TrackRef::Online { id, provider } => self.online.get(&online_key(provider, id)),
+            TrackRef::Remote { server_id, id } => self.remote.get(&remote_key(server_id, id)),
+        };
+        entry.cloned().unwrap_or_default()
+    }
+
+    fn bookmarks_mut(&mut self, track: &TrackRef) -> &mut Vec<Bookmark> {
+        match track {
+            TrackRef::Local { key, .. } => self.local.entry(key.clone()).or_default(),
+            TrackRef::Online { id, provider } => {
+                self.online.entry(online_key(provider, id)).or_default()
+            }
+            TrackRef::Remote { server_id, id } => {
+                self.remote.entry(remote_key(server_id, id)).or_default()
+            }
+        }
+    }
+
+    fn add(&mut self, track: &TrackRef, bookmark: Bookmark) {
+        let bookmarks = self.bookmarks_mut(track);
+        bookmarks.push(bookmark);
+        bookmarks.sort_by_key(|bookmark| bookmark.position_ms);
+    }
+
+    /// 返回是否找到并删除；曲目没有书签后移除整条记录
+    fn remove(&mut self, track: &TrackRef, bookmark_id: &str) -> bool {
+        let bookmarks = self.bookmarks_mut(track);
+        let before = bookmarks.len();
+        bookmarks.retain(|bookmark| bookmark.id != bookmark_id);
+        let removed = bookmarks.len() != before;
+        self.local.retain(|_, bookmarks| !bookmarks.is_empty());
+        self.online.retain(|_, bookmarks| !bookmarks.is_empty());
+        self.remote.retain(|_, bookmarks| !bookmarks.is_empty());
+        removed
+    }
+}
+
+fn generate_bookmark_id() -> String {
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(7)
+        .map(|c| char::from(c).to_ascii_lowercase())
+        .collect();
+    format!("bm_{}_{}", now_ms(), suffix)
+}
+
+fn format_position(position_ms: u64) -> String {
+    let seconds = position_ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// 名称为空时以位置（m:ss）命名
+fn normalize_bookmark_name(name: Option<String>, position_ms: u64) -> String {
+    let name = name.unwrap_or_default();
+    let name = name.trim();
+    if name.is_empty() {
+        return format_position(position_ms);
+    }
+    name.chars().take(MAX_BOOKMARK_NAME_LEN).collect()
+}
+
+fn same_track(a: &TrackRef, b: &TrackRef) -> bool {
+    match (a, b) {
+        (TrackRef::Local { key: a, .. }, TrackRef::Local { key: b, .. }) => a == b,
+        (
+            TrackRef::Online {
+                id: a,
+                provider: provider_a,
+            },
+            TrackRef::Online {
+                id: b,
+                provider: provider_b,
+            },
+        ) => provider_a == provider_b && a == b,
+        (
+            TrackRef::Remote {
+                server_id: server_a,
+                id: a,
+            },
+            TrackRef::Remote {
+                server_id: server_b,
+                id: b,
+            },
+        ) => server_a == server_b && a == b,
+        _ => false,
+    }
+}
+
+fn is_current_track(app_handle: &AppHandle, track: &TrackRef) -> bool {
+    app_handle
+        .try_state::<NowPlayingState>()
+        .and_then(|state| state.0.lock().ok().and_then(|session| session.clone()))
+        .is_some_and(|session| {
+            let current =
+                TrackRef::from_playback_track(&session.track, session.local_path.as_deref());
+            same_track(&current, track)
+        })
+}
+
+/// 加锁读改写；closure 返回 true 表示需要写回
+fn with_store<T>(
+    app_handle: &AppHandle,
+    f: impl FnOnce(&mut BookmarkStore) -> Result<(T, bool), String>,
+) -> Result<T, String> {
+    let _guard = BOOKMARKS_LOCK
+        .lock()
+        .map_err(|_| "bookmarks lock poisoned".to_string())?;
+    let path = storage::app_data_file(app_handle, BOOKMARKS_FILE)?;
+    let mut store: BookmarkStore = storage::read_json(&path)?;
+    let (value, changed) = f(&mut store)?;
+    if changed {
+        storage::write_json(&path, &store)?;
+    }
+    Ok(value)
+}
+
+fn emit_bookmarks_changed(app_handle: &AppHandle, track: &TrackRef, bookmarks: Vec<Bookmark>) {
+    let _ = app_handle.emit(
+        "bookmarks-changed",
+        BookmarksChangedEvent {
+            track: track.clone(),
+            bookmarks,
+        },
+    );
+}
+
+#[tauri::command]
+pub fn get_track_bookmarks(
+    app_handle: AppHandle,
+    track: TrackRef,
+) -> Result<Vec<Bookmark>, String> {
+    with_store(&app_handle, |store| Ok((store.bookmarks(&track), false)))
+}
+
+/// 未指定位置时在当前播放位置添加，此时曲目必须正在播放
+#[tauri::command]
+pub fn add_bookmark(
+    app_handle: AppHandle,
+    playback_rate: tauri::State<'_, PlaybackRateState>,
+    track: TrackRef,
+    name: Option<String>,
+    position_ms: Option<u64>,
+) -> Result<Bookmark, String> {
+    let position_ms = match position_ms {
+        Some(position_ms) => position_ms,
+        None if is_current_track(&app_handle, &track) => {
+            playback_rate.position().as_millis() as u64
+        }
+        None => return Err("Track is not playing".to_string()),
+    };
+    let bookmark = Bookmark {
+        id: generate_bookmark_id(),
+        name: normalize_bookmark_name(name, position_ms),
+        position_ms,
+        created_ms: now_ms(),
+    };
+    let bookmarks = with_store(&app_handle, |store| {
+        store.add(&track, bookmark.clone());
+        Ok((store.bookmarks(&track), true))
+    })?;
+    emit_bookmarks_changed(&app_handle, &track, bookmarks);
+    Ok(bookmark)
+}
+
+#[tauri::command]
+pub fn rename_bookmark(
+    app_handle: AppHandle,
+    track: TrackRef,
+    bookmark_id: String,
+    name: String,
+) -> Result<Bookmark, String> {
+    let (bookmark, bookmarks) = with_store(&app_handle, |store| {
+        let bookmark = store
+            .bookmarks_mut(&track)
+            .iter_mut()
+            .find(|bookmark| bookmark.id == bookmark_id)
+            .ok_or_else(|| format!("Bookmark not found: {}", bookmark_id))?;
+        bookmark.name = normalize_bookmark_name(Some(name), bookmark.position_ms);
+        let bookmark = bookmark.clone();
+        Ok(((bookmark, store.bookmarks(&track)), true))
+    })?;
+    emit_bookmarks_changed(&app_handle, &track, bookmarks);
+    Ok(bookmark)
+}
+
+#[tauri::command]
+pub fn remove_bookmark(
+    app_handle: AppHandle,
+    track: TrackRef,
+    bookmark_id: String,
+) -> Result<(), String> {
+    let bookmarks = with_store(&app_handle, |store| {
+        if !store.remove(&track, &bookmark_id) {
+            return Err(format!("Bookmark not found: {}", bookmark_id));
+        }
+        Ok((store.bookmarks(&track), true))
+    })?;
+    emit_bookmarks_changed(&app_handle, &track, bookmarks);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn jump_to_bookmark(
+    app_handle: AppHandle,
+    sink: tauri::State<'_, Arc<Mutex<rodio::Sink>>>,
+    duration: tauri::State<'_, PlaybackDurationState>,
+    track: TrackRef,
+    bookmark_id: String,
+) -> Result<SeekResult, String> {
+    if !is_current_track(&app_handle, &track) {
+        return Err("Bookmark belongs to a track that is not playing".to_string());
+    }
+    let position_ms = with_store(&app_handle, |store| {
+        let position_ms = store
+            .bookmarks(&track)
+            .iter()
+            .find(|bookmark| bookmark.id == bookmark_id)
+            .map(|bookmark| bookmark.position_ms)
+            .ok_or_else(|| format!("Bookmark not found: {}", bookmark_id))?;
+        Ok((position_ms, false))
+    })?;
+    music::seek_current_track(&app_handle, &sink, &duration, position_ms).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_points_replace_conflicting_end_and_restart_at_b() {
+        let mut ab = AbLoop {
+            track_id: 3,
+            ..AbLoop::default()
+        };
+        ab.set_point(LoopPoint::A, 10_000, 60_000);
+        assert!(!ab.is_active());
+        ab.set_point(LoopPoint::B, 20_000, 60_000);
+        assert!(ab.is_active());
+
+        assert_eq!(ab.restart_position(3, 19_900), None);
+        assert_eq!(ab.restart_position(3, 20_000), Some(10_000));
+        // 切歌后旧循环不再生效
+        assert_eq!(ab.restart_position(4, 25_000), None);
+        assert!(!ab.status(4).active);
+
+        // A 移到 B 之后会清掉 B
+        ab.set_point(LoopPoint::A, 30_000, 60_000);
+        assert_eq!((ab.a_ms, ab.b_ms), (Some(30_000), None));
+
+        // B 点不会超过曲目结尾前的保护区
+        ab.set_point(LoopPoint::B, 60_000, 60_000);
+        assert_eq!(ab.b_ms, Some(60_000 - LOOP_END_GUARD_MS));
+        assert!(ab.is_active());
+    }
+
+    #[test]
+    fn bookmarks_are_kept_per_track_in_position_order() {
+        let local = TrackRef::Local {
+            key: "album/song.mp3".into(),
+            path: None,
+        };
+        let online = TrackRef::Online {
+            id: "42".into(),
+            provider: "netease".into(),
+        };
+        let bookmark = |id: &str, position_ms: u64| Bookmark {
+            id: id.into(),
+            name: normalize_bookmark_name(None, position_ms),
+            position_ms,
+            created_ms: 0,
+        };
+
+        let mut store = BookmarkStore::default();
+        store.add(&local, bookmark("b", 95_000));
+        store.add(&local, bookmark("a", 5_000));
+        store.add(&online, bookmark("c", 1_000));
+
+        let names: Vec<_> = store
+            .bookmarks(&local)
+            .into_iter()
+            .map(|bookmark| bookmark.name)
+            .collect();
+        assert_eq!(names, ["0:05", "1:35"]);
+        assert_eq!(store.bookmarks(&online).len(), 1);
+
+        assert!(!store.remove(&online, "a"));
+        assert!(store.remove(&online, "c"));
+        assert!(store.online.is_empty());
+        assert_eq!(store.local.len(), 1);
+    }
+
+    #[test]
+    fn remote_tracks_match_by_server_and_id() {
+        let remote = |server_id: &str, id: &str| TrackRef::Remote {
+            server_id: server_id.into(),
+            id: id.into(),
+        };
+        assert!(same_track(&remote("s1", "7"), &remote("s1", "7")));
+        assert!(!same_track(&remote("s1", "7"), &remote("s2", "7")));
+        assert!(!same_track(&remote("s1", "7"), &remote("s1", "8")));
+        assert!(!same_track(
+            &remote("s1", "7"),
+            &TrackRef::Online {
+                id: "7".into(),
+                provider: "netease".into(),
+            }
+        ));
+    }
+
+    #[test]
+    fn online_tracks_are_keyed_by_provider() {
+        let online = |provider: &str| TrackRef::Online {
+            id: "1".into(),
+            provider: provider.into(),
+        };
+        assert!(!same_track(&online("mock"), &online("netease")));
+
+        let mut store = BookmarkStore::default();
+        store.add(
+            &online("mock"),
+            Bookmark {
+                id: "a".into(),
+                name: "0:01".into(),
+                position_ms: 1_000,
+                created_ms: 0,
+            },
+        );
+        assert!(store.bookmarks(&online("netease")).is_empty());
+        assert_eq!(store.bookmarks(&online("mock")).len(), 1);
+        assert!(store.online.contains_key("mock:1"));
+
+        // 旧数据没有 provider 字段，按网易云读取，键仍是裸 id
+        let legacy: TrackRef = serde_json::from_str(r#"{"source":"online","id":"1"}"#).unwrap();
+        assert_eq!(legacy, online("netease"));
+    }
+}
@@ -0,0 +1,268 @@
+// CUE 表解析：整轨专辑按 FILE/TRACK/INDEX 拆分成带起止偏移的分轨
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// CUE 时间戳的帧率：mm:ss:ff 中每秒 75 帧
+const CUE_FRAMES_PER_SECOND: u64 = 75;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CueSheet {
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub files: Vec<CueFile>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CueFile {
+    pub name: String,
+    pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CueTrack {
+    pub number: u32,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub start_ms: u64,
+    /// 为空表示播放到文件末尾
+    pub end_ms: Option<u64>,
+    pregap_ms: Option<u64>,
+}
+
+/// UTF-8（可带 BOM）优先，否则按 GBK 解码，兼容常见的旧抓轨软件输出
+pub fn decode_cue_text(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::GBK.decode(bytes).0.into_owned(),
+    }
+}
+
+fn parse_timestamp(value: &str) -> Option<u64> {
+    let mut parts = value.split(':').map(|part| part.parse::<u64>().ok());
+    let minutes = parts.next()??;
+    let seconds = parts.next()??;
+    let frames = parts.next()??;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((minutes * 60 + seconds) * 1000 + frames * 1000 / CUE_FRAMES_PER_SECOND)
+}
+
+/// 拆分一行为命令与参数，双引号内的空格保留
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut has_token = false;
+    for ch in line.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                has_token = true;
+            }
+            ch if ch.is_whitespace() && !quoted => {
+                if has_token {
+                    tokens.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            ch => {
+                current.push(ch);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn non_empty(value: Option<&String>) -> Option<String> {
+    value
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+pub fn parse_cue(text: &str) -> CueSheet {
+    let mut sheet = CueSheet::default();
+    for line in text.lines() {
+        let tokens = tokenize(line);
+        let Some(command) = tokens.first() else {
+            continue;
+        };
+        let track = sheet
+            .files
+            .last_mut()
+            .and_then(|file| file.tracks.last_mut());
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                if let Some(name) = non_empty(tokens.get(1)) {
+                    sheet.files.push(CueFile {
+                        name,
+                        tracks: Vec::new(),
+                    });
+                }
+            }
+            "TRACK" => {
+                let is_audio = tokens
+                    .get(2)
+                    .is_none_or(|kind| kind.eq_ignore_ascii_case("AUDIO"));
+                let number = tokens.get(1).and_then(|number| number.parse().ok());
+                if let (Some(file), Some(number), true) = (sheet.files.last_mut(), number, is_audio)
+                {
+                    file.tracks.push(CueTrack {
+                        number,
+                        ..CueTrack::default()
+                    });
+                }
+            }
+            "TITLE" => match track {
+                Some(track) => track.title = non_empty(tokens.get(1)),
+                None => sheet.title = non_empty(tokens.get(1)),
+            },
+            "PERFORMER" => match track {
+                Some(track) => track.performer = non_empty(tokens.get(1)),
+                None => sheet.performer = non_empty(tokens.get(1)),
+            },
+            "INDEX" => {
+                let (Some(track), Some(index), Some(time)) = (
+                    track,
+                    tokens.get(1).and_then(|index| index.parse::<u32>().ok()),
+                    tokens.get(2).and_then(|time| parse_timestamp(time)),
+                ) else {
+                    continue;
+                };
+                match index {
+                    0 => track.pregap_ms = Some(time),
+                    1 => track.start_ms = time,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 分轨在下一轨的 pregap（INDEX 00）处结束，没有 pregap 时在下一轨起点结束
+    for file in &mut sheet.files {
+        let boundaries: Vec<u64> = file
+            .tracks
+            .iter()
+            .map(|track| track.pregap_ms.unwrap_or(track.start_ms))
+            .collect();
+        for (index, track) in file.tracks.iter_mut().enumerate() {
+            track.end_ms = boundaries
+                .get(index + 1)
+                .copied()
+                .filter(|end| *end > track.start_ms);
+        }
+    }
+    sheet
+}
+
+/// 音频文件旁边的 CUE 表：`专辑.cue` 或 `专辑.flac.cue`
+pub fn sheet_path_for(audio_path: &Path) -> Option<PathBuf> {
+    let with_stem = audio_path.with_extension("cue");
+    let mut with_name = audio_path.as_os_str().to_owned();
+    with_name.push(".cue");
+    [with_stem, PathBuf::from(with_name)]
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+/// 读取并解析音频文件对应的 CUE 表，只返回属于该文件的分轨
+pub fn tracks_for_audio_file(audio_path: &Path) -> Option<(CueSheet, Vec<CueTrack>, PathBuf)> {
+    let sheet_path = sheet_path_for(audio_path)?;
+    let bytes = fs::read(&sheet_path).ok()?;
+    let mut sheet = parse_cue(&decode_cue_text(&bytes));
+
+    let file_name = audio_path.file_name()?.to_str()?;
+    let stem = audio_path.file_stem()?.to_str()?;
+    let matches = |name: &str| {
+        let name = Path::new(name);
+        let referenced_name = name.file_name().and_then(|name| name.to_str());
+        let referenced_stem = name.file_stem().and_then(|stem| stem.to_str());
+        referenced_name.is_some_and(|name| name.eq_ignore_ascii_case(file_name))
+            || referenced_stem.is_some_and(|name| name.eq_ignore_ascii_case(stem))
+    };
+    // 抓轨时常把 FILE 写成 .wav 再转码成 .flac，所以也按文件名主干匹配；只有一个 FILE 时直接使用
+    let index = if sheet.files.len() == 1 {
+        0
+    } else {
+        sheet.files.iter().position(|file| matches(&file.name))?
+    };
+    let tracks = std::mem::take(&mut sheet.files[index].tracks);
+    sheet.files.clear();
+    (!tracks.is_empty()).then_some((sheet, tracks, sheet_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = r#"REM GENRE Rock
+PERFORMER "Some Band"
+TITLE "Live Album"
+FILE "Live Album.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "Opening"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Second Song"
+    PERFORMER "Guest Singer"
+    INDEX 00 03:10:00
+    INDEX 01 03:12:37
+  TRACK 03 AUDIO
+    TITLE "Closing"
+    INDEX 01 07:00:00
+"#;
+
+    #[test]
+    fn parses_tracks_with_offsets_and_performers() {
+        let sheet = parse_cue(SHEET);
+        assert_eq!(sheet.title.as_deref(), Some("Live Album"));
+        assert_eq!(sheet.performer.as_deref(), Some("Some Band"));
+        assert_eq!(sheet.files.len(), 1);
+        assert_eq!(sheet.files[0].name, "Live Album.wav");
+
+        let tracks = &sheet.files[0].tracks;
+        assert_eq!(tracks.len(), 3);
+        assert_eq!(tracks[0].start_ms, 0);
+        // 第一轨在第二轨的 pregap 处结束
+        assert_eq!(tracks[0].end_ms, Some(190_000));
+        assert_eq!(tracks[1].start_ms, 192_493);
+        assert_eq!(tracks[1].performer.as_deref(), Some("Guest Singer"));
+        assert_eq!(tracks[1].end_ms, Some(420_000));
+        assert_eq!(tracks[2].title.as_deref(), Some("Closing"));
+        assert_eq!(tracks[2].end_ms, None);
+    }
+
+    #[test]
+    fn decodes_bom_and_gbk_sheets() {
+        let mut utf8 = b"\xEF\xBB\xBF".to_vec();
+        utf8.extend_from_slice("TITLE \"专辑\"".as_bytes());
+        assert_eq!(decode_cue_text(&utf8), "TITLE \"专辑\"");
+
+        let (gbk, _, _) = encoding_rs::GBK.encode("TITLE \"专辑\"");
+        assert_eq!(decode_cue_text(&gbk), "TITLE \"专辑\"");
+    }
+
+    #[test]
+    fn finds_sheet_next_to_audio_file_by_stem() {
+        let dir = std::env::temp_dir().join(format!("rmusic-cue-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let audio = dir.join("Live Album.flac");
+        fs::write(&audio, b"").unwrap();
+        fs::write(dir.join("Live Album.cue"), SHEET).unwrap();
+
+        let (sheet, tracks, sheet_path) = tracks_for_audio_file(&audio).unwrap();
+        assert_eq!(sheet_path, dir.join("Live Album.cue"));
+        assert_eq!(sheet.title.as_deref(), Some("Live Album"));
+        assert_eq!(tracks.len(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
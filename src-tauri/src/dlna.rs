@@ -0,0 +1,1357 @@
+// DLNA / UPnP 投放：SSDP 发现局域网里的 MediaRenderer，通过 AVTransport / RenderingControl 的 SOAP 接口控制播放。
+// 本地文件由内置 HTTP 服务（只提供当前投放的那一个文件）交给渲染器拉取；投放时本地 Sink 只放静音占位，进度与播完由轮询渲染器得到
+
+use futures_util::future::join_all;
+use hyper::header::{self, HeaderValue};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use rodio::Sink;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Sender;
+use tokio::sync::Mutex;
+
+use crate::local_server::{content_type, escape_xml, file_response};
+use crate::music::{
+    MusicState, PlaybackDurationState, PlaybackRange, PlaybackTrack, PlaybackTrackIdState,
+};
+use crate::netease;
+use crate::remote_control::random_token;
+use crate::time_stretch::PlaybackRateState;
+
+const SSDP_ADDRESS: &str = "239.255.255.250:1900";
+const MEDIA_RENDERER: &str = "urn:schemas-upnp-org:device:MediaRenderer:1";
+const AV_TRANSPORT_PREFIX: &str = "urn:schemas-upnp-org:service:AVTransport:";
+const RENDERING_CONTROL_PREFIX: &str = "urn:schemas-upnp-org:service:RenderingControl:";
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+const DESCRIPTION_TIMEOUT: Duration = Duration::from_secs(5);
+const SOAP_TIMEOUT: Duration = Duration::from_secs(5);
+const POSITION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// 连续这么多次轮询失败视为渲染器离线
+const MAX_POLL_FAILURES: u32 = 5;
+/// 停止时离结尾不到这个时长算播完，否则视为在渲染器上手动停止
+const END_TOLERANCE_MS: u64 = 5_000;
+const MEDIA_PATH_PREFIX: &str = "/media/";
+/// DLNA.ORG_OP=01：支持按字节 Range 跳转
+const DLNA_CONTENT_FEATURES: &str =
+    "DLNA.ORG_OP=01;DLNA.ORG_FLAGS=01700000000000000000000000000000";
+const RENDERER_ERROR_EVENT: &str = "dlna-renderer-error";
+
+#[derive(Debug, Clone, PartialEq)]
+struct ServiceControl {
+    service_type: String,
+    control_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DlnaRenderer {
+    /// 设备 UDN；没有时用描述文件地址
+    pub id: String,
+    pub name: String,
+    pub model: Option<String>,
+    /// 设备描述文件地址
+    pub location: String,
+    #[serde(skip)]
+    av_transport: ServiceControl,
+    #[serde(skip)]
+    rendering_control: Option<ServiceControl>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RendererErrorEvent {
+    renderer_id: String,
+    name: String,
+    message: String,
+}
+
+/// 交给渲染器的媒体：本地文件经内置 HTTP 服务提供，其余直接给出地址
+pub enum RendererMedia {
+    File {
+        path: PathBuf,
+        range: PlaybackRange,
+        duration_ms: u64,
+    },
+    Url {
+        url: String,
+    },
+}
+
+/// 已在渲染器上开始播放的曲目；CUE 分轨的位置相对 offset_ms
+pub struct RendererPlayback {
+    renderer: DlnaRenderer,
+    offset_ms: u64,
+    end_ms: Option<u64>,
+    pub duration_ms: u64,
+}
+
+struct RendererSession {
+    renderer: DlnaRenderer,
+    track_id: u64,
+    generation: u64,
+    offset_ms: u64,
+    end_ms: Option<u64>,
+    /// 渲染器进入 PLAYING 之前收到的跳转 / 暂停先记下，开始播放后补发
+    started: bool,
+    pending_seek_ms: Option<u64>,
+    pending_pause: bool,
+}
+
+struct MediaServer {
+    port: u16,
+    files: Arc<StdMutex<HashMap<String, PathBuf>>>,
+}
+
+#[derive(Default)]
+pub struct DlnaState {
+    /// 最近一次发现的渲染器
+    renderers: StdMutex<Vec<DlnaRenderer>>,
+    /// 当前输出；None 为本机
+    output: StdMutex<Option<DlnaRenderer>>,
+    session: StdMutex<Option<RendererSession>>,
+    media: StdMutex<Option<MediaServer>>,
+    last_volume: StdMutex<Option<u8>>,
+}
+
+impl DlnaState {
+    fn output(&self) -> Option<DlnaRenderer> {
+        self.output.lock().ok().and_then(|output| output.clone())
+    }
+
+    fn take_session(&self) -> Option<RendererSession> {
+        self.session
+            .lock()
+            .ok()
+            .and_then(|mut session| session.take())
+    }
+
+    fn with_session<T>(
+        &self,
+        track_id: u64,
+        f: impl FnOnce(&mut RendererSession) -> T,
+    ) -> Option<T> {
+        let mut session = self.session.lock().ok()?;
+        session
+            .as_mut()
+            .filter(|session| session.track_id == track_id)
+            .map(f)
+    }
+
+    fn with_current_session<T>(&self, f: impl FnOnce(&mut RendererSession) -> T) -> Option<T> {
+        let mut session = self.session.lock().ok()?;
+        session.as_mut().map(f)
+    }
+
+    /// 登记要提供给渲染器的文件，首次使用时启动内置 HTTP 服务
+    fn serve_file(&self, renderer: &DlnaRenderer, path: &Path) -> Result<String, String> {
+        let host = local_address_for(&renderer.location)?;
+        let mut media = self
+            .media
+            .lock()
+            .map_err(|_| "DLNA media server state poisoned".to_string())?;
+        if media.is_none() {
+            *media = Some(start_media_server()?);
+        }
+        let server = media
+            .as_ref()
+            .ok_or_else(|| "DLNA media server not running".to_string())?;
+        let token = server.register(path)?;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("audio");
+        Ok(format!(
+            "http://{}{}{}.{}",
+            SocketAddr::new(host, server.port),
+            MEDIA_PATH_PREFIX,
+            token,
+            extension
+        ))
+    }
+}
+
+impl MediaServer {
+    /// 渲染器只需要当前这一首，换曲时旧地址随即失效
+    fn register(&self, path: &Path) -> Result<String, String> {
+        let token = random_token();
+        let mut files = self
+            .files
+            .lock()
+            .map_err(|_| "DLNA media files poisoned".to_string())?;
+        files.clear();
+        files.insert(token.clone(), path.to_path_buf());
+        Ok(token)
+    }
+}
+
+fn ssdp_search_request() -> String {
+    format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {}\r\n\r\n",
+        SSDP_ADDRESS, MEDIA_RENDERER
+    )
+}
+
+/// SSDP 响应是 HTTP 格式，取其中的 LOCATION（设备描述文件地址）
+fn parse_ssdp_location(response: &str) -> Option<String> {
+    let mut lines = response.lines();
+    let status = lines.next()?;
+    if !status.starts_with("HTTP/") || !status.contains(" 200") {
+        return None;
+    }
+    lines
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim()
+                .eq_ignore_ascii_case("location")
+                .then(|| value.trim().to_string())
+        })
+        .filter(|location| !location.is_empty())
+}
+
+async fn search_locations() -> Result<Vec<String>, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .map_err(|e| format!("bind SSDP socket error: {}", e))?;
+    let request = ssdp_search_request();
+    // UDP 组播可能丢包，多发一次
+    for _ in 0..2 {
+        socket
+            .send_to(request.as_bytes(), SSDP_ADDRESS)
+            .await
+            .map_err(|e| format!("send SSDP search error: {}", e))?;
+    }
+    let deadline = tokio::time::Instant::now() + DISCOVERY_TIMEOUT;
+    let mut locations = Vec::new();
+    let mut buffer = [0u8; 4096];
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer)).await
+    {
+        let Ok((length, _)) = received else {
+            break;
+        };
+        if let Some(location) = parse_ssdp_location(&String::from_utf8_lossy(&buffer[..length])) {
+            if !locations.contains(&location) {
+                locations.push(location);
+            }
+        }
+    }
+    Ok(locations)
+}
+
+#[derive(Debug, Default)]
+struct DeviceDraft {
+    device_type: String,
+    name: Option<String>,
+    model: Option<String>,
+    udn: Option<String>,
+    services: Vec<ServiceControl>,
+}
+
+impl DeviceDraft {
+    fn service(&self, prefix: &str) -> Option<&ServiceControl> {
+        self.services
+            .iter()
+            .find(|service| service.service_type.starts_with(prefix))
+    }
+}
+
+fn resolve_url(base: &str, url: &str) -> Result<String, String> {
+    reqwest::Url::parse(base)
+        .and_then(|base| base.join(url))
+        .map(String::from)
+        .map_err(|e| format!("invalid control URL {}: {}", url, e))
+}
+
+fn read_text(text: &mut String, value: quick_xml::events::BytesText) {
+    match value.unescape() {
+        Ok(value) => text.push_str(&value),
+        Err(_) => text.push_str(&String::from_utf8_lossy(&value)),
+    }
+}
+
+/// 设备描述：MediaRenderer 可能是根设备，也可能嵌在 deviceList 里；控制地址相对 URLBase（没有时相对描述文件地址）
+fn parse_device_description(xml: &str, location: &str) -> Result<DlnaRenderer, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut url_base: Option<String> = None;
+    let mut open_devices: Vec<DeviceDraft> = Vec::new();
+    let mut devices: Vec<DeviceDraft> = Vec::new();
+    let mut service: Option<ServiceControl> = None;
+    let mut text = String::new();
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("parse device description error: {}", e))?;
+        match event {
+            Event::Start(start) => {
+                match start.local_name().as_ref() {
+                    b"device" => open_devices.push(DeviceDraft::default()),
+                    b"service" => {
+                        service = Some(ServiceControl {
+                            service_type: String::new(),
+                            control_url: String::new(),
+                        })
+                    }
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Text(value) => read_text(&mut text, value),
+            Event::End(end) => {
+                let value = std::mem::take(&mut text).trim().to_string();
+                match end.local_name().as_ref() {
+                    b"URLBase" => url_base = Some(value).filter(|base| !base.is_empty()),
+                    b"device" => devices.extend(open_devices.pop()),
+                    b"service" => {
+                        if let (Some(service), Some(device)) =
+                            (service.take(), open_devices.last_mut())
+                        {
+                            device.services.push(service);
+                        }
+                    }
+                    b"serviceType" if service.is_some() => {
+                        if let Some(service) = service.as_mut() {
+                            service.service_type = value;
+                        }
+                    }
+                    b"controlURL" if service.is_some() => {
+                        if let Some(service) = service.as_mut() {
+                            service.control_url = value;
+                        }
+                    }
+                    field if service.is_none() && !value.is_empty() => {
+                        if let Some(device) = open_devices.last_mut() {
+                            match field {
+                                b"deviceType" => device.device_type = value,
+                                b"friendlyName" => device.name = Some(value),
+                                b"modelName" => device.model = Some(value),
+                                b"UDN" => device.udn = Some(value),
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    // 内层设备先结束，根设备在最后
+    let root_name = devices.last().and_then(|device| device.name.clone());
+    let device = devices
+        .iter()
+        .filter(|device| device.service(AV_TRANSPORT_PREFIX).is_some())
+        .max_by_key(|device| device.device_type.starts_with(MEDIA_RENDERER))
+        .ok_or_else(|| format!("not a media renderer: {}", location))?;
+    let base = url_base.as_deref().unwrap_or(location);
+    let control = |service: &ServiceControl| -> Result<ServiceControl, String> {
+        Ok(ServiceControl {
+            service_type: service.service_type.clone(),
+            control_url: resolve_url(base, &service.control_url)?,
+        })
+    };
+    let av_transport = device
+        .service(AV_TRANSPORT_PREFIX)
+        .map(&control)
+        .ok_or_else(|| format!("not a media renderer: {}", location))??;
+    let rendering_control = device
+        .service(RENDERING_CONTROL_PREFIX)
+        .map(&control)
+        .transpose()?;
+    Ok(DlnaRenderer {
+        id: device.udn.clone().unwrap_or_else(|| location.to_string()),
+        name: device
+            .name
+            .clone()
+            .or(root_name)
+            .unwrap_or_else(|| "DLNA Renderer".to_string()),
+        model: device.model.clone(),
+        location: location.to_string(),
+        av_transport,
+        rendering_control,
+    })
+}
+
+async fn fetch_renderer(location: String) -> Result<DlnaRenderer, String> {
+    let client = netease::get_client()?;
+    let body = tokio::time::timeout(DESCRIPTION_TIMEOUT, async {
+        client.get(&location).send().await?.text().await
+    })
+    .await
+    .map_err(|_| format!("device description timed out: {}", location))?
+    .map_err(|e| format!("fetch device description error: {}", e))?;
+    parse_device_description(&body, &location)
+}
+
+fn soap_envelope(service_type: &str, action: &str, args: &[(&str, &str)]) -> String {
+    let args: String = args
+        .iter()
+        .map(|(name, value)| format!("<{0}>{1}</{0}>", name, escape_xml(value)))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+         <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+         <s:Body><u:{action} xmlns:u=\"{service}\">{args}</u:{action}></s:Body></s:Envelope>",
+        action = action,
+        service = escape_xml(service_type),
+        args = args
+    )
+}
+
+/// SOAP 响应与错误里的叶子元素（去掉命名空间前缀）
+fn parse_soap_values(xml: &str) -> HashMap<String, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut values = HashMap::new();
+    let mut text = String::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(_)) => text.clear(),
+            Ok(Event::Text(value)) => read_text(&mut text, value),
+            Ok(Event::End(end)) => {
+                let name = String::from_utf8_lossy(end.local_name().as_ref()).into_owned();
+                values.insert(name, std::mem::take(&mut text).trim().to_string());
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    values
+}
+
+async fn soap_action(
+    service: &ServiceControl,
+    action: &str,
+    args: &[(&str, &str)],
+) -> Result<HashMap<String, String>, String> {
+    let client = netease::get_client()?;
+    let request = client
+        .post(&service.control_url)
+        .header(reqwest::header::CONTENT_TYPE, "text/xml; charset=\"utf-8\"")
+        .header(
+            "SOAPACTION",
+            format!("\"{}#{}\"", service.service_type, action),
+        )
+        .body(soap_envelope(&service.service_type, action, args))
+        .send();
+    let response = tokio::time::timeout(SOAP_TIMEOUT, request)
+        .await
+        .map_err(|_| format!("{} timed out", action))?
+        .map_err(|e| format!("{} error: {}", action, e))?;
+    let status = response.status();
+    let body = tokio::time::timeout(SOAP_TIMEOUT, response.text())
+        .await
+        .map_err(|_| format!("{} timed out", action))?
+        .map_err(|e| format!("{} error: {}", action, e))?;
+    let values = parse_soap_values(&body);
+    if !status.is_success() {
+        let detail = values
+            .get("errorDescription")
+            .or_else(|| values.get("faultstring"))
+            .filter(|detail| !detail.is_empty())
+            .cloned()
+            .unwrap_or_else(|| format!("HTTP {}", status));
+        return Err(format!("{} error: {}", action, detail));
+    }
+    Ok(values)
+}
+
+/// UPnP 的时间格式 H+:MM:SS
+fn format_time(ms: u64) -> String {
+    let seconds = ms / 1000;
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// 解析 `0:03:25` / `00:03:25.500`；NOT_IMPLEMENTED 等非时间值返回 None
+fn parse_time(text: &str) -> Option<u64> {
+    let mut parts = text.trim().split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+    Some(hours * 3_600_000 + minutes * 60_000 + (seconds * 1000.0).round() as u64)
+}
+
+struct TransportStatus {
+    state: String,
+    position_ms: Option<u64>,
+    duration_ms: Option<u64>,
+}
+
+impl DlnaRenderer {
+    async fn av_transport(
+        &self,
+        action: &str,
+        args: &[(&str, &str)],
+    ) -> Result<HashMap<String, String>, String> {
+        let mut full_args = vec![("InstanceID", "0")];
+        full_args.extend_from_slice(args);
+        soap_action(&self.av_transport, action, &full_args).await
+    }
+
+    async fn load(&self, url: &str, metadata: &str) -> Result<(), String> {
+        self.av_transport(
+            "SetAVTransportURI",
+            &[("CurrentURI", url), ("CurrentURIMetaData", metadata)],
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn play(&self) -> Result<(), String> {
+        self.av_transport("Play", &[("Speed", "1")])
+            .await
+            .map(|_| ())
+    }
+
+    async fn pause(&self) -> Result<(), String> {
+        self.av_transport("Pause", &[]).await.map(|_| ())
+    }
+
+    async fn stop(&self) -> Result<(), String> {
+        self.av_transport("Stop", &[]).await.map(|_| ())
+    }
+
+    async fn seek(&self, position_ms: u64) -> Result<(), String> {
+        let target = format_time(position_ms);
+        self.av_transport("Seek", &[("Unit", "REL_TIME"), ("Target", &target)])
+            .await
+            .map(|_| ())
+    }
+
+    async fn status(&self) -> Result<TransportStatus, String> {
+        let transport = self.av_transport("GetTransportInfo", &[]).await?;
+        let position = self.av_transport("GetPositionInfo", &[]).await?;
+        Ok(TransportStatus {
+            state: transport
+                .get("CurrentTransportState")
+                .cloned()
+                .unwrap_or_default(),
+            position_ms: position.get("RelTime").and_then(|time| parse_time(time)),
+            duration_ms: position
+                .get("TrackDuration")
+                .and_then(|time| parse_time(time))
+                .filter(|duration| *duration > 0),
+        })
+    }
+
+    async fn set_volume(&self, volume: u8) -> Result<(), String> {
+        let Some(service) = &self.rendering_control else {
+            return Ok(());
+        };
+        let volume = volume.to_string();
+        soap_action(
+            service,
+            "SetVolume",
+            &[
+                ("InstanceID", "0"),
+                ("Channel", "Master"),
+                ("DesiredVolume", &volume),
+            ],
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+fn didl_metadata(url: &str, mime: Option<&str>, track: Option<&PlaybackTrack>) -> String {
+    let (title, artist, album, art_url) = match track {
+        Some(PlaybackTrack::Local { file }) => (
+            file.title.clone().unwrap_or_else(|| file.file_name.clone()),
+            file.artist.clone(),
+            file.album.clone(),
+            None,
+        ),
+        Some(PlaybackTrack::Online { song }) => (
+            song.name.clone(),
+            Some(song.artists.join(", ")),
+            Some(song.album.clone()),
+            Some(song.pic_url.clone()),
+        ),
+        Some(PlaybackTrack::Remote { song }) => (
+            song.song.name.clone(),
+            Some(song.song.artists.join(", ")),
+            Some(song.song.album.clone()),
+            None,
+        ),
+        None => ("rmusic".to_string(), None, None, None),
+    };
+    let mut item = format!("<dc:title>{}</dc:title>", escape_xml(&title));
+    if let Some(artist) = artist.filter(|artist| !artist.is_empty()) {
+        item.push_str(&format!(
+            "<upnp:artist>{0}</upnp:artist><dc:creator>{0}</dc:creator>",
+            escape_xml(&artist)
+        ));
+    }
+    if let Some(album) = album.filter(|album| !album.is_empty()) {
+        item.push_str(&format!("<upnp:album>{}</upnp:album>", escape_xml(&album)));
+    }
+    if let Some(art_url) = art_url.filter(|url| url.starts_with("http")) {
+        item.push_str(&format!(
+            "<upnp:albumArtURI>{}</upnp:albumArtURI>",
+            escape_xml(&art_url)
+        ));
+    }
+    format!(
+        "<DIDL-Lite xmlns=\"urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+         xmlns:upnp=\"urn:schemas-upnp-org:metadata-1-0/upnp/\">\
+         <item id=\"0\" parentID=\"-1\" restricted=\"1\">{}\
+         <upnp:class>object.item.audioItem.musicTrack</upnp:class>\
+         <res protocolInfo=\"http-get:*:{}:*\">{}</res></item></DIDL-Lite>",
+        item,
+        mime.unwrap_or("*"),
+        escape_xml(url)
+    )
+}
+
+/// 在线缓存文件的扩展名是 .audio，按文件头判断格式
+fn detect_audio_mime(header: &[u8]) -> &'static str {
+    if header.starts_with(b"fLaC") {
+        "audio/flac"
+    } else if header.starts_with(b"OggS") {
+        "audio/ogg"
+    } else if header.starts_with(b"RIFF") {
+        "audio/wav"
+    } else if header.get(4..8) == Some(b"ftyp") {
+        "audio/mp4"
+    } else {
+        "audio/mpeg"
+    }
+}
+
+fn media_mime(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    match content_type(extension) {
+        mime if mime.starts_with("audio/") => mime,
+        _ => {
+            let mut header = [0u8; 12];
+            let read = std::fs::File::open(path)
+                .and_then(|mut file| file.read(&mut header))
+                .unwrap_or(0);
+            detect_audio_mime(&header[..read])
+        }
+    }
+}
+
+/// 与渲染器通信时使用的本机地址（局域网内可达的那块网卡）
+fn local_address_for(location: &str) -> Result<IpAddr, String> {
+    let url =
+        reqwest::Url::parse(location).map_err(|e| format!("invalid renderer address: {}", e))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("invalid renderer address: {}", location))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let target = (host, url.port_or_known_default().unwrap_or(80))
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve renderer address error: {}", e))?
+        .next()
+        .ok_or_else(|| format!("resolve renderer address error: {}", host))?;
+    let bind: IpAddr = if target.is_ipv4() {
+        Ipv4Addr::UNSPECIFIED.into()
+    } else {
+        Ipv6Addr::UNSPECIFIED.into()
+    };
+    // UDP connect 不发包，只让系统选出路由对应的本机地址
+    let socket = std::net::UdpSocket::bind((bind, 0))
+        .and_then(|socket| socket.connect(target).map(|_| socket))
+        .map_err(|e| format!("find local address error: {}", e))?;
+    socket
+        .local_addr()
+        .map(|address| address.ip())
+        .map_err(|e| format!("find local address error: {}", e))
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+async fn handle_media_request(
+    files: Arc<StdMutex<HashMap<String, PathBuf>>>,
+    request: Request<Body>,
+) -> Response<Body> {
+    let is_head = request.method() == Method::HEAD;
+    if !is_head && request.method() != Method::GET {
+        return status_response(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let path = request
+        .uri()
+        .path()
+        .strip_prefix(MEDIA_PATH_PREFIX)
+        .and_then(|name| name.split('.').next())
+        .and_then(|token| files.lock().ok()?.get(token).cloned())
+        .filter(|path| path.is_file());
+    let Some(path) = path else {
+        return status_response(StatusCode::NOT_FOUND);
+    };
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    let Ok(mut response) = file_response(&path, media_mime(&path), range).await else {
+        return status_response(StatusCode::NOT_FOUND);
+    };
+    let headers = response.headers_mut();
+    headers.insert(
+        "transfermode.dlna.org",
+        HeaderValue::from_static("Streaming"),
+    );
+    headers.insert(
+        "contentfeatures.dlna.org",
+        HeaderValue::from_static(DLNA_CONTENT_FEATURES),
+    );
+    if is_head {
+        *response.body_mut() = Body::empty();
+    }
+    response
+}
+
+/// 监听所有网卡的随机端口：渲染器从局域网访问，只能取到登记过的文件
+fn start_media_server() -> Result<MediaServer, String> {
+    let listener = std::net::TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| format!("bind DLNA media server: {}", e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("configure DLNA media server: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("bind DLNA media server: {}", e))?
+        .port();
+    let files = Arc::new(StdMutex::new(HashMap::new()));
+    let shared = Arc::clone(&files);
+    let server = Server::from_tcp(listener)
+        .map_err(|e| format!("start DLNA media server: {}", e))?
+        .serve(make_service_fn(move |_| {
+            let files = Arc::clone(&shared);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let files = Arc::clone(&files);
+                    async move { Ok::<_, Infallible>(handle_media_request(files, request).await) }
+                }))
+            }
+        }));
+    tokio::spawn(async move {
+        if let Err(error) = server.await {
+            eprintln!("DLNA media server error: {}", error);
+        }
+    });
+    Ok(MediaServer { port, files })
+}
+
+/// 当前投放的渲染器；None 表示本机输出
+pub fn active_renderer(app_handle: &AppHandle) -> Option<DlnaRenderer> {
+    app_handle.state::<DlnaState>().output()
+}
+
+/// 让渲染器载入并开始播放；CUE 分轨等渲染器开始播放后再跳到起点
+pub async fn start_renderer_playback(
+    app_handle: &AppHandle,
+    renderer: &DlnaRenderer,
+    media: RendererMedia,
+    track: Option<&PlaybackTrack>,
+) -> Result<RendererPlayback, String> {
+    let (url, mime, range, duration_ms) = match media {
+        RendererMedia::File {
+            path,
+            range,
+            duration_ms,
+        } => {
+            let url = app_handle
+                .state::<DlnaState>()
+                .serve_file(renderer, &path)?;
+            (url, Some(media_mime(&path)), range, duration_ms)
+        }
+        RendererMedia::Url { url } => (url, None, PlaybackRange::default(), 0),
+    };
+    // 部分渲染器播放中不接受新地址，先停止；本来就停着时的报错不影响
+    let _ = renderer.stop().await;
+    renderer
+        .load(&url, &didl_metadata(&url, mime, track))
+        .await?;
+    renderer.play().await?;
+    Ok(RendererPlayback {
+        renderer: renderer.clone(),
+        offset_ms: range.start_ms,
+        end_ms: range.end_ms,
+        duration_ms,
+    })
+}
+
+/// 本地会话建立后开始轮询渲染器：位置写入 PlaybackRateState，播完时清空本地 Sink，由结束监听发出 playback-ended
+pub fn track_renderer_playback(
+    app_handle: &AppHandle,
+    playback: RendererPlayback,
+    track_id: u64,
+    generation: u64,
+) {
+    let session = RendererSession {
+        renderer: playback.renderer,
+        track_id,
+        generation,
+        offset_ms: playback.offset_ms,
+        end_ms: playback.end_ms,
+        started: false,
+        pending_seek_ms: (playback.offset_ms > 0).then_some(playback.offset_ms),
+        pending_pause: false,
+    };
+    if let Ok(mut current) = app_handle.state::<DlnaState>().session.lock() {
+        *current = Some(session);
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        poll_renderer(app_handle, track_id).await;
+    });
+}
+
+async fn set_local_paused(app_handle: &AppHandle, paused: bool) {
+    let sink = app_handle.state::<Arc<Mutex<Sink>>>();
+    let sink = sink.lock().await;
+    if paused {
+        sink.pause();
+    } else {
+        sink.play();
+    }
+}
+
+/// 渲染器播完：清空本地静音占位（期间已换曲时不动）
+async fn finish_renderer_track(app_handle: &AppHandle, generation: u64, position_ms: u64) {
+    let sink = app_handle.state::<Arc<Mutex<Sink>>>();
+    let sink = sink.lock().await;
+    // 结束监听要求位置大于 0 才算播完
+    let finished = app_handle
+        .state::<PlaybackRateState>()
+        .set_external_position(generation, Duration::from_millis(position_ms.max(1)));
+    if finished {
+        sink.clear();
+    }
+}
+
+async fn poll_renderer(app_handle: AppHandle, track_id: u64) {
+    let state = app_handle.state::<DlnaState>();
+    let playback_rate = app_handle.state::<PlaybackRateState>();
+    let duration = app_handle.state::<PlaybackDurationState>();
+    let current_track_id = app_handle.state::<PlaybackTrackIdState>();
+    let mut last_state = String::new();
+    let mut last_position_ms = 0u64;
+    let mut failures = 0u32;
+    loop {
+        tokio::time::sleep(POSITION_POLL_INTERVAL).await;
+        if *current_track_id.0.lock().await != track_id {
+            break;
+        }
+        let Some((renderer, generation, offset_ms, end_ms)) =
+            state.with_session(track_id, |session| {
+                (
+                    session.renderer.clone(),
+                    session.generation,
+                    session.offset_ms,
+                    session.end_ms,
+                )
+            })
+        else {
+            break;
+        };
+
+        let status = match renderer.status().await {
+            Ok(status) => {
+                failures = 0;
+                status
+            }
+            Err(error) => {
+                failures += 1;
+                if failures == MAX_POLL_FAILURES {
+                    set_local_paused(&app_handle, true).await;
+                    let _ = app_handle.emit(
+                        RENDERER_ERROR_EVENT,
+                        RendererErrorEvent {
+                            renderer_id: renderer.id.clone(),
+                            name: renderer.name.clone(),
+                            message: error,
+                        },
+                    );
+                }
+                continue;
+            }
+        };
+
+        let mut transport_state = status.state;
+        if transport_state == "PLAYING" {
+            let pending = state.with_session(track_id, |session| {
+                let pending = (!session.started)
+                    .then(|| (session.pending_seek_ms.take(), session.pending_pause));
+                session.started = true;
+                pending
+            });
+            if let Some((seek_ms, pause)) = pending.flatten() {
+                if let Some(seek_ms) = seek_ms {
+                    if let Err(error) = renderer.seek(seek_ms).await {
+                        eprintln!("DLNA seek error: {}", error);
+                    }
+                }
+                if pause {
+                    match renderer.pause().await {
+                        Ok(()) => transport_state = "PAUSED_PLAYBACK".to_string(),
+                        Err(error) => eprintln!("DLNA pause error: {}", error),
+                    }
+                }
+            }
+        }
+
+        let is_active = matches!(transport_state.as_str(), "PLAYING" | "PAUSED_PLAYBACK");
+        if let (true, Some(position_ms)) = (is_active, status.position_ms) {
+            last_position_ms = position_ms.saturating_sub(offset_ms);
+            playback_rate
+                .set_external_position(generation, Duration::from_millis(last_position_ms));
+        }
+        let duration_ms = {
+            let mut duration = duration.0.lock().await;
+            if *duration == 0 {
+                if let Some(total_ms) = end_ms.or(status.duration_ms) {
+                    *duration = total_ms.saturating_sub(offset_ms);
+                }
+            }
+            *duration
+        };
+
+        if let (Some(end_ms), Some(position_ms)) = (end_ms, status.position_ms) {
+            if is_active && position_ms >= end_ms {
+                let _ = renderer.stop().await;
+                finish_renderer_track(&app_handle, generation, duration_ms).await;
+                break;
+            }
+        }
+
+        if transport_state != last_state {
+            let started = state
+                .with_session(track_id, |session| session.started)
+                .unwrap_or(false);
+            match transport_state.as_str() {
+                // 在渲染器（或其遥控器）上暂停 / 继续时同步本地状态
+                "PLAYING" => set_local_paused(&app_handle, false).await,
+                "PAUSED_PLAYBACK" => set_local_paused(&app_handle, true).await,
+                "STOPPED" | "NO_MEDIA_PRESENT" if started => {
+                    if duration_ms == 0 || last_position_ms + END_TOLERANCE_MS >= duration_ms {
+                        finish_renderer_track(
+                            &app_handle,
+                            generation,
+                            last_position_ms.max(duration_ms),
+                        )
+                        .await;
+                        break;
+                    }
+                    set_local_paused(&app_handle, true).await;
+                }
+                _ => {}
+            }
+            last_state = transport_state;
+        }
+    }
+}
+
+/// 暂停 / 继续 / 音量经 MusicState 广播转给当前渲染器；渲染器开始播放前先记下
+async fn forward_control(app_handle: &AppHandle, event: MusicState) -> Result<(), String> {
+    let state = app_handle.state::<DlnaState>();
+    match event {
+        MusicState::Pause | MusicState::Recovery => {
+            let pause = matches!(event, MusicState::Pause);
+            let renderer = state
+                .with_current_session(|session| {
+                    if !session.started {
+                        session.pending_pause = pause;
+                        return None;
+                    }
+                    Some(session.renderer.clone())
+                })
+                .flatten();
+            match renderer {
+                Some(renderer) if pause => renderer.pause().await,
+                Some(renderer) => renderer.play().await,
+                None => Ok(()),
+            }
+        }
+        MusicState::Volume(volume) => {
+            let Some(renderer) = state.with_current_session(|session| session.renderer.clone())
+            else {
+                return Ok(());
+            };
+            // 拖动音量条时事件很密，只发送变化了的整数音量
+            let volume = volume.round().clamp(0.0, 100.0) as u8;
+            let changed = state
+                .last_volume
+                .lock()
+                .map(|mut last| last.replace(volume) != Some(volume))
+                .unwrap_or(true);
+            if !changed {
+                return Ok(());
+            }
+            renderer.set_volume(volume).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 渲染器播放中时把跳转转发过去；返回 false 表示当前是本机输出
+pub async fn seek(app_handle: &AppHandle, position_ms: u64) -> Result<bool, String> {
+    let state = app_handle.state::<DlnaState>();
+    let Some((renderer, generation, target_ms)) = state.with_current_session(|session| {
+        let target_ms = session.offset_ms + position_ms;
+        if !session.started {
+            session.pending_seek_ms = Some(target_ms);
+            return (None, session.generation, target_ms);
+        }
+        (
+            Some(session.renderer.clone()),
+            session.generation,
+            target_ms,
+        )
+    }) else {
+        return Ok(false);
+    };
+    if let Some(renderer) = renderer {
+        renderer.seek(target_ms).await?;
+    }
+    app_handle
+        .state::<PlaybackRateState>()
+        .set_external_position(generation, Duration::from_millis(position_ms));
+    Ok(true)
+}
+
+/// 订阅播放控制广播，输出为渲染器时转发
+pub fn start_dlna_output(app_handle: &AppHandle) {
+    let mut receiver = app_handle.state::<Sender<MusicState>>().subscribe();
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            if let Err(error) = forward_control(&app_handle, event).await {
+                eprintln!("DLNA control error: {}", error);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn discover_dlna_renderers(
+    state: tauri::State<'_, DlnaState>,
+) -> Result<Vec<DlnaRenderer>, String> {
+    let locations = search_locations().await?;
+    let mut renderers: Vec<DlnaRenderer> = Vec::new();
+    for result in join_all(locations.into_iter().map(fetch_renderer)).await {
+        match result {
+            Ok(renderer) if !renderers.iter().any(|known| known.id == renderer.id) => {
+                renderers.push(renderer)
+            }
+            Ok(_) => {}
+            Err(error) => eprintln!("Skip DLNA device: {}", error),
+        }
+    }
+    renderers.sort_by_key(|renderer| renderer.name.to_lowercase());
+    // 正在使用的渲染器这次没响应也保留在列表里
+    if let Some(active) = state.output() {
+        if !renderers.iter().any(|renderer| renderer.id == active.id) {
+            renderers.push(active);
+        }
+    }
+    if let Ok(mut known) = state.renderers.lock() {
+        *known = renderers.clone();
+    }
+    Ok(renderers)
+}
+
+#[tauri::command]
+pub fn get_playback_output(state: tauri::State<'_, DlnaState>) -> Option<DlnaRenderer> {
+    state.output()
+}
+
+/// 切换输出：停掉正在投放的曲目并暂停本地 Sink，由前端在新输出上从当前位置重新播放
+#[tauri::command]
+pub async fn set_playback_output(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DlnaState>,
+    renderer_id: Option<String>,
+) -> Result<Option<DlnaRenderer>, String> {
+    let next = match renderer_id {
+        Some(id) => Some(
+            state
+                .renderers
+                .lock()
+                .map_err(|_| "DLNA state poisoned".to_string())?
+                .iter()
+                .find(|renderer| renderer.id == id)
+                .cloned()
+                .ok_or_else(|| format!("DLNA renderer not found: {}", id))?,
+        ),
+        None => None,
+    };
+    *state
+        .output
+        .lock()
+        .map_err(|_| "DLNA state poisoned".to_string())? = next.clone();
+    if let Ok(mut last_volume) = state.last_volume.lock() {
+        *last_volume = None;
+    }
+    if let Some(session) = state.take_session() {
+        if let Err(error) = session.renderer.stop().await {
+            eprintln!("Failed to stop DLNA renderer: {}", error);
+        }
+    }
+    set_local_paused(&app_handle, true).await;
+    Ok(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{canned, serve};
+
+    const DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:MediaServer:1</deviceType>
+    <friendlyName>Living Room</friendlyName>
+    <UDN>uuid:root</UDN>
+    <deviceList>
+      <device>
+        <deviceType>urn:schemas-upnp-org:device:MediaRenderer:1</deviceType>
+        <modelName>AVR-X1600H</modelName>
+        <UDN>uuid:renderer</UDN>
+        <serviceList>
+          <service>
+            <serviceType>urn:schemas-upnp-org:service:RenderingControl:1</serviceType>
+            <controlURL>RenderingControl/ctrl</controlURL>
+          </service>
+          <service>
+            <serviceType>urn:schemas-upnp-org:service:AVTransport:1</serviceType>
+            <controlURL>/upnp/AVTransport/ctrl</controlURL>
+          </service>
+        </serviceList>
+      </device>
+    </deviceList>
+  </device>
+</root>"#;
+
+    #[test]
+    fn parses_ssdp_response_and_embedded_renderer_description() {
+        let response = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\n\
+                        Location: http://192.168.1.20:8080/desc/device.xml\r\n\
+                        ST: urn:schemas-upnp-org:device:MediaRenderer:1\r\n\r\n";
+        let location = parse_ssdp_location(response).unwrap();
+        assert_eq!(location, "http://192.168.1.20:8080/desc/device.xml");
+        assert_eq!(
+            parse_ssdp_location("NOTIFY * HTTP/1.1\r\nLOCATION: x\r\n"),
+            None
+        );
+
+        let renderer = parse_device_description(DESCRIPTION, &location).unwrap();
+        assert_eq!(renderer.id, "uuid:renderer");
+        // 内嵌设备没有名字时沿用根设备的名字
+        assert_eq!(renderer.name, "Living Room");
+        assert_eq!(renderer.model.as_deref(), Some("AVR-X1600H"));
+        assert_eq!(
+            renderer.av_transport.control_url,
+            "http://192.168.1.20:8080/upnp/AVTransport/ctrl"
+        );
+        assert_eq!(
+            renderer.rendering_control.unwrap().control_url,
+            "http://192.168.1.20:8080/desc/RenderingControl/ctrl"
+        );
+
+        let with_base = DESCRIPTION.replace(
+            "<device>",
+            "<URLBase>http://192.168.1.20:49152/</URLBase><device>",
+        );
+        let renderer = parse_device_description(&with_base, &location).unwrap();
+        assert_eq!(
+            renderer.av_transport.control_url,
+            "http://192.168.1.20:49152/upnp/AVTransport/ctrl"
+        );
+        assert!(parse_device_description("<root><device/></root>", &location).is_err());
+    }
+
+    #[test]
+    fn builds_soap_requests_and_reads_values() {
+        let envelope = soap_envelope(
+            "urn:schemas-upnp-org:service:AVTransport:1",
+            "SetAVTransportURI",
+            &[
+                ("InstanceID", "0"),
+                ("CurrentURIMetaData", "<DIDL-Lite>&</DIDL-Lite>"),
+            ],
+        );
+        assert!(envelope.contains(
+            "<u:SetAVTransportURI xmlns:u=\"urn:schemas-upnp-org:service:AVTransport:1\">"
+        ));
+        assert!(envelope.contains(
+            "<CurrentURIMetaData>&lt;DIDL-Lite&gt;&amp;&lt;/DIDL-Lite&gt;</CurrentURIMetaData>"
+        ));
+
+        let values = parse_soap_values(
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body>
+            <u:GetPositionInfoResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1">
+            <Track>1</Track><TrackDuration>0:04:05</TrackDuration><RelTime>00:01:02.500</RelTime>
+            </u:GetPositionInfoResponse></s:Body></s:Envelope>"#,
+        );
+        assert_eq!(values["TrackDuration"], "0:04:05");
+        assert_eq!(parse_time(&values["TrackDuration"]), Some(245_000));
+        assert_eq!(parse_time(&values["RelTime"]), Some(62_500));
+        assert_eq!(parse_time("NOT_IMPLEMENTED"), None);
+        assert_eq!(format_time(3_725_900), "1:02:05");
+
+        assert_eq!(detect_audio_mime(b"fLaC\0\0\0\x22"), "audio/flac");
+        assert_eq!(detect_audio_mime(b"\0\0\0\x20ftypM4A "), "audio/mp4");
+        assert_eq!(detect_audio_mime(b"ID3\x04"), "audio/mpeg");
+    }
+
+    #[tokio::test]
+    async fn sends_transport_actions_to_renderer() {
+        let server = serve(|request| async move {
+            let action = request.header("soapaction").unwrap_or_default();
+            if action.ends_with("#Seek\"") {
+                canned(
+                    500,
+                    &[],
+                    "<s:Envelope><s:Body><s:Fault><detail><UPnPError>\
+                     <errorCode>711</errorCode>\
+                     <errorDescription>Illegal seek target</errorDescription>\
+                     </UPnPError></detail></s:Fault></s:Body></s:Envelope>",
+                )
+            } else if action.ends_with("#GetTransportInfo\"") {
+                canned(
+                    200,
+                    &[],
+                    "<r><CurrentTransportState>PLAYING</CurrentTransportState></r>",
+                )
+            } else if action.ends_with("#GetPositionInfo\"") {
+                canned(
+                    200,
+                    &[],
+                    "<r><TrackDuration>0:03:00</TrackDuration><RelTime>0:00:42</RelTime></r>",
+                )
+            } else {
+                canned(200, &[], "<r/>")
+            }
+        });
+
+        let location = server.url("/device.xml");
+        let renderer = parse_device_description(DESCRIPTION, &location).unwrap();
+        let metadata = didl_metadata("http://host/media/a.flac", Some("audio/flac"), None);
+        renderer
+            .load("http://host/media/a.flac", &metadata)
+            .await
+            .unwrap();
+        renderer.play().await.unwrap();
+        renderer.set_volume(35).await.unwrap();
+        let status = renderer.status().await.unwrap();
+        assert_eq!(status.state, "PLAYING");
+        assert_eq!(status.position_ms, Some(42_000));
+        assert_eq!(status.duration_ms, Some(180_000));
+        let error = renderer.seek(10_000).await.unwrap_err();
+        assert!(error.contains("Illegal seek target"), "{}", error);
+
+        let calls: Vec<(String, String)> = server
+            .requests()
+            .iter()
+            .map(|request| {
+                let action = request.header("soapaction").unwrap_or_default();
+                (action.to_string(), request.body_text())
+            })
+            .collect();
+        let actions: Vec<&str> = calls
+            .iter()
+            .map(|(action, _)| action.rsplit('#').next().unwrap().trim_end_matches('"'))
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                "SetAVTransportURI",
+                "Play",
+                "SetVolume",
+                "GetTransportInfo",
+                "GetPositionInfo",
+                "Seek"
+            ]
+        );
+        assert!(calls[0]
+            .0
+            .starts_with("\"urn:schemas-upnp-org:service:AVTransport:1#"));
+        assert!(calls[0]
+            .1
+            .contains("<CurrentURI>http://host/media/a.flac</CurrentURI>"));
+        assert!(calls[0].1.contains("http-get:*:audio/flac:*"));
+        assert!(calls[1].1.contains("<Speed>1</Speed>"));
+        assert!(calls[2].1.contains("<DesiredVolume>35</DesiredVolume>"));
+        assert!(calls[5].1.contains("<Target>0:00:10</Target>"));
+    }
+
+    #[tokio::test]
+    async fn media_server_serves_only_the_registered_file_with_ranges() {
+        let dir = std::env::temp_dir().join(format!("rmusic-dlna-{}", random_token()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("first.flac");
+        let second = dir.join("second.audio");
+        std::fs::write(&first, b"fLaC0123456789").unwrap();
+        std::fs::write(&second, b"ID3abcdefghij").unwrap();
+
+        let server = start_media_server().unwrap();
+        let base = format!("http://127.0.0.1:{}{}", server.port, MEDIA_PATH_PREFIX);
+        let old_token = server.register(&first).unwrap();
+        let token = server.register(&second).unwrap();
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("{}{}.audio", base, token))
+            .header("Range", "bytes=3-5")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 206);
+        assert_eq!(response.headers()["content-type"], "audio/mpeg");
+        assert_eq!(response.headers()["transfermode.dlna.org"], "Streaming");
+        assert_eq!(response.bytes().await.unwrap().as_ref(), b"abc");
+
+        let head = client
+            .head(format!("{}{}.audio", base, token))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(head.status(), 200);
+        assert_eq!(head.headers()["content-length"], "13");
+
+        let replaced = client
+            .get(format!("{}{}.flac", base, old_token))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(replaced.status(), 404);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
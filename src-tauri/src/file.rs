@@ -1,14 +1,17 @@
-use crate::music::MusicFile;
+use crate::cue;
+use crate::music::{CueRange, MusicFile};
 use crate::netease;
-use crate::netease::get_song_url;
+use crate::online_api::OnlineApiState;
+use crate::provider;
+use crate::storage::{commit_temp_file, unique_temp_path_for};
 use rodio::{Decoder, Source};
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, create_dir_all, read_dir, File};
 use std::io::{BufReader, ErrorKind, Write};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, UNIX_EPOCH};
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
@@ -18,7 +21,7 @@ use tauri::Manager;
 use tokio::io::AsyncWriteExt;
 
 const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
-const LIBRARY_INDEX_VERSION: u32 = 3;
+const LIBRARY_INDEX_VERSION: u32 = 4;
 
 #[derive(Serialize, Deserialize)]
 struct LibraryIndex {
@@ -43,7 +46,7 @@ fn supported_audio_extension(path: &Path) -> Option<String> {
         .then_some(extension)
 }
 
-fn path_key(path: &Path) -> String {
+pub(crate) fn path_key(path: &Path) -> String {
     let mut hasher = Sha1::new();
     hasher.update(path.to_string_lossy().as_bytes());
     format!("{:x}", hasher.finalize())
@@ -72,9 +75,9 @@ fn library_index_path(app_handle: &AppHandle, scan_path: &Path) -> Result<PathBu
     Ok(dir.join(format!("{}.json", path_key(scan_path))))
 }
 
-fn read_library_index(index_path: &Path, scan_path: &Path) -> Vec<MusicFile> {
+pub(crate) fn read_library_index(index_path: &Path, scan_path: &Path) -> Vec<MusicFile> {
     read_library_index_value(index_path, scan_path)
-        .filter(|index| index.version == 2 || index.version == LIBRARY_INDEX_VERSION)
+        .filter(|index| (2..=LIBRARY_INDEX_VERSION).contains(&index.version))
         .map(|index| index.files)
         .unwrap_or_default()
 }
@@ -117,7 +120,7 @@ fn write_library_index(
     write_bytes_to_file(&bytes, index_path)
 }
 
-fn modified_ms(path: &Path) -> u64 {
+pub(crate) fn modified_ms(path: &Path) -> u64 {
     path.metadata()
         .and_then(|metadata| metadata.modified())
         .ok()
@@ -243,6 +246,7 @@ fn enrich_music_files(scan_path: &Path, files: &mut [MusicFile], cached_files: &
         .iter()
         .map(|file| (file.relative_path.as_str(), file))
         .collect();
+    let mut source_metadata: HashMap<String, AudioMetadata> = HashMap::new();
 
     for file in files {
         if let Some(cached) = cached_by_path.get(file.relative_path.as_str()) {
@@ -256,6 +260,32 @@ fn enrich_music_files(scan_path: &Path, files: &mut [MusicFile], cached_files: &
             }
         }
 
+        if let Some(cue) = file.cue.clone() {
+            // 分轨标题等来自 CUE 表，缺失的字段和最后一轨的时长用整轨文件的标签补齐
+            let absolute_path = if scan_path.is_file() {
+                scan_path.to_path_buf()
+            } else {
+                scan_path.join(&cue.source_path)
+            };
+            let metadata = source_metadata
+                .entry(cue.source_path.clone())
+                .or_insert_with(|| read_audio_metadata(&absolute_path, &file.extension));
+            if file.title.is_none() {
+                file.title = Some(format!("Track {:02}", cue.track));
+            }
+            if file.artist.is_none() {
+                file.artist.clone_from(&metadata.artist);
+            }
+            if file.album.is_none() {
+                file.album.clone_from(&metadata.album);
+            }
+            if cue.end_ms.is_none() {
+                file.duration_ms = metadata.duration_ms.saturating_sub(cue.start_ms);
+            }
+            rebuild_search_text(file);
+            continue;
+        }
+
         let absolute_path = if scan_path.is_file() {
             scan_path.to_path_buf()
         } else {
@@ -287,25 +317,54 @@ fn music_file_from_path(id: i32, absolute_path: &Path, relative_path: &Path) ->
         artist: None,
         album: None,
         duration_ms: 0,
+        cue: None,
+        path: None,
     })
 }
 
-fn unique_temp_path_for(target_path: &Path) -> PathBuf {
-    let unique = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|duration| duration.as_nanos())
-        .unwrap_or(0);
-    let file_name = target_path
-        .file_name()
-        .and_then(|name| name.to_str())
-        .unwrap_or("download");
-
-    target_path.with_file_name(format!(
-        "{}.{}.{}.tmp",
-        file_name,
-        std::process::id(),
-        unique
-    ))
+/// 有同名 CUE 表的整轨文件替换成按分轨拆开的虚拟曲目；
+/// 虚拟曲目的 file_name/relative_path 为 `原路径#分轨号`，实际音频路径记录在 cue 中
+fn expand_cue_sheets(scan_path: &Path, files: Vec<MusicFile>) -> Vec<MusicFile> {
+    let mut expanded = Vec::with_capacity(files.len());
+    for file in files {
+        let absolute_path = if scan_path.is_file() {
+            scan_path.to_path_buf()
+        } else {
+            scan_path.join(&file.relative_path)
+        };
+        let Some((sheet, tracks, sheet_path)) = cue::tracks_for_audio_file(&absolute_path) else {
+            expanded.push(file);
+            continue;
+        };
+
+        let modified_ms = file.modified_ms.max(modified_ms(&sheet_path));
+        for track in tracks {
+            let name = format!("{}#{:02}", file.relative_path, track.number);
+            let mut virtual_file = MusicFile {
+                file_name: name.clone(),
+                key: format!("{}#{:02}", file.key, track.number),
+                relative_path: name,
+                modified_ms,
+                title: track.title,
+                artist: track.performer.or_else(|| sheet.performer.clone()),
+                album: sheet.title.clone(),
+                duration_ms: track
+                    .end_ms
+                    .map(|end_ms| end_ms.saturating_sub(track.start_ms))
+                    .unwrap_or(0),
+                cue: Some(CueRange {
+                    source_path: file.relative_path.clone(),
+                    track: track.number,
+                    start_ms: track.start_ms,
+                    end_ms: track.end_ms,
+                }),
+                ..file.clone()
+            };
+            rebuild_search_text(&mut virtual_file);
+            expanded.push(virtual_file);
+        }
+    }
+    expanded
 }
 
 async fn write_response_to_file(
@@ -385,15 +444,6 @@ fn available_import_path(target_path: &Path) -> PathBuf {
     unreachable!("unbounded counter should eventually find an available import path")
 }
 
-fn commit_temp_file(tmp_path: &Path, target_path: &Path) -> Result<(), String> {
-    #[cfg(windows)]
-    if target_path.exists() {
-        fs::remove_file(target_path).map_err(|e| format!("replace file: {}", e))?;
-    }
-
-    fs::rename(tmp_path, target_path).map_err(|e| format!("commit file error: {}", e))
-}
-
 fn commit_new_temp_file(tmp_path: &Path, target_path: &Path) -> Result<(), String> {
     fs::hard_link(tmp_path, target_path).map_err(|e| {
         if e.kind() == ErrorKind::AlreadyExists {
@@ -538,15 +588,26 @@ fn scan_directory_incremental(
 
     if can_reuse_listing {
         if let Some(cached_files) = cached_files_by_parent.get(&relative_path) {
+            let mut seen = HashSet::new();
             for cached_file in cached_files {
-                let absolute_path = base_path.join(&cached_file.relative_path);
+                // CUE 分轨还原成整轨文件，之后统一重新拆分
+                let source_path = cached_file
+                    .cue
+                    .as_ref()
+                    .map_or(cached_file.relative_path.as_str(), |cue| {
+                        cue.source_path.as_str()
+                    });
+                if !seen.insert(source_path) {
+                    continue;
+                }
+                let absolute_path = base_path.join(source_path);
                 let Ok(file_metadata) = fs::symlink_metadata(&absolute_path) else {
                     continue;
                 };
                 if !file_metadata.is_file() || file_metadata.file_type().is_symlink() {
                     continue;
                 }
-                let relative = Path::new(&cached_file.relative_path);
+                let relative = Path::new(source_path);
                 if let Some(file) = music_file_from_path(0, &absolute_path, relative) {
                     files.push(file);
                 }
@@ -655,10 +716,6 @@ fn scan_files_incremental(
             &mut music_files,
             &mut directories,
         );
-        music_files.sort_by(|a, b| a.file_name.cmp(&b.file_name));
-        for (index, file) in music_files.iter_mut().enumerate() {
-            file.id = index as i32;
-        }
     } else if supported_audio_extension(scan_path).is_some() {
         if let Some(file_name) = scan_path.file_name() {
             if let Some(file) = music_file_from_path(0, scan_path, Path::new(file_name)) {
@@ -666,9 +723,40 @@ fn scan_files_incremental(
             }
         }
     }
+    let mut music_files = expand_cue_sheets(scan_path, music_files);
+    music_files.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    for (index, file) in music_files.iter_mut().enumerate() {
+        file.id = index as i32;
+    }
     (music_files, directories)
 }
 
+/// 库外的文件或目录（命令行、打开方式传入）：扫描并读取元数据，path 记录绝对路径。
+/// file_name 是前端的曲目标识，这里换成绝对路径，避免与库内同名文件冲突
+pub(crate) fn external_music_files(path: &Path) -> Vec<MusicFile> {
+    let (mut files, _) = scan_files_incremental(path, None);
+    enrich_music_files(path, &mut files, &[]);
+    for file in &mut files {
+        let source_path = file
+            .cue
+            .as_ref()
+            .map_or(file.relative_path.as_str(), |cue| cue.source_path.as_str());
+        let absolute_path = if path.is_file() {
+            path.to_path_buf()
+        } else {
+            path.join(source_path)
+        };
+        let absolute_path = absolute_path.to_string_lossy().to_string();
+        file.file_name = match &file.cue {
+            Some(cue) => format!("{}#{:02}", absolute_path, cue.track),
+            None => absolute_path.clone(),
+        };
+        file.relative_path.clone_from(&file.file_name);
+        file.path = Some(absolute_path);
+    }
+    files
+}
+
 #[tauri::command]
 pub async fn load_cached_music_files(
     path: Option<String>,
@@ -735,9 +823,12 @@ pub async fn download_music(
     song_name: String,
     artist: String,
     default_directory: Option<String>,
+    provider: Option<String>,
 ) -> Result<String, String> {
+    let api = app_handle.state::<OnlineApiState>();
+    let source = provider::get_provider(&api, provider.as_deref())?;
     // 获取歌曲下载URL
-    let song_url = get_song_url(song_hash.clone()).await?;
+    let song_url = source.song_url(song_hash.clone()).await?;
 
     let client = netease::get_client()?;
 
@@ -790,10 +881,7 @@ pub async fn download_music(
     // 下载封面图片
     let base_filename = file_name.replace(".mp3", "");
 
-    use crate::netease::get_song_cover;
-
-    let cover_url_result =
-        get_song_cover(song_hash.clone(), song_name.clone(), artist.clone()).await;
+    let cover_url_result = source.song_cover(song_hash.clone()).await;
     if let Ok(cover_url) = cover_url_result {
         if !cover_url.is_empty() {
             // 2. 下载封面图片
@@ -813,9 +901,7 @@ pub async fn download_music(
         }
     }
     // 3. 尝试下载歌词
-    use crate::netease::get_song_lyric;
-
-    match get_song_lyric(song_hash.clone()).await {
+    match source.song_lyric(song_hash.clone()).await {
         Ok(lyric_content) => {
             if !lyric_content.is_empty() {
                 let lyric_path = lyrics_dir.join(format!("{}.lrc", base_filename));
@@ -859,6 +945,32 @@ fn sidecar_stem(file_name: &str) -> String {
     }
 }
 
+/// 媒体库根目录下 cover/ 中与曲目同名的封面
+pub(crate) fn find_cover_file(base_dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let stem = sidecar_stem(file_name);
+    ["jpg", "jpeg", "png", "webp"]
+        .into_iter()
+        .map(|ext| base_dir.join("cover").join(format!("{}.{}", stem, ext)))
+        .find(|path| path.exists())
+}
+
+pub(crate) fn lyric_file_path(base_dir: &Path, file_name: &str) -> PathBuf {
+    base_dir
+        .join("lyrics")
+        .join(format!("{}.lrc", sidecar_stem(file_name)))
+}
+
+/// 媒体库的扫描目录、媒体根目录（封面 / 歌词）与索引文件，参数含义与 scan_files 一致
+pub(crate) fn library_paths(
+    app_handle: &AppHandle,
+    default_directory: Option<String>,
+) -> Result<(PathBuf, PathBuf, PathBuf), String> {
+    let scan_path = resolve_scan_path(None, default_directory.clone(), app_handle)?;
+    let base_dir = local_media_base_dir(app_handle, default_directory)?;
+    let index_path = library_index_path(app_handle, &scan_path)?;
+    Ok((scan_path, base_dir, index_path))
+}
+
 /// load local cover path for direct asset protocol rendering
 #[tauri::command]
 pub fn load_local_cover_path(
@@ -867,23 +979,16 @@ pub fn load_local_cover_path(
     default_directory: Option<String>,
 ) -> Result<Option<String>, String> {
     let base_dir = local_media_base_dir(&app_handle, default_directory)?;
-    let stem = sidecar_stem(&file_name);
-
-    for ext in ["jpg", "jpeg", "png", "webp"] {
-        let path = base_dir.join("cover").join(format!("{}.{}", stem, ext));
-        if path.exists() {
-            app_handle
-                .asset_protocol_scope()
-                .allow_file(&path)
-                .map_err(|e| format!("allow cover asset path error: {}", e))?;
-            return path
-                .to_str()
-                .map(|path| Some(path.to_string()))
-                .ok_or_else(|| "cover path trans error".to_string());
-        }
-    }
-
-    Ok(None)
+    let Some(path) = find_cover_file(&base_dir, &file_name) else {
+        return Ok(None);
+    };
+    app_handle
+        .asset_protocol_scope()
+        .allow_file(&path)
+        .map_err(|e| format!("allow cover asset path error: {}", e))?;
+    path.to_str()
+        .map(|path| Some(path.to_string()))
+        .ok_or_else(|| "cover path trans error".to_string())
 }
 
 /// load local lyric text without transferring cover bytes
@@ -894,9 +999,7 @@ pub fn load_local_lyric(
     default_directory: Option<String>,
 ) -> Result<String, String> {
     let base_dir = local_media_base_dir(&app_handle, default_directory)?;
-    let lyrics_path = base_dir
-        .join("lyrics")
-        .join(format!("{}.lrc", sidecar_stem(&file_name)));
+    let lyrics_path = lyric_file_path(&base_dir, &file_name);
 
     if !lyrics_path.exists() {
         return Ok(String::new());
@@ -0,0 +1,663 @@
+// 播放历史：播放达到收听阈值后由后端追加到 app_data_dir/play-history.jsonl，并提供统计查询
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::music::{MusicFile, NowPlayingState, PlaybackTrack};
+use crate::netease::SongInfo;
+use crate::provider;
+use crate::remote_source::RemoteSong;
+use crate::scrobble;
+use crate::storage::app_data_file;
+use crate::timestamp::now_ms;
+use crate::user_meta::{self, ListenOutcome, TrackRef};
+
+const PLAY_HISTORY_FILE: &str = "play-history.jsonl";
+/// 短于该时长的曲目不计入历史
+const MIN_RECORDABLE_TRACK: Duration = Duration::from_secs(30);
+/// 收听达到曲目时长的一半或该上限（取较小者）即视为一次有效播放
+const MAX_LISTEN_THRESHOLD: Duration = Duration::from_secs(240);
+/// 两次轮询之间位置前进超过该值视为拖动进度，不计入收听时长
+const MAX_POSITION_STEP: Duration = Duration::from_secs(2);
+const DEFAULT_QUERY_LIMIT: usize = 50;
+
+static PLAY_HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum HistoryItem {
+    Local {
+        key: String,
+        file_name: String,
+        title: Option<String>,
+        artist: Option<String>,
+        album: Option<String>,
+    },
+    Online {
+        song: SongInfo,
+    },
+    Remote {
+        song: RemoteSong,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayHistoryEntry {
+    #[serde(flatten)]
+    pub item: HistoryItem,
+    pub played_at_ms: u64,
+    pub listened_ms: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct HistoryRange {
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrackStat {
+    #[serde(flatten)]
+    pub item: HistoryItem,
+    pub play_count: u64,
+    pub listened_ms: u64,
+    pub last_played_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArtistStat {
+    pub artist: String,
+    pub play_count: u64,
+    pub listened_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlbumStat {
+    pub album: String,
+    pub artist: String,
+    pub play_count: u64,
+    pub listened_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListeningTotals {
+    pub play_count: u64,
+    pub listened_ms: u64,
+}
+
+/// 单次播放的收听会话：由播放结束监控累计收听时长，曲目结束或被替换时写入历史
+pub struct ListenSession {
+    pub track: PlaybackTrack,
+    pub started_at_ms: u64,
+    pub duration_ms: u64,
+    /// 本地曲目的绝对路径，供用户元数据计算内容指纹
+    pub local_path: Option<String>,
+    listened_ms: AtomicU64,
+    finished: AtomicBool,
+    skipped: AtomicBool,
+}
+
+impl ListenSession {
+    pub fn new(track: PlaybackTrack, duration_ms: u64) -> Self {
+        Self {
+            track,
+            started_at_ms: now_ms(),
+            duration_ms,
+            local_path: None,
+            listened_ms: AtomicU64::new(0),
+            finished: AtomicBool::new(false),
+            skipped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn with_local_path(mut self, local_path: Option<String>) -> Self {
+        self.local_path = local_path;
+        self
+    }
+
+    /// 曲目被切换（而不是自然播放结束）时调用；未达到收听阈值则计为一次跳过
+    pub fn mark_skipped(&self) {
+        self.skipped.store(true, Ordering::SeqCst);
+    }
+
+    /// 根据相邻两次轮询的位置累计收听时长，跳跃（拖动进度）不计入
+    pub fn observe_progress(&self, previous_position_ms: u64, position_ms: u64) {
+        let delta = position_ms.saturating_sub(previous_position_ms);
+        if delta > 0 && delta <= MAX_POSITION_STEP.as_millis() as u64 {
+            self.listened_ms.fetch_add(delta, Ordering::SeqCst);
+        }
+    }
+
+    pub fn listened_ms(&self) -> u64 {
+        self.listened_ms.load(Ordering::SeqCst)
+    }
+
+    pub fn passed_listen_threshold(&self) -> bool {
+        passes_listen_threshold(self.effective_duration_ms(), self.listened_ms())
+    }
+
+    fn effective_duration_ms(&self) -> u64 {
+        if self.duration_ms > 0 {
+            self.duration_ms
+        } else {
+            self.track.duration_ms()
+        }
+    }
+
+    /// 标记会话结束；只有第一次调用返回 true
+    fn mark_finished(&self) -> bool {
+        !self.finished.swap(true, Ordering::SeqCst)
+    }
+}
+
+/// 时长未知时按上限阈值判断
+pub(crate) fn passes_listen_threshold(duration_ms: u64, listened_ms: u64) -> bool {
+    let max_threshold = MAX_LISTEN_THRESHOLD.as_millis() as u64;
+    if duration_ms == 0 {
+        return listened_ms >= max_threshold;
+    }
+    if duration_ms < MIN_RECORDABLE_TRACK.as_millis() as u64 {
+        return false;
+    }
+    listened_ms >= (duration_ms / 2).min(max_threshold)
+}
+
+impl From<&PlaybackTrack> for HistoryItem {
+    fn from(track: &PlaybackTrack) -> Self {
+        match track {
+            PlaybackTrack::Local { file } => HistoryItem::Local {
+                key: file.key.clone(),
+                file_name: file.file_name.clone(),
+                title: file.title.clone(),
+                artist: file.artist.clone(),
+                album: file.album.clone(),
+            },
+            PlaybackTrack::Online { song } => HistoryItem::Online { song: song.clone() },
+            PlaybackTrack::Remote { song } => HistoryItem::Remote { song: song.clone() },
+        }
+    }
+}
+
+impl HistoryItem {
+    fn key(&self) -> String {
+        match self {
+            HistoryItem::Local { key, .. } => format!("local:{}", key),
+            HistoryItem::Online { song } => {
+                format!(
+                    "online:{}",
+                    provider::song_cache_key(&song.provider, &song.id)
+                )
+            }
+            HistoryItem::Remote { song } => {
+                format!("remote:{}:{}", song.server_id, song.song.id)
+            }
+        }
+    }
+
+    fn artists(&self) -> Vec<String> {
+        match self {
+            HistoryItem::Local { artist, .. } => artist.iter().cloned().collect(),
+            HistoryItem::Online { song } => song.artists.clone(),
+            HistoryItem::Remote { song } => song.song.artists.clone(),
+        }
+    }
+
+    fn album(&self) -> Option<&str> {
+        match self {
+            HistoryItem::Local { album, .. } => album.as_deref(),
+            HistoryItem::Online { song } => Some(song.album.as_str()),
+            HistoryItem::Remote { song } => Some(song.song.album.as_str()),
+        }
+        .filter(|album| !album.trim().is_empty())
+    }
+}
+
+fn append_history_entry(path: &Path, entry: &PlayHistoryEntry) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create app_data_dir: {}", e))?;
+    }
+    let mut line =
+        serde_json::to_string(entry).map_err(|e| format!("serialize play history: {}", e))?;
+    line.push('\n');
+
+    let _guard = PLAY_HISTORY_LOCK
+        .lock()
+        .map_err(|_| "play history lock poisoned".to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("open play history: {}", e))?;
+    file.write_all(line.as_bytes())
+        .map_err(|e| format!("write play history: {}", e))
+}
+
+/// 逐行读取历史；损坏的行（例如写入中途断电）直接跳过
+fn read_history_entries(path: &Path) -> Result<Vec<PlayHistoryEntry>, String> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let _guard = PLAY_HISTORY_LOCK
+        .lock()
+        .map_err(|_| "play history lock poisoned".to_string())?;
+    let file = File::open(path).map_err(|e| format!("open play history: {}", e))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<PlayHistoryEntry>(&line).ok())
+        .collect())
+}
+
+fn entries_in_range(entries: Vec<PlayHistoryEntry>, range: HistoryRange) -> Vec<PlayHistoryEntry> {
+    entries
+        .into_iter()
+        .filter(|entry| range.from_ms.is_none_or(|from| entry.played_at_ms >= from))
+        .filter(|entry| range.to_ms.is_none_or(|to| entry.played_at_ms < to))
+        .collect()
+}
+
+/// 结束收听会话：达到阈值则写入历史。重复调用是安全的。
+pub fn finish_listen(app_handle: &AppHandle, session: &ListenSession) -> Option<PlayHistoryEntry> {
+    if !session.mark_finished() {
+        return None;
+    }
+    let track_ref = TrackRef::from_playback_track(&session.track, session.local_path.as_deref());
+    if !session.passed_listen_threshold() {
+        if session.skipped.load(Ordering::SeqCst) {
+            user_meta::record_listen(app_handle, &track_ref, ListenOutcome::Skipped);
+        }
+        return None;
+    }
+    user_meta::record_listen(
+        app_handle,
+        &track_ref,
+        ListenOutcome::Played {
+            at_ms: session.started_at_ms,
+        },
+    );
+
+    let entry = PlayHistoryEntry {
+        item: HistoryItem::from(&session.track),
+        played_at_ms: session.started_at_ms,
+        listened_ms: session.listened_ms(),
+        duration_ms: session.effective_duration_ms(),
+    };
+    let result = app_data_file(app_handle, PLAY_HISTORY_FILE)
+        .and_then(|path| append_history_entry(&path, &entry));
+    if let Err(e) = result {
+        eprintln!("Failed to record play history: {}", e);
+        return None;
+    }
+    scrobble::enqueue_listen(app_handle, &entry);
+    Some(entry)
+}
+
+/// 在阻塞线程池里结算收听会话，避免历史、用户元数据与 scrobble 队列的文件读写占住异步任务
+pub async fn finish_listen_blocking(app_handle: AppHandle, session: Arc<ListenSession>) {
+    let result =
+        tauri::async_runtime::spawn_blocking(move || finish_listen(&app_handle, &session)).await;
+    if let Err(e) = result {
+        eprintln!("Failed to join listen session task: {}", e);
+    }
+}
+
+/// 外部客户端（如 Subsonic 客户端的 scrobble）上报的完整播放：按整首收听写入历史并提交 scrobble
+pub(crate) fn record_external_play(
+    app_handle: &AppHandle,
+    file: &MusicFile,
+    local_path: &Path,
+    played_at_ms: u64,
+) -> Result<(), String> {
+    let track = PlaybackTrack::Local { file: file.clone() };
+    let local_path = local_path.to_string_lossy();
+    user_meta::record_listen(
+        app_handle,
+        &TrackRef::from_playback_track(&track, Some(local_path.as_ref())),
+        ListenOutcome::Played {
+            at_ms: played_at_ms,
+        },
+    );
+    let entry = PlayHistoryEntry {
+        item: HistoryItem::from(&track),
+        played_at_ms,
+        listened_ms: file.duration_ms,
+        duration_ms: file.duration_ms,
+    };
+    append_history_entry(&app_data_file(app_handle, PLAY_HISTORY_FILE)?, &entry)?;
+    scrobble::enqueue_listen(app_handle, &entry);
+    Ok(())
+}
+
+/// 退出应用前结算当前曲目的收听时长
+pub fn finish_current_listen(app_handle: &AppHandle) {
+    let Some(now_playing) = app_handle.try_state::<NowPlayingState>() else {
+        return;
+    };
+    let session = now_playing
+        .0
+        .lock()
+        .ok()
+        .and_then(|current| current.clone());
+    if let Some(session) = session {
+        finish_listen(app_handle, &session);
+    }
+}
+
+fn top_tracks(entries: &[PlayHistoryEntry], limit: usize) -> Vec<TrackStat> {
+    let mut stats: HashMap<String, TrackStat> = HashMap::new();
+    for entry in entries {
+        let stat = stats.entry(entry.item.key()).or_insert_with(|| TrackStat {
+            item: entry.item.clone(),
+            play_count: 0,
+            listened_ms: 0,
+            last_played_ms: 0,
+        });
+        stat.play_count += 1;
+        stat.listened_ms += entry.listened_ms;
+        if entry.played_at_ms >= stat.last_played_ms {
+            // 保留最近一次记录的元数据（标签可能被修改过）
+            stat.item = entry.item.clone();
+            stat.last_played_ms = entry.played_at_ms;
+        }
+    }
+    let mut stats: Vec<TrackStat> = stats.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.play_count
+            .cmp(&a.play_count)
+            .then(b.listened_ms.cmp(&a.listened_ms))
+            .then(b.last_played_ms.cmp(&a.last_played_ms))
+    });
+    stats.truncate(limit);
+    stats
+}
+
+fn top_artists(entries: &[PlayHistoryEntry], limit: usize) -> Vec<ArtistStat> {
+    let mut stats: HashMap<String, ArtistStat> = HashMap::new();
+    for entry in entries {
+        for artist in entry.item.artists() {
+            let artist = artist.trim();
+            if artist.is_empty() {
+                continue;
+            }
+            let stat = stats
+                .entry(artist.to_lowercase())
+                .or_insert_with(|| ArtistStat {
+                    artist: artist.to_string(),
+                    play_count: 0,
+                    listened_ms: 0,
+                });
+            stat.play_count += 1;
+            stat.listened_ms += entry.listened_ms;
+        }
+    }
+    let mut stats: Vec<ArtistStat> = stats.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.play_count
+            .cmp(&a.play_count)
+            .then(b.listened_ms.cmp(&a.listened_ms))
+            .then(a.artist.cmp(&b.artist))
+    });
+    stats.truncate(limit);
+    stats
+}
+
+fn top_albums(entries: &[PlayHistoryEntry], limit: usize) -> Vec<AlbumStat> {
+    let mut stats: HashMap<(String, String), AlbumStat> = HashMap::new();
+    for entry in entries {
+        let Some(album) = entry.item.album() else {
+            continue;
+        };
+        let artist = entry.item.artists().into_iter().next().unwrap_or_default();
+        let stat = stats
+            .entry((album.to_lowercase(), artist.to_lowercase()))
+            .or_insert_with(|| AlbumStat {
+                album: album.to_string(),
+                artist: artist.clone(),
+                play_count: 0,
+                listened_ms: 0,
+            });
+        stat.play_count += 1;
+        stat.listened_ms += entry.listened_ms;
+    }
+    let mut stats: Vec<AlbumStat> = stats.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.play_count
+            .cmp(&a.play_count)
+            .then(b.listened_ms.cmp(&a.listened_ms))
+            .then(a.album.cmp(&b.album))
+    });
+    stats.truncate(limit);
+    stats
+}
+
+fn recently_played(entries: Vec<PlayHistoryEntry>, limit: usize) -> Vec<PlayHistoryEntry> {
+    let mut seen = HashSet::new();
+    let mut entries = entries;
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.played_at_ms));
+    entries
+        .into_iter()
+        .filter(|entry| seen.insert(entry.item.key()))
+        .take(limit)
+        .collect()
+}
+
+fn load_entries(
+    app_handle: &AppHandle,
+    range: HistoryRange,
+) -> Result<Vec<PlayHistoryEntry>, String> {
+    let path = app_data_file(app_handle, PLAY_HISTORY_FILE)?;
+    Ok(entries_in_range(read_history_entries(&path)?, range))
+}
+
+/// 最近播放（按曲目去重，最新在前）
+#[tauri::command]
+pub async fn get_recently_played(
+    app_handle: AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<PlayHistoryEntry>, String> {
+    tokio::task::spawn_blocking(move || {
+        let entries = load_entries(&app_handle, HistoryRange::default())?;
+        Ok(recently_played(
+            entries,
+            limit.unwrap_or(DEFAULT_QUERY_LIMIT),
+        ))
+    })
+    .await
+    .map_err(|e| format!("play history task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_top_tracks(
+    app_handle: AppHandle,
+    range: Option<HistoryRange>,
+    limit: Option<usize>,
+) -> Result<Vec<TrackStat>, String> {
+    tokio::task::spawn_blocking(move || {
+        let entries = load_entries(&app_handle, range.unwrap_or_default())?;
+        Ok(top_tracks(&entries, limit.unwrap_or(DEFAULT_QUERY_LIMIT)))
+    })
+    .await
+    .map_err(|e| format!("play history task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_top_artists(
+    app_handle: AppHandle,
+    range: Option<HistoryRange>,
+    limit: Option<usize>,
+) -> Result<Vec<ArtistStat>, String> {
+    tokio::task::spawn_blocking(move || {
+        let entries = load_entries(&app_handle, range.unwrap_or_default())?;
+        Ok(top_artists(&entries, limit.unwrap_or(DEFAULT_QUERY_LIMIT)))
+    })
+    .await
+    .map_err(|e| format!("play history task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_top_albums(
+    app_handle: AppHandle,
+    range: Option<HistoryRange>,
+    limit: Option<usize>,
+) -> Result<Vec<AlbumStat>, String> {
+    tokio::task::spawn_blocking(move || {
+        let entries = load_entries(&app_handle, range.unwrap_or_default())?;
+        Ok(top_albums(&entries, limit.unwrap_or(DEFAULT_QUERY_LIMIT)))
+    })
+    .await
+    .map_err(|e| format!("play history task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_listening_totals(
+    app_handle: AppHandle,
+    range: Option<HistoryRange>,
+) -> Result<ListeningTotals, String> {
+    tokio::task::spawn_blocking(move || {
+        let entries = load_entries(&app_handle, range.unwrap_or_default())?;
+        Ok(ListeningTotals {
+            play_count: entries.len() as u64,
+            listened_ms: entries.iter().map(|entry| entry.listened_ms).sum(),
+        })
+    })
+    .await
+    .map_err(|e| format!("play history task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn online_song(id: &str, artist: &str, album: &str) -> SongInfo {
+        SongInfo {
+            id: id.into(),
+            name: format!("Song {}", id),
+            artists: vec![artist.into()],
+            album: album.into(),
+            duration: 200_000,
+            pic_url: String::new(),
+            file_hash: id.into(),
+            provider: "netease".into(),
+        }
+    }
+
+    fn online_entry(id: &str, artist: &str, album: &str, played_at_ms: u64) -> PlayHistoryEntry {
+        PlayHistoryEntry {
+            item: HistoryItem::Online {
+                song: online_song(id, artist, album),
+            },
+            played_at_ms,
+            listened_ms: 100_000,
+            duration_ms: 200_000,
+        }
+    }
+
+    #[test]
+    fn listen_threshold_uses_half_duration_capped_at_four_minutes() {
+        assert!(!passes_listen_threshold(20_000, 20_000));
+        assert!(!passes_listen_threshold(180_000, 89_999));
+        assert!(passes_listen_threshold(180_000, 90_000));
+        assert!(!passes_listen_threshold(600_000, 239_999));
+        assert!(passes_listen_threshold(600_000, 240_000));
+        assert!(passes_listen_threshold(0, 240_000));
+    }
+
+    #[test]
+    fn listen_session_ignores_seek_jumps() {
+        let session = ListenSession::new(
+            PlaybackTrack::Online {
+                song: online_song("1", "A", "X"),
+            },
+            200_000,
+        );
+        session.observe_progress(0, 250);
+        session.observe_progress(250, 500);
+        session.observe_progress(500, 60_000);
+        session.observe_progress(60_000, 10_000);
+        assert_eq!(session.listened_ms(), 500);
+    }
+
+    #[test]
+    fn history_file_round_trips_and_skips_corrupt_lines() {
+        let dir = std::env::temp_dir().join(format!(
+            "rmusic-history-{}-{}",
+            std::process::id(),
+            now_ms()
+        ));
+        let path = dir.join(PLAY_HISTORY_FILE);
+        append_history_entry(&path, &online_entry("1", "A", "X", 10)).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{not json\n")
+            .unwrap();
+        append_history_entry(&path, &online_entry("2", "B", "Y", 20)).unwrap();
+
+        let entries = read_history_entries(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        let in_range = entries_in_range(
+            entries,
+            HistoryRange {
+                from_ms: Some(15),
+                to_ms: None,
+            },
+        );
+        assert_eq!(in_range.len(), 1);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn statistics_rank_by_play_count() {
+        let entries = vec![
+            online_entry("1", "A", "X", 10),
+            online_entry("2", "B", "Y", 20),
+            online_entry("1", "A", "X", 30),
+            online_entry("3", "a", "X", 40),
+        ];
+
+        let tracks = top_tracks(&entries, 10);
+        assert_eq!(tracks[0].play_count, 2);
+        assert_eq!(tracks[0].last_played_ms, 30);
+
+        let artists = top_artists(&entries, 1);
+        assert_eq!(artists.len(), 1);
+        assert_eq!(artists[0].play_count, 3);
+
+        let albums = top_albums(&entries, 10);
+        assert_eq!(albums[0].album, "X");
+        assert_eq!(albums[0].play_count, 3);
+
+        let recent = recently_played(entries, 10);
+        let ids: Vec<_> = recent
+            .iter()
+            .map(|entry| match &entry.item {
+                HistoryItem::Online { song } => song.id.clone(),
+                HistoryItem::Remote { song } => song.song.id.clone(),
+                HistoryItem::Local { key, .. } => key.clone(),
+            })
+            .collect();
+        assert_eq!(ids, vec!["3", "1", "2"]);
+    }
+
+    #[test]
+    fn online_keys_include_the_provider() {
+        let netease = online_entry("1", "A", "X", 10);
+        let mut mock = online_entry("1", "A", "X", 20);
+        if let HistoryItem::Online { song } = &mut mock.item {
+            song.provider = "mock".into();
+        }
+        assert_eq!(netease.item.key(), "online:1");
+        assert_eq!(mock.item.key(), "online:mock:1");
+        assert_eq!(top_tracks(&[netease, mock], 10).len(), 2);
+    }
+}
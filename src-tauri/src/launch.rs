@@ -0,0 +1,203 @@
+// 命令行与“打开方式”传入的文件：解析成播放 / 加入队列请求。
+// 首次启动时暂存到前端就绪后领取，已运行时由单实例回调通过事件推送
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex as StdMutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::file::external_music_files;
+use crate::music::MusicFile;
+
+#[derive(Clone, Copy, Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenMode {
+    #[default]
+    Play,
+    Enqueue,
+}
+
+#[derive(Clone, Serialize, Debug)]
+pub struct OpenFilesRequest {
+    pub mode: OpenMode,
+    pub files: Vec<MusicFile>,
+}
+
+#[derive(Default)]
+struct PendingOpenFilesInner {
+    /// 前端已经领取过一次，之后的请求直接走事件
+    frontend_ready: bool,
+    pending: Option<OpenFilesRequest>,
+}
+
+#[derive(Default)]
+pub struct PendingOpenFiles(StdMutex<PendingOpenFilesInner>);
+
+fn is_playlist(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("m3u") || ext.eq_ignore_ascii_case("m3u8"))
+}
+
+fn file_uri_path(value: &str) -> Option<PathBuf> {
+    let rest = value.strip_prefix("file://")?;
+    // file:///C:/Music/a.mp3 在 Windows 上需要去掉开头的斜杠
+    let rest = match rest.strip_prefix('/') {
+        Some(stripped) if stripped.get(1..2) == Some(":") => stripped,
+        _ => rest,
+    };
+    let decoded = urlencoding::decode(rest).ok()?;
+    Some(PathBuf::from(decoded.into_owned()))
+}
+
+pub(crate) fn resolve_path(value: &str, base: &Path) -> PathBuf {
+    let path = file_uri_path(value).unwrap_or_else(|| PathBuf::from(value));
+    if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    }
+}
+
+/// 解析 argv（第一个元素是程序路径）：`--enqueue`/`-e` 加入队列，`--play` 立即播放，其余参数视为路径；
+/// 其他以 - 开头的参数（如开机自启附带的参数）忽略
+pub fn parse_args(args: &[String], cwd: &Path) -> (OpenMode, Vec<PathBuf>) {
+    let mut mode = OpenMode::Play;
+    let mut paths = Vec::new();
+    for arg in args.iter().skip(1) {
+        match arg.as_str() {
+            "--enqueue" | "-e" => mode = OpenMode::Enqueue,
+            "--play" => mode = OpenMode::Play,
+            flag if flag.starts_with('-') => {}
+            value if !value.trim().is_empty() => paths.push(resolve_path(value, cwd)),
+            _ => {}
+        }
+    }
+    (mode, paths)
+}
+
+/// M3U / M3U8 中的本地条目，相对路径以播放列表所在目录为基准；网络地址跳过
+pub fn read_m3u(path: &Path) -> Vec<PathBuf> {
+    let Ok(bytes) = fs::read(path) else {
+        return Vec::new();
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('\u{feff}'))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| line.starts_with("file://") || !line.contains("://"))
+        .map(|line| resolve_path(line, base))
+        .collect()
+}
+
+fn resolve_files(paths: &[PathBuf]) -> Vec<MusicFile> {
+    let mut files = Vec::new();
+    for path in paths {
+        if is_playlist(path) {
+            for entry in read_m3u(path) {
+                files.extend(external_music_files(&entry));
+            }
+        } else {
+            files.extend(external_music_files(path));
+        }
+    }
+    files
+}
+
+fn deliver(app_handle: &AppHandle, request: OpenFilesRequest) {
+    let Some(state) = app_handle.try_state::<PendingOpenFiles>() else {
+        return;
+    };
+    let Ok(mut inner) = state.0.lock() else {
+        return;
+    };
+    if inner.frontend_ready {
+        drop(inner);
+        let _ = app_handle.emit("open-files", request);
+    } else {
+        inner.pending = Some(request);
+    }
+}
+
+/// 在后台线程解析文件列表（目录可能很大），完成后交给前端
+pub fn open_paths(app_handle: &AppHandle, mode: OpenMode, paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let files = resolve_files(&paths);
+        if files.is_empty() {
+            eprintln!("No playable files in launch arguments: {:?}", paths);
+            return;
+        }
+        deliver(&app_handle, OpenFilesRequest { mode, files });
+    });
+}
+
+pub fn open_args(app_handle: &AppHandle, args: &[String], cwd: &Path) {
+    let (mode, paths) = parse_args(args, cwd);
+    open_paths(app_handle, mode, paths);
+}
+
+/// 前端启动完成后领取首次启动时传入的文件；之后的请求通过 open-files 事件推送
+#[tauri::command]
+pub fn take_pending_open_files(
+    state: tauri::State<'_, PendingOpenFiles>,
+) -> Result<Option<OpenFilesRequest>, String> {
+    let mut inner = state
+        .0
+        .lock()
+        .map_err(|_| "pending open files state poisoned".to_string())?;
+    inner.frontend_ready = true;
+    Ok(inner.pending.take())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_modes_paths_and_ignores_unknown_flags() {
+        let cwd = Path::new("/home/user/Music");
+        let (mode, paths) = parse_args(
+            &args(&["rmusic", "--flag1", "song.flac", "/tmp/other.mp3"]),
+            cwd,
+        );
+        assert_eq!(mode, OpenMode::Play);
+        assert_eq!(
+            paths,
+            vec![cwd.join("song.flac"), PathBuf::from("/tmp/other.mp3")]
+        );
+
+        let (mode, paths) = parse_args(&args(&["rmusic", "-e", "file:///tmp/My%20Song.mp3"]), cwd);
+        assert_eq!(mode, OpenMode::Enqueue);
+        assert_eq!(paths, vec![PathBuf::from("/tmp/My Song.mp3")]);
+
+        assert!(parse_args(&args(&["rmusic"]), cwd).1.is_empty());
+    }
+
+    #[test]
+    fn reads_local_entries_from_m3u() {
+        let dir = std::env::temp_dir().join(format!("rmusic-m3u-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let playlist = dir.join("list.m3u8");
+        fs::write(
+            &playlist,
+            "\u{feff}#EXTM3U\n#EXTINF:123,Artist - Title\na.mp3\n\nhttp://radio.example/stream\n/abs/b.flac\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_m3u(&playlist),
+            vec![dir.join("a.mp3"), PathBuf::from("/abs/b.flac")]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,30 +1,119 @@
+use audio_output::{
+    get_channel_options, get_output_device, list_output_devices, restore_output_device,
+    set_channel_options, set_output_device, start_output_watchdog,
+};
+use bookmarks::{
+    add_bookmark, clear_ab_loop, get_ab_loop, get_track_bookmarks, jump_to_bookmark,
+    remove_bookmark, rename_bookmark, set_loop_point, AbLoopState,
+};
+use dlna::{
+    discover_dlna_renderers, get_playback_output, set_playback_output, start_dlna_output, DlnaState,
+};
 use file::{
     download_music, get_default_music_dir, import_music, load_cached_music_files,
     load_local_cover_path, load_local_lyric, scan_files,
 };
+use history::{
+    get_listening_totals, get_recently_played, get_top_albums, get_top_artists, get_top_tracks,
+};
+use launch::{take_pending_open_files, PendingOpenFiles};
 use music::{
     clear_online_audio_cache, get_online_audio_cache_path, get_online_audio_cache_size,
     get_playback_state, play_track, prefetch_netease_song, prepare_playback_request, seek_to,
-    Music, MusicState, PlaybackRequestIdState,
+    Music, MusicState, NowPlayingState, PlaybackRequestIdState, PlaybackVolumeState,
+};
+use netease::check_online_service_status;
+use online_api::{get_online_api_settings, set_online_api_settings, OnlineApiState};
+use playlist::{
+    add_playlist_items, create_playlist, delete_playlist, duplicate_playlist, read_playlists,
+    remove_playlist_items, rename_playlist, reorder_playlist_item,
+};
+use podcast::{
+    delete_podcast_download, download_podcast_episode, get_podcast_settings, get_podcasts,
+    refresh_podcasts, set_podcast_episode_played, set_podcast_playback_rate, set_podcast_settings,
+    start_podcast_refresh, subscribe_podcast, unsubscribe_podcast,
+};
+use provider::{
+    get_artist_top_songs, get_online_album, get_song_cover, get_song_lyric, get_song_url,
+    play_netease_song, search_online_mix, search_songs,
+};
+use radio::{
+    add_radio_station, get_radio_stations, import_radio_stations, remove_radio_station,
+    rename_radio_station,
+};
+use remote_api::{
+    get_remote_api_settings, get_remote_api_status, set_remote_api_settings, start_remote_api,
+    RemoteApiState,
 };
-use netease::{
-    check_online_service_status, get_artist_top_songs, get_song_cover, get_song_lyric,
-    get_song_url, play_netease_song, search_online_mix, search_songs,
+use remote_control::start_remote_control;
+use remote_source::{
+    delete_remote_server, get_remote_album, get_remote_album_list, get_remote_artist,
+    get_remote_servers, save_remote_server, search_remote_songs,
+};
+use scrobble::{
+    clear_scrobble_queue, flush_scrobble_queue, get_scrobble_status, get_scrobbler_settings,
+    lastfm_authenticate, set_scrobbler_settings, start_scrobbler, ScrobblerState,
+};
+use service::{
+    ensure_online_service, get_online_service_logs, restart_online_service, OnlineServiceProcess,
+};
+use session::{restore_session, start_session_autosave, update_session_queue, SessionState};
+use sleep_timer::{cancel_sleep_timer, get_sleep_timer_status, start_sleep_timer, SleepTimerState};
+use std::path::Path;
+use subsonic::{
+    get_subsonic_server_settings, get_subsonic_server_status, set_subsonic_server_settings,
+    start_subsonic_server, SubsonicServerState,
 };
-use playlist::{read_playlists, write_playlists};
-use service::{ensure_online_service, restart_online_service, OnlineServiceProcess};
 use tauri::Manager;
 use tauri_plugin_autostart::MacosLauncher;
 use tauri_plugin_window_state::{StateFlags, WindowExt};
+use time_stretch::PlaybackRateState;
 use tokio::sync::broadcast::Sender;
 use tray::{quit_app as quit_app_handle, setup_tray};
+use user_meta::{
+    get_all_user_metadata, get_track_user_metadata, get_user_metadata_settings,
+    set_user_metadata_settings, update_track_user_metadata,
+};
+use visualizer::{start_visualizer, subscribe_visualizer, unsubscribe_visualizer, VisualizerState};
+use waveform::get_waveform_peaks;
 
+mod audio_output;
+mod bookmarks;
+mod cue;
+mod dlna;
 mod file;
+mod history;
+mod launch;
+mod local_server;
+#[cfg(any(test, debug_assertions))]
+mod mock_provider;
+#[cfg(target_os = "linux")]
+mod mpris;
 mod music;
 mod netease;
+mod online_api;
 mod playlist;
+mod podcast;
+mod provider;
+mod radio;
+mod rating_tags;
+mod remote_api;
+mod remote_control;
+mod remote_source;
+mod scrobble;
 mod service;
+mod session;
+mod sleep_timer;
+mod storage;
+mod subsonic;
+#[cfg(test)]
+mod test_server;
+mod time_stretch;
+mod timestamp;
 mod tray;
+mod user_meta;
+mod visualizer;
+mod waveform;
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -32,22 +121,35 @@ enum PlaybackControlAction {
     Play,
     Pause,
     Volume,
+    Rate,
 }
 
 /// Handle playback control actions that do not start a new track.
 #[tauri::command]
 fn control_playback(
     sender: tauri::State<Sender<MusicState>>,
+    volume_state: tauri::State<PlaybackVolumeState>,
+    rate_state: tauri::State<PlaybackRateState>,
     action: PlaybackControlAction,
     volume: Option<f32>,
+    rate: Option<f32>,
+    preserve_pitch: Option<bool>,
 ) -> Result<(), String> {
     let music_state = match action {
         PlaybackControlAction::Play => MusicState::Recovery,
         PlaybackControlAction::Pause => MusicState::Pause,
         PlaybackControlAction::Volume => {
             let volume = volume.ok_or_else(|| "Missing volume".to_string())?;
+            if let Ok(mut current) = volume_state.0.lock() {
+                *current = Some(volume);
+            }
             MusicState::Volume(volume)
         }
+        PlaybackControlAction::Rate => MusicState::PlaybackRate {
+            rate: rate.ok_or_else(|| "Missing rate".to_string())?,
+            // 未指定时沿用当前的音高设置
+            preserve_pitch: preserve_pitch.unwrap_or_else(|| rate_state.preserve_pitch()),
+        },
     };
 
     sender
@@ -63,6 +165,16 @@ fn quit_app(app_handle: tauri::AppHandle) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // `rmusic ctl ...` 只把命令转发给正在运行的实例，不初始化音频也不打开窗口
+    let context = tauri::generate_context!();
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("ctl") {
+        std::process::exit(remote_control::run_ctl(
+            &context.config().identifier,
+            &args[2..],
+        ));
+    }
+
     let music = match Music::new() {
         Ok(music) => music,
         Err(e) => {
@@ -80,7 +192,9 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_window_state::Builder::new().build())
         .manage(OnlineServiceProcess::default())
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+            // 已在运行时再次打开文件（双击、打开方式、命令行），交给当前实例处理
+            launch::open_args(app, &args, Path::new(&cwd));
             let window = app
                 .get_webview_window("main")
                 .expect("failed to get main window");
@@ -92,6 +206,16 @@ pub fn run() {
                 eprintln!("Failed to focus main window: {}", e);
             }
         }))
+        .register_asynchronous_uri_scheme_protocol(
+            remote_source::COVER_SCHEME,
+            |context, request, responder| {
+                let app_handle = context.app_handle().clone();
+                let path = request.uri().path().to_string();
+                tauri::async_runtime::spawn(async move {
+                    responder.respond(remote_source::cover_response(&app_handle, &path).await);
+                });
+            },
+        )
         .setup(|app| {
             // setup the tray icon
             if let Err(e) = setup_tray(app) {
@@ -107,6 +231,29 @@ pub fn run() {
                 })
                 .expect("failed to get main window");
 
+            // 离线队列与设置需要 app_data_dir，放在 setup 中加载
+            app.manage(OnlineApiState::load(app.handle()));
+            app.manage(ScrobblerState::load(app.handle()));
+            start_scrobbler(app.handle());
+            start_session_autosave(app.handle());
+            start_podcast_refresh(app.handle());
+            restore_output_device(app.handle());
+            start_output_watchdog(app.handle());
+            start_visualizer(app.handle());
+            start_dlna_output(app.handle());
+            start_remote_control(app.handle());
+            app.manage(RemoteApiState::load(app.handle()));
+            start_remote_api(app.handle());
+            app.manage(SubsonicServerState::load(app.handle()));
+            start_subsonic_server(app.handle());
+            #[cfg(target_os = "linux")]
+            mpris::start_mpris(app.handle());
+
+            // 首次启动时命令行传入的文件，等前端就绪后领取
+            let args: Vec<String> = std::env::args().collect();
+            let cwd = std::env::current_dir().unwrap_or_default();
+            launch::open_args(app.handle(), &args, &cwd);
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -123,8 +270,11 @@ pub fn run() {
             scan_files,
             load_cached_music_files,
             check_online_service_status,
+            get_online_api_settings,
+            set_online_api_settings,
             ensure_online_service,
             restart_online_service,
+            get_online_service_logs,
             search_songs,
             search_online_mix,
             get_artist_top_songs,
@@ -134,18 +284,116 @@ pub fn run() {
             get_default_music_dir,
             download_music,
             get_song_lyric,
+            get_online_album,
             load_local_cover_path,
             load_local_lyric,
             get_song_cover,
             read_playlists,
-            write_playlists
+            create_playlist,
+            rename_playlist,
+            delete_playlist,
+            add_playlist_items,
+            remove_playlist_items,
+            reorder_playlist_item,
+            duplicate_playlist,
+            get_recently_played,
+            get_top_tracks,
+            get_top_artists,
+            get_top_albums,
+            get_listening_totals,
+            get_scrobbler_settings,
+            set_scrobbler_settings,
+            get_scrobble_status,
+            flush_scrobble_queue,
+            clear_scrobble_queue,
+            lastfm_authenticate,
+            get_track_user_metadata,
+            get_all_user_metadata,
+            update_track_user_metadata,
+            get_user_metadata_settings,
+            set_user_metadata_settings,
+            restore_session,
+            update_session_queue,
+            start_sleep_timer,
+            cancel_sleep_timer,
+            get_sleep_timer_status,
+            set_loop_point,
+            clear_ab_loop,
+            get_ab_loop,
+            get_track_bookmarks,
+            add_bookmark,
+            rename_bookmark,
+            remove_bookmark,
+            jump_to_bookmark,
+            list_output_devices,
+            get_output_device,
+            set_output_device,
+            get_channel_options,
+            set_channel_options,
+            subscribe_visualizer,
+            unsubscribe_visualizer,
+            get_waveform_peaks,
+            take_pending_open_files,
+            get_remote_api_settings,
+            set_remote_api_settings,
+            get_remote_api_status,
+            get_subsonic_server_settings,
+            set_subsonic_server_settings,
+            get_subsonic_server_status,
+            get_remote_servers,
+            save_remote_server,
+            delete_remote_server,
+            search_remote_songs,
+            get_remote_album_list,
+            get_remote_album,
+            get_remote_artist,
+            get_radio_stations,
+            add_radio_station,
+            import_radio_stations,
+            rename_radio_station,
+            remove_radio_station,
+            get_podcasts,
+            subscribe_podcast,
+            unsubscribe_podcast,
+            refresh_podcasts,
+            get_podcast_settings,
+            set_podcast_settings,
+            set_podcast_episode_played,
+            set_podcast_playback_rate,
+            download_podcast_episode,
+            delete_podcast_download,
+            discover_dlna_renderers,
+            get_playback_output,
+            set_playback_output
         ])
         // share sender, sink, and duration with the frontend
         .manage(music.event_sender)
+        .manage(music.output)
         .manage(music.sink)
         .manage(music.current_duration_ms)
         .manage(music.current_track_id)
+        .manage(music.playback_rate)
+        .manage(music.channel_options)
         .manage(PlaybackRequestIdState::default())
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .manage(NowPlayingState::default())
+        .manage(PlaybackVolumeState::default())
+        .manage(SessionState::default())
+        .manage(SleepTimerState::default())
+        .manage(AbLoopState::default())
+        .manage(VisualizerState::default())
+        .manage(PendingOpenFiles::default())
+        .manage(DlnaState::default())
+        .build(context)
+        .expect("error while running tauri application")
+        .run(|_app_handle, _event| {
+            // macOS 的“打开方式”通过 Apple Event 传入，不走命令行参数
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = _event {
+                let paths = urls
+                    .into_iter()
+                    .filter_map(|url| url.to_file_path().ok())
+                    .collect();
+                launch::open_paths(_app_handle, launch::OpenMode::Play, paths);
+            }
+        });
 }
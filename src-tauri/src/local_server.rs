@@ -0,0 +1,281 @@
+// 应用内嵌 HTTP 服务（远程控制 API、Subsonic 服务端、DLNA 媒体服务）共用的启停管理：记录当前实例的地址、停止信号与任务句柄。
+// 重启时先等旧实例真正退出、释放监听端口，再绑定新地址，避免同一地址重新绑定时报 EADDRINUSE。
+// 另外提供按 Range 串流文件、按扩展名取 Content-Type 与 XML 转义这几个各服务都要用的工具
+
+use hyper::header::{self, HeaderValue};
+use hyper::{Body, Response, StatusCode};
+use serde::Serialize;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Mutex as StdMutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+
+/// 优雅关闭要等现有连接结束；超过这个时间直接中止服务任务
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+    pub last_error: Option<String>,
+}
+
+pub struct RunningServer {
+    address: SocketAddr,
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl RunningServer {
+    /// task 结束即表示监听器已关闭；shutdown 置为 true 时服务应开始退出
+    pub fn new(address: SocketAddr, shutdown: watch::Sender<bool>, task: JoinHandle<()>) -> Self {
+        Self {
+            address,
+            shutdown,
+            task,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ServerSlot {
+    server: StdMutex<Option<RunningServer>>,
+    last_error: StdMutex<Option<String>>,
+    /// 串行化重启，两次设置保存不会同时绑定同一地址
+    restarting: Mutex<()>,
+}
+
+impl ServerSlot {
+    pub fn set_last_error(&self, error: Option<String>) {
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = error;
+        }
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        let address = self
+            .server
+            .lock()
+            .ok()
+            .and_then(|server| server.as_ref().map(|server| server.address.to_string()));
+        ServerStatus {
+            running: address.is_some(),
+            address,
+            last_error: self.last_error.lock().ok().and_then(|error| error.clone()),
+        }
+    }
+
+    /// 发出停止信号并等待服务任务结束；超时则中止任务，返回时监听端口已释放
+    async fn stop(&self) {
+        let Some(server) = self.server.lock().ok().and_then(|mut server| server.take()) else {
+            return;
+        };
+        let _ = server.shutdown.send(true);
+        let mut task = server.task;
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut task)
+            .await
+            .is_err()
+        {
+            task.abort();
+            let _ = task.await;
+        }
+    }
+
+    /// 停掉旧实例；enabled 时再用 start 启动新实例，并记录启动结果
+    pub async fn restart<F, Fut>(&self, enabled: bool, start: F) -> Result<(), String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<RunningServer, String>>,
+    {
+        let _restarting = self.restarting.lock().await;
+        self.stop().await;
+        if !enabled {
+            self.set_last_error(None);
+            return Ok(());
+        }
+        match start().await {
+            Ok(server) => {
+                self.set_last_error(None);
+                if let Ok(mut current) = self.server.lock() {
+                    *current = Some(server);
+                }
+                Ok(())
+            }
+            Err(error) => {
+                self.set_last_error(Some(error.clone()));
+                Err(error)
+            }
+        }
+    }
+}
+
+/// 按扩展名取 Content-Type，未知类型返回 application/octet-stream
+pub fn content_type(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 转义文本与属性值里的 XML 特殊字符
+pub fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// `bytes=start-end` / `bytes=start-` / `bytes=-suffix`，返回闭区间
+fn parse_range(value: &str, size: u64) -> Option<(u64, u64)> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    let (start, end) = spec.split(',').next()?.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (size.saturating_sub(suffix), size.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, size.checked_sub(1)?),
+        (start, end) => (
+            start.parse().ok()?,
+            end.parse::<u64>().ok()?.min(size.checked_sub(1)?),
+        ),
+    };
+    (start <= end && end < size).then_some((start, end))
+}
+
+/// 按 Range 串流文件，客户端拖动进度依赖 206 响应；文件打不开时由调用方决定错误响应
+pub async fn file_response(
+    path: &Path,
+    mime: &'static str,
+    range: Option<&str>,
+) -> std::io::Result<Response<Body>> {
+    let file = tokio::fs::File::open(path).await?;
+    let size = file
+        .metadata()
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let range = range.map(|range| parse_range(range, size));
+    if range == Some(None) {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+        if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", size)) {
+            response.headers_mut().insert(header::CONTENT_RANGE, value);
+        }
+        return Ok(response);
+    }
+    let (start, end) = range.flatten().unwrap_or((0, size.saturating_sub(1)));
+    let length = if size == 0 { 0 } else { end - start + 1 };
+
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let mut file = file;
+        if file.seek(std::io::SeekFrom::Start(start)).await.is_err() {
+            return;
+        }
+        let mut remaining = length;
+        let mut buffer = vec![0u8; STREAM_CHUNK_BYTES];
+        while remaining > 0 {
+            let want = buffer.len().min(remaining as usize);
+            let read = match file.read(&mut buffer[..want]).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+            remaining -= read as u64;
+            if sender
+                .send_data(hyper::body::Bytes::copy_from_slice(&buffer[..read]))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let mut response = Response::new(body);
+    if range.is_some() {
+        *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+    }
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(mime));
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Ok(value) = HeaderValue::from_str(&length.to_string()) {
+        headers.insert(header::CONTENT_LENGTH, value);
+    }
+    if range.is_some() {
+        if let Ok(value) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, size)) {
+            headers.insert(header::CONTENT_RANGE, value);
+        }
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// 收到停止信号后稍等片刻才释放监听器，模拟还有连接未关闭的服务
+    fn start_slow_server(address: SocketAddr) -> Result<RunningServer, String> {
+        let listener = TcpListener::bind(address).map_err(|e| e.to_string())?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+        let (shutdown, mut shutdown_receiver) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            let _ = shutdown_receiver.changed().await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(listener);
+        });
+        Ok(RunningServer::new(address, shutdown, task))
+    }
+
+    #[tokio::test]
+    async fn restart_waits_for_the_old_server_to_release_its_address() {
+        let slot = ServerSlot::default();
+        slot.restart(true, || async {
+            start_slow_server("127.0.0.1:0".parse().unwrap())
+        })
+        .await
+        .unwrap();
+        let address: SocketAddr = slot.status().address.unwrap().parse().unwrap();
+
+        slot.restart(true, || async { start_slow_server(address) })
+            .await
+            .unwrap();
+        let status = slot.status();
+        assert_eq!(status.address, Some(address.to_string()));
+        assert_eq!(status.last_error, None);
+
+        slot.restart(false, || async { start_slow_server(address) })
+            .await
+            .unwrap();
+        assert!(!slot.status().running);
+        assert!(TcpListener::bind(address).is_ok());
+    }
+
+    #[test]
+    fn parses_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-", 10), Some((0, 9)));
+        assert_eq!(parse_range("bytes=-4", 10), Some((6, 9)));
+        assert_eq!(parse_range("bytes=5-100", 10), Some((5, 9)));
+        assert_eq!(parse_range("bytes=10-", 10), None);
+    }
+}
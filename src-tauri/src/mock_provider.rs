@@ -0,0 +1,282 @@
+// 离线的在线音乐来源：歌曲、歌手、专辑与歌词都由编号确定性生成，播放地址和封面指向进程内的 HTTP 服务。
+// 只在调试和测试构建里注册，没有网易云服务时也能走通搜索 → 取地址 → 缓存播放的整条在线流程
+
+use crate::netease::{
+    AlbumInfo, AlbumResult, ArtistInfo, ArtistSongsResult, SearchResult, SongInfo,
+};
+use crate::provider::{MusicProvider, ProviderFuture};
+use hyper::header::{self, HeaderValue};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::f32::consts::PI;
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::OnceLock;
+
+pub const PROVIDER_ID: &str = "mock";
+const ARTISTS: [&str; 3] = ["Mock Artist A", "Mock Artist B", "Mock Artist C"];
+const ALBUM_COUNT: u32 = 6;
+const SONGS_PER_ALBUM: u32 = 4;
+const SAMPLE_RATE: u32 = 8_000;
+const SONG_DURATION_MS: u64 = 1_000;
+
+static SERVER_BASE: OnceLock<Result<String, String>> = OnceLock::new();
+
+pub struct MockProvider;
+
+/// 进程内 HTTP 服务的地址；服务跑在 tauri 的全局运行时上，不随某个调用方的运行时退出
+fn server_base() -> Result<&'static str, String> {
+    SERVER_BASE
+        .get_or_init(start_server)
+        .as_deref()
+        .map_err(|e| e.clone())
+}
+
+fn start_server() -> Result<String, String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .map_err(|e| format!("bind mock provider server: {}", e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("configure mock provider server: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("bind mock provider server: {}", e))?
+        .port();
+    tauri::async_runtime::spawn(async move {
+        let server = match Server::from_tcp(listener) {
+            Ok(server) => server,
+            Err(error) => {
+                eprintln!("Mock provider server error: {}", error);
+                return;
+            }
+        };
+        let service = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|request| async move {
+                Ok::<_, Infallible>(handle_request(request))
+            }))
+        });
+        if let Err(error) = server.serve(service).await {
+            eprintln!("Mock provider server error: {}", error);
+        }
+    });
+    Ok(format!("http://127.0.0.1:{}", port))
+}
+
+fn handle_request(request: Request<Body>) -> Response<Body> {
+    let path = request.uri().path();
+    let song = |prefix: &str, ext: &str| {
+        path.strip_prefix(prefix)
+            .and_then(|name| name.strip_suffix(ext))
+            .and_then(|id| id.parse::<u32>().ok())
+            .filter(|n| song_exists(*n))
+    };
+    let (body, content_type) = if let Some(n) = song("/audio/", ".wav") {
+        (wav_bytes(n), "audio/wav")
+    } else if let Some(n) = song("/cover/", ".svg") {
+        (cover_svg(n).into_bytes(), "image/svg+xml")
+    } else {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    };
+    let mut response = Response::new(Body::from(body));
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+/// 1 秒单声道 16 位 PCM 正弦波，频率随歌曲编号变化
+fn wav_bytes(n: u32) -> Vec<u8> {
+    let frequency = 220.0 + 20.0 * n as f32;
+    let samples = SAMPLE_RATE as u64 * SONG_DURATION_MS / 1000;
+    let data_len = (samples * 2) as u32;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for i in 0..samples {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let sample = ((2.0 * PI * frequency * t).sin() * i16::MAX as f32 * 0.3) as i16;
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+fn cover_svg(n: u32) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"64\" height=\"64\"><rect width=\"64\" height=\"64\" fill=\"hsl({},60%,50%)\"/></svg>",
+        n * 37 % 360
+    )
+}
+
+fn song_exists(n: u32) -> bool {
+    (1..=ALBUM_COUNT * SONGS_PER_ALBUM).contains(&n)
+}
+
+fn album_of(n: u32) -> u32 {
+    (n - 1) / SONGS_PER_ALBUM + 1
+}
+
+fn artist_of_album(album: u32) -> usize {
+    ((album - 1) % ARTISTS.len() as u32) as usize
+}
+
+fn parse_number(id: &str, exists: impl Fn(u32) -> bool, kind: &str) -> Result<u32, String> {
+    id.trim()
+        .parse::<u32>()
+        .ok()
+        .filter(|n| exists(*n))
+        .ok_or_else(|| format!("{} not found: {}", kind, id))
+}
+
+fn song(base: &str, n: u32) -> SongInfo {
+    let album = album_of(n);
+    SongInfo {
+        id: n.to_string(),
+        name: format!("Mock Song {}", n),
+        artists: vec![ARTISTS[artist_of_album(album)].to_string()],
+        album: format!("Mock Album {}", album),
+        duration: SONG_DURATION_MS,
+        pic_url: format!("{}/cover/{}.svg", base, n),
+        file_hash: n.to_string(),
+        provider: PROVIDER_ID.to_string(),
+    }
+}
+
+fn all_songs(base: &str) -> impl Iterator<Item = SongInfo> + '_ {
+    (1..=ALBUM_COUNT * SONGS_PER_ALBUM).map(move |n| song(base, n))
+}
+
+fn artist(base: &str, index: usize) -> ArtistInfo {
+    ArtistInfo {
+        id: (index + 1).to_string(),
+        name: ARTISTS[index].to_string(),
+        // 歌手头像取其第一张专辑第一首歌的封面
+        pic_url: format!("{}/cover/{}.svg", base, index as u32 * SONGS_PER_ALBUM + 1),
+    }
+}
+
+fn matches(song: &SongInfo, keywords: &str) -> bool {
+    let keywords = keywords.trim().to_lowercase();
+    song.name.to_lowercase().contains(&keywords)
+        || song.album.to_lowercase().contains(&keywords)
+        || song
+            .artists
+            .iter()
+            .any(|artist| artist.to_lowercase().contains(&keywords))
+}
+
+impl MusicProvider for MockProvider {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn search_songs(
+        &self,
+        keywords: String,
+        page: u32,
+        page_size: u32,
+    ) -> ProviderFuture<'_, SearchResult> {
+        Box::pin(async move {
+            let base = server_base()?;
+            let found: Vec<SongInfo> = all_songs(base)
+                .filter(|song| matches(song, &keywords))
+                .collect();
+            let total = found.len() as u32;
+            let songs = found
+                .into_iter()
+                .skip((page.saturating_sub(1) * page_size) as usize)
+                .take(page_size as usize)
+                .collect();
+            Ok(SearchResult { songs, total })
+        })
+    }
+
+    fn search_artists(&self, keywords: String, limit: u32) -> ProviderFuture<'_, Vec<ArtistInfo>> {
+        Box::pin(async move {
+            let base = server_base()?;
+            let keywords = keywords.trim().to_lowercase();
+            Ok((0..ARTISTS.len())
+                .filter(|index| ARTISTS[*index].to_lowercase().contains(&keywords))
+                .take(limit as usize)
+                .map(|index| artist(base, index))
+                .collect())
+        })
+    }
+
+    fn artist_top_songs(&self, id: String, limit: u32) -> ProviderFuture<'_, ArtistSongsResult> {
+        Box::pin(async move {
+            let base = server_base()?;
+            let index =
+                parse_number(&id, |n| (1..=ARTISTS.len() as u32).contains(&n), "Artist")? - 1;
+            let songs: Vec<SongInfo> = (1..=ALBUM_COUNT * SONGS_PER_ALBUM)
+                .filter(|n| artist_of_album(album_of(*n)) == index as usize)
+                .take(limit as usize)
+                .map(|n| song(base, n))
+                .collect();
+            Ok(ArtistSongsResult {
+                artist: artist(base, index as usize),
+                total: songs.len() as u32,
+                songs,
+            })
+        })
+    }
+
+    fn song_url(&self, id: String) -> ProviderFuture<'_, String> {
+        Box::pin(async move {
+            let n = parse_number(&id, song_exists, "Song")?;
+            Ok(format!("{}/audio/{}.wav", server_base()?, n))
+        })
+    }
+
+    fn song_detail(&self, id: String) -> ProviderFuture<'_, SongInfo> {
+        Box::pin(async move {
+            let n = parse_number(&id, song_exists, "Song")?;
+            Ok(song(server_base()?, n))
+        })
+    }
+
+    fn song_lyric(&self, id: String) -> ProviderFuture<'_, String> {
+        Box::pin(async move {
+            let n = parse_number(&id, song_exists, "Song")?;
+            let album = album_of(n);
+            Ok(format!(
+                "[00:00.00]Mock Song {}\n[00:00.50]{}\n",
+                n,
+                ARTISTS[artist_of_album(album)]
+            ))
+        })
+    }
+
+    fn album(&self, id: String) -> ProviderFuture<'_, AlbumResult> {
+        Box::pin(async move {
+            let base = server_base()?;
+            let album = parse_number(&id, |n| (1..=ALBUM_COUNT).contains(&n), "Album")?;
+            let first = (album - 1) * SONGS_PER_ALBUM + 1;
+            let songs: Vec<SongInfo> = (first..first + SONGS_PER_ALBUM)
+                .map(|n| song(base, n))
+                .collect();
+            Ok(AlbumResult {
+                album: AlbumInfo {
+                    id: album.to_string(),
+                    name: format!("Mock Album {}", album),
+                    artists: vec![ARTISTS[artist_of_album(album)].to_string()],
+                    pic_url: format!("{}/cover/{}.svg", base, first),
+                    provider: PROVIDER_ID.to_string(),
+                },
+                total: songs.len() as u32,
+                songs,
+            })
+        })
+    }
+}
@@ -0,0 +1,731 @@
+// Linux MPRIS2：把播放状态发布到 D-Bus 会话总线，供桌面媒体键、playerctl 与 GNOME/KDE 媒体控件使用。
+// D-Bus 接口只读写一份快照并把控制请求放进通道，由应用侧任务转成 ctl 同款的播放控制，便于在私有总线上测试
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::{connection, fdo, interface, Connection};
+
+use crate::file::load_local_cover_path;
+use crate::music::{get_playback_state, NowPlayingState, PlaybackTrack, PlaybackVolumeState};
+use crate::remote_control::{execute, ControlCommand};
+use crate::remote_source::{self, RemoteSong};
+use crate::time_stretch::{MAX_PLAYBACK_RATE, MIN_PLAYBACK_RATE};
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.rmusic";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK_PATH: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// 实际位置与按播放速度推算的位置相差超过该值时视为跳转，发出 Seeked 信号
+const SEEK_DETECT_THRESHOLD_US: i64 = 1_500_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MprisStatus {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
+impl MprisStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            MprisStatus::Playing => "Playing",
+            MprisStatus::Paused => "Paused",
+            MprisStatus::Stopped => "Stopped",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MprisSnapshot {
+    /// 后端的曲目序号；0 表示没有曲目
+    pub track_id: u64,
+    pub status: MprisStatus,
+    pub title: String,
+    pub artists: Vec<String>,
+    pub album: String,
+    pub art_url: Option<String>,
+    pub length_us: i64,
+    pub position_us: i64,
+    pub rate: f64,
+    /// 0.0 - 1.0
+    pub volume: f64,
+    /// position_us 的采样时间，播放中按速度外推当前位置
+    pub sampled_at: Instant,
+}
+
+impl Default for MprisSnapshot {
+    fn default() -> Self {
+        Self {
+            track_id: 0,
+            status: MprisStatus::Stopped,
+            title: String::new(),
+            artists: Vec::new(),
+            album: String::new(),
+            art_url: None,
+            length_us: 0,
+            position_us: 0,
+            rate: 1.0,
+            volume: 0.5,
+            sampled_at: Instant::now(),
+        }
+    }
+}
+
+impl MprisSnapshot {
+    fn position_at(&self, now: Instant) -> i64 {
+        if self.status != MprisStatus::Playing {
+            return self.position_us;
+        }
+        let elapsed = now.saturating_duration_since(self.sampled_at).as_micros() as f64;
+        let position = self.position_us + (elapsed * self.rate) as i64;
+        if self.length_us > 0 {
+            position.min(self.length_us)
+        } else {
+            position
+        }
+    }
+
+    fn track_path(&self) -> String {
+        if self.track_id == 0 {
+            NO_TRACK_PATH.to_string()
+        } else {
+            format!("/com/rmusic/track/{}", self.track_id)
+        }
+    }
+
+    fn same_metadata(&self, other: &MprisSnapshot) -> bool {
+        self.track_id == other.track_id
+            && self.title == other.title
+            && self.artists == other.artists
+            && self.album == other.album
+            && self.art_url == other.art_url
+            && self.length_us == other.length_us
+    }
+}
+
+/// D-Bus 调用转成的控制请求；播放控制复用 `rmusic ctl` 的命令
+#[derive(Debug, PartialEq)]
+pub enum MprisCommand {
+    Control(ControlCommand),
+    Raise,
+    Quit,
+}
+
+pub struct MprisShared {
+    snapshot: StdMutex<MprisSnapshot>,
+    commands: UnboundedSender<MprisCommand>,
+}
+
+impl MprisShared {
+    pub fn new() -> (Arc<Self>, UnboundedReceiver<MprisCommand>) {
+        let (commands, receiver) = unbounded_channel();
+        let shared = Arc::new(Self {
+            snapshot: StdMutex::new(MprisSnapshot::default()),
+            commands,
+        });
+        (shared, receiver)
+    }
+
+    fn snapshot(&self) -> MprisSnapshot {
+        self.snapshot
+            .lock()
+            .map(|snapshot| snapshot.clone())
+            .unwrap_or_default()
+    }
+
+    fn send(&self, command: MprisCommand) {
+        let _ = self.commands.send(command);
+    }
+
+    fn control(&self, command: ControlCommand) {
+        self.send(MprisCommand::Control(command));
+    }
+
+    fn seek_to_us(&self, position_us: i64) {
+        self.control(ControlCommand::Seek {
+            position_ms: (position_us.max(0) / 1000) as u64,
+        });
+    }
+}
+
+fn owned(value: Value<'_>) -> Option<OwnedValue> {
+    OwnedValue::try_from(value).ok()
+}
+
+fn metadata_for(snapshot: &MprisSnapshot) -> HashMap<String, OwnedValue> {
+    let mut metadata = HashMap::new();
+    if let Some(path) = ObjectPath::try_from(snapshot.track_path())
+        .ok()
+        .and_then(|path| owned(Value::from(path)))
+    {
+        metadata.insert("mpris:trackid".to_string(), path);
+    }
+    if snapshot.track_id == 0 {
+        return metadata;
+    }
+    let mut insert = |key: &str, value: Value<'_>| {
+        if let Some(value) = owned(value) {
+            metadata.insert(key.to_string(), value);
+        }
+    };
+    insert("mpris:length", Value::from(snapshot.length_us));
+    insert("xesam:title", Value::from(snapshot.title.as_str()));
+    insert("xesam:artist", Value::from(snapshot.artists.clone()));
+    insert("xesam:album", Value::from(snapshot.album.as_str()));
+    if let Some(art_url) = &snapshot.art_url {
+        insert("mpris:artUrl", Value::from(art_url.as_str()));
+    }
+    metadata
+}
+
+struct MediaPlayer2 {
+    shared: Arc<MprisShared>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl MediaPlayer2 {
+    fn raise(&self) {
+        self.shared.send(MprisCommand::Raise);
+    }
+
+    fn quit(&self) {
+        self.shared.send(MprisCommand::Quit);
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "rmusic"
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> &str {
+        "rmusic"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct MediaPlayer2Player {
+    shared: Arc<MprisShared>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl MediaPlayer2Player {
+    fn play(&self) {
+        self.shared.control(ControlCommand::Play);
+    }
+
+    fn pause(&self) {
+        self.shared.control(ControlCommand::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.shared.control(ControlCommand::Toggle);
+    }
+
+    /// 没有“停止”概念，按暂停处理
+    fn stop(&self) {
+        self.shared.control(ControlCommand::Pause);
+    }
+
+    fn next(&self) {
+        self.shared.control(ControlCommand::Next);
+    }
+
+    fn previous(&self) {
+        self.shared.control(ControlCommand::Prev);
+    }
+
+    /// 相对跳转：跳到负值时从头开始，超过结尾时由 seek 逻辑切到下一首
+    fn seek(&self, offset: i64) {
+        let snapshot = self.shared.snapshot();
+        if snapshot.track_id == 0 {
+            return;
+        }
+        let position = snapshot.position_at(Instant::now()).saturating_add(offset);
+        self.shared.seek_to_us(position);
+    }
+
+    /// 曲目已切换（track_id 不匹配）或位置越界时按规范忽略
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        let snapshot = self.shared.snapshot();
+        if snapshot.track_id == 0 || track_id.as_str() != snapshot.track_path() {
+            return;
+        }
+        if position < 0 || (snapshot.length_us > 0 && position > snapshot.length_us) {
+            return;
+        }
+        self.shared.seek_to_us(position);
+    }
+
+    fn open_uri(&self, _uri: &str) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "OpenUri is not supported".to_string(),
+        ))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        self.shared.snapshot().status.as_str()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        self.shared.snapshot().rate
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        MIN_PLAYBACK_RATE as f64
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        MAX_PLAYBACK_RATE as f64
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        metadata_for(&self.shared.snapshot())
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.shared.snapshot().volume
+    }
+
+    #[zbus(property)]
+    fn set_volume(&self, volume: f64) {
+        if volume.is_finite() {
+            self.shared.control(ControlCommand::Volume {
+                volume: (volume.clamp(0.0, 1.0) * 100.0) as f32,
+            });
+        }
+    }
+
+    /// 位置变化不发 PropertiesChanged（规范要求），客户端按需读取
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.shared.snapshot().position_at(Instant::now())
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.shared.snapshot().track_id != 0
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.shared.snapshot().track_id != 0
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.shared.snapshot().length_us > 0
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// 在给定总线上注册服务名与两个接口；生产环境用会话总线，测试用私有总线
+pub async fn serve(
+    builder: connection::Builder<'_>,
+    shared: Arc<MprisShared>,
+) -> zbus::Result<Connection> {
+    builder
+        .name(BUS_NAME)?
+        .serve_at(
+            OBJECT_PATH,
+            MediaPlayer2 {
+                shared: shared.clone(),
+            },
+        )?
+        .serve_at(OBJECT_PATH, MediaPlayer2Player { shared })?
+        .build()
+        .await
+}
+
+/// 更新快照并为变化的属性发出 PropertiesChanged；位置跳变时发出 Seeked
+pub async fn publish(
+    connection: &Connection,
+    shared: &MprisShared,
+    next: MprisSnapshot,
+) -> zbus::Result<()> {
+    let previous = {
+        let Ok(mut snapshot) = shared.snapshot.lock() else {
+            return Ok(());
+        };
+        std::mem::replace(&mut *snapshot, next.clone())
+    };
+
+    let iface = connection
+        .object_server()
+        .interface::<_, MediaPlayer2Player>(OBJECT_PATH)
+        .await?;
+    let emitter = iface.signal_emitter();
+    let player = iface.get().await;
+    if !previous.same_metadata(&next) {
+        player.metadata_changed(emitter).await?;
+        player.can_seek_changed(emitter).await?;
+    }
+    if previous.track_id != next.track_id {
+        player.can_play_changed(emitter).await?;
+        player.can_pause_changed(emitter).await?;
+    }
+    if previous.status != next.status {
+        player.playback_status_changed(emitter).await?;
+    }
+    if previous.volume != next.volume {
+        player.volume_changed(emitter).await?;
+    }
+    if previous.rate != next.rate {
+        player.rate_changed(emitter).await?;
+    }
+    let expected = previous.position_at(next.sampled_at);
+    if previous.track_id == next.track_id
+        && next.track_id != 0
+        && (next.position_us - expected).abs() > SEEK_DETECT_THRESHOLD_US
+    {
+        MediaPlayer2Player::seeked(emitter, next.position_us).await?;
+    }
+    Ok(())
+}
+
+async fn track_metadata(
+    app_handle: &AppHandle,
+    track: &PlaybackTrack,
+) -> (String, Vec<String>, String, Option<String>) {
+    match track {
+        PlaybackTrack::Local { file } => {
+            let title = file.title.clone().unwrap_or_else(|| file.file_name.clone());
+            let artists = file.artist.iter().cloned().collect();
+            let art_url = load_local_cover_path(app_handle.clone(), file.file_name.clone(), None)
+                .ok()
+                .flatten()
+                .map(|path| format!("file://{}", path));
+            (
+                title,
+                artists,
+                file.album.clone().unwrap_or_default(),
+                art_url,
+            )
+        }
+        PlaybackTrack::Online { song } => (
+            song.name.clone(),
+            song.artists.clone(),
+            song.album.clone(),
+            Some(song.pic_url.clone()).filter(|url| !url.is_empty()),
+        ),
+        PlaybackTrack::Remote {
+            song: RemoteSong { song, .. },
+        } => (
+            song.name.clone(),
+            song.artists.clone(),
+            song.album.clone(),
+            remote_source::external_cover_url(app_handle, &song.pic_url).await,
+        ),
+    }
+}
+
+async fn read_snapshot(app_handle: &AppHandle, previous: &MprisSnapshot) -> MprisSnapshot {
+    let sampled_at = Instant::now();
+    let Ok(state) = get_playback_state(
+        app_handle.state(),
+        app_handle.state(),
+        app_handle.state(),
+        app_handle.state(),
+    )
+    .await
+    else {
+        return previous.clone();
+    };
+    let volume = app_handle
+        .state::<PlaybackVolumeState>()
+        .0
+        .lock()
+        .ok()
+        .and_then(|volume| *volume)
+        .map(|volume| (volume as f64 / 100.0).clamp(0.0, 1.0))
+        .unwrap_or(previous.volume);
+    let track = app_handle
+        .state::<NowPlayingState>()
+        .0
+        .lock()
+        .ok()
+        .and_then(|session| session.as_ref().map(|session| session.track.clone()));
+
+    let status = if !state.has_track || state.is_ended {
+        MprisStatus::Stopped
+    } else if state.is_paused {
+        MprisStatus::Paused
+    } else {
+        MprisStatus::Playing
+    };
+    let mut snapshot = MprisSnapshot {
+        track_id: if state.has_track { state.track_id } else { 0 },
+        status,
+        length_us: state.duration_ms as i64 * 1000,
+        position_us: state.position_ms as i64 * 1000,
+        rate: state.playback_rate as f64,
+        volume,
+        sampled_at,
+        ..MprisSnapshot::default()
+    };
+    if snapshot.track_id == previous.track_id && previous.track_id != 0 {
+        // 同一首曲目不重复查找封面
+        snapshot.title = previous.title.clone();
+        snapshot.artists = previous.artists.clone();
+        snapshot.album = previous.album.clone();
+        snapshot.art_url = previous.art_url.clone();
+    } else if let (Some(track), true) = (track, snapshot.track_id != 0) {
+        (
+            snapshot.title,
+            snapshot.artists,
+            snapshot.album,
+            snapshot.art_url,
+        ) = track_metadata(app_handle, &track).await;
+    }
+    snapshot
+}
+
+async fn run_commands(app_handle: AppHandle, mut receiver: UnboundedReceiver<MprisCommand>) {
+    while let Some(command) = receiver.recv().await {
+        match command {
+            MprisCommand::Control(command) => {
+                if let Err(error) = execute(&app_handle, command).await {
+                    eprintln!("MPRIS command failed: {}", error);
+                }
+            }
+            MprisCommand::Raise => {
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+            MprisCommand::Quit => crate::tray::quit_app(&app_handle),
+        }
+    }
+}
+
+/// 连接会话总线并定期同步播放状态；没有会话总线（如纯 TTY）时静默跳过
+pub fn start_mpris(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let (shared, receiver) = MprisShared::new();
+        let connection = match connection::Builder::session() {
+            Ok(builder) => serve(builder, shared.clone()).await,
+            Err(error) => Err(error),
+        };
+        let connection = match connection {
+            Ok(connection) => connection,
+            Err(error) => {
+                eprintln!("MPRIS unavailable: {}", error);
+                return;
+            }
+        };
+        tauri::async_runtime::spawn(run_commands(app_handle.clone(), receiver));
+
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            let previous = shared.snapshot();
+            let next = read_snapshot(&app_handle, &previous).await;
+            if let Err(error) = publish(&connection, &shared, next).await {
+                eprintln!("MPRIS publish error: {}", error);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use zbus::zvariant::OwnedObjectPath;
+    use zbus::Proxy;
+
+    const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+    /// 启动一个私有的 dbus-daemon，返回进程与地址；系统没有 dbus-daemon 时返回 None
+    fn private_bus() -> Option<(Child, String)> {
+        let mut child = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(child.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some((child, address.trim().to_string()))
+    }
+
+    fn playing_snapshot() -> MprisSnapshot {
+        MprisSnapshot {
+            track_id: 7,
+            status: MprisStatus::Playing,
+            title: "Song".to_string(),
+            artists: vec!["Artist".to_string()],
+            album: "Album".to_string(),
+            art_url: Some("https://example.com/cover.jpg".to_string()),
+            length_us: 200_000_000,
+            position_us: 10_000_000,
+            ..MprisSnapshot::default()
+        }
+    }
+
+    #[test]
+    fn position_advances_only_while_playing() {
+        let snapshot = playing_snapshot();
+        let later = snapshot.sampled_at + Duration::from_secs(2);
+        assert_eq!(snapshot.position_at(later), 12_000_000);
+
+        let paused = MprisSnapshot {
+            status: MprisStatus::Paused,
+            ..snapshot.clone()
+        };
+        assert_eq!(paused.position_at(later), 10_000_000);
+
+        let fast = MprisSnapshot {
+            rate: 2.0,
+            position_us: 199_000_000,
+            ..snapshot
+        };
+        assert_eq!(fast.position_at(later), 200_000_000);
+    }
+
+    #[test]
+    fn metadata_only_has_track_id_without_track() {
+        let metadata = metadata_for(&MprisSnapshot::default());
+        assert_eq!(metadata.len(), 1);
+        assert!(metadata.contains_key("mpris:trackid"));
+
+        let metadata = metadata_for(&playing_snapshot());
+        assert_eq!(
+            String::try_from(metadata["xesam:title"].clone()).unwrap(),
+            "Song"
+        );
+        assert_eq!(
+            i64::try_from(metadata["mpris:length"].clone()).unwrap(),
+            200_000_000
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_player_on_private_bus() {
+        let Some((mut daemon, address)) = private_bus() else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+
+        let (shared, mut receiver) = MprisShared::new();
+        let builder = connection::Builder::address(address.as_str()).unwrap();
+        let server = serve(builder, shared.clone()).await.unwrap();
+        publish(&server, &shared, playing_snapshot()).await.unwrap();
+
+        let client = connection::Builder::address(address.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let player = Proxy::new(&client, BUS_NAME, OBJECT_PATH, PLAYER_INTERFACE)
+            .await
+            .unwrap();
+
+        let status: String = player.get_property("PlaybackStatus").await.unwrap();
+        assert_eq!(status, "Playing");
+        let metadata: HashMap<String, OwnedValue> = player.get_property("Metadata").await.unwrap();
+        assert_eq!(
+            OwnedObjectPath::try_from(metadata["mpris:trackid"].clone())
+                .unwrap()
+                .as_str(),
+            "/com/rmusic/track/7"
+        );
+
+        let _: () = player.call("PlayPause", &()).await.unwrap();
+        assert_eq!(
+            receiver.recv().await,
+            Some(MprisCommand::Control(ControlCommand::Toggle))
+        );
+
+        let track = ObjectPath::try_from("/com/rmusic/track/7").unwrap();
+        let _: () = player
+            .call("SetPosition", &(track, 30_000_000i64))
+            .await
+            .unwrap();
+        assert_eq!(
+            receiver.recv().await,
+            Some(MprisCommand::Control(ControlCommand::Seek {
+                position_ms: 30_000
+            }))
+        );
+
+        // 过期的 track id 被忽略，下一条收到的是 Next
+        let stale = ObjectPath::try_from("/com/rmusic/track/6").unwrap();
+        let _: () = player.call("SetPosition", &(stale, 0i64)).await.unwrap();
+        let _: () = player.call("Next", &()).await.unwrap();
+        assert_eq!(
+            receiver.recv().await,
+            Some(MprisCommand::Control(ControlCommand::Next))
+        );
+
+        player.set_property("Volume", 0.25f64).await.unwrap();
+        assert_eq!(
+            receiver.recv().await,
+            Some(MprisCommand::Control(ControlCommand::Volume {
+                volume: 25.0
+            }))
+        );
+
+        let _ = daemon.kill();
+        let _ = daemon.wait();
+    }
+}
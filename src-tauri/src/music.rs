@@ -1,20 +1,35 @@
-use rodio::cpal::FromSample;
-use rodio::{Decoder, OutputStream, Sample, Sink, Source};
+use rodio::source::SamplesConverter;
+use rodio::{Decoder, Sink, Source};
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering};
 use std::sync::{Arc, Condvar, Mutex as StdMutex, OnceLock, Weak};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::AsyncWriteExt;
 use tokio::sync::broadcast::Sender;
 use tokio::sync::{broadcast, Mutex};
 
-use crate::netease;
+use crate::audio_output::AudioOutput;
+use crate::bookmarks;
+use crate::dlna;
+use crate::history::{self, ListenSession};
+use crate::netease::{self, SongInfo};
+use crate::online_api::OnlineApiState;
+use crate::podcast;
+use crate::provider;
+use crate::radio;
+use crate::remote_source::{self, RemoteSong};
+use crate::scrobble;
+use crate::session;
+use crate::storage::unique_temp_path_for;
+use crate::time_stretch::PlaybackRateState;
+use crate::visualizer::VisualizerState;
+use crate::waveform;
 
 const MAX_ONLINE_AUDIO_CACHE_BYTES: u64 = 1024 * 1024 * 1024;
 const MAX_ONLINE_AUDIO_CACHE_FILES: usize = 200;
@@ -32,9 +47,11 @@ pub struct PlaybackState {
     pub is_paused: bool,
     pub has_track: bool,
     pub track_id: u64,
+    pub playback_rate: f32,
+    pub preserve_pitch: bool,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Clone, Serialize, Debug)]
 pub struct PlayStartResult {
     pub position_ms: u64,
     pub duration_ms: u64,
@@ -50,19 +67,62 @@ struct PlaybackEndedEvent {
     track_id: u64,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum PlaybackSource {
-    Local { path: String },
-    Online { url: String, cache_key: String },
+    Local {
+        path: String,
+        /// CUE 分轨只播放文件中的一段
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        range: Option<PlaybackRange>,
+    },
+    Online {
+        url: String,
+        cache_key: String,
+    },
+    /// 远程 Subsonic 服务器上的歌曲，播放时按服务器配置生成串流地址
+    Remote {
+        server_id: String,
+        song_id: String,
+    },
+    /// 网络电台直播流，不写入在线音频缓存
+    Radio {
+        url: String,
+    },
+    /// 播客单集：已下载时播放本地文件，否则边下边播
+    Podcast {
+        feed_id: String,
+        episode_id: String,
+    },
+}
+
+/// 正在播放的曲目元数据，由前端随 play_track 一起传入，用于播放历史等后端功能
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PlaybackTrack {
+    Local { file: MusicFile },
+    Online { song: SongInfo },
+    Remote { song: RemoteSong },
+}
+
+impl PlaybackTrack {
+    pub fn duration_ms(&self) -> u64 {
+        match self {
+            PlaybackTrack::Local { file } => file.duration_ms,
+            PlaybackTrack::Online { song } => song.duration,
+            PlaybackTrack::Remote { song } => song.song.duration,
+        }
+    }
 }
 
 pub struct Music {
     pub event_sender: Sender<MusicState>,
-    _stream: OutputStream,
+    pub output: AudioOutput,
     pub sink: Arc<Mutex<Sink>>,
     pub current_duration_ms: PlaybackDurationState,
     pub current_track_id: PlaybackTrackIdState,
+    pub playback_rate: PlaybackRateState,
+    pub channel_options: ChannelOptionsState,
 }
 
 #[derive(Clone)]
@@ -74,6 +134,189 @@ pub struct PlaybackTrackIdState(pub Arc<Mutex<u64>>);
 #[derive(Clone, Default)]
 pub struct PlaybackRequestIdState(pub Arc<AtomicU64>);
 
+/// 用户设置的音量（前端滑块 0-100），用于会话恢复
+#[derive(Clone, Default)]
+pub struct PlaybackVolumeState(pub Arc<StdMutex<Option<f32>>>);
+
+#[derive(Clone, Default)]
+pub struct NowPlayingState(pub Arc<StdMutex<Option<Arc<ListenSession>>>>);
+
+/// 声道处理选项：左右平衡（-1 全左，1 全右）、单声道混音、左右互换
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ChannelOptions {
+    pub balance: f32,
+    pub mono: bool,
+    pub swap_channels: bool,
+}
+
+const CHANNEL_MONO: u8 = 1;
+const CHANNEL_SWAP: u8 = 2;
+
+pub struct ChannelOptionsControl {
+    balance_bits: AtomicU32,
+    flags: AtomicU8,
+}
+
+/// 声道处理设置，由 sink 中的 ChannelProcessor 每帧读取，修改即时生效
+#[derive(Clone)]
+pub struct ChannelOptionsState(pub Arc<ChannelOptionsControl>);
+
+impl Default for ChannelOptionsState {
+    fn default() -> Self {
+        Self(Arc::new(ChannelOptionsControl {
+            balance_bits: AtomicU32::new(0.0f32.to_bits()),
+            flags: AtomicU8::new(0),
+        }))
+    }
+}
+
+impl ChannelOptionsState {
+    pub fn get(&self) -> ChannelOptions {
+        let flags = self.0.flags.load(Ordering::Relaxed);
+        ChannelOptions {
+            balance: f32::from_bits(self.0.balance_bits.load(Ordering::Relaxed)),
+            mono: flags & CHANNEL_MONO != 0,
+            swap_channels: flags & CHANNEL_SWAP != 0,
+        }
+    }
+
+    pub fn set_balance(&self, balance: f32) {
+        let balance = if balance.is_finite() {
+            balance.clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        self.0
+            .balance_bits
+            .store(balance.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_flag(&self, flag: u8, enabled: bool) {
+        if enabled {
+            self.0.flags.fetch_or(flag, Ordering::Relaxed);
+        } else {
+            self.0.flags.fetch_and(!flag, Ordering::Relaxed);
+        }
+    }
+
+    pub fn set_mono(&self, mono: bool) {
+        self.set_flag(CHANNEL_MONO, mono);
+    }
+
+    pub fn set_swap_channels(&self, swap: bool) {
+        self.set_flag(CHANNEL_SWAP, swap);
+    }
+
+    pub fn set(&self, options: ChannelOptions) {
+        self.set_balance(options.balance);
+        self.set_mono(options.mono);
+        self.set_swap_channels(options.swap_channels);
+    }
+
+    pub fn wrap<S>(&self, source: S) -> ChannelProcessor<S>
+    where
+        S: Source<Item = f32>,
+    {
+        let channels = (source.channels() as usize).max(1);
+        ChannelProcessor {
+            inner: source,
+            control: Arc::clone(&self.0),
+            frame: Vec::with_capacity(channels),
+            cursor: 0,
+        }
+    }
+}
+
+/// 按整帧读取采样后依次做左右互换、单声道混音和平衡；非立体声只做混音
+pub struct ChannelProcessor<S> {
+    inner: S,
+    control: Arc<ChannelOptionsControl>,
+    frame: Vec<f32>,
+    cursor: usize,
+}
+
+impl<S> ChannelProcessor<S>
+where
+    S: Source<Item = f32>,
+{
+    fn fill_frame(&mut self) -> bool {
+        let channels = (self.inner.channels() as usize).max(1);
+        self.frame.clear();
+        self.cursor = 0;
+        for _ in 0..channels {
+            match self.inner.next() {
+                Some(sample) => self.frame.push(sample),
+                None => break,
+            }
+        }
+        if self.frame.is_empty() {
+            return false;
+        }
+
+        let flags = self.control.flags.load(Ordering::Relaxed);
+        let balance = f32::from_bits(self.control.balance_bits.load(Ordering::Relaxed));
+        let stereo = self.frame.len() == 2;
+        if stereo && flags & CHANNEL_SWAP != 0 {
+            self.frame.swap(0, 1);
+        }
+        if flags & CHANNEL_MONO != 0 && self.frame.len() > 1 {
+            let mixed = self.frame.iter().sum::<f32>() / self.frame.len() as f32;
+            self.frame.fill(mixed);
+        }
+        if stereo && balance != 0.0 {
+            self.frame[0] *= (1.0 - balance).min(1.0);
+            self.frame[1] *= (1.0 + balance).min(1.0);
+        }
+        true
+    }
+}
+
+impl<S> Iterator for ChannelProcessor<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.cursor >= self.frame.len() && !self.fill_frame() {
+            return None;
+        }
+        let sample = self.frame[self.cursor];
+        self.cursor += 1;
+        Some(sample)
+    }
+}
+
+impl<S> Source for ChannelProcessor<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner
+            .current_frame_len()
+            .map(|len| len + self.frame.len() - self.cursor)
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.frame.clear();
+        self.cursor = 0;
+        self.inner.try_seek(pos)
+    }
+}
+
 #[derive(Default)]
 struct ProgressiveDownloadState {
     downloaded: u64,
@@ -163,7 +406,8 @@ impl Seek for ProgressiveFileReader {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct MusicFile {
     pub id: i32,
     pub file_name: String,
@@ -176,6 +420,122 @@ pub struct MusicFile {
     pub artist: Option<String>,
     pub album: Option<String>,
     pub duration_ms: u64,
+    /// CUE 表拆出的虚拟分轨；为空表示普通文件
+    pub cue: Option<CueRange>,
+    /// 库外文件（命令行、打开方式传入）的绝对路径；库内文件为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// 虚拟分轨对应的实际音频文件（相对路径）与起止偏移
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct CueRange {
+    pub source_path: String,
+    pub track: u32,
+    pub start_ms: u64,
+    pub end_ms: Option<u64>,
+}
+
+/// 播放文件中的一段：从 start_ms 开始，到 end_ms 结束（为空则到文件末尾）
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct PlaybackRange {
+    pub start_ms: u64,
+    pub end_ms: Option<u64>,
+}
+
+/// 把源限制在 PlaybackRange 内并把时间轴平移到 0，对后续处理链来说就是一首独立的曲目
+pub struct RangedSource<S> {
+    inner: S,
+    start: Duration,
+    /// 剩余可输出的采样数，为空表示不限制
+    remaining: Option<u64>,
+    limit: Option<u64>,
+}
+
+impl<S> RangedSource<S>
+where
+    S: Source<Item = f32>,
+{
+    pub(crate) fn new(mut inner: S, range: PlaybackRange) -> Result<Self, String> {
+        let start = Duration::from_millis(range.start_ms);
+        if range.start_ms > 0 {
+            inner
+                .try_seek(start)
+                .map_err(|e| format!("seek to track start error: {:?}", e))?;
+        }
+        let limit = range.end_ms.map(|end_ms| {
+            let frames = end_ms.saturating_sub(range.start_ms) * inner.sample_rate() as u64 / 1000;
+            frames * inner.channels() as u64
+        });
+        Ok(Self {
+            inner,
+            start,
+            remaining: limit,
+            limit,
+        })
+    }
+}
+
+impl<S> Iterator for RangedSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(remaining) = self.remaining.as_mut() {
+            if *remaining == 0 {
+                return None;
+            }
+            *remaining -= 1;
+        }
+        self.inner.next()
+    }
+}
+
+impl<S> Source for RangedSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        let inner = self.inner.current_frame_len();
+        match (inner, self.remaining) {
+            (Some(len), Some(remaining)) => Some(len.min(remaining as usize)),
+            (None, Some(remaining)) => Some(remaining as usize),
+            (len, None) => len,
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        match self.limit {
+            Some(limit) => Some(Duration::from_millis(
+                limit * 1000
+                    / (self.inner.sample_rate() as u64 * self.inner.channels() as u64).max(1),
+            )),
+            None => self
+                .inner
+                .total_duration()
+                .map(|total| total.saturating_sub(self.start)),
+        }
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.inner.try_seek(self.start + pos)?;
+        if let Some(limit) = self.limit {
+            let frames = pos.as_millis() as u64 * self.inner.sample_rate() as u64 / 1000;
+            self.remaining = Some(limit.saturating_sub(frames * self.inner.channels() as u64));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -183,20 +543,32 @@ pub enum MusicState {
     Recovery,
     Pause,
     Volume(f32),
+    /// 播放速度 0.5-2.0；preserve_pitch 为 false 时音高随速度变化
+    PlaybackRate {
+        rate: f32,
+        preserve_pitch: bool,
+    },
+    /// 左右平衡 -1.0-1.0
+    Balance(f32),
+    MonoDownmix(bool),
+    SwapChannels(bool),
 }
 
 impl Music {
     pub fn new() -> Result<Self, String> {
         let (event_sender, mut event_receiver) = broadcast::channel(100);
 
-        let (_stream, stream_handle) =
-            OutputStream::try_default().map_err(|e| format!("init audio output: {}", e))?;
-        let sink = Arc::new(Mutex::new(
-            Sink::try_new(&stream_handle).map_err(|e| format!("init audio sink: {}", e))?,
-        ));
+        // Sink 不直接绑定设备，队列输出交给 AudioOutput，切换设备时曲目与进度保持不变
+        let (sink, queue_output) = Sink::new_idle();
+        let output = AudioOutput::start(queue_output)?;
+        let sink = Arc::new(Mutex::new(sink));
         let sink_clone = Arc::clone(&sink);
         let duration_clone = Arc::new(Mutex::new(0u64));
         let track_id = Arc::new(Mutex::new(0u64));
+        let playback_rate = PlaybackRateState::default();
+        let playback_rate_clone = playback_rate.clone();
+        let channel_options = ChannelOptionsState::default();
+        let channel_options_clone = channel_options.clone();
 
         // spawn a thread to handle the music events
         tokio::spawn(async move {
@@ -219,16 +591,27 @@ impl Music {
                         let curved = normalized * normalized;
                         sink.set_volume(curved * 2.0);
                     }
+                    MusicState::PlaybackRate {
+                        rate,
+                        preserve_pitch,
+                    } => {
+                        playback_rate_clone.set(rate, preserve_pitch);
+                    }
+                    MusicState::Balance(balance) => channel_options_clone.set_balance(balance),
+                    MusicState::MonoDownmix(mono) => channel_options_clone.set_mono(mono),
+                    MusicState::SwapChannels(swap) => channel_options_clone.set_swap_channels(swap),
                 }
             }
         });
 
         Ok(Self {
             event_sender,
-            _stream,
+            output,
             sink,
             current_duration_ms: PlaybackDurationState(duration_clone),
             current_track_id: PlaybackTrackIdState(track_id),
+            playback_rate,
+            channel_options,
         })
     }
 }
@@ -245,6 +628,23 @@ fn decode_file(path: &Path) -> Result<(Decoder<BufReader<File>>, u64), String> {
     Ok((source, duration_ms))
 }
 
+type DecodedLocalSource = RangedSource<SamplesConverter<Decoder<BufReader<File>>, f32>>;
+
+/// 解码本地文件；带 range 时只保留其中一段，返回的时长也是这一段的长度
+fn decode_local_file(
+    path: &Path,
+    range: Option<PlaybackRange>,
+) -> Result<(DecodedLocalSource, u64), String> {
+    let (decoded, file_duration_ms) = decode_file(path)?;
+    let range = range.unwrap_or_default();
+    let duration_ms = match range.end_ms {
+        Some(end_ms) => end_ms.saturating_sub(range.start_ms),
+        None => file_duration_ms.saturating_sub(range.start_ms),
+    };
+    let source = RangedSource::new(decoded.convert_samples(), range)?;
+    Ok((source, duration_ms))
+}
+
 fn decode_progressive_file(
     path: &Path,
     state: SharedProgressiveDownloadState,
@@ -259,7 +659,38 @@ fn decode_progressive_file(
     Ok((source, duration_ms))
 }
 
+/// 解码器探测格式时会阻塞等待首批数据，放到阻塞线程里执行
+async fn decode_radio_stream(
+    app_handle: &AppHandle,
+    url: &str,
+) -> Result<Decoder<BufReader<radio::RadioStreamReader>>, String> {
+    let reader = radio::open_radio_stream(app_handle, url);
+    let buffer = reader.shared_buffer();
+    let decoded =
+        tauri::async_runtime::spawn_blocking(move || Decoder::new(BufReader::new(reader)))
+            .await
+            .map_err(|e| format!("decode radio stream task error: {}", e))?;
+    // 连接失败时下载端的错误比解码器的“无法识别格式”更有用
+    decoded.map_err(|e| {
+        buffer
+            .error()
+            .unwrap_or_else(|| format!("decode radio stream error: {}", e))
+    })
+}
+
+/// 解码后的处理链：变速 -> 声道处理 -> 可视化旁路
+fn playback_chain<S>(app_handle: &AppHandle, source: S) -> impl Source<Item = f32> + Send
+where
+    S: Source<Item = f32> + Send + 'static,
+{
+    let playback_rate = app_handle.state::<PlaybackRateState>();
+    let channel_options = app_handle.state::<ChannelOptionsState>();
+    let visualizer = app_handle.state::<VisualizerState>();
+    visualizer.tap(channel_options.wrap(playback_rate.wrap(source)))
+}
+
 async fn replace_sink_source<S>(
+    app_handle: &AppHandle,
     source: S,
     duration_ms: u64,
     sink: Arc<Mutex<Sink>>,
@@ -268,9 +699,7 @@ async fn replace_sink_source<S>(
     request_id: u64,
 ) -> Result<(), String>
 where
-    S: Source + Send + 'static,
-    f32: FromSample<S::Item>,
-    S::Item: Sample + Send,
+    S: Source<Item = f32> + Send + 'static,
 {
     let sink_lock = sink.lock().await;
     ensure_playback_request_current(Some((request_state, request_id)))?;
@@ -278,7 +707,7 @@ where
     ensure_playback_request_current(Some((request_state, request_id)))?;
     *dur = duration_ms;
     sink_lock.clear();
-    sink_lock.append(source);
+    sink_lock.append(playback_chain(app_handle, source));
     if sink_lock.is_paused() {
         sink_lock.play();
     }
@@ -289,21 +718,51 @@ fn start_playback_end_monitor(
     app_handle: AppHandle,
     sink: Arc<Mutex<Sink>>,
     duration: Arc<Mutex<u64>>,
+    playback_rate: PlaybackRateState,
     current_track_id: Arc<Mutex<u64>>,
     expected_track_id: u64,
+    listen_session: Option<Arc<ListenSession>>,
 ) {
     tauri::async_runtime::spawn(async move {
+        let mut last_position_ms = 0u64;
         loop {
-            tokio::time::sleep(PLAYBACK_END_POLL_INTERVAL).await;
+            let poll_interval = if bookmarks::is_ab_loop_active(&app_handle, expected_track_id) {
+                bookmarks::AB_LOOP_POLL_INTERVAL
+            } else {
+                PLAYBACK_END_POLL_INTERVAL
+            };
+            tokio::time::sleep(poll_interval).await;
 
             if *current_track_id.lock().await != expected_track_id {
-                return;
+                break;
             }
 
-            let (is_empty, position_ms) = {
+            let (is_empty, is_paused, position_ms) = {
                 let sink = sink.lock().await;
-                (sink.empty(), sink.get_pos().as_millis() as u64)
+                (
+                    sink.empty(),
+                    sink.is_paused(),
+                    playback_rate.position().as_millis() as u64,
+                )
             };
+            if let Some(session) = &listen_session {
+                if !is_paused {
+                    session.observe_progress(last_position_ms, position_ms);
+                }
+            }
+            last_position_ms = position_ms;
+            if !is_empty && !is_paused {
+                if let Some(loop_start_ms) =
+                    bookmarks::ab_loop_restart_position(&app_handle, expected_track_id, position_ms)
+                {
+                    let sink = sink.lock().await;
+                    match sink.try_seek(Duration::from_millis(loop_start_ms)) {
+                        Ok(()) => last_position_ms = loop_start_ms,
+                        Err(e) => eprintln!("A-B loop seek error: {:?}", e),
+                    }
+                    continue;
+                }
+            }
             if !is_empty || position_ms == 0 {
                 continue;
             }
@@ -317,7 +776,11 @@ fn start_playback_end_monitor(
                     track_id: expected_track_id,
                 },
             );
-            return;
+            break;
+        }
+
+        if let Some(session) = listen_session {
+            history::finish_listen_blocking(app_handle, session).await;
         }
     });
 }
@@ -342,28 +805,15 @@ fn online_cache_path(app_handle: &AppHandle, cache_key: &str) -> Result<PathBuf,
     Ok(cache_dir.join(format!("{}.audio", digest)))
 }
 
-pub(crate) fn is_online_audio_cached(app_handle: &AppHandle, cache_key: &str) -> bool {
+/// 已完整缓存的在线音频路径；下载中的临时文件不算
+pub(crate) fn cached_online_audio_path(app_handle: &AppHandle, cache_key: &str) -> Option<PathBuf> {
     online_cache_path(app_handle, cache_key)
         .ok()
-        .and_then(|path| fs::metadata(path).ok())
-        .is_some_and(|metadata| metadata.len() > 0)
+        .filter(|path| fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0))
 }
 
-fn unique_temp_path_for(target_path: &Path) -> PathBuf {
-    let unique = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|duration| duration.as_nanos())
-        .unwrap_or(0);
-    let file_name = target_path
-        .file_name()
-        .and_then(|name| name.to_str())
-        .unwrap_or("cache");
-    target_path.with_file_name(format!(
-        "{}.{}.{}.tmp",
-        file_name,
-        std::process::id(),
-        unique
-    ))
+pub(crate) fn is_online_audio_cached(app_handle: &AppHandle, cache_key: &str) -> bool {
+    cached_online_audio_path(app_handle, cache_key).is_some()
 }
 
 fn online_download_lock(cache_path: &Path) -> Result<Arc<Mutex<()>>, String> {
@@ -752,27 +1202,248 @@ pub fn clear_online_audio_cache(app_handle: AppHandle) -> Result<(), String> {
             let _ = fs::remove_file(path);
         }
     }
-    Ok(())
+    waveform::clear_waveform_cache(&app_handle)
+}
+
+/// 新曲目载入 sink 之后：分配 track id、结算上一首的收听会话并启动播放结束监控
+async fn start_track_session(
+    app_handle: AppHandle,
+    sink: &Arc<Mutex<Sink>>,
+    duration: &PlaybackDurationState,
+    track_id: &PlaybackTrackIdState,
+    now_playing: &NowPlayingState,
+    track: Option<PlaybackTrack>,
+    local_path: Option<String>,
+) -> Result<(u64, u64), String> {
+    let next_track_id = {
+        let mut id = track_id.0.lock().await;
+        *id = id.saturating_add(1);
+        *id
+    };
+
+    let duration_ms = *duration.0.lock().await;
+    if let Some(track) = &track {
+        scrobble::submit_now_playing(&app_handle, track);
+    }
+    let playback_rate = app_handle.state::<PlaybackRateState>().inner().clone();
+    let listen_session = track
+        .map(|track| Arc::new(ListenSession::new(track, duration_ms).with_local_path(local_path)));
+    let previous_session = std::mem::replace(
+        &mut *now_playing
+            .0
+            .lock()
+            .map_err(|_| "now playing state poisoned".to_string())?,
+        listen_session.clone(),
+    );
+    if let Some(previous_session) = previous_session {
+        previous_session.mark_skipped();
+        history::finish_listen_blocking(app_handle.clone(), previous_session).await;
+    }
+    start_playback_end_monitor(
+        app_handle,
+        Arc::clone(sink),
+        Arc::clone(&duration.0),
+        playback_rate,
+        Arc::clone(&track_id.0),
+        next_track_id,
+        listen_session,
+    );
+    Ok((next_track_id, duration_ms))
+}
+
+/// 以暂停状态载入曲目并跳到指定位置，用于启动时恢复上次的播放会话
+pub(crate) async fn load_paused_track(
+    app_handle: &AppHandle,
+    path: &Path,
+    range: Option<PlaybackRange>,
+    position_ms: u64,
+    track: Option<PlaybackTrack>,
+    local_path: Option<String>,
+) -> Result<PlayStartResult, String> {
+    let sink = app_handle.state::<Arc<Mutex<Sink>>>();
+    let duration = app_handle.state::<PlaybackDurationState>();
+    let track_id = app_handle.state::<PlaybackTrackIdState>();
+    let now_playing = app_handle.state::<NowPlayingState>();
+
+    let (decoded_source, duration_ms) = decode_local_file(path, range)?;
+    let position_ms = if duration_ms > 0 {
+        position_ms.min(duration_ms)
+    } else {
+        position_ms
+    };
+    {
+        let sink = sink.lock().await;
+        *duration.0.lock().await = duration_ms;
+        sink.clear();
+        sink.pause();
+        sink.append(playback_chain(app_handle, decoded_source));
+        if position_ms > 0 {
+            sink.try_seek(Duration::from_millis(position_ms))
+                .map_err(|e| format!("seek error: {:?}", e))?;
+        }
+    }
+
+    let (next_track_id, duration_ms) = start_track_session(
+        app_handle.clone(),
+        &sink,
+        &duration,
+        &track_id,
+        &now_playing,
+        track,
+        local_path,
+    )
+    .await?;
+    Ok(PlayStartResult {
+        position_ms,
+        duration_ms,
+        is_paused: true,
+        has_track: true,
+        track_id: next_track_id,
+    })
+}
+
+/// 在线与远程来源的实际下载地址和缓存键
+async fn resolve_stream_source(
+    app_handle: &AppHandle,
+    source: PlaybackSource,
+) -> Result<(String, String), String> {
+    match source {
+        PlaybackSource::Online { url, cache_key } if url.trim().is_empty() => Ok((
+            provider::song_url_for_cache_key(&app_handle.state::<OnlineApiState>(), &cache_key)
+                .await?,
+            cache_key,
+        )),
+        PlaybackSource::Online { url, cache_key } => Ok((url, cache_key)),
+        PlaybackSource::Remote { server_id, song_id } => {
+            let server = remote_source::find_server(app_handle, &server_id)?;
+            Ok((
+                server.stream_url(&song_id),
+                remote_source::remote_cache_key(&server_id, &song_id),
+            ))
+        }
+        PlaybackSource::Local { path, .. } => Err(format!("not a streaming source: {}", path)),
+        PlaybackSource::Radio { url } => Err(format!("not a cacheable source: {}", url)),
+        PlaybackSource::Podcast { episode_id, .. } => {
+            Err(format!("unresolved podcast episode: {}", episode_id))
+        }
+    }
+}
+
+/// 渲染器要拉取的媒体：本地文件与已缓存的在线音频由内置 HTTP 服务提供，其余直接给出地址
+async fn renderer_media(
+    app_handle: &AppHandle,
+    source: PlaybackSource,
+) -> Result<dlna::RendererMedia, String> {
+    match source {
+        PlaybackSource::Local { path, range } => {
+            let path = PathBuf::from(path);
+            let (_, duration_ms) = decode_local_file(&path, range)?;
+            Ok(dlna::RendererMedia::File {
+                path,
+                range: range.unwrap_or_default(),
+                duration_ms,
+            })
+        }
+        PlaybackSource::Radio { url } => Ok(dlna::RendererMedia::Url {
+            url: radio::resolve_stream_url(&url).await?,
+        }),
+        source => {
+            let (url, cache_key) = resolve_stream_source(app_handle, source).await?;
+            Ok(match cached_online_audio_path(app_handle, &cache_key) {
+                Some(path) => {
+                    let duration_ms = decode_file(&path).map(|(_, ms)| ms).unwrap_or(0);
+                    dlna::RendererMedia::File {
+                        path,
+                        range: PlaybackRange::default(),
+                        duration_ms,
+                    }
+                }
+                None => dlna::RendererMedia::Url { url },
+            })
+        }
+    }
+}
+
+/// 交给 DLNA 渲染器播放；本地 Sink 换成不经处理链的静音占位，保持“有曲目”和暂停状态，位置改由渲染器轮询写入
+async fn play_on_renderer(
+    app_handle: &AppHandle,
+    renderer: &dlna::DlnaRenderer,
+    source: PlaybackSource,
+    track: Option<&PlaybackTrack>,
+    request_state: &PlaybackRequestIdState,
+    request_id: u64,
+) -> Result<(dlna::RendererPlayback, u64), String> {
+    let media = renderer_media(app_handle, source).await?;
+    ensure_playback_request_current(Some((request_state, request_id)))?;
+    let playback = dlna::start_renderer_playback(app_handle, renderer, media, track).await?;
+
+    let sink = app_handle.state::<Arc<Mutex<Sink>>>();
+    let duration = app_handle.state::<PlaybackDurationState>();
+    let sink_lock = sink.lock().await;
+    ensure_playback_request_current(Some((request_state, request_id)))?;
+    *duration.0.lock().await = playback.duration_ms;
+    sink_lock.clear();
+    sink_lock.append(rodio::source::Zero::<f32>::new(2, 44_100));
+    if sink_lock.is_paused() {
+        sink_lock.play();
+    }
+    let generation = app_handle.state::<PlaybackRateState>().detach();
+    Ok((playback, generation))
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn play_track(
     app_handle: AppHandle,
     sink: tauri::State<'_, Arc<Mutex<Sink>>>,
     duration: tauri::State<'_, PlaybackDurationState>,
     track_id: tauri::State<'_, PlaybackTrackIdState>,
     request_state: tauri::State<'_, PlaybackRequestIdState>,
+    now_playing: tauri::State<'_, NowPlayingState>,
     source: PlaybackSource,
     request_id: u64,
+    track: Option<PlaybackTrack>,
 ) -> Result<PlayStartResult, String> {
     register_playback_request_id(&request_state, request_id)?;
+    let session_source = source.clone();
+    let source = match source {
+        PlaybackSource::Podcast {
+            feed_id,
+            episode_id,
+        } => podcast::resolve_playback_source(&app_handle, &feed_id, &episode_id)?,
+        source => source,
+    };
+    let local_path = match &source {
+        PlaybackSource::Local { path, .. } => Some(path.clone()),
+        PlaybackSource::Online { .. }
+        | PlaybackSource::Remote { .. }
+        | PlaybackSource::Radio { .. }
+        | PlaybackSource::Podcast { .. } => None,
+    };
 
+    // 输出切到 DLNA 渲染器时由渲染器自己拉流播放
+    let renderer_playback = match dlna::active_renderer(&app_handle) {
+        Some(renderer) => Some(
+            play_on_renderer(
+                &app_handle,
+                &renderer,
+                source.clone(),
+                track.as_ref(),
+                &request_state,
+                request_id,
+            )
+            .await?,
+        ),
+        None => None,
+    };
     match source {
-        PlaybackSource::Local { path } => {
+        _ if renderer_playback.is_some() => {}
+        PlaybackSource::Local { path, range } => {
             let source_path = PathBuf::from(path);
-            let (decoded_source, duration_ms) = decode_file(&source_path)?;
+            let (decoded_source, duration_ms) = decode_local_file(&source_path, range)?;
             ensure_playback_request_current(Some((&request_state, request_id)))?;
             replace_sink_source(
+                &app_handle,
                 decoded_source,
                 duration_ms,
                 Arc::clone(&sink),
@@ -782,12 +1453,23 @@ pub async fn play_track(
             )
             .await?;
         }
-        PlaybackSource::Online { url, cache_key } => {
-            let resolved_url = if url.trim().is_empty() {
-                netease::get_song_url(cache_key.clone()).await?
-            } else {
-                url
-            };
+        PlaybackSource::Radio { url } => {
+            let decoded_source = decode_radio_stream(&app_handle, &url).await?;
+            ensure_playback_request_current(Some((&request_state, request_id)))?;
+            // 直播没有总时长，前端按 0 显示为直播
+            replace_sink_source(
+                &app_handle,
+                decoded_source.convert_samples(),
+                0,
+                Arc::clone(&sink),
+                Arc::clone(&duration.0),
+                &request_state,
+                request_id,
+            )
+            .await?;
+        }
+        source => {
+            let (resolved_url, cache_key) = resolve_stream_source(&app_handle, source).await?;
             let (source_path, download_state) = progressive_online_file(
                 &app_handle,
                 &resolved_url,
@@ -801,7 +1483,8 @@ pub async fn play_track(
                 decode_progressive_file(&source_path, download_state)?;
             ensure_playback_request_current(Some((&request_state, request_id)))?;
             replace_sink_source(
-                decoded_source,
+                &app_handle,
+                decoded_source.convert_samples(),
                 duration_ms,
                 Arc::clone(&sink),
                 Arc::clone(&duration.0),
@@ -811,20 +1494,28 @@ pub async fn play_track(
             .await?;
         }
     }
-    let next_track_id = {
-        let mut id = track_id.0.lock().await;
-        *id = id.saturating_add(1);
-        *id
-    };
-
-    let duration_ms = *duration.0.lock().await;
-    start_playback_end_monitor(
+    session::set_current_track(&app_handle, session_source.clone(), track.clone());
+    let progress_handle = app_handle.clone();
+    let (next_track_id, duration_ms) = start_track_session(
         app_handle,
-        Arc::clone(&sink),
-        Arc::clone(&duration.0),
-        Arc::clone(&track_id.0),
-        next_track_id,
-    );
+        &sink,
+        &duration,
+        &track_id,
+        &now_playing,
+        track,
+        local_path,
+    )
+    .await?;
+    if let PlaybackSource::Podcast {
+        feed_id,
+        episode_id,
+    } = session_source
+    {
+        podcast::track_episode_progress(&progress_handle, feed_id, episode_id, next_track_id);
+    }
+    if let Some((playback, generation)) = renderer_playback {
+        dlna::track_renderer_playback(&progress_handle, playback, next_track_id, generation);
+    }
 
     Ok(PlayStartResult {
         position_ms: 0,
@@ -835,6 +1526,7 @@ pub async fn play_track(
     })
 }
 
+/// id 为在线音频缓存键：网易云是裸 id，其他来源是 "来源:id"
 #[tauri::command]
 pub async fn prefetch_netease_song(app_handle: AppHandle, id: String) -> Result<(), String> {
     if id.trim().is_empty() {
@@ -844,7 +1536,7 @@ pub async fn prefetch_netease_song(app_handle: AppHandle, id: String) -> Result<
         return Ok(());
     }
 
-    let url = netease::get_song_url(id.clone()).await?;
+    let url = provider::song_url_for_cache_key(&app_handle.state::<OnlineApiState>(), &id).await?;
     cached_online_file(&app_handle, &url, &id, None).await?;
     Ok(())
 }
@@ -854,10 +1546,10 @@ pub async fn get_playback_state(
     sink: tauri::State<'_, Arc<Mutex<Sink>>>,
     duration: tauri::State<'_, PlaybackDurationState>,
     track_id: tauri::State<'_, PlaybackTrackIdState>,
+    playback_rate: tauri::State<'_, PlaybackRateState>,
 ) -> Result<PlaybackState, String> {
     let sink = sink.lock().await;
-    let position = sink.get_pos();
-    let position_ms = position.as_millis() as u64;
+    let position_ms = playback_rate.position().as_millis() as u64;
     let duration_ms = *duration.0.lock().await;
     let has_track = duration_ms > 0 || !sink.empty() || position_ms > 0;
     let is_ended = has_track && sink.empty() && position_ms > 0;
@@ -869,6 +1561,8 @@ pub async fn get_playback_state(
         is_paused: sink.is_paused(),
         has_track,
         track_id: *track_id.0.lock().await,
+        playback_rate: playback_rate.rate(),
+        preserve_pitch: playback_rate.preserve_pitch(),
     })
 }
 
@@ -878,10 +1572,11 @@ pub struct SeekResult {
     pub should_play_next: bool,
 }
 
-#[tauri::command]
-pub async fn seek_to(
-    sink: tauri::State<'_, Arc<Mutex<Sink>>>,
-    duration: tauri::State<'_, PlaybackDurationState>,
+/// 当前曲目内跳转；超出时长时提示前端切到下一首
+pub(crate) async fn seek_current_track(
+    app_handle: &AppHandle,
+    sink: &Arc<Mutex<Sink>>,
+    duration: &PlaybackDurationState,
     position_ms: u64,
 ) -> Result<SeekResult, String> {
     let actual_duration = *duration.0.lock().await;
@@ -891,6 +1586,12 @@ pub async fn seek_to(
             should_play_next: true,
         });
     }
+    if dlna::seek(app_handle, position_ms).await? {
+        return Ok(SeekResult {
+            success: true,
+            should_play_next: false,
+        });
+    }
     let sink = sink.lock().await;
     let duration = Duration::from_millis(position_ms);
     sink.try_seek(duration)
@@ -901,6 +1602,16 @@ pub async fn seek_to(
     })
 }
 
+#[tauri::command]
+pub async fn seek_to(
+    app_handle: AppHandle,
+    sink: tauri::State<'_, Arc<Mutex<Sink>>>,
+    duration: tauri::State<'_, PlaybackDurationState>,
+    position_ms: u64,
+) -> Result<SeekResult, String> {
+    seek_current_track(&app_handle, &sink, &duration, position_ms).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -929,4 +1640,57 @@ mod tests {
         assert!(!is_clearable_online_cache_artifact(Path::new("notes.txt")));
         assert!(!is_clearable_online_cache_artifact(Path::new("tmp")));
     }
+
+    #[test]
+    fn channel_processor_applies_swap_mono_and_balance_live() {
+        use rodio::buffer::SamplesBuffer;
+
+        let options = ChannelOptionsState::default();
+        let samples = vec![1.0f32, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+        let mut processor = options.wrap(SamplesBuffer::new(2, 8_000, samples));
+
+        assert_eq!(processor.next(), Some(1.0));
+        assert_eq!(processor.next(), Some(0.0));
+
+        options.set_swap_channels(true);
+        assert_eq!(processor.next(), Some(0.0));
+        assert_eq!(processor.next(), Some(1.0));
+
+        options.set(ChannelOptions {
+            mono: true,
+            ..ChannelOptions::default()
+        });
+        assert_eq!(processor.next(), Some(0.5));
+        assert_eq!(processor.next(), Some(0.5));
+
+        options.set_balance(0.5);
+        assert_eq!(processor.next(), Some(0.25));
+        assert_eq!(processor.next(), Some(0.5));
+        assert_eq!(processor.next(), None);
+    }
+
+    #[test]
+    fn ranged_source_plays_only_the_track_and_rebases_time() {
+        use rodio::buffer::SamplesBuffer;
+
+        // 1kHz 单声道，每毫秒一个采样，采样值即时间（ms）
+        let samples: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+        let range = PlaybackRange {
+            start_ms: 200,
+            end_ms: Some(500),
+        };
+        let source =
+            RangedSource::new(SamplesBuffer::new(1, 1000, samples.clone()), range).unwrap();
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(300)));
+        let played: Vec<f32> = source.collect();
+        assert_eq!(played.len(), 300);
+        assert_eq!(played.first(), Some(&200.0));
+        assert_eq!(played.last(), Some(&499.0));
+
+        let mut source = RangedSource::new(SamplesBuffer::new(1, 1000, samples), range).unwrap();
+        source.try_seek(Duration::from_millis(250)).unwrap();
+        let rest: Vec<f32> = source.collect();
+        assert_eq!(rest.len(), 50);
+        assert_eq!(rest.first(), Some(&450.0));
+    }
 }
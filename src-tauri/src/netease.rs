@@ -1,6 +1,9 @@
+use crate::online_api::{EndpointStatus, OnlineApiState};
+use crate::provider::{MusicProvider, ProviderFuture};
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SongInfo {
@@ -11,6 +14,9 @@ pub struct SongInfo {
     pub duration: u64, // ms
     pub pic_url: String,
     pub file_hash: String, // file hash for the song
+    // 歌曲所属的在线来源，旧数据里没有该字段时视为网易云
+    #[serde(default = "crate::provider::default_provider")]
+    pub provider: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,6 +53,22 @@ pub struct ArtistSongsResult {
     pub total: u32,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlbumInfo {
+    pub id: String,
+    pub name: String,
+    pub artists: Vec<String>,
+    pub pic_url: String,
+    pub provider: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlbumResult {
+    pub album: AlbumInfo,
+    pub songs: Vec<SongInfo>,
+    pub total: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PlaySongResult {
     pub url: String,
@@ -61,9 +83,12 @@ pub struct OnlineServiceStatus {
     pub available: bool,
     pub status_code: Option<u16>,
     pub message: String,
+    /// 每个已配置端点的探测结果与延迟统计
+    pub endpoints: Vec<EndpointStatus>,
 }
 
-const LOCAL_API_BASE: &str = "http://localhost:3000";
+pub const PROVIDER_ID: &str = "netease";
+const STATUS_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.0.0 Safari/537.36";
 const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(8);
 const HTTP_RESPONSE_HEADERS_TIMEOUT: Duration = Duration::from_secs(15);
@@ -113,43 +138,76 @@ pub fn get_client() -> Result<reqwest::Client, String> {
         .ok_or_else(|| "Failed to initialize HTTP client".to_string())
 }
 
+/// 探测所有已配置端点；整体状态取第一个可用端点（都不可用时取第一个端点的错误）
 #[tauri::command]
-pub async fn check_online_service_status() -> Result<OnlineServiceStatus, String> {
+pub async fn check_online_service_status(
+    api: tauri::State<'_, OnlineApiState>,
+) -> Result<OnlineServiceStatus, String> {
+    online_service_status(&api).await
+}
+
+pub async fn online_service_status(api: &OnlineApiState) -> Result<OnlineServiceStatus, String> {
     let client = get_client()?;
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|duration| duration.as_millis())
         .unwrap_or(0);
-    let url = format!("{}/login/status?timestamp={}", LOCAL_API_BASE, timestamp);
+    let endpoints = join_all(
+        api.configured_endpoints()
+            .into_iter()
+            .map(|base| probe_endpoint(api, &client, base, timestamp)),
+    )
+    .await;
+
+    let preferred = endpoints
+        .iter()
+        .find(|endpoint| endpoint.available)
+        .or_else(|| endpoints.first());
+    let (available, status_code, message) = match preferred {
+        Some(endpoint) if endpoint.available => (true, endpoint.status_code, "ok".to_string()),
+        Some(endpoint) => (
+            false,
+            endpoint.status_code,
+            endpoint.last_error.clone().unwrap_or_default(),
+        ),
+        None => (false, None, "No online API endpoint configured".to_string()),
+    };
+    Ok(OnlineServiceStatus {
+        available,
+        status_code,
+        message,
+        endpoints,
+    })
+}
 
-    match client
-        .get(&url)
-        .timeout(Duration::from_secs(2))
-        .send()
-        .await
-    {
-        Ok(response) => {
-            let status = response.status();
-            if status.is_success() {
-                Ok(OnlineServiceStatus {
-                    available: true,
-                    status_code: Some(status.as_u16()),
-                    message: "ok".to_string(),
-                })
-            } else {
-                Ok(OnlineServiceStatus {
-                    available: false,
-                    status_code: Some(status.as_u16()),
-                    message: format!("HTTP {}", status),
-                })
-            }
-        }
-        Err(error) => Ok(OnlineServiceStatus {
-            available: false,
-            status_code: None,
-            message: error.to_string(),
-        }),
+async fn probe_endpoint(
+    api: &OnlineApiState,
+    client: &reqwest::Client,
+    base: String,
+    timestamp: u128,
+) -> EndpointStatus {
+    let url = format!("{}/login/status?timestamp={}", base, timestamp);
+    let started = Instant::now();
+    let (status_code, error) = match client.get(&url).timeout(STATUS_PROBE_TIMEOUT).send().await {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (
+            Some(response.status().as_u16()),
+            Some(format!("HTTP {}", response.status())),
+        ),
+        Err(error) => (None, Some(error.to_string())),
+    };
+    match &error {
+        None => api.record_success(&base, started.elapsed()),
+        Some(error) => api.record_failure(&base, error),
     }
+
+    let mut status = api.endpoint_status(&base);
+    status.available = error.is_none();
+    status.status_code = status_code;
+    if error.is_some() {
+        status.latency_ms = None;
+    }
+    status
 }
 
 /// get client response
@@ -228,6 +286,52 @@ async fn get_response_json_once(
     })
 }
 
+/// 按 online_api 给出的顺序依次请求各端点。连接失败、超时、5xx 与非 JSON 响应切换到下一个端点，
+/// HTTP 4xx 属于请求本身的问题，直接返回；只有最后一个候选端点做瞬时错误重试
+async fn request_json(
+    api: &OnlineApiState,
+    build_url: impl Fn(&str) -> String,
+) -> Result<serde_json::Value, String> {
+    let client = get_client()?;
+    let endpoints = api.ordered_endpoints();
+    let mut failures: Vec<(&str, String)> = Vec::new();
+    for (index, base) in endpoints.iter().enumerate() {
+        let url = build_url(base);
+        let started = Instant::now();
+        let result = if index + 1 == endpoints.len() {
+            get_response_json(client.clone(), url).await
+        } else {
+            get_response_json_once(client.clone(), url).await
+        };
+        match result {
+            Err(error) if is_endpoint_error(&error) => {
+                api.record_failure(base, &error);
+                failures.push((base, error));
+            }
+            result => {
+                api.record_success(base, started.elapsed());
+                return result;
+            }
+        }
+    }
+    match failures.len() {
+        0 => Err("No online API endpoint configured".to_string()),
+        1 => Err(failures.remove(0).1),
+        _ => Err(format!(
+            "All online API endpoints failed: {}",
+            failures
+                .iter()
+                .map(|(base, error)| format!("{}: {}", base, error))
+                .collect::<Vec<_>>()
+                .join("; ")
+        )),
+    }
+}
+
+fn is_endpoint_error(error: &str) -> bool {
+    is_transient_request_error(error) || error.starts_with("API request error: HTTP 5")
+}
+
 fn is_transient_request_error(error: &str) -> bool {
     error.starts_with("API request timed out")
         || (error.starts_with("API request error:") && !error.contains("HTTP "))
@@ -237,32 +341,42 @@ fn is_transient_request_error(error: &str) -> bool {
         || error.starts_with("Serialize json error:")
 }
 
-/// search online songs by keywords
-#[tauri::command]
-pub async fn search_songs(
-    keywords: String,
-    page: Option<u32>,
-    pagesize: Option<u32>,
-    local_api: Option<String>,
-) -> Result<SearchResult, String> {
-    let client = get_client()?;
-    let search_request = SearchRequest {
-        keywords: keywords.clone(),
-        page: page.unwrap_or(1),
-        pagesize: pagesize.unwrap_or(7),
-    }; // use local_api if provided, otherwise use default
-    let local_api = local_api.unwrap_or_else(|| LOCAL_API_BASE.to_string());
-
-    // Use /cloudsearch so we can get album cover (al.picUrl) directly.
-    let url = build_cloudsearch_url(
-        &local_api,
-        &search_request.keywords,
-        search_request.page,
-        search_request.pagesize,
-    );
+/// /cloudsearch 与 /song/detail 的歌曲条目结构相同：歌手在 "ar"，专辑在 "al"，时长 "dt"（毫秒）
+fn song_info_from_json(song: &serde_json::Value) -> Option<SongInfo> {
+    let id = song["id"].as_u64()?.to_string();
+    let name = song["name"].as_str().unwrap_or("unknown").to_string();
+    let artists = if let Some(artists_array) = song["ar"].as_array() {
+        artists_array
+            .iter()
+            .filter_map(|artist| artist["name"].as_str().map(|s| s.to_string()))
+            .collect()
+    } else {
+        vec!["unknown artist".to_string()]
+    };
+    let album = song["al"]["name"]
+        .as_str()
+        .unwrap_or("unknown album")
+        .to_string();
+    let duration = song["dt"]
+        .as_u64()
+        .or_else(|| song["duration"].as_u64())
+        .unwrap_or(0);
+    // 封面直接取 al.picUrl
+    let pic_url = song["al"]["picUrl"].as_str().unwrap_or("").to_string();
 
-    let response_json: serde_json::Value = get_response_json(client, url).await?;
+    Some(SongInfo {
+        id: id.clone(),
+        name,
+        artists,
+        album,
+        duration,
+        pic_url,
+        file_hash: id,
+        provider: PROVIDER_ID.to_string(),
+    })
+}
 
+fn check_code(response_json: &serde_json::Value) -> Result<(), String> {
     let code = response_json
         .get("code")
         .and_then(|v| v.as_u64())
@@ -270,6 +384,77 @@ pub async fn search_songs(
     if code != 200 {
         return Err(format!("API return error: code {}", code));
     }
+    Ok(())
+}
+
+/// 网易云音乐：请求按 online_api 的端点顺序发往内置服务或自建的 NeteaseCloudMusicApi 实例
+pub struct NeteaseProvider<'a> {
+    pub api: &'a OnlineApiState,
+}
+
+impl MusicProvider for NeteaseProvider<'_> {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn search_songs(
+        &self,
+        keywords: String,
+        page: u32,
+        page_size: u32,
+    ) -> ProviderFuture<'_, SearchResult> {
+        Box::pin(search_songs(self.api, keywords, page, page_size))
+    }
+
+    fn search_artists(&self, keywords: String, limit: u32) -> ProviderFuture<'_, Vec<ArtistInfo>> {
+        Box::pin(search_artists(self.api, keywords, limit))
+    }
+
+    fn artist_top_songs(&self, id: String, limit: u32) -> ProviderFuture<'_, ArtistSongsResult> {
+        Box::pin(get_artist_top_songs(self.api, id, limit))
+    }
+
+    fn song_url(&self, id: String) -> ProviderFuture<'_, String> {
+        Box::pin(get_song_url(self.api, id))
+    }
+
+    fn song_detail(&self, id: String) -> ProviderFuture<'_, SongInfo> {
+        Box::pin(get_song_detail(self.api, id))
+    }
+
+    fn song_lyric(&self, id: String) -> ProviderFuture<'_, String> {
+        Box::pin(get_song_lyric(self.api, id))
+    }
+
+    fn album(&self, id: String) -> ProviderFuture<'_, AlbumResult> {
+        Box::pin(get_album(self.api, id))
+    }
+}
+
+/// search online songs by keywords
+async fn search_songs(
+    api: &OnlineApiState,
+    keywords: String,
+    page: u32,
+    pagesize: u32,
+) -> Result<SearchResult, String> {
+    let search_request = SearchRequest {
+        keywords,
+        page,
+        pagesize,
+    };
+
+    // Use /cloudsearch so we can get album cover (al.picUrl) directly.
+    let response_json = request_json(api, |base| {
+        build_cloudsearch_url(
+            base,
+            &search_request.keywords,
+            search_request.page,
+            search_request.pagesize,
+        )
+    })
+    .await?;
+    check_code(&response_json)?;
 
     let result = response_json
         .get("result")
@@ -285,166 +470,48 @@ pub async fn search_songs(
         .and_then(|v| v.as_u64())
         .unwrap_or(0) as u32;
 
-    let mut songs = Vec::new();
-    for song in songs_value {
-        let id = song["id"]
-            .as_u64()
-            .map(|id| id.to_string())
-            .unwrap_or_default();
-        if id.is_empty() {
-            continue;
-        }
-
-        let song_name = song["name"].as_str().unwrap_or("unknown").to_string();
-
-        // /cloudsearch returns artists in "ar"
-        let artists = if let Some(artists_array) = song["ar"].as_array() {
-            artists_array
-                .iter()
-                .filter_map(|artist| artist["name"].as_str().map(|s| s.to_string()))
-                .collect()
-        } else {
-            vec!["unknown artist".to_string()]
-        };
-        // /cloudsearch returns album in "al"
-        let album_name = song["al"]["name"]
-            .as_str()
-            .unwrap_or("unknown album")
-            .to_string();
-        // /cloudsearch uses "dt" as duration in ms
-        let duration = song["dt"]
-            .as_u64()
-            .or_else(|| song["duration"].as_u64())
-            .unwrap_or(0);
-
-        // /cloudsearch provides cover directly: al.picUrl
-        let pic_url = song["al"]["picUrl"].as_str().unwrap_or("").to_string();
-
-        songs.push(SongInfo {
-            id: id.clone(),
-            name: song_name,
-            artists,
-            album: album_name,
-            duration,
-            pic_url,
-            file_hash: id,
-        });
-    }
+    let songs = songs_value.iter().filter_map(song_info_from_json).collect();
 
     Ok(SearchResult { songs, total })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn cloudsearch_url_encodes_keywords_and_uses_offset() {
-        assert_eq!(
-            build_cloudsearch_url(LOCAL_API_BASE, "周杰伦 稻香", 3, 20),
-            "http://localhost:3000/cloudsearch?keywords=%E5%91%A8%E6%9D%B0%E4%BC%A6%20%E7%A8%BB%E9%A6%99&limit=20&offset=40"
-        );
-    }
-
-    #[test]
-    fn cloudsearch_url_saturates_first_page_offset() {
-        assert_eq!(
-            build_cloudsearch_url(LOCAL_API_BASE, "a/b", 0, 7),
-            "http://localhost:3000/cloudsearch?keywords=a%2Fb&limit=7&offset=0"
-        );
-    }
-}
-
-/// 综合在线搜索：返回“相关歌手 + 歌曲列表（可分页）”
-///
-/// - 歌曲：走 /cloudsearch (type=1) 以获得 songCount 便于分页
-/// - 歌手：走 /search (type=1018) 直接拿 result.artist.artists 的头像/名称
-#[tauri::command]
-pub async fn search_online_mix(
+/// 歌手搜索走 /search (type=1018)，直接拿 result.artist.artists 的头像/名称
+async fn search_artists(
+    api: &OnlineApiState,
     keywords: String,
-    page: Option<u32>,
-    pagesize: Option<u32>,
-    artist_limit: Option<u32>,
-) -> Result<SearchMixResult, String> {
-    let client = get_client()?;
-    let kw = keywords.clone();
-    let page = page.unwrap_or(1);
-    let pagesize = pagesize.unwrap_or(7);
-    let artist_limit = artist_limit.unwrap_or(6);
-
-    let songs_fut = search_songs(
-        kw.clone(),
-        Some(page),
-        Some(pagesize),
-        Some(LOCAL_API_BASE.into()),
-    );
-    let artists_fut = async {
-        // /search: offset 为偏移量
-        let encoded_kw = urlencoding::encode(&kw);
-        let url = format!(
+    limit: u32,
+) -> Result<Vec<ArtistInfo>, String> {
+    // /search: offset 为偏移量
+    let encoded_kw = urlencoding::encode(&keywords);
+    let response_json = request_json(api, |base| {
+        format!(
             "{}/search?keywords={}&type=1018&limit={}&offset=0",
-            LOCAL_API_BASE, encoded_kw, artist_limit
-        );
-        let response_json: serde_json::Value = get_response_json(client.clone(), url).await?;
-        let code = response_json
-            .get("code")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(500);
-        if code != 200 {
-            return Err(format!("API return error: code {}", code));
-        }
+            base, encoded_kw, limit
+        )
+    })
+    .await?;
+    check_code(&response_json)?;
 
-        let artists_value = response_json["result"]["artist"]["artists"]
-            .as_array()
-            .unwrap_or(&Vec::new())
-            .to_owned();
+    let artists_value = response_json["result"]["artist"]["artists"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .to_owned();
 
-        let mut artists = Vec::new();
-        for a in artists_value {
-            let id = a["id"].as_u64().map(|v| v.to_string()).unwrap_or_default();
-            if id.is_empty() {
-                continue;
-            }
-            let name = a["name"].as_str().unwrap_or("unknown").to_string();
-            let pic_url = a["img1v1Url"]
-                .as_str()
-                .or_else(|| a["picUrl"].as_str())
-                .unwrap_or("")
-                .to_string();
-            artists.push(ArtistInfo { id, name, pic_url });
-        }
-        Ok::<Vec<ArtistInfo>, String>(artists)
-    };
-
-    let (songs_res, artists_res) = tokio::join!(songs_fut, artists_fut);
-    let (songs_res, artists) = match (songs_res, artists_res) {
-        (Ok(songs), Ok(artists)) => (songs, artists),
-        (Ok(songs), Err(error)) => {
-            eprintln!("Artist search unavailable, returning song results: {error}");
-            (songs, Vec::new())
-        }
-        (Err(error), Ok(artists)) => {
-            eprintln!("Song search unavailable, returning artist results: {error}");
-            (
-                SearchResult {
-                    songs: Vec::new(),
-                    total: 0,
-                },
-                artists,
-            )
-        }
-        (Err(song_error), Err(artist_error)) => {
-            return Err(format!(
-                "Song and artist search failed: songs: {song_error}; artists: {artist_error}"
-            ));
+    let mut artists = Vec::new();
+    for a in artists_value {
+        let id = a["id"].as_u64().map(|v| v.to_string()).unwrap_or_default();
+        if id.is_empty() {
+            continue;
         }
-    };
-
-    Ok(SearchMixResult {
-        artists,
-        songs: songs_res.songs,
-        total: songs_res.total,
-    })
+        let name = a["name"].as_str().unwrap_or("unknown").to_string();
+        let pic_url = a["img1v1Url"]
+            .as_str()
+            .or_else(|| a["picUrl"].as_str())
+            .unwrap_or("")
+            .to_string();
+        artists.push(ArtistInfo { id, name, pic_url });
+    }
+    Ok(artists)
 }
 
 /// 获取歌手热门歌曲（用于“只看该歌手歌曲”页面）
@@ -452,14 +519,11 @@ pub async fn search_online_mix(
 /// 兼容两类常见实现：
 /// - /artist/top/song?id=...  -> songs[]
 /// - /artists?id=...          -> hotSongs[]
-#[tauri::command]
-pub async fn get_artist_top_songs(
+async fn get_artist_top_songs(
+    api: &OnlineApiState,
     id: String,
-    limit: Option<u32>,
+    limit: u32,
 ) -> Result<ArtistSongsResult, String> {
-    let client = get_client()?;
-    let limit = limit.unwrap_or(50);
-
     async fn parse_songs(arr: Option<&Vec<serde_json::Value>>) -> Vec<SongInfo> {
         let mut songs = Vec::new();
         let Some(arr) = arr else { return songs };
@@ -507,17 +571,17 @@ pub async fn get_artist_top_songs(
                 duration,
                 pic_url,
                 file_hash: id,
+                provider: PROVIDER_ID.to_string(),
             });
         }
         songs
     }
 
     // 先用 /artist/top/song
-    let url_top = format!(
-        "{}/artist/top/song?id={}&limit={}",
-        LOCAL_API_BASE, id, limit
-    );
-    let json_top = get_response_json(client.clone(), url_top).await?;
+    let json_top = request_json(api, |base| {
+        format!("{}/artist/top/song?id={}&limit={}", base, id, limit)
+    })
+    .await?;
     if json_top.get("code").and_then(|v| v.as_u64()).unwrap_or(500) == 200 {
         let artist_name = json_top["artist"]["name"]
             .as_str()
@@ -545,8 +609,7 @@ pub async fn get_artist_top_songs(
     }
 
     // 回退 /artists?id=
-    let url_artists = format!("{}/artists?id={}", LOCAL_API_BASE, id);
-    let json_artists = get_response_json(client, url_artists).await?;
+    let json_artists = request_json(api, |base| format!("{}/artists?id={}", base, id)).await?;
     let code = json_artists
         .get("code")
         .and_then(|v| v.as_u64())
@@ -576,22 +639,12 @@ pub async fn get_artist_top_songs(
 }
 
 /// get song url by file_hash or song id
-#[tauri::command]
-pub async fn get_song_url(id: String) -> Result<String, String> {
-    let client = get_client()?;
-
-    // Check if the id is a hash (for Kugou API) or a numeric ID (for NetEase API)
-    let url = format!("{}/song/url?id={}&level=exhigh", LOCAL_API_BASE, id);
-
-    let response_json: serde_json::Value = get_response_json(client, url.clone()).await?;
-
-    let code = response_json
-        .get("code")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(500);
-    if code != 200 {
-        return Err(format!("API return error: code {}", code));
-    }
+async fn get_song_url(api: &OnlineApiState, id: String) -> Result<String, String> {
+    let response_json = request_json(api, |base| {
+        format!("{}/song/url?id={}&level=exhigh", base, id)
+    })
+    .await?;
+    check_code(&response_json)?;
 
     let data = response_json
         .get("data")
@@ -625,98 +678,30 @@ pub async fn get_song_url(id: String) -> Result<String, String> {
     Ok(play_url.to_string())
 }
 
-/// play online song by id
-#[tauri::command]
-pub async fn play_netease_song(
-    app_handle: tauri::AppHandle,
-    id: String,
-    name: String,
-    artist: String,
-    pic_url: Option<String>,
-) -> Result<PlaySongResult, String> {
-    let cover_id = id.clone();
-    let cover_name = name.clone();
-    let cover_artist = artist.clone();
-    let cover_future = async move {
-        match pic_url.filter(|url| !url.trim().is_empty()) {
-            Some(url) => Ok(url),
-            None => get_song_cover(cover_id, cover_name, cover_artist).await,
-        }
-    };
-    let url_future = async {
-        if crate::music::is_online_audio_cached(&app_handle, &id) {
-            Ok(String::new())
-        } else {
-            get_song_url(id.clone()).await
-        }
-    };
-    let (url_result, cover_result) = tokio::join!(url_future, cover_future);
-    let url = url_result?;
-    let pic_url = cover_result.unwrap_or_default();
-    // 组装结果
-    let result = PlaySongResult {
-        url,
-        id,
-        name,
-        artist,
-        pic_url,
-    };
-
-    Ok(result)
-}
-
-#[tauri::command]
-pub async fn get_song_cover(_id: String, name: String, artist: String) -> Result<String, String> {
-    // Backward-compatible signature: current frontend calls this with (id, name, artist),
-    // but cover can be reliably fetched by id via NetEase /song/detail.
-    // We keep name/artist only for logging/debugging.
-    let id = _id;
+/// 按 id 获取单曲信息（/song/detail），封面也从这里取
+async fn get_song_detail(api: &OnlineApiState, id: String) -> Result<SongInfo, String> {
     if id.trim().is_empty() {
         return Err("Empty song id".to_string());
     }
+    let response_json = request_json(api, |base| {
+        format!("{}/song/detail?ids={}", base, id.trim())
+    })
+    .await?;
+    check_code(&response_json)?;
 
-    let _ = (name, artist);
-    let client = get_client()?;
-    let url = format!("{}/song/detail?ids={}", LOCAL_API_BASE, id);
-    let response_json: serde_json::Value = get_response_json(client, url).await?;
-
-    let code = response_json
-        .get("code")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(500);
-    if code != 200 {
-        return Err(format!("API return error: code {}", code));
-    }
-
-    let songs_value = response_json
+    response_json
         .get("songs")
         .and_then(|v| v.as_array())
-        .ok_or_else(|| "No songs array in response".to_string())?;
-    if songs_value.is_empty() {
-        return Err("No songs in response".to_string());
-    }
-
-    let pic_url = songs_value[0]["al"]["picUrl"]
-        .as_str()
-        .unwrap_or("")
-        .to_string();
-    if pic_url.trim().is_empty() {
-        return Err("Empty cover url".to_string());
-    }
-
-    Ok(pic_url)
+        .and_then(|songs| songs.first())
+        .and_then(song_info_from_json)
+        .ok_or_else(|| format!("Song not found: {}", id))
 }
 
 /// Get song lyrics directly with a single function call
 /// Instead of using search_lyric -> get_lyric -> get_lyric_decoded
-#[tauri::command]
-pub async fn get_song_lyric(id: String) -> Result<String, String> {
-    let client = get_client()?;
-
+async fn get_song_lyric(api: &OnlineApiState, id: String) -> Result<String, String> {
     // We can directly get the lyrics with the song ID
-    let url = format!("{}/lyric?id={}", LOCAL_API_BASE, id);
-
-    let response_json: serde_json::Value = get_response_json(client, url).await?;
+    let response_json = request_json(api, |base| format!("{}/lyric?id={}", base, id)).await?;
 
     // Check if the response contains the lrc field
     let lrc = response_json
@@ -736,3 +721,175 @@ pub async fn get_song_lyric(id: String) -> Result<String, String> {
 
     Ok(content)
 }
+
+/// /album?id= 返回专辑信息和曲目；曲目里的 al 通常不带 picUrl，用专辑封面补上
+fn album_from_json(response_json: &serde_json::Value) -> Result<AlbumResult, String> {
+    check_code(response_json)?;
+    let album = &response_json["album"];
+    let id = album["id"]
+        .as_u64()
+        .map(|v| v.to_string())
+        .ok_or_else(|| "No album in response".to_string())?;
+    let pic_url = album["picUrl"].as_str().unwrap_or("").to_string();
+    let artists = album["artists"]
+        .as_array()
+        .map(|artists| {
+            artists
+                .iter()
+                .filter_map(|artist| artist["name"].as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let songs: Vec<SongInfo> = response_json["songs"]
+        .as_array()
+        .map(|songs| {
+            songs
+                .iter()
+                .filter_map(song_info_from_json)
+                .map(|mut song| {
+                    if song.pic_url.is_empty() {
+                        song.pic_url = pic_url.clone();
+                    }
+                    song
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(AlbumResult {
+        album: AlbumInfo {
+            id,
+            name: album["name"]
+                .as_str()
+                .unwrap_or("unknown album")
+                .to_string(),
+            artists,
+            pic_url,
+            provider: PROVIDER_ID.to_string(),
+        },
+        total: songs.len() as u32,
+        songs,
+    })
+}
+
+async fn get_album(api: &OnlineApiState, id: String) -> Result<AlbumResult, String> {
+    if id.trim().is_empty() {
+        return Err("Empty album id".to_string());
+    }
+    let response_json =
+        request_json(api, |base| format!("{}/album?id={}", base, id.trim())).await?;
+    album_from_json(&response_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::online_api::{OnlineApiSettings, BUNDLED_API_BASE};
+    use crate::test_server::{canned, serve};
+
+    /// 假的 API 实例：/lyric 返回固定歌词，其他路径返回 {"code":200}
+    fn start_fake_api() -> String {
+        serve(|request| async move {
+            let body = if request.path() == "/lyric" {
+                r#"{"code":200,"lrc":{"lyric":"[00:00.00]ok"}}"#
+            } else {
+                r#"{"code":200}"#
+            };
+            canned(200, &[], body)
+        })
+        .base()
+    }
+
+    fn unused_endpoint() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port())
+    }
+
+    #[tokio::test]
+    async fn requests_fail_over_to_the_next_endpoint() {
+        let dead = unused_endpoint();
+        let live = start_fake_api();
+        let api = OnlineApiState::with_settings(OnlineApiSettings {
+            use_bundled_service: false,
+            endpoints: vec![dead.clone(), live.clone()],
+        });
+
+        assert_eq!(
+            get_song_lyric(&api, "1".to_string()).await.unwrap(),
+            "[00:00.00]ok"
+        );
+        // 失败的端点进入冷却，之后先请求健康的端点
+        assert_eq!(api.ordered_endpoints(), vec![live.clone(), dead.clone()]);
+
+        let status = online_service_status(&api).await.unwrap();
+        assert!(status.available);
+        assert_eq!(status.status_code, Some(200));
+        assert_eq!(status.endpoints.len(), 2);
+        assert_eq!(status.endpoints[0].url, dead);
+        assert!(!status.endpoints[0].available);
+        assert_eq!(status.endpoints[0].failures, 2);
+        assert!(status.endpoints[1].available);
+        assert_eq!(status.endpoints[1].successes, 2);
+        assert!(status.endpoints[1].latency_ms.is_some());
+    }
+
+    #[test]
+    fn cloudsearch_url_encodes_keywords_and_uses_offset() {
+        assert_eq!(
+            build_cloudsearch_url(BUNDLED_API_BASE, "周杰伦 稻香", 3, 20),
+            "http://localhost:3000/cloudsearch?keywords=%E5%91%A8%E6%9D%B0%E4%BC%A6%20%E7%A8%BB%E9%A6%99&limit=20&offset=40"
+        );
+    }
+
+    #[test]
+    fn cloudsearch_url_saturates_first_page_offset() {
+        assert_eq!(
+            build_cloudsearch_url(BUNDLED_API_BASE, "a/b", 0, 7),
+            "http://localhost:3000/cloudsearch?keywords=a%2Fb&limit=7&offset=0"
+        );
+    }
+
+    #[test]
+    fn song_info_reads_detail_fields_and_skips_missing_id() {
+        let song = serde_json::json!({
+            "id": 347230,
+            "name": "海阔天空",
+            "ar": [{ "name": "Beyond" }],
+            "al": { "name": "乐与怒", "picUrl": "http://p1.music.126.net/a.jpg" },
+            "dt": 326000
+        });
+        let info = song_info_from_json(&song).unwrap();
+        assert_eq!(info.id, "347230");
+        assert_eq!(info.artists, vec!["Beyond".to_string()]);
+        assert_eq!(info.album, "乐与怒");
+        assert_eq!(info.duration, 326000);
+        assert_eq!(info.file_hash, "347230");
+        assert_eq!(info.provider, PROVIDER_ID);
+
+        assert!(song_info_from_json(&serde_json::json!({ "name": "no id" })).is_none());
+    }
+
+    #[test]
+    fn album_reads_songs_and_falls_back_to_album_cover() {
+        let json = serde_json::json!({
+            "code": 200,
+            "album": {
+                "id": 34720,
+                "name": "乐与怒",
+                "picUrl": "http://p1.music.126.net/album.jpg",
+                "artists": [{ "name": "Beyond" }]
+            },
+            "songs": [
+                { "id": 347230, "name": "海阔天空", "ar": [{ "name": "Beyond" }], "al": { "name": "乐与怒" }, "dt": 326000 },
+                { "name": "no id" }
+            ]
+        });
+        let result = album_from_json(&json).unwrap();
+        assert_eq!(result.album.id, "34720");
+        assert_eq!(result.album.artists, vec!["Beyond".to_string()]);
+        assert_eq!(result.total, 1);
+        assert_eq!(result.songs[0].pic_url, "http://p1.music.126.net/album.jpg");
+
+        assert!(album_from_json(&serde_json::json!({ "code": 404 })).is_err());
+    }
+}
@@ -0,0 +1,392 @@
+// 网易云 API 端点：内置 sidecar 加上用户配置的自建 NeteaseCloudMusicApi 实例，设置保存在 app_data_dir/online_api_settings.json。
+// 每个端点记录成功 / 失败次数与延迟；请求失败的端点进入冷却期，期间排到健康端点之后，netease.rs 按这个顺序故障切换
+
+use crate::storage::{app_data_file, read_json_or_default, write_json};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex as StdMutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+const SETTINGS_FILE: &str = "online_api_settings.json";
+/// 内置服务的默认地址；端口被占用时 service.rs 改用空闲端口并通过 set_bundled_port 更新
+pub const BUNDLED_API_BASE: &str = "http://localhost:3000";
+/// 失败后的冷却时间，连续失败时翻倍，最多 BASE << MAX_COOLDOWN_SHIFT
+const BASE_FAILURE_COOLDOWN: Duration = Duration::from_secs(15);
+const MAX_COOLDOWN_SHIFT: u32 = 4;
+/// 平均延迟的指数滑动权重
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnlineApiSettings {
+    /// 是否使用内置的 sidecar 服务；启用时排在自建实例之前
+    pub use_bundled_service: bool,
+    /// 自建实例地址，按顺序尝试
+    pub endpoints: Vec<String>,
+}
+
+impl Default for OnlineApiSettings {
+    fn default() -> Self {
+        Self {
+            use_bundled_service: true,
+            endpoints: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct EndpointHealth {
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    last_latency_ms: Option<u64>,
+    average_latency_ms: Option<f64>,
+    last_error: Option<String>,
+    cooldown_until: Option<Instant>,
+}
+
+/// 单个端点的健康状况，随 check_online_service_status 返回给前端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointStatus {
+    pub url: String,
+    pub bundled: bool,
+    pub available: bool,
+    pub status_code: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub average_latency_ms: Option<u64>,
+    pub successes: u64,
+    pub failures: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+struct ApiState {
+    settings: OnlineApiSettings,
+    bundled_base: String,
+    health: HashMap<String, EndpointHealth>,
+}
+
+impl Default for ApiState {
+    fn default() -> Self {
+        Self {
+            settings: OnlineApiSettings::default(),
+            bundled_base: BUNDLED_API_BASE.to_string(),
+            health: HashMap::new(),
+        }
+    }
+}
+
+/// 端点设置与健康统计，启动时由 lib.rs 注册为托管状态
+#[derive(Debug, Default)]
+pub struct OnlineApiState(StdMutex<ApiState>);
+
+/// 去掉首尾空白与末尾的 /，只接受 http(s) 地址
+fn normalize_endpoint(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    let parsed =
+        reqwest::Url::parse(url).map_err(|e| format!("Invalid API endpoint {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!(
+            "Invalid API endpoint {}: expected http(s) URL",
+            url
+        ));
+    }
+    Ok(url.to_string())
+}
+
+/// bundled_base 为内置服务当前的地址（端口可能已被换掉），与它相同的自建地址会被去掉
+fn normalize_settings(
+    settings: OnlineApiSettings,
+    bundled_base: &str,
+) -> Result<OnlineApiSettings, String> {
+    let mut endpoints: Vec<String> = Vec::new();
+    for endpoint in &settings.endpoints {
+        let endpoint = normalize_endpoint(endpoint)?;
+        if endpoint != bundled_base && !endpoints.contains(&endpoint) {
+            endpoints.push(endpoint);
+        }
+    }
+    if !settings.use_bundled_service && endpoints.is_empty() {
+        return Err("At least one online API endpoint is required".to_string());
+    }
+    Ok(OnlineApiSettings {
+        use_bundled_service: settings.use_bundled_service,
+        endpoints,
+    })
+}
+
+impl ApiState {
+    fn configured_endpoints(&self) -> Vec<String> {
+        let bundled = self
+            .settings
+            .use_bundled_service
+            .then(|| self.bundled_base.clone());
+        // 内置服务换端口后可能与之前保存的自建地址重合，只保留一份
+        let endpoints = self
+            .settings
+            .endpoints
+            .iter()
+            .filter(|url| bundled.as_ref() != Some(*url))
+            .cloned()
+            .collect::<Vec<_>>();
+        bundled.into_iter().chain(endpoints).collect()
+    }
+
+    /// 不在冷却期的端点保持配置顺序排在前面；冷却中的按冷却结束时间排在后面，全部失败时仍会尝试
+    fn ordered_endpoints(&self, now: Instant) -> Vec<String> {
+        let (mut ready, mut cooling): (Vec<_>, Vec<_>) =
+            self.configured_endpoints().into_iter().partition(|url| {
+                self.health
+                    .get(url)
+                    .and_then(|health| health.cooldown_until)
+                    .is_none_or(|until| until <= now)
+            });
+        cooling.sort_by_key(|url| {
+            self.health
+                .get(url)
+                .and_then(|health| health.cooldown_until)
+        });
+        ready.append(&mut cooling);
+        ready
+    }
+
+    fn record_success(&mut self, url: &str, latency: Duration) {
+        let health = self.health.entry(url.to_string()).or_default();
+        let latency_ms = latency.as_millis() as u64;
+        health.successes += 1;
+        health.consecutive_failures = 0;
+        health.cooldown_until = None;
+        health.last_latency_ms = Some(latency_ms);
+        health.average_latency_ms = Some(match health.average_latency_ms {
+            Some(average) => average + LATENCY_SMOOTHING * (latency_ms as f64 - average),
+            None => latency_ms as f64,
+        });
+    }
+
+    fn record_failure(&mut self, url: &str, error: &str, now: Instant) {
+        let health = self.health.entry(url.to_string()).or_default();
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        health.last_error = Some(error.to_string());
+        let shift = (health.consecutive_failures - 1).min(MAX_COOLDOWN_SHIFT);
+        health.cooldown_until = Some(now + BASE_FAILURE_COOLDOWN * (1 << shift));
+    }
+
+    fn status(&self, url: &str) -> EndpointStatus {
+        let health = self.health.get(url).cloned().unwrap_or_default();
+        EndpointStatus {
+            url: url.to_string(),
+            bundled: self.settings.use_bundled_service && url == self.bundled_base,
+            available: health.successes > 0 && health.consecutive_failures == 0,
+            status_code: None,
+            latency_ms: health.last_latency_ms,
+            average_latency_ms: health.average_latency_ms.map(|ms| ms.round() as u64),
+            successes: health.successes,
+            failures: health.failures,
+            last_error: health.last_error,
+        }
+    }
+}
+
+impl OnlineApiState {
+    /// 启动时载入设置；读取失败时沿用默认值（只用内置服务）
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let settings = app_data_file(app_handle, SETTINGS_FILE)
+            .map(|path| read_json_or_default::<OnlineApiSettings>(&path))
+            .unwrap_or_default();
+        let settings = normalize_settings(settings, BUNDLED_API_BASE).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            OnlineApiSettings::default()
+        });
+        Self::with_settings(settings)
+    }
+
+    pub fn with_settings(settings: OnlineApiSettings) -> Self {
+        Self(StdMutex::new(ApiState {
+            settings,
+            ..ApiState::default()
+        }))
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut ApiState) -> T) -> T {
+        let mut state = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut state)
+    }
+
+    /// 按配置顺序列出的端点（内置服务在前）
+    pub fn configured_endpoints(&self) -> Vec<String> {
+        self.with(|state| state.configured_endpoints())
+    }
+
+    /// 本次请求应依次尝试的端点
+    pub fn ordered_endpoints(&self) -> Vec<String> {
+        self.with(|state| state.ordered_endpoints(Instant::now()))
+    }
+
+    /// 内置服务实际监听的端口变化时调用；旧地址的统计随之丢弃
+    pub fn set_bundled_port(&self, port: u16) {
+        let base = format!("http://localhost:{}", port);
+        self.with(|state| {
+            if state.bundled_base != base {
+                let previous = std::mem::replace(&mut state.bundled_base, base);
+                state.health.remove(&previous);
+            }
+        });
+    }
+
+    pub fn uses_bundled_service(&self) -> bool {
+        self.with(|state| state.settings.use_bundled_service)
+    }
+
+    pub fn record_success(&self, url: &str, latency: Duration) {
+        self.with(|state| state.record_success(url, latency));
+    }
+
+    pub fn record_failure(&self, url: &str, error: &str) {
+        self.with(|state| state.record_failure(url, error, Instant::now()));
+    }
+
+    /// 端点当前的统计数据（available 只反映历史记录，探测结果由调用方覆盖）
+    pub fn endpoint_status(&self, url: &str) -> EndpointStatus {
+        self.with(|state| state.status(url))
+    }
+}
+
+#[tauri::command]
+pub fn get_online_api_settings(state: State<'_, OnlineApiState>) -> OnlineApiSettings {
+    state.with(|state| state.settings.clone())
+}
+
+#[tauri::command]
+pub fn set_online_api_settings(
+    app_handle: AppHandle,
+    state: State<'_, OnlineApiState>,
+    settings: OnlineApiSettings,
+) -> Result<OnlineApiSettings, String> {
+    let bundled_base = state.with(|state| state.bundled_base.clone());
+    let settings = normalize_settings(settings, &bundled_base)?;
+    write_json(&app_data_file(&app_handle, SETTINGS_FILE)?, &settings)?;
+    state.with(|state| {
+        state.settings = settings.clone();
+        let configured = state.configured_endpoints();
+        state.health.retain(|url, _| configured.contains(url));
+    });
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(endpoints: &[&str]) -> ApiState {
+        ApiState {
+            settings: OnlineApiSettings {
+                use_bundled_service: true,
+                endpoints: endpoints.iter().map(|url| url.to_string()).collect(),
+            },
+            ..ApiState::default()
+        }
+    }
+
+    #[test]
+    fn settings_are_normalized_and_validated() {
+        let settings = normalize_settings(
+            OnlineApiSettings {
+                use_bundled_service: false,
+                endpoints: vec![
+                    " https://api.example.com/ ".to_string(),
+                    "https://api.example.com".to_string(),
+                    "http://localhost:3000/".to_string(),
+                    "http://10.0.0.2:3000".to_string(),
+                ],
+            },
+            BUNDLED_API_BASE,
+        )
+        .unwrap();
+        assert_eq!(
+            settings.endpoints,
+            vec!["https://api.example.com", "http://10.0.0.2:3000"]
+        );
+
+        assert!(normalize_endpoint("ftp://example.com").is_err());
+        assert!(normalize_endpoint("not a url").is_err());
+        assert!(normalize_settings(
+            OnlineApiSettings {
+                use_bundled_service: false,
+                endpoints: Vec::new(),
+            },
+            BUNDLED_API_BASE,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn moved_bundled_service_is_not_listed_twice() {
+        let state = OnlineApiState::with_settings(OnlineApiSettings {
+            use_bundled_service: true,
+            endpoints: vec!["http://localhost:3001".to_string(), "http://a".to_string()],
+        });
+        state.set_bundled_port(3001);
+        assert_eq!(
+            state.configured_endpoints(),
+            vec!["http://localhost:3001", "http://a"]
+        );
+
+        // 保存设置时按当前地址去重，原来的默认端口作为自建实例保留
+        let settings = normalize_settings(
+            OnlineApiSettings {
+                use_bundled_service: true,
+                endpoints: vec![
+                    "http://localhost:3001/".to_string(),
+                    BUNDLED_API_BASE.to_string(),
+                ],
+            },
+            "http://localhost:3001",
+        )
+        .unwrap();
+        assert_eq!(settings.endpoints, vec![BUNDLED_API_BASE]);
+    }
+
+    #[test]
+    fn failing_endpoints_cool_down_behind_healthy_ones() {
+        let mut state = state_with(&["http://a", "http://b"]);
+        let now = Instant::now();
+        assert_eq!(
+            state.ordered_endpoints(now),
+            vec![BUNDLED_API_BASE, "http://a", "http://b"]
+        );
+
+        state.record_failure(BUNDLED_API_BASE, "refused", now);
+        state.record_failure("http://a", "refused", now);
+        state.record_failure("http://a", "refused", now);
+        assert_eq!(
+            state.ordered_endpoints(now),
+            vec!["http://b", BUNDLED_API_BASE, "http://a"]
+        );
+        // 冷却结束后恢复配置顺序；成功一次清除冷却
+        assert_eq!(
+            state.ordered_endpoints(now + BASE_FAILURE_COOLDOWN * 2),
+            vec![BUNDLED_API_BASE, "http://a", "http://b"]
+        );
+        state.record_success("http://a", Duration::from_millis(100));
+        assert_eq!(
+            state.ordered_endpoints(now),
+            vec!["http://a", "http://b", BUNDLED_API_BASE]
+        );
+
+        state.record_success("http://a", Duration::from_millis(200));
+        let status = state.status("http://a");
+        assert!(status.available);
+        assert_eq!(status.successes, 2);
+        assert_eq!(status.failures, 2);
+        assert_eq!(status.latency_ms, Some(200));
+        assert_eq!(status.average_latency_ms, Some(130));
+        assert!(!status.bundled);
+        assert!(state.status(BUNDLED_API_BASE).bundled);
+        assert!(!state.status(BUNDLED_API_BASE).available);
+    }
+}
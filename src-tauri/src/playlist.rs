@@ -1,16 +1,26 @@
 // 播放列表持久化：在应用数据目录读写 playlists.json，与前端 Playlist/PlaylistItem 结构一致
 
+use rand::distributions::Alphanumeric;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::io::{BufReader, BufWriter, Write};
+use std::fs::File;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Mutex;
 use tauri::AppHandle;
-use tauri::Manager;
+use tauri::Emitter;
+
+use crate::remote_source;
+use crate::storage;
+use crate::timestamp::now_ms;
 
 const PLAYLISTS_FILE: &str = "playlists.json";
+const PLAYLISTS_CHANGED_EVENT: &str = "playlists-changed";
+
+/// 所有对 playlists.json 的“读-改-写”都在这把锁内完成，避免并发命令互相覆盖。
+static PLAYLISTS_LOCK: Mutex<()> = Mutex::new(());
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SongInfo {
     pub id: String,
     pub name: String,
@@ -21,115 +31,481 @@ pub struct SongInfo {
     pub pic_url: String,
     #[serde(default)]
     pub file_hash: String,
+    #[serde(default = "crate::provider::default_provider")]
+    pub provider: String,
+}
+
+/// 远程 Subsonic 服务器上的歌曲：SongInfo 字段加上所属服务器 id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredRemoteSongInfo")]
+pub struct RemoteSongInfo {
+    pub server_id: String,
+    #[serde(flatten)]
+    pub song: SongInfo,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Deserialize)]
+struct StoredRemoteSongInfo {
+    server_id: String,
+    #[serde(flatten)]
+    song: SongInfo,
+}
+
+/// 旧版本保存的封面地址带有服务器认证参数，读取时换成代理地址
+impl From<StoredRemoteSongInfo> for RemoteSongInfo {
+    fn from(stored: StoredRemoteSongInfo) -> Self {
+        let mut song = stored.song;
+        song.pic_url = remote_source::strip_cover_credentials(&stored.server_id, &song.pic_url);
+        Self {
+            server_id: stored.server_id,
+            song,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum PlaylistItem {
     #[serde(rename = "local")]
     Local { file_name: String },
     #[serde(rename = "online")]
     Online { song: SongInfo },
+    #[serde(rename = "remote")]
+    Remote { song: RemoteSongInfo },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Playlist {
     pub id: String,
     pub name: String,
     pub items: Vec<PlaylistItem>,
     pub created_at: u64,
+    /// 乐观并发版本号：每次修改该播放列表时递增
+    #[serde(default)]
+    pub revision: u64,
 }
 
-fn playlists_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
-    let dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("app_data_dir: {}", e))?;
-    Ok(dir.join(PLAYLISTS_FILE))
+/// 单次修改产生的差异，随 `playlists-changed` 事件发送给所有窗口
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlaylistChange {
+    Created {
+        playlist: Playlist,
+    },
+    Renamed {
+        id: String,
+        name: String,
+        revision: u64,
+    },
+    Deleted {
+        id: String,
+    },
+    ItemsAdded {
+        id: String,
+        index: usize,
+        items: Vec<PlaylistItem>,
+        revision: u64,
+    },
+    ItemsRemoved {
+        id: String,
+        indices: Vec<usize>,
+        revision: u64,
+    },
+    ItemMoved {
+        id: String,
+        from_index: usize,
+        to_index: usize,
+        revision: u64,
+    },
 }
 
-fn unique_temp_path_for(target_path: &Path) -> PathBuf {
-    let unique = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|duration| duration.as_nanos())
-        .unwrap_or(0);
-    let file_name = target_path
-        .file_name()
-        .and_then(|name| name.to_str())
-        .unwrap_or("playlists.json");
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaylistsChangedEvent {
+    pub changes: Vec<PlaylistChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlaylistMutationResult {
+    pub playlist: Option<Playlist>,
+    pub change: PlaylistChange,
+}
 
-    target_path.with_file_name(format!(
-        "{}.{}.{}.tmp",
-        file_name,
-        std::process::id(),
-        unique
-    ))
+pub(crate) fn playlists_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    storage::app_data_file(app_handle, PLAYLISTS_FILE)
 }
 
-fn commit_temp_file(tmp_path: &Path, target_path: &Path) -> Result<(), String> {
-    #[cfg(windows)]
-    if target_path.exists() {
-        fs::remove_file(target_path).map_err(|e| format!("replace playlists file: {}", e))?;
+fn write_playlists_to_path(path: &Path, playlists: &[Playlist]) -> Result<(), String> {
+    storage::write_json(path, playlists)
+}
+
+pub(crate) fn read_playlists_from_path(path: &Path) -> Result<Vec<Playlist>, String> {
+    if !path.exists() {
+        return Ok(vec![]);
     }
+    let f = File::open(path).map_err(|e| format!("open playlists: {}", e))?;
+    let reader = BufReader::new(f);
+    serde_json::from_reader(reader).map_err(|e| format!("parse playlists: {}", e))
+}
 
-    fs::rename(tmp_path, target_path).map_err(|e| format!("commit playlists file: {}", e))
+fn generate_playlist_id() -> String {
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(7)
+        .map(|c| char::from(c).to_ascii_lowercase())
+        .collect();
+    format!("pl_{}_{}", now_ms(), suffix)
 }
 
-fn write_playlists_to_path(path: &Path, playlists: &[Playlist]) -> Result<(), String> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("create app_data_dir: {}", e))?;
+/// 判断两个播放列表项是否为同一首歌（与前端 isSamePlaylistItem 保持一致）
+fn is_same_playlist_item(a: &PlaylistItem, b: &PlaylistItem) -> bool {
+    match (a, b) {
+        (PlaylistItem::Local { file_name: a }, PlaylistItem::Local { file_name: b }) => a == b,
+        (PlaylistItem::Online { song: a }, PlaylistItem::Online { song: b }) => {
+            a.provider == b.provider && a.id == b.id
+        }
+        (PlaylistItem::Remote { song: a }, PlaylistItem::Remote { song: b }) => {
+            a.server_id == b.server_id && a.song.id == b.song.id
+        }
+        _ => false,
+    }
+}
+
+fn find_playlist_mut<'a>(
+    playlists: &'a mut [Playlist],
+    id: &str,
+    expected_revision: Option<u64>,
+) -> Result<&'a mut Playlist, String> {
+    let playlist = playlists
+        .iter_mut()
+        .find(|playlist| playlist.id == id)
+        .ok_or_else(|| format!("playlist not found: {}", id))?;
+    if let Some(expected) = expected_revision {
+        if playlist.revision != expected {
+            return Err(format!(
+                "playlist revision conflict: expected {}, found {}",
+                expected, playlist.revision
+            ));
+        }
+    }
+    Ok(playlist)
+}
+
+fn apply_create(playlists: &mut Vec<Playlist>, id: String, name: &str, now: u64) -> PlaylistChange {
+    let trimmed = name.trim();
+    let playlist = Playlist {
+        id,
+        name: if trimmed.is_empty() {
+            "新建播放列表".to_string()
+        } else {
+            trimmed.to_string()
+        },
+        items: vec![],
+        created_at: now,
+        revision: 1,
+    };
+    playlists.push(playlist.clone());
+    PlaylistChange::Created { playlist }
+}
+
+fn apply_rename(
+    playlists: &mut [Playlist],
+    id: &str,
+    name: &str,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistChange, String> {
+    let playlist = find_playlist_mut(playlists, id, expected_revision)?;
+    let trimmed = name.trim();
+    if !trimmed.is_empty() && trimmed != playlist.name {
+        playlist.name = trimmed.to_string();
+        playlist.revision += 1;
     }
+    Ok(PlaylistChange::Renamed {
+        id: playlist.id.clone(),
+        name: playlist.name.clone(),
+        revision: playlist.revision,
+    })
+}
 
-    let tmp_path = unique_temp_path_for(path);
-    let result: Result<(), String> = (|| {
-        let f =
-            File::create(&tmp_path).map_err(|e| format!("create playlists temp file: {}", e))?;
-        let mut writer = BufWriter::new(f);
-        serde_json::to_writer_pretty(&mut writer, playlists)
-            .map_err(|e| format!("serialize playlists: {}", e))?;
-        writer
-            .flush()
-            .map_err(|e| format!("flush playlists: {}", e))?;
-        writer
-            .get_ref()
-            .sync_all()
-            .map_err(|e| format!("sync playlists: {}", e))?;
-        drop(writer);
-        commit_temp_file(&tmp_path, path)
-    })();
+fn apply_delete(
+    playlists: &mut Vec<Playlist>,
+    id: &str,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistChange, String> {
+    find_playlist_mut(playlists, id, expected_revision)?;
+    playlists.retain(|playlist| playlist.id != id);
+    Ok(PlaylistChange::Deleted { id: id.to_string() })
+}
 
-    if result.is_err() {
-        let _ = fs::remove_file(&tmp_path);
+/// 追加或插入歌曲；已存在的歌曲（以及本次请求内的重复项）会被跳过
+fn apply_add_items(
+    playlists: &mut [Playlist],
+    id: &str,
+    items: Vec<PlaylistItem>,
+    index: Option<usize>,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistChange, String> {
+    let playlist = find_playlist_mut(playlists, id, expected_revision)?;
+    let mut accepted: Vec<PlaylistItem> = Vec::new();
+    for item in items {
+        let exists = playlist
+            .items
+            .iter()
+            .chain(accepted.iter())
+            .any(|existing| is_same_playlist_item(existing, &item));
+        if !exists {
+            accepted.push(item);
+        }
     }
 
-    result
+    let insert_at = index
+        .unwrap_or(playlist.items.len())
+        .min(playlist.items.len());
+    if !accepted.is_empty() {
+        playlist
+            .items
+            .splice(insert_at..insert_at, accepted.iter().cloned());
+        playlist.revision += 1;
+    }
+    Ok(PlaylistChange::ItemsAdded {
+        id: playlist.id.clone(),
+        index: insert_at,
+        items: accepted,
+        revision: playlist.revision,
+    })
+}
+
+fn apply_remove_items(
+    playlists: &mut [Playlist],
+    id: &str,
+    indices: Vec<usize>,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistChange, String> {
+    let playlist = find_playlist_mut(playlists, id, expected_revision)?;
+    let mut indices: Vec<usize> = indices
+        .into_iter()
+        .filter(|index| *index < playlist.items.len())
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+
+    // 从后往前删除，保证前面的下标不被移动
+    for index in indices.iter().rev() {
+        playlist.items.remove(*index);
+    }
+    if !indices.is_empty() {
+        playlist.revision += 1;
+    }
+    Ok(PlaylistChange::ItemsRemoved {
+        id: playlist.id.clone(),
+        indices,
+        revision: playlist.revision,
+    })
+}
+
+fn apply_reorder(
+    playlists: &mut [Playlist],
+    id: &str,
+    from_index: usize,
+    to_index: usize,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistChange, String> {
+    let playlist = find_playlist_mut(playlists, id, expected_revision)?;
+    if from_index >= playlist.items.len() {
+        return Err(format!("playlist index out of range: {}", from_index));
+    }
+    let item = playlist.items.remove(from_index);
+    let to_index = to_index.min(playlist.items.len());
+    playlist.items.insert(to_index, item);
+    if from_index != to_index {
+        playlist.revision += 1;
+    }
+    Ok(PlaylistChange::ItemMoved {
+        id: playlist.id.clone(),
+        from_index,
+        to_index,
+        revision: playlist.revision,
+    })
+}
+
+/// 复制前核对源列表的版本，避免把其他窗口刚改过的旧内容复制出去
+fn apply_duplicate(
+    playlists: &mut Vec<Playlist>,
+    id: &str,
+    new_id: String,
+    name: Option<String>,
+    now: u64,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistChange, String> {
+    let source = find_playlist_mut(playlists, id, expected_revision)?;
+    let name = name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("{} (copy)", source.name));
+    let items = source.items.clone();
+    let playlist = Playlist {
+        id: new_id,
+        name,
+        items,
+        created_at: now,
+        revision: 1,
+    };
+    playlists.push(playlist.clone());
+    Ok(PlaylistChange::Created { playlist })
+}
+
+fn changed_playlist(playlists: &[Playlist], change: &PlaylistChange) -> Option<Playlist> {
+    let id = match change {
+        PlaylistChange::Created { playlist } => return Some(playlist.clone()),
+        PlaylistChange::Renamed { id, .. }
+        | PlaylistChange::ItemsAdded { id, .. }
+        | PlaylistChange::ItemsRemoved { id, .. }
+        | PlaylistChange::ItemMoved { id, .. } => id,
+        PlaylistChange::Deleted { .. } => return None,
+    };
+    playlists
+        .iter()
+        .find(|playlist| &playlist.id == id)
+        .cloned()
+}
+
+/// 在锁内读取、修改并原子写回 playlists.json，随后广播差异
+fn mutate_playlists<F>(app_handle: &AppHandle, mutate: F) -> Result<PlaylistMutationResult, String>
+where
+    F: FnOnce(&mut Vec<Playlist>) -> Result<PlaylistChange, String>,
+{
+    let path = playlists_path(app_handle)?;
+    let (playlist, change) = {
+        let _guard = PLAYLISTS_LOCK
+            .lock()
+            .map_err(|_| "playlists lock poisoned".to_string())?;
+        let mut playlists = read_playlists_from_path(&path)?;
+        let change = mutate(&mut playlists)?;
+        write_playlists_to_path(&path, &playlists)?;
+        (changed_playlist(&playlists, &change), change)
+    };
+
+    if let Err(e) = app_handle.emit(
+        PLAYLISTS_CHANGED_EVENT,
+        PlaylistsChangedEvent {
+            changes: vec![change.clone()],
+        },
+    ) {
+        eprintln!("Failed to emit playlists-changed event: {}", e);
+    }
+    Ok(PlaylistMutationResult { playlist, change })
 }
 
 /// 从应用数据目录读取播放列表
 #[tauri::command]
 pub fn read_playlists(app_handle: AppHandle) -> Result<Vec<Playlist>, String> {
     let path = playlists_path(&app_handle)?;
-    if !path.exists() {
-        return Ok(vec![]);
-    }
-    let f = File::open(&path).map_err(|e| format!("open playlists: {}", e))?;
-    let reader = BufReader::new(f);
-    let list: Vec<Playlist> =
-        serde_json::from_reader(reader).map_err(|e| format!("parse playlists: {}", e))?;
-    Ok(list)
+    let _guard = PLAYLISTS_LOCK
+        .lock()
+        .map_err(|_| "playlists lock poisoned".to_string())?;
+    read_playlists_from_path(&path)
 }
 
-/// 将播放列表写入应用数据目录
 #[tauri::command]
-pub fn write_playlists(app_handle: AppHandle, playlists: Vec<Playlist>) -> Result<(), String> {
-    let path = playlists_path(&app_handle)?;
-    write_playlists_to_path(&path, &playlists)
+pub fn create_playlist(
+    app_handle: AppHandle,
+    name: String,
+) -> Result<PlaylistMutationResult, String> {
+    mutate_playlists(&app_handle, |playlists| {
+        Ok(apply_create(
+            playlists,
+            generate_playlist_id(),
+            &name,
+            now_ms(),
+        ))
+    })
+}
+
+#[tauri::command]
+pub fn rename_playlist(
+    app_handle: AppHandle,
+    id: String,
+    name: String,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistMutationResult, String> {
+    mutate_playlists(&app_handle, |playlists| {
+        apply_rename(playlists, &id, &name, expected_revision)
+    })
+}
+
+#[tauri::command]
+pub fn delete_playlist(
+    app_handle: AppHandle,
+    id: String,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistMutationResult, String> {
+    mutate_playlists(&app_handle, |playlists| {
+        apply_delete(playlists, &id, expected_revision)
+    })
+}
+
+#[tauri::command]
+pub fn add_playlist_items(
+    app_handle: AppHandle,
+    id: String,
+    items: Vec<PlaylistItem>,
+    index: Option<usize>,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistMutationResult, String> {
+    mutate_playlists(&app_handle, |playlists| {
+        apply_add_items(playlists, &id, items, index, expected_revision)
+    })
+}
+
+#[tauri::command]
+pub fn remove_playlist_items(
+    app_handle: AppHandle,
+    id: String,
+    indices: Vec<usize>,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistMutationResult, String> {
+    mutate_playlists(&app_handle, |playlists| {
+        apply_remove_items(playlists, &id, indices, expected_revision)
+    })
+}
+
+#[tauri::command]
+pub fn reorder_playlist_item(
+    app_handle: AppHandle,
+    id: String,
+    from_index: usize,
+    to_index: usize,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistMutationResult, String> {
+    mutate_playlists(&app_handle, |playlists| {
+        apply_reorder(playlists, &id, from_index, to_index, expected_revision)
+    })
+}
+
+#[tauri::command]
+pub fn duplicate_playlist(
+    app_handle: AppHandle,
+    id: String,
+    name: Option<String>,
+    expected_revision: Option<u64>,
+) -> Result<PlaylistMutationResult, String> {
+    mutate_playlists(&app_handle, |playlists| {
+        apply_duplicate(
+            playlists,
+            &id,
+            generate_playlist_id(),
+            name,
+            now_ms(),
+            expected_revision,
+        )
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     fn unique_test_dir(name: &str) -> PathBuf {
         let unique = SystemTime::now()
@@ -139,19 +515,6 @@ mod tests {
         std::env::temp_dir().join(format!("rmusic-{}-{}-{}", name, std::process::id(), unique))
     }
 
-    #[test]
-    fn unique_temp_path_stays_next_to_playlists_file() {
-        let target = Path::new("/tmp/rmusic-data/playlists.json");
-        let tmp = unique_temp_path_for(target);
-
-        assert_eq!(tmp.parent(), target.parent());
-        assert_ne!(tmp, target);
-        assert!(tmp
-            .file_name()
-            .and_then(|name| name.to_str())
-            .is_some_and(|name| { name.starts_with("playlists.json.") && name.ends_with(".tmp") }));
-    }
-
     #[test]
     fn write_playlists_to_path_commits_valid_json() {
         let dir = unique_test_dir("playlist-write");
@@ -163,6 +526,7 @@ mod tests {
                 file_name: "Artist - Song.mp3".into(),
             }],
             created_at: 123,
+            revision: 1,
         }];
 
         write_playlists_to_path(&path, &playlists).unwrap();
@@ -180,4 +544,101 @@ mod tests {
 
         let _ = fs::remove_dir_all(dir);
     }
+
+    fn local(name: &str) -> PlaylistItem {
+        PlaylistItem::Local {
+            file_name: name.into(),
+        }
+    }
+
+    fn sample_playlists() -> Vec<Playlist> {
+        let mut playlists = Vec::new();
+        apply_create(&mut playlists, "pl_a".into(), "  Work ", 1);
+        apply_add_items(
+            &mut playlists,
+            "pl_a",
+            vec![local("A.mp3"), local("B.mp3"), local("C.mp3")],
+            None,
+            None,
+        )
+        .unwrap();
+        playlists
+    }
+
+    #[test]
+    fn add_items_skips_duplicates_and_bumps_revision() {
+        let mut playlists = sample_playlists();
+        assert_eq!(playlists[0].name, "Work");
+        assert_eq!(playlists[0].revision, 2);
+
+        let change = apply_add_items(
+            &mut playlists,
+            "pl_a",
+            vec![local("A.mp3"), local("D.mp3"), local("D.mp3")],
+            Some(1),
+            Some(2),
+        )
+        .unwrap();
+
+        let PlaylistChange::ItemsAdded {
+            index,
+            items,
+            revision,
+            ..
+        } = change
+        else {
+            panic!("unexpected change");
+        };
+        assert_eq!(index, 1);
+        assert_eq!(items, vec![local("D.mp3")]);
+        assert_eq!(revision, 3);
+        assert_eq!(
+            playlists[0].items,
+            vec![
+                local("A.mp3"),
+                local("D.mp3"),
+                local("B.mp3"),
+                local("C.mp3")
+            ]
+        );
+    }
+
+    #[test]
+    fn stale_expected_revision_is_rejected_without_changes() {
+        let mut playlists = sample_playlists();
+        let before = playlists.clone();
+
+        assert!(apply_rename(&mut playlists, "pl_a", "Gym", Some(1)).is_err());
+        assert!(apply_remove_items(&mut playlists, "pl_a", vec![0], Some(1)).is_err());
+        assert_eq!(playlists, before);
+    }
+
+    #[test]
+    fn remove_and_reorder_items_use_original_indices() {
+        let mut playlists = sample_playlists();
+
+        apply_remove_items(&mut playlists, "pl_a", vec![2, 0, 9, 0], None).unwrap();
+        assert_eq!(playlists[0].items, vec![local("B.mp3")]);
+
+        apply_add_items(&mut playlists, "pl_a", vec![local("E.mp3")], None, None).unwrap();
+        apply_reorder(&mut playlists, "pl_a", 1, 0, None).unwrap();
+        assert_eq!(playlists[0].items, vec![local("E.mp3"), local("B.mp3")]);
+        assert!(apply_reorder(&mut playlists, "pl_a", 5, 0, None).is_err());
+    }
+
+    #[test]
+    fn duplicate_and_delete_playlists() {
+        let mut playlists = sample_playlists();
+
+        assert!(apply_duplicate(&mut playlists, "pl_a", "pl_b".into(), None, 2, Some(1)).is_err());
+        assert_eq!(playlists.len(), 1);
+        apply_duplicate(&mut playlists, "pl_a", "pl_b".into(), None, 2, Some(2)).unwrap();
+        assert_eq!(playlists[1].name, "Work (copy)");
+        assert_eq!(playlists[1].items, playlists[0].items);
+        assert_eq!(playlists[1].revision, 1);
+
+        apply_delete(&mut playlists, "pl_a", None).unwrap();
+        assert_eq!(playlists.len(), 1);
+        assert_eq!(playlists[0].id, "pl_b");
+    }
 }
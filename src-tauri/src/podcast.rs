@@ -0,0 +1,1405 @@
+// 播客订阅：RSS / Atom 源与单集列表保存在 app_data_dir/podcasts.json，按设置的间隔后台刷新（带 ETag / Last-Modified 条件请求）。
+// 单集在线播放走在线音频的边下边播路径，也可以下载到 app_data_dir/podcasts/<feed_id>/ 离线收听；收听进度与已播状态随单集保存
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::music::{PlaybackSource, PlaybackTrackIdState};
+use crate::netease;
+use crate::storage::{self, app_data_file};
+use crate::time_stretch::{clamp_rate, PlaybackRateState};
+use crate::timestamp::{days_from_civil, now_ms};
+
+const PODCASTS_FILE: &str = "podcasts.json";
+const DOWNLOADS_DIR: &str = "podcasts";
+const PODCASTS_UPDATED_EVENT: &str = "podcasts-updated";
+const EPISODE_UPDATED_EVENT: &str = "podcast-episode-updated";
+const DOWNLOAD_PROGRESS_EVENT: &str = "podcast-download-progress";
+const DEFAULT_REFRESH_INTERVAL_MINUTES: u32 = 60;
+const MIN_REFRESH_INTERVAL_MINUTES: u32 = 15;
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const FEED_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const DOWNLOAD_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+const DOWNLOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const PROGRESS_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 所有对 podcasts.json 的“读-改-写”都在这把锁内完成；网络请求不持有锁
+static STORE_LOCK: StdMutex<()> = StdMutex::new(());
+/// 正在下载的单集，避免重复下载同一文件
+static ACTIVE_DOWNLOADS: StdMutex<Option<HashSet<String>>> = StdMutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PodcastSettings {
+    /// 后台刷新间隔（分钟），0 表示只手动刷新
+    pub refresh_interval_minutes: u32,
+}
+
+impl Default for PodcastSettings {
+    fn default() -> Self {
+        Self {
+            refresh_interval_minutes: DEFAULT_REFRESH_INTERVAL_MINUTES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PodcastEpisode {
+    /// guid（没有时用音频地址）的摘要，作为文件名与缓存键
+    pub id: String,
+    pub guid: String,
+    pub title: String,
+    /// 节目说明（HTML）
+    pub show_notes: String,
+    pub audio_url: String,
+    pub mime_type: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub duration_ms: u64,
+    pub published_at: Option<u64>,
+    pub image_url: Option<String>,
+    pub link: Option<String>,
+    #[serde(default)]
+    pub position_ms: u64,
+    #[serde(default)]
+    pub played: bool,
+    #[serde(default)]
+    pub download_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PodcastFeed {
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    pub author: Option<String>,
+    pub image_url: Option<String>,
+    pub link: Option<String>,
+    /// 该节目的默认播放速度，None 使用当前速度
+    #[serde(default)]
+    pub playback_rate: Option<f32>,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    #[serde(default)]
+    pub last_checked_at: u64,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    pub subscribed_at: u64,
+    /// 按发布时间倒序
+    pub episodes: Vec<PodcastEpisode>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PodcastStore {
+    settings: PodcastSettings,
+    feeds: Vec<PodcastFeed>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PodcastRefreshResult {
+    pub feed_id: String,
+    pub new_episodes: usize,
+    pub not_modified: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EpisodeUpdatedEvent {
+    feed_id: String,
+    episode: PodcastEpisode,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DownloadProgressEvent {
+    feed_id: String,
+    episode_id: String,
+    downloaded_bytes: u64,
+    total_bytes: Option<u64>,
+}
+
+fn short_digest(value: &str, len: usize) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(value.as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    digest[..len].to_string()
+}
+
+pub fn feed_id_for_url(url: &str) -> String {
+    short_digest(url.trim(), 12)
+}
+
+/// 在线收听时的缓存键，与网易云歌曲 id 不会冲突
+pub fn podcast_cache_key(feed_id: &str, episode_id: &str) -> String {
+    format!("podcast:{}:{}", feed_id, episode_id)
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let prefix = name.get(..3)?.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| *month == prefix)
+        .map(|index| index as u32 + 1)
+}
+
+fn timestamp_ms(date: (i64, u32, u32), time: (i64, i64, i64), offset_minutes: i64) -> Option<u64> {
+    let (year, month, day) = date;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let (hour, minute, second) = time;
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
+        - offset_minutes * 60;
+    u64::try_from(seconds).ok().map(|seconds| seconds * 1000)
+}
+
+/// 时区：Z / GMT / UT、北美缩写，或 +0800 / +08:00
+fn parse_zone_offset(zone: &str) -> Option<i64> {
+    let offset = match zone.to_ascii_uppercase().as_str() {
+        "" | "Z" | "GMT" | "UT" | "UTC" => 0,
+        "EST" => -300,
+        "EDT" => -240,
+        "CST" => -360,
+        "CDT" => -300,
+        "MST" => -420,
+        "MDT" => -360,
+        "PST" => -480,
+        "PDT" => -420,
+        _ => {
+            let sign = match zone.as_bytes().first()? {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let digits: String = zone[1..].chars().filter(|c| *c != ':').collect();
+            if digits.len() != 4 {
+                return None;
+            }
+            let hours: i64 = digits[..2].parse().ok()?;
+            let minutes: i64 = digits[2..].parse().ok()?;
+            sign * (hours * 60 + minutes)
+        }
+    };
+    Some(offset)
+}
+
+fn parse_clock(text: &str) -> Option<(i64, i64, i64)> {
+    let mut parts = text.split(':');
+    let hour = parts.next()?.parse().ok()?;
+    let minute = parts.next()?.parse().ok()?;
+    let second = match parts.next() {
+        Some(second) => second.split('.').next()?.parse().ok()?,
+        None => 0,
+    };
+    Some((hour, minute, second))
+}
+
+/// RSS pubDate：`Wed, 02 Oct 2002 13:00:00 GMT`
+fn parse_rfc2822(text: &str) -> Option<u64> {
+    let text = text.split_once(',').map_or(text, |(_, rest)| rest);
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    let day = parts.first()?.parse().ok()?;
+    let month = month_number(parts.get(1)?)?;
+    let year: i64 = parts.get(2)?.parse().ok()?;
+    let year = match year {
+        0..=49 => year + 2000,
+        50..=99 => year + 1900,
+        _ => year,
+    };
+    let time = parse_clock(parts.get(3)?)?;
+    let offset = parse_zone_offset(parts.get(4).copied().unwrap_or(""))?;
+    timestamp_ms((year, month, day), time, offset)
+}
+
+/// Atom 时间：`2003-12-13T18:30:02.25+01:00`
+fn parse_rfc3339(text: &str) -> Option<u64> {
+    let (date, time) = text.split_once(['T', 't', ' '])?;
+    let mut date_parts = date.split('-');
+    let year = date_parts.next()?.parse().ok()?;
+    let month = date_parts.next()?.parse().ok()?;
+    let day = date_parts.next()?.parse().ok()?;
+    let zone_start = time
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| matches!(c, 'Z' | 'z' | '+' | '-'))
+        .map_or(time.len(), |(index, _)| index);
+    let clock = parse_clock(&time[..zone_start])?;
+    let offset = parse_zone_offset(&time[zone_start..])?;
+    timestamp_ms((year, month, day), clock, offset)
+}
+
+fn parse_feed_date(text: &str) -> Option<u64> {
+    let text = text.trim();
+    parse_rfc3339(text).or_else(|| parse_rfc2822(text))
+}
+
+/// itunes:duration：`1:02:03`、`62:03` 或秒数
+fn parse_duration_ms(text: &str) -> u64 {
+    let seconds = text
+        .trim()
+        .split(':')
+        .try_fold(0f64, |total, part| {
+            part.trim()
+                .parse::<f64>()
+                .ok()
+                .map(|value| total * 60.0 + value)
+        })
+        .unwrap_or(0.0);
+    (seconds.max(0.0) * 1000.0) as u64
+}
+
+fn tag_name(start: &BytesStart) -> String {
+    String::from_utf8_lossy(start.name().as_ref()).into_owned()
+}
+
+fn attribute(start: &BytesStart, key: &str) -> Option<String> {
+    start
+        .try_get_attribute(key)
+        .ok()
+        .flatten()
+        .and_then(|attribute| attribute.unescape_value().ok())
+        .and_then(|value| non_empty(value.into_owned()))
+}
+
+#[derive(Debug, Default)]
+struct EpisodeDraft {
+    guid: Option<String>,
+    title: String,
+    content: Option<String>,
+    summary: Option<String>,
+    audio_url: Option<String>,
+    mime_type: Option<String>,
+    size_bytes: Option<u64>,
+    duration_ms: u64,
+    published_at: Option<u64>,
+    updated_at: Option<u64>,
+    image_url: Option<String>,
+    link: Option<String>,
+}
+
+impl EpisodeDraft {
+    fn set_enclosure(&mut self, start: &BytesStart, url_key: &str) {
+        if self.audio_url.is_some() {
+            return;
+        }
+        self.audio_url = attribute(start, url_key);
+        self.mime_type = attribute(start, "type");
+        self.size_bytes = attribute(start, "length")
+            .or_else(|| attribute(start, "fileSize"))
+            .and_then(|size| size.parse().ok())
+            .filter(|size| *size > 0);
+    }
+
+    fn set_attributes(&mut self, name: &str, start: &BytesStart) {
+        match name {
+            "enclosure" => self.set_enclosure(start, "url"),
+            "media:content" => {
+                let is_audio =
+                    attribute(start, "type").is_none_or(|kind| kind.starts_with("audio"));
+                if is_audio {
+                    self.set_enclosure(start, "url");
+                }
+            }
+            "link" => match attribute(start, "rel").as_deref() {
+                Some("enclosure") => self.set_enclosure(start, "href"),
+                Some("alternate") | None => {
+                    if let Some(href) = attribute(start, "href") {
+                        self.link.get_or_insert(href);
+                    }
+                }
+                _ => {}
+            },
+            "itunes:image" => self.image_url = attribute(start, "href").or(self.image_url.take()),
+            _ => {}
+        }
+    }
+
+    fn set_text(&mut self, name: &str, value: String) {
+        match name {
+            "guid" | "id" => self.guid = non_empty(value),
+            "title" => self.title = value,
+            "content:encoded" | "content" => self.content = non_empty(value),
+            "description" | "summary" | "itunes:summary" if self.summary.is_none() => {
+                self.summary = non_empty(value);
+            }
+            "pubDate" | "published" | "dc:date" => self.published_at = parse_feed_date(&value),
+            "updated" => self.updated_at = parse_feed_date(&value),
+            "itunes:duration" => self.duration_ms = parse_duration_ms(&value),
+            "link" => {
+                if let Some(link) = non_empty(value) {
+                    self.link = Some(link);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 没有音频附件的条目（纯文字文章）不算单集
+    fn finish(self) -> Option<PodcastEpisode> {
+        let audio_url = self.audio_url?;
+        let guid = self.guid.unwrap_or_else(|| audio_url.clone());
+        Some(PodcastEpisode {
+            id: short_digest(&guid, 16),
+            guid,
+            title: if self.title.trim().is_empty() {
+                audio_url.clone()
+            } else {
+                self.title.trim().to_string()
+            },
+            show_notes: self.content.or(self.summary).unwrap_or_default(),
+            audio_url,
+            mime_type: self.mime_type,
+            size_bytes: self.size_bytes,
+            duration_ms: self.duration_ms,
+            published_at: self.published_at.or(self.updated_at),
+            image_url: self.image_url,
+            link: self.link,
+            ..PodcastEpisode::default()
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct ParsedFeed {
+    title: String,
+    description: String,
+    author: Option<String>,
+    image_url: Option<String>,
+    link: Option<String>,
+    episodes: Vec<PodcastEpisode>,
+}
+
+impl ParsedFeed {
+    fn set_attributes(&mut self, name: &str, start: &BytesStart) {
+        match name {
+            "itunes:image" => self.image_url = attribute(start, "href").or(self.image_url.take()),
+            "link" if matches!(attribute(start, "rel").as_deref(), Some("alternate") | None) => {
+                if let Some(href) = attribute(start, "href") {
+                    self.link.get_or_insert(href);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set_text(&mut self, name: &str, parent: &str, value: String) {
+        match (parent, name) {
+            ("channel" | "feed", "title") => self.title = value,
+            ("channel" | "feed", "description" | "subtitle" | "itunes:summary")
+                if self.description.is_empty() =>
+            {
+                self.description = value;
+            }
+            ("channel" | "feed", "itunes:author") | ("author", "name") => {
+                self.author = non_empty(value).or(self.author.take());
+            }
+            ("image", "url") | ("feed", "logo" | "icon") if self.image_url.is_none() => {
+                self.image_url = non_empty(value);
+            }
+            ("channel", "link") => self.link = non_empty(value).or(self.link.take()),
+            _ => {}
+        }
+    }
+}
+
+/// 同时支持 RSS 2.0（含 iTunes 扩展）与 Atom
+fn parse_feed(xml: &str) -> Result<ParsedFeed, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut feed = ParsedFeed::default();
+    let mut path: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut entry: Option<EpisodeDraft> = None;
+    let mut is_feed = false;
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("parse feed error: {}", e))?;
+        match event {
+            Event::Start(start) if path.is_empty() => {
+                let name = tag_name(&start);
+                is_feed = matches!(name.as_str(), "rss" | "feed" | "rdf:RDF");
+                if !is_feed {
+                    return Err(format!("not a podcast feed: <{}>", name));
+                }
+                path.push(name);
+            }
+            Event::Start(start) => {
+                let name = tag_name(&start);
+                if matches!(name.as_str(), "item" | "entry") {
+                    entry = Some(EpisodeDraft::default());
+                }
+                match entry.as_mut() {
+                    Some(draft) => draft.set_attributes(&name, &start),
+                    None => feed.set_attributes(&name, &start),
+                }
+                path.push(name);
+                text.clear();
+            }
+            Event::Empty(start) => {
+                let name = tag_name(&start);
+                match entry.as_mut() {
+                    Some(draft) => draft.set_attributes(&name, &start),
+                    None => feed.set_attributes(&name, &start),
+                }
+            }
+            Event::Text(value) => match value.unescape() {
+                Ok(value) => text.push_str(&value),
+                // 节目说明里常见 &nbsp; 等 HTML 实体，按原文保留
+                Err(_) => text.push_str(&String::from_utf8_lossy(&value)),
+            },
+            Event::CData(value) => text.push_str(&String::from_utf8_lossy(&value.into_inner())),
+            Event::End(_) => {
+                let name = path.pop().unwrap_or_default();
+                let value = std::mem::take(&mut text).trim().to_string();
+                if matches!(name.as_str(), "item" | "entry") {
+                    if let Some(episode) = entry.take().and_then(EpisodeDraft::finish) {
+                        feed.episodes.push(episode);
+                    }
+                } else if let Some(draft) = entry.as_mut() {
+                    draft.set_text(&name, value);
+                } else {
+                    let parent = path.last().map(String::as_str).unwrap_or_default();
+                    feed.set_text(&name, parent, value);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if !is_feed {
+        return Err("empty feed".to_string());
+    }
+    sort_episodes(&mut feed.episodes);
+    Ok(feed)
+}
+
+fn sort_episodes(episodes: &mut [PodcastEpisode]) {
+    episodes.sort_by_key(|episode| std::cmp::Reverse(episode.published_at));
+}
+
+/// 新内容覆盖单集信息，保留收听进度、已播与下载状态；源里已移除的旧单集继续保留
+fn merge_episodes(
+    existing: &[PodcastEpisode],
+    fetched: Vec<PodcastEpisode>,
+) -> (Vec<PodcastEpisode>, usize) {
+    let previous: HashMap<&str, &PodcastEpisode> = existing
+        .iter()
+        .map(|episode| (episode.id.as_str(), episode))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut new_episodes = 0;
+    let mut merged = Vec::with_capacity(fetched.len().max(existing.len()));
+    for mut episode in fetched {
+        if !seen.insert(episode.id.clone()) {
+            continue;
+        }
+        match previous.get(episode.id.as_str()) {
+            Some(old) => {
+                episode.position_ms = old.position_ms;
+                episode.played = old.played;
+                episode.download_path = old.download_path.clone();
+            }
+            None => new_episodes += 1,
+        }
+        merged.push(episode);
+    }
+    merged.extend(
+        existing
+            .iter()
+            .filter(|episode| !seen.contains(&episode.id))
+            .cloned(),
+    );
+    sort_episodes(&mut merged);
+    (merged, new_episodes)
+}
+
+enum FeedFetch {
+    NotModified,
+    Updated {
+        body: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+fn response_header(
+    response: &reqwest::Response,
+    name: reqwest::header::HeaderName,
+) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| non_empty(value.to_string()))
+}
+
+/// 带上次的 ETag / Last-Modified 发起条件请求，源未变化时服务器返回 304
+async fn fetch_feed(
+    url: &str,
+    etag: Option<&str>,
+    last_modified: Option<&str>,
+) -> Result<FeedFetch, String> {
+    let client = netease::get_client()?;
+    let mut request = client.get(url);
+    if let Some(etag) = etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+    }
+    let response = tokio::time::timeout(FEED_REQUEST_TIMEOUT, request.send())
+        .await
+        .map_err(|_| {
+            format!(
+                "fetch feed timed out after {}s",
+                FEED_REQUEST_TIMEOUT.as_secs()
+            )
+        })?
+        .map_err(|e| format!("fetch feed error: {}", e))?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(FeedFetch::NotModified);
+    }
+    if !response.status().is_success() {
+        return Err(format!("fetch feed error: HTTP {}", response.status()));
+    }
+    let etag = response_header(&response, reqwest::header::ETAG);
+    let last_modified = response_header(&response, reqwest::header::LAST_MODIFIED);
+    let body = tokio::time::timeout(FEED_REQUEST_TIMEOUT, response.text())
+        .await
+        .map_err(|_| {
+            format!(
+                "read feed timed out after {}s",
+                FEED_REQUEST_TIMEOUT.as_secs()
+            )
+        })?
+        .map_err(|e| format!("read feed error: {}", e))?;
+    Ok(FeedFetch::Updated {
+        body,
+        etag,
+        last_modified,
+    })
+}
+
+fn store_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_data_file(app_handle, PODCASTS_FILE)
+}
+
+fn downloads_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_data_file(app_handle, DOWNLOADS_DIR)
+}
+
+/// 文件损坏时返回错误而不是空的订阅列表，避免下一次写入把原文件覆盖掉
+fn read_store(path: &Path) -> Result<PodcastStore, String> {
+    storage::read_json(path)
+}
+
+fn update_store<T>(
+    path: &Path,
+    update: impl FnOnce(&mut PodcastStore) -> Result<T, String>,
+) -> Result<T, String> {
+    let _guard = STORE_LOCK
+        .lock()
+        .map_err(|_| "podcast store lock poisoned".to_string())?;
+    let mut store = read_store(path)?;
+    let result = update(&mut store)?;
+    storage::write_json(path, &store)?;
+    Ok(result)
+}
+
+fn find_feed<'a>(
+    store: &'a mut PodcastStore,
+    feed_id: &str,
+) -> Result<&'a mut PodcastFeed, String> {
+    store
+        .feeds
+        .iter_mut()
+        .find(|feed| feed.id == feed_id)
+        .ok_or_else(|| format!("podcast not found: {}", feed_id))
+}
+
+fn find_episode<'a>(
+    store: &'a mut PodcastStore,
+    feed_id: &str,
+    episode_id: &str,
+) -> Result<&'a mut PodcastEpisode, String> {
+    find_feed(store, feed_id)?
+        .episodes
+        .iter_mut()
+        .find(|episode| episode.id == episode_id)
+        .ok_or_else(|| format!("podcast episode not found: {}", episode_id))
+}
+
+async fn subscribe_at(path: &Path, url: &str) -> Result<PodcastFeed, String> {
+    let url = url.trim();
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("invalid feed url: {}", url));
+    }
+    let feed_id = feed_id_for_url(url);
+    if let Some(feed) = read_store(path)?
+        .feeds
+        .into_iter()
+        .find(|feed| feed.id == feed_id)
+    {
+        return Ok(feed);
+    }
+    let FeedFetch::Updated {
+        body,
+        etag,
+        last_modified,
+    } = fetch_feed(url, None, None).await?
+    else {
+        return Err("feed server returned 304 without a cached copy".to_string());
+    };
+    let parsed = parse_feed(&body)?;
+    let now = now_ms();
+    let feed = PodcastFeed {
+        id: feed_id,
+        url: url.to_string(),
+        title: if parsed.title.trim().is_empty() {
+            url.to_string()
+        } else {
+            parsed.title
+        },
+        description: parsed.description,
+        author: parsed.author,
+        image_url: parsed.image_url,
+        link: parsed.link,
+        playback_rate: None,
+        etag,
+        last_modified,
+        last_checked_at: now,
+        last_error: None,
+        subscribed_at: now,
+        episodes: parsed.episodes,
+    };
+    update_store(path, |store| {
+        if let Some(existing) = store.feeds.iter().find(|existing| existing.id == feed.id) {
+            return Ok(existing.clone());
+        }
+        store.feeds.push(feed.clone());
+        Ok(feed)
+    })
+}
+
+async fn refresh_at(path: &Path, feed_id: &str) -> Result<PodcastRefreshResult, String> {
+    let feed = read_store(path)?
+        .feeds
+        .into_iter()
+        .find(|feed| feed.id == feed_id)
+        .ok_or_else(|| format!("podcast not found: {}", feed_id))?;
+    let fetched = fetch_feed(
+        &feed.url,
+        feed.etag.as_deref(),
+        feed.last_modified.as_deref(),
+    )
+    .await
+    .and_then(|fetched| match fetched {
+        FeedFetch::NotModified => Ok(None),
+        FeedFetch::Updated {
+            body,
+            etag,
+            last_modified,
+        } => Ok(Some((parse_feed(&body)?, etag, last_modified))),
+    });
+
+    update_store(path, |store| {
+        let feed = find_feed(store, feed_id)?;
+        feed.last_checked_at = now_ms();
+        let mut result = PodcastRefreshResult {
+            feed_id: feed_id.to_string(),
+            new_episodes: 0,
+            not_modified: false,
+            error: None,
+        };
+        match fetched {
+            Ok(None) => {
+                feed.last_error = None;
+                result.not_modified = true;
+            }
+            Ok(Some((parsed, etag, last_modified))) => {
+                if !parsed.title.trim().is_empty() {
+                    feed.title = parsed.title;
+                }
+                feed.description = parsed.description;
+                feed.author = parsed.author;
+                feed.image_url = parsed.image_url;
+                feed.link = parsed.link;
+                feed.etag = etag;
+                feed.last_modified = last_modified;
+                feed.last_error = None;
+                let (episodes, new_episodes) = merge_episodes(&feed.episodes, parsed.episodes);
+                feed.episodes = episodes;
+                result.new_episodes = new_episodes;
+            }
+            // 记录失败原因和检查时间，避免后台每分钟重试
+            Err(error) => {
+                feed.last_error = Some(error.clone());
+                result.error = Some(error);
+            }
+        }
+        Ok(result)
+    })
+}
+
+fn update_episode(
+    path: &Path,
+    feed_id: &str,
+    episode_id: &str,
+    update: impl FnOnce(&mut PodcastEpisode),
+) -> Result<PodcastEpisode, String> {
+    update_store(path, |store| {
+        let episode = find_episode(store, feed_id, episode_id)?;
+        update(episode);
+        Ok(episode.clone())
+    })
+}
+
+fn emit_episode_updated(app_handle: &AppHandle, feed_id: &str, episode: PodcastEpisode) {
+    let event = EpisodeUpdatedEvent {
+        feed_id: feed_id.to_string(),
+        episode,
+    };
+    if let Err(error) = app_handle.emit(EPISODE_UPDATED_EVENT, event) {
+        eprintln!("Failed to emit podcast episode update: {}", error);
+    }
+}
+
+/// 播放单集：已下载时播放本地文件，否则按在线音频边下边播
+pub fn resolve_playback_source(
+    app_handle: &AppHandle,
+    feed_id: &str,
+    episode_id: &str,
+) -> Result<PlaybackSource, String> {
+    let mut store = read_store(&store_path(app_handle)?)?;
+    let episode = find_episode(&mut store, feed_id, episode_id)?;
+    if let Some(path) = episode
+        .download_path
+        .as_ref()
+        .filter(|path| Path::new(path).is_file())
+    {
+        return Ok(PlaybackSource::Local {
+            path: path.clone(),
+            range: None,
+        });
+    }
+    Ok(PlaybackSource::Online {
+        url: episode.audio_url.clone(),
+        cache_key: podcast_cache_key(feed_id, episode_id),
+    })
+}
+
+/// 单集开始播放后定期记录收听位置；切换曲目时保存最后位置，自然播完则标记为已播
+pub fn track_episode_progress(
+    app_handle: &AppHandle,
+    feed_id: String,
+    episode_id: String,
+    track_id: u64,
+) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let Ok(path) = store_path(&app_handle) else {
+            return;
+        };
+        let current_track_id = Arc::clone(&app_handle.state::<PlaybackTrackIdState>().0);
+        let sink = Arc::clone(app_handle.state::<Arc<Mutex<rodio::Sink>>>().inner());
+        let playback_rate = app_handle.state::<PlaybackRateState>().inner().clone();
+        let mut last_position_ms = None;
+        let mut saved_position_ms = None;
+        let mut last_saved_at = Instant::now();
+        let mut played = false;
+        loop {
+            tokio::time::sleep(PROGRESS_POLL_INTERVAL).await;
+            if *current_track_id.lock().await != track_id {
+                break;
+            }
+            let (is_empty, position_ms) = {
+                let sink = sink.lock().await;
+                (sink.empty(), playback_rate.position().as_millis() as u64)
+            };
+            if is_empty && position_ms > 0 {
+                played = true;
+                break;
+            }
+            last_position_ms = Some(position_ms);
+            if last_saved_at.elapsed() >= PROGRESS_SAVE_INTERVAL
+                && saved_position_ms != last_position_ms
+            {
+                last_saved_at = Instant::now();
+                saved_position_ms = last_position_ms;
+                if let Err(error) = update_episode(&path, &feed_id, &episode_id, |episode| {
+                    episode.position_ms = position_ms;
+                }) {
+                    eprintln!("Failed to save podcast progress: {}", error);
+                    return;
+                }
+            }
+        }
+
+        let result = if played {
+            update_episode(&path, &feed_id, &episode_id, |episode| {
+                episode.played = true;
+                episode.position_ms = 0;
+            })
+        } else if let Some(position_ms) =
+            last_position_ms.filter(|_| saved_position_ms != last_position_ms)
+        {
+            update_episode(&path, &feed_id, &episode_id, |episode| {
+                episode.position_ms = position_ms;
+            })
+        } else {
+            return;
+        };
+        match result {
+            Ok(episode) => emit_episode_updated(&app_handle, &feed_id, episode),
+            Err(error) => eprintln!("Failed to save podcast progress: {}", error),
+        }
+    });
+}
+
+fn episode_extension(episode: &PodcastEpisode) -> String {
+    let from_url = episode
+        .audio_url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .filter(|extension| {
+            (2..=4).contains(&extension.len())
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    from_url.unwrap_or_else(|| {
+        match episode.mime_type.as_deref().unwrap_or_default() {
+            "audio/mp4" | "audio/x-m4a" | "audio/m4a" => "m4a",
+            "audio/ogg" | "audio/vorbis" => "ogg",
+            "audio/opus" => "opus",
+            "audio/aac" | "audio/aacp" => "aac",
+            "audio/flac" | "audio/x-flac" => "flac",
+            "audio/wav" | "audio/x-wav" => "wav",
+            _ => "mp3",
+        }
+        .to_string()
+    })
+}
+
+/// 下载期间占用的单集键，结束（包括失败）时释放
+struct DownloadGuard(String);
+
+impl DownloadGuard {
+    fn acquire(key: String) -> Result<Self, String> {
+        let mut active = ACTIVE_DOWNLOADS
+            .lock()
+            .map_err(|_| "podcast download lock poisoned".to_string())?;
+        if !active.get_or_insert_with(HashSet::new).insert(key.clone()) {
+            return Err("episode is already downloading".to_string());
+        }
+        Ok(Self(key))
+    }
+}
+
+impl Drop for DownloadGuard {
+    fn drop(&mut self) {
+        if let Ok(mut active) = ACTIVE_DOWNLOADS.lock() {
+            if let Some(active) = active.as_mut() {
+                active.remove(&self.0);
+            }
+        }
+    }
+}
+
+async fn download_episode_at(
+    path: &Path,
+    downloads_dir: &Path,
+    feed_id: &str,
+    episode_id: &str,
+    on_progress: impl Fn(u64, Option<u64>),
+) -> Result<PodcastEpisode, String> {
+    let mut store = read_store(path)?;
+    let episode = find_episode(&mut store, feed_id, episode_id)?.clone();
+    if episode
+        .download_path
+        .as_ref()
+        .is_some_and(|path| Path::new(path).is_file())
+    {
+        return Ok(episode);
+    }
+    let _guard = DownloadGuard::acquire(podcast_cache_key(feed_id, episode_id))?;
+
+    let target_dir = downloads_dir.join(feed_id);
+    tokio::fs::create_dir_all(&target_dir)
+        .await
+        .map_err(|e| format!("create podcast download dir error: {}", e))?;
+    let target = target_dir.join(format!("{}.{}", episode_id, episode_extension(&episode)));
+    let part = target.with_extension("part");
+
+    let client = netease::get_client()?;
+    let mut response = netease::get_response(client, episode.audio_url.clone()).await?;
+    let total_bytes = response.content_length().or(episode.size_bytes);
+    let result: Result<(), String> = async {
+        let mut file = tokio::fs::File::create(&part)
+            .await
+            .map_err(|e| format!("create podcast download file error: {}", e))?;
+        let mut downloaded = 0u64;
+        let mut last_report = Instant::now();
+        while let Some(chunk) = tokio::time::timeout(DOWNLOAD_IDLE_TIMEOUT, response.chunk())
+            .await
+            .map_err(|_| {
+                format!(
+                    "podcast download stalled for {}s",
+                    DOWNLOAD_IDLE_TIMEOUT.as_secs()
+                )
+            })?
+            .map_err(|e| format!("read response data error: {}", e))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("write podcast download error: {}", e))?;
+            downloaded += chunk.len() as u64;
+            if last_report.elapsed() >= DOWNLOAD_PROGRESS_INTERVAL {
+                last_report = Instant::now();
+                on_progress(downloaded, total_bytes);
+            }
+        }
+        file.flush()
+            .await
+            .map_err(|e| format!("flush podcast download error: {}", e))?;
+        drop(file);
+        on_progress(downloaded, total_bytes);
+        tokio::fs::rename(&part, &target)
+            .await
+            .map_err(|e| format!("save podcast download error: {}", e))
+    }
+    .await;
+    if let Err(error) = result {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(error);
+    }
+
+    let download_path = target.to_string_lossy().to_string();
+    update_episode(path, feed_id, episode_id, |episode| {
+        episode.download_path = Some(download_path);
+    })
+}
+
+/// 后台按设置的间隔刷新所有订阅，有新单集时通知前端
+pub fn start_podcast_refresh(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Ok(path) = store_path(&app_handle) else {
+                return;
+            };
+            let store = match read_store(&path) {
+                Ok(store) => store,
+                Err(e) => {
+                    eprintln!("Podcast auto refresh skipped: {}", e);
+                    continue;
+                }
+            };
+            let interval_minutes = store.settings.refresh_interval_minutes;
+            if interval_minutes == 0 {
+                continue;
+            }
+            let due_before = now_ms().saturating_sub(interval_minutes as u64 * 60_000);
+            let due: Vec<String> = store
+                .feeds
+                .iter()
+                .filter(|feed| feed.last_checked_at <= due_before)
+                .map(|feed| feed.id.clone())
+                .collect();
+            let mut results = Vec::new();
+            for feed_id in due {
+                match refresh_at(&path, &feed_id).await {
+                    Ok(result) => results.push(result),
+                    Err(error) => eprintln!("Failed to refresh podcast {}: {}", feed_id, error),
+                }
+            }
+            if results.iter().any(|result| result.new_episodes > 0) {
+                if let Err(error) = app_handle.emit(PODCASTS_UPDATED_EVENT, results) {
+                    eprintln!("Failed to emit podcasts update: {}", error);
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_podcasts(app_handle: AppHandle) -> Result<Vec<PodcastFeed>, String> {
+    Ok(read_store(&store_path(&app_handle)?)?.feeds)
+}
+
+#[tauri::command]
+pub async fn subscribe_podcast(app_handle: AppHandle, url: String) -> Result<PodcastFeed, String> {
+    subscribe_at(&store_path(&app_handle)?, &url).await
+}
+
+/// 取消订阅时一并删除已下载的单集
+#[tauri::command]
+pub fn unsubscribe_podcast(app_handle: AppHandle, feed_id: String) -> Result<(), String> {
+    update_store(&store_path(&app_handle)?, |store| {
+        store.feeds.retain(|feed| feed.id != feed_id);
+        Ok(())
+    })?;
+    let dir = downloads_dir(&app_handle)?.join(&feed_id);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| format!("remove podcast downloads {}: {}", dir.display(), e))?;
+    }
+    Ok(())
+}
+
+/// 指定 feed_id 时只刷新该节目并返回其错误；否则刷新全部，单个失败记录在结果里
+#[tauri::command]
+pub async fn refresh_podcasts(
+    app_handle: AppHandle,
+    feed_id: Option<String>,
+) -> Result<Vec<PodcastRefreshResult>, String> {
+    let path = store_path(&app_handle)?;
+    if let Some(feed_id) = feed_id {
+        let result = refresh_at(&path, &feed_id).await?;
+        return match result.error {
+            Some(error) => Err(error),
+            None => Ok(vec![result]),
+        };
+    }
+    let feed_ids: Vec<String> = read_store(&path)?
+        .feeds
+        .into_iter()
+        .map(|feed| feed.id)
+        .collect();
+    let mut results = Vec::with_capacity(feed_ids.len());
+    for feed_id in feed_ids {
+        results.push(refresh_at(&path, &feed_id).await?);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn get_podcast_settings(app_handle: AppHandle) -> Result<PodcastSettings, String> {
+    Ok(read_store(&store_path(&app_handle)?)?.settings)
+}
+
+#[tauri::command]
+pub fn set_podcast_settings(
+    app_handle: AppHandle,
+    settings: PodcastSettings,
+) -> Result<PodcastSettings, String> {
+    let mut settings = settings;
+    if settings.refresh_interval_minutes > 0 {
+        settings.refresh_interval_minutes = settings
+            .refresh_interval_minutes
+            .max(MIN_REFRESH_INTERVAL_MINUTES);
+    }
+    update_store(&store_path(&app_handle)?, |store| {
+        store.settings = settings.clone();
+        Ok(settings)
+    })
+}
+
+/// 手动标记已播 / 未播；两种情况都从头开始
+#[tauri::command]
+pub fn set_podcast_episode_played(
+    app_handle: AppHandle,
+    feed_id: String,
+    episode_id: String,
+    played: bool,
+) -> Result<PodcastEpisode, String> {
+    update_episode(
+        &store_path(&app_handle)?,
+        &feed_id,
+        &episode_id,
+        |episode| {
+            episode.played = played;
+            episode.position_ms = 0;
+        },
+    )
+}
+
+/// 节目的默认播放速度；None 表示沿用当前速度
+#[tauri::command]
+pub fn set_podcast_playback_rate(
+    app_handle: AppHandle,
+    feed_id: String,
+    rate: Option<f32>,
+) -> Result<PodcastFeed, String> {
+    update_store(&store_path(&app_handle)?, |store| {
+        let feed = find_feed(store, &feed_id)?;
+        feed.playback_rate = rate.map(clamp_rate);
+        Ok(feed.clone())
+    })
+}
+
+#[tauri::command]
+pub async fn download_podcast_episode(
+    app_handle: AppHandle,
+    feed_id: String,
+    episode_id: String,
+) -> Result<PodcastEpisode, String> {
+    let path = store_path(&app_handle)?;
+    let dir = downloads_dir(&app_handle)?;
+    let emitter = app_handle.clone();
+    let episode = download_episode_at(&path, &dir, &feed_id, &episode_id, |downloaded, total| {
+        let event = DownloadProgressEvent {
+            feed_id: feed_id.clone(),
+            episode_id: episode_id.clone(),
+            downloaded_bytes: downloaded,
+            total_bytes: total,
+        };
+        if let Err(error) = emitter.emit(DOWNLOAD_PROGRESS_EVENT, event) {
+            eprintln!("Failed to emit podcast download progress: {}", error);
+        }
+    })
+    .await?;
+    Ok(episode)
+}
+
+#[tauri::command]
+pub fn delete_podcast_download(
+    app_handle: AppHandle,
+    feed_id: String,
+    episode_id: String,
+) -> Result<PodcastEpisode, String> {
+    let mut download_path = None;
+    let episode = update_episode(
+        &store_path(&app_handle)?,
+        &feed_id,
+        &episode_id,
+        |episode| {
+            download_path = episode.download_path.take();
+        },
+    )?;
+    if let Some(path) = download_path.filter(|path| Path::new(path).exists()) {
+        std::fs::remove_file(&path).map_err(|e| format!("remove {}: {}", path, e))?;
+    }
+    Ok(episode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{canned, serve};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+  <channel>
+    <title>Test Show</title>
+    <link>https://show.example</link>
+    <description>About things</description>
+    <itunes:author>Host</itunes:author>
+    <itunes:image href="https://show.example/cover.jpg"/>
+    <item>
+      <title>Episode 1</title>
+      <guid isPermaLink="false">ep-1</guid>
+      <pubDate>Wed, 02 Oct 2002 13:00:00 GMT</pubDate>
+      <itunes:duration>1:02:03</itunes:duration>
+      <description>Short &amp; sweet</description>
+      <content:encoded><![CDATA[<p>Notes&nbsp;here</p>]]></content:encoded>
+      <enclosure url="https://cdn.example/ep1.mp3?x=1" type="audio/mpeg" length="1234"/>
+    </item>
+    <item>
+      <title>Blog post without audio</title>
+    </item>
+  </channel>
+</rss>"#;
+
+    fn store_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rmusic-podcast-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_ms()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parses_rss_items_with_itunes_extensions() {
+        let feed = parse_feed(RSS).unwrap();
+        assert_eq!(feed.title, "Test Show");
+        assert_eq!(feed.author.as_deref(), Some("Host"));
+        assert_eq!(
+            feed.image_url.as_deref(),
+            Some("https://show.example/cover.jpg")
+        );
+        assert_eq!(feed.episodes.len(), 1);
+        let episode = &feed.episodes[0];
+        assert_eq!(episode.guid, "ep-1");
+        assert_eq!(episode.show_notes, "<p>Notes&nbsp;here</p>");
+        assert_eq!(episode.duration_ms, 3_723_000);
+        assert_eq!(episode.published_at, Some(1_033_563_600_000));
+        assert_eq!(episode.size_bytes, Some(1234));
+        assert_eq!(episode_extension(episode), "mp3");
+        assert!(parse_feed("<html><body/></html>").is_err());
+    }
+
+    #[test]
+    fn parses_atom_entries_and_dates() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Show</title>
+  <author><name>Writer</name></author>
+  <entry>
+    <id>urn:1</id>
+    <title>Old</title>
+    <updated>2020-01-01T00:00:00Z</updated>
+    <link rel="alternate" href="https://atom.example/1"/>
+    <link rel="enclosure" href="https://atom.example/1.ogg" type="audio/ogg" length="99"/>
+  </entry>
+  <entry>
+    <id>urn:2</id>
+    <title>New</title>
+    <published>2020-01-02T08:00:00+08:00</published>
+    <summary>Summary text</summary>
+    <link rel="enclosure" href="https://atom.example/2.ogg"/>
+  </entry>
+</feed>"#;
+        let feed = parse_feed(atom).unwrap();
+        assert_eq!(feed.author.as_deref(), Some("Writer"));
+        let titles: Vec<&str> = feed.episodes.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["New", "Old"]);
+        assert_eq!(feed.episodes[0].published_at, Some(1_577_923_200_000));
+        assert_eq!(feed.episodes[0].show_notes, "Summary text");
+        assert_eq!(
+            feed.episodes[1].link.as_deref(),
+            Some("https://atom.example/1")
+        );
+
+        assert_eq!(
+            parse_feed_date("Tue, 10 Jun 2003 04:00:00 +0200"),
+            Some(1_055_210_400_000)
+        );
+        assert_eq!(
+            parse_feed_date("10 Jun 03 04:00 EDT"),
+            Some(1_055_232_000_000)
+        );
+        assert_eq!(parse_duration_ms("95"), 95_000);
+        assert_eq!(parse_duration_ms("bad"), 0);
+    }
+
+    #[test]
+    fn merge_keeps_listening_state_and_counts_new_episodes() {
+        let mut old = parse_feed(RSS).unwrap().episodes;
+        old[0].played = true;
+        old[0].download_path = Some("/tmp/ep1.mp3".to_string());
+        let mut fetched = parse_feed(RSS).unwrap().episodes;
+        let mut newer = fetched[0].clone();
+        newer.id = "new".to_string();
+        newer.published_at = Some(2_000_000_000_000);
+        fetched.push(newer);
+
+        let (merged, new_episodes) = merge_episodes(&old, fetched);
+        assert_eq!(new_episodes, 1);
+        assert_eq!(merged[0].id, "new");
+        assert!(merged[1].played);
+        assert_eq!(merged[1].download_path.as_deref(), Some("/tmp/ep1.mp3"));
+    }
+
+    #[test]
+    fn corrupt_store_is_reported_and_left_untouched() {
+        let path = store_dir("corrupt").join(PODCASTS_FILE);
+        std::fs::write(&path, "{ not json").unwrap();
+
+        assert!(read_store(&path).is_err());
+        assert!(update_store(&path, |store| {
+            store.settings.refresh_interval_minutes = 5;
+            Ok(())
+        })
+        .is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ not json");
+    }
+
+    #[tokio::test]
+    async fn refresh_uses_conditional_requests_against_local_feed_server() {
+        let version = Arc::new(AtomicUsize::new(1));
+        let current = Arc::clone(&version);
+        let server = serve(move |request| {
+            let version = current.load(Ordering::SeqCst);
+            async move {
+                let etag = format!("\"v{}\"", version);
+                if request.header("if-none-match") == Some(etag.as_str()) {
+                    return canned(304, &[], "");
+                }
+                let body = match request.path() {
+                    "/ep1.mp3" => "ID3audio".to_string(),
+                    _ if version == 1 => RSS.to_string(),
+                    _ => RSS.replace(
+                        "<item>",
+                        "<item><title>Episode 2</title><guid>ep-2</guid>\
+                         <enclosure url=\"https://cdn.example/ep2.mp3\"/></item><item>",
+                    ),
+                };
+                canned(
+                    200,
+                    &[
+                        ("etag", etag.as_str()),
+                        ("last-modified", "Wed, 02 Oct 2002 13:00:00 GMT"),
+                    ],
+                    body,
+                )
+            }
+        });
+
+        let dir = store_dir("refresh");
+        let path = dir.join(PODCASTS_FILE);
+        let url = server.url("/feed.xml");
+        let feed = subscribe_at(&path, &url).await.unwrap();
+        assert_eq!(feed.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(feed.episodes.len(), 1);
+        let episode_id = feed.episodes[0].id.clone();
+        update_episode(&path, &feed.id, &episode_id, |episode| {
+            episode.position_ms = 42_000;
+        })
+        .unwrap();
+
+        let result = refresh_at(&path, &feed.id).await.unwrap();
+        assert!(result.not_modified);
+        assert_eq!(server.requests().len(), 2);
+
+        version.store(2, Ordering::SeqCst);
+        let result = refresh_at(&path, &feed.id).await.unwrap();
+        assert_eq!(result.new_episodes, 1);
+        let stored = read_store(&path).unwrap().feeds.remove(0);
+        assert_eq!(stored.etag.as_deref(), Some("\"v2\""));
+        assert_eq!(stored.episodes.len(), 2);
+        let kept = stored.episodes.iter().find(|e| e.id == episode_id).unwrap();
+        assert_eq!(kept.position_ms, 42_000);
+
+        // 离线下载：写入节目目录并记录路径
+        update_episode(&path, &feed.id, &episode_id, |episode| {
+            episode.audio_url = server.url("/ep1.mp3");
+        })
+        .unwrap();
+        let downloads = dir.join(DOWNLOADS_DIR);
+        let episode = download_episode_at(&path, &downloads, &feed.id, &episode_id, |_, _| {})
+            .await
+            .unwrap();
+        let download_path = episode.download_path.unwrap();
+        assert_eq!(std::fs::read(&download_path).unwrap(), b"ID3audio");
+        assert!(download_path.ends_with(".mp3"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
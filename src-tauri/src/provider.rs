@@ -0,0 +1,369 @@
+// 在线音乐来源：搜索、歌手热门歌曲、播放地址、封面、歌词和专辑统一经过 MusicProvider，网易云（netease.rs）是默认实现。
+// 歌曲和在线播放列表项带上来源 id，命令按 id 分发；调试与测试构建额外注册离线的 mock 来源（mock_provider.rs）
+
+use crate::netease::{
+    self, AlbumResult, ArtistInfo, ArtistSongsResult, PlaySongResult, SearchMixResult,
+    SearchResult, SongInfo,
+};
+use crate::online_api::OnlineApiState;
+use futures_util::future::BoxFuture;
+use tauri::State;
+
+#[cfg(any(test, debug_assertions))]
+use crate::mock_provider;
+
+pub type ProviderFuture<'a, T> = BoxFuture<'a, Result<T, String>>;
+
+pub const DEFAULT_PROVIDER: &str = netease::PROVIDER_ID;
+
+/// 旧数据里的歌曲没有来源字段，一律视为网易云
+pub fn default_provider() -> String {
+    DEFAULT_PROVIDER.to_string()
+}
+
+pub trait MusicProvider: Send + Sync {
+    fn id(&self) -> &'static str;
+
+    fn search_songs(
+        &self,
+        keywords: String,
+        page: u32,
+        page_size: u32,
+    ) -> ProviderFuture<'_, SearchResult>;
+
+    fn search_artists(&self, keywords: String, limit: u32) -> ProviderFuture<'_, Vec<ArtistInfo>>;
+
+    fn artist_top_songs(&self, id: String, limit: u32) -> ProviderFuture<'_, ArtistSongsResult>;
+
+    fn song_url(&self, id: String) -> ProviderFuture<'_, String>;
+
+    fn song_detail(&self, id: String) -> ProviderFuture<'_, SongInfo>;
+
+    /// 默认取单曲详情里的封面
+    fn song_cover(&self, id: String) -> ProviderFuture<'_, String> {
+        Box::pin(async move {
+            let song = self.song_detail(id).await?;
+            if song.pic_url.trim().is_empty() {
+                return Err("Empty cover url".to_string());
+            }
+            Ok(song.pic_url)
+        })
+    }
+
+    fn song_lyric(&self, id: String) -> ProviderFuture<'_, String>;
+
+    fn album(&self, id: String) -> ProviderFuture<'_, AlbumResult>;
+}
+
+/// 按来源 id 取实现；None 或空字符串为网易云，网易云的请求按 api 中的端点顺序发出
+pub fn get_provider<'a>(
+    api: &'a OnlineApiState,
+    id: Option<&str>,
+) -> Result<Box<dyn MusicProvider + 'a>, String> {
+    match id.map(str::trim).filter(|id| !id.is_empty()) {
+        None | Some(netease::PROVIDER_ID) => Ok(Box::new(netease::NeteaseProvider { api })),
+        #[cfg(any(test, debug_assertions))]
+        Some(mock_provider::PROVIDER_ID) => Ok(Box::new(mock_provider::MockProvider)),
+        Some(other) => Err(format!("Unknown music provider: {}", other)),
+    }
+}
+
+fn is_registered_provider(id: &str) -> bool {
+    #[cfg(any(test, debug_assertions))]
+    if id == mock_provider::PROVIDER_ID {
+        return true;
+    }
+    id == netease::PROVIDER_ID
+}
+
+/// 在线音频缓存键：网易云沿用裸 id（兼容已有缓存），其他来源加 "来源:" 前缀
+pub fn song_cache_key(provider: &str, id: &str) -> String {
+    if provider.is_empty() || provider == DEFAULT_PROVIDER {
+        id.to_string()
+    } else {
+        format!("{}:{}", provider, id)
+    }
+}
+
+/// song_cache_key 的逆过程；前缀不是已注册的来源时整个键当作网易云 id
+pub fn parse_song_cache_key(key: &str) -> (&str, &str) {
+    match key.split_once(':') {
+        Some((provider, id)) if is_registered_provider(provider) => (provider, id),
+        _ => (DEFAULT_PROVIDER, key),
+    }
+}
+
+/// 只有缓存键时取播放地址（播放与预取时前端传的是缓存键）
+pub async fn song_url_for_cache_key(
+    api: &OnlineApiState,
+    cache_key: &str,
+) -> Result<String, String> {
+    let (provider, id) = parse_song_cache_key(cache_key);
+    get_provider(api, Some(provider))?
+        .song_url(id.to_string())
+        .await
+}
+
+#[tauri::command]
+pub async fn search_songs(
+    api: State<'_, OnlineApiState>,
+    keywords: String,
+    page: Option<u32>,
+    pagesize: Option<u32>,
+    provider: Option<String>,
+) -> Result<SearchResult, String> {
+    get_provider(&api, provider.as_deref())?
+        .search_songs(keywords, page.unwrap_or(1), pagesize.unwrap_or(7))
+        .await
+}
+
+/// 综合在线搜索：返回“相关歌手 + 歌曲列表（可分页）”；任一半失败时仍返回另一半
+#[tauri::command]
+pub async fn search_online_mix(
+    api: State<'_, OnlineApiState>,
+    keywords: String,
+    page: Option<u32>,
+    pagesize: Option<u32>,
+    artist_limit: Option<u32>,
+    provider: Option<String>,
+) -> Result<SearchMixResult, String> {
+    search_mix(
+        get_provider(&api, provider.as_deref())?.as_ref(),
+        keywords,
+        page,
+        pagesize,
+        artist_limit,
+    )
+    .await
+}
+
+async fn search_mix(
+    provider: &dyn MusicProvider,
+    keywords: String,
+    page: Option<u32>,
+    pagesize: Option<u32>,
+    artist_limit: Option<u32>,
+) -> Result<SearchMixResult, String> {
+    let songs_fut =
+        provider.search_songs(keywords.clone(), page.unwrap_or(1), pagesize.unwrap_or(7));
+    let artists_fut = provider.search_artists(keywords, artist_limit.unwrap_or(6));
+
+    let (songs_res, artists_res) = tokio::join!(songs_fut, artists_fut);
+    let (songs_res, artists) = match (songs_res, artists_res) {
+        (Ok(songs), Ok(artists)) => (songs, artists),
+        (Ok(songs), Err(error)) => {
+            eprintln!("Artist search unavailable, returning song results: {error}");
+            (songs, Vec::new())
+        }
+        (Err(error), Ok(artists)) => {
+            eprintln!("Song search unavailable, returning artist results: {error}");
+            (
+                SearchResult {
+                    songs: Vec::new(),
+                    total: 0,
+                },
+                artists,
+            )
+        }
+        (Err(song_error), Err(artist_error)) => {
+            return Err(format!(
+                "Song and artist search failed: songs: {song_error}; artists: {artist_error}"
+            ));
+        }
+    };
+
+    Ok(SearchMixResult {
+        artists,
+        songs: songs_res.songs,
+        total: songs_res.total,
+    })
+}
+
+/// 获取歌手热门歌曲（用于“只看该歌手歌曲”页面）
+#[tauri::command]
+pub async fn get_artist_top_songs(
+    api: State<'_, OnlineApiState>,
+    id: String,
+    limit: Option<u32>,
+    provider: Option<String>,
+) -> Result<ArtistSongsResult, String> {
+    get_provider(&api, provider.as_deref())?
+        .artist_top_songs(id, limit.unwrap_or(50))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_song_url(
+    api: State<'_, OnlineApiState>,
+    id: String,
+    provider: Option<String>,
+) -> Result<String, String> {
+    get_provider(&api, provider.as_deref())?.song_url(id).await
+}
+
+/// play online song by id
+#[tauri::command]
+pub async fn play_netease_song(
+    app_handle: tauri::AppHandle,
+    api: State<'_, OnlineApiState>,
+    id: String,
+    name: String,
+    artist: String,
+    pic_url: Option<String>,
+    provider: Option<String>,
+) -> Result<PlaySongResult, String> {
+    let source = get_provider(&api, provider.as_deref())?;
+    let cache_key = song_cache_key(source.id(), &id);
+    let cover_future = async {
+        match pic_url.filter(|url| !url.trim().is_empty()) {
+            Some(url) => Ok(url),
+            None => source.song_cover(id.clone()).await,
+        }
+    };
+    let url_future = async {
+        if crate::music::is_online_audio_cached(&app_handle, &cache_key) {
+            Ok(String::new())
+        } else {
+            source.song_url(id.clone()).await
+        }
+    };
+    let (url_result, cover_result) = tokio::join!(url_future, cover_future);
+    let url = url_result?;
+    let pic_url = cover_result.unwrap_or_default();
+
+    Ok(PlaySongResult {
+        url,
+        id,
+        name,
+        artist,
+        pic_url,
+    })
+}
+
+#[tauri::command]
+pub async fn get_song_cover(
+    api: State<'_, OnlineApiState>,
+    id: String,
+    provider: Option<String>,
+) -> Result<String, String> {
+    if id.trim().is_empty() {
+        return Err("Empty song id".to_string());
+    }
+    get_provider(&api, provider.as_deref())?
+        .song_cover(id)
+        .await
+}
+
+#[tauri::command]
+pub async fn get_song_lyric(
+    api: State<'_, OnlineApiState>,
+    id: String,
+    provider: Option<String>,
+) -> Result<String, String> {
+    get_provider(&api, provider.as_deref())?
+        .song_lyric(id)
+        .await
+}
+
+#[tauri::command]
+pub async fn get_online_album(
+    api: State<'_, OnlineApiState>,
+    id: String,
+    provider: Option<String>,
+) -> Result<AlbumResult, String> {
+    get_provider(&api, provider.as_deref())?.album(id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn cache_keys_keep_bare_netease_ids() {
+        assert_eq!(song_cache_key(netease::PROVIDER_ID, "347230"), "347230");
+        assert_eq!(song_cache_key("", "347230"), "347230");
+        assert_eq!(song_cache_key("mock", "3"), "mock:3");
+
+        assert_eq!(parse_song_cache_key("347230"), ("netease", "347230"));
+        assert_eq!(parse_song_cache_key("mock:3"), ("mock", "3"));
+        // 远程服务器的缓存键等未注册前缀原样当作网易云 id
+        assert_eq!(
+            parse_song_cache_key("subsonic:a:b"),
+            ("netease", "subsonic:a:b")
+        );
+        let api = OnlineApiState::default();
+        assert!(get_provider(&api, Some("nope")).is_err());
+        assert_eq!(
+            get_provider(&api, Some(" ")).unwrap().id(),
+            netease::PROVIDER_ID
+        );
+    }
+
+    #[test]
+    fn provider_field_defaults_to_netease_for_old_songs() {
+        let song: SongInfo = serde_json::from_value(serde_json::json!({
+            "id": "1", "name": "a", "artists": [], "album": "", "duration": 0,
+            "pic_url": "", "file_hash": "1"
+        }))
+        .unwrap();
+        assert_eq!(song.provider, DEFAULT_PROVIDER);
+    }
+
+    #[tokio::test]
+    async fn mock_provider_serves_the_whole_online_flow() {
+        let api = OnlineApiState::default();
+        let source = get_provider(&api, Some("mock")).unwrap();
+        let mix = search_mix(
+            source.as_ref(),
+            "artist b".to_string(),
+            Some(1),
+            Some(3),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(mix.artists.len(), 1);
+        assert_eq!(mix.total, 8);
+        assert_eq!(mix.songs.len(), 3);
+        let song = mix.songs[0].clone();
+        assert_eq!(song.provider, "mock");
+
+        // 播放地址 → 下载 → 解码
+        let url = source.song_url(song.id.clone()).await.unwrap();
+        assert_eq!(
+            song_url_for_cache_key(&api, &song_cache_key(&song.provider, &song.id))
+                .await
+                .unwrap(),
+            url
+        );
+        let client = netease::get_client().unwrap();
+        let bytes = netease::get_response(client.clone(), url)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let decoder = rodio::Decoder::new(Cursor::new(bytes.to_vec())).unwrap();
+        assert_eq!(decoder.count(), 8_000);
+
+        let cover = source.song_cover(song.id.clone()).await.unwrap();
+        assert_eq!(cover, song.pic_url);
+        let response = netease::get_response(client, cover).await.unwrap();
+        assert_eq!(response.headers()["content-type"], "image/svg+xml");
+
+        let lyric = source.song_lyric(song.id.clone()).await.unwrap();
+        assert!(lyric.starts_with(&format!("[00:00.00]{}", song.name)));
+
+        let top = source
+            .artist_top_songs(mix.artists[0].id.clone(), 50)
+            .await
+            .unwrap();
+        assert!(top.songs.iter().any(|item| item.id == song.id));
+
+        let album = source.album("2".to_string()).await.unwrap();
+        assert_eq!(album.album.name, song.album);
+        assert_eq!(album.total, 4);
+        assert!(source.song_url("999".to_string()).await.is_err());
+    }
+}
@@ -0,0 +1,905 @@
+// 网络电台（Shoutcast / Icecast）：电台列表保存在 app_data_dir/radio_stations.json，支持 .pls / .m3u 电台文件。
+// 直播流不写入 online-audio 缓存，只在内存里保留一段滑动窗口；ICY 元数据中的 StreamTitle 作为正在播放事件发给前端，断流后自动重连
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::netease;
+use crate::remote_control::random_token;
+use crate::storage::{self, app_data_file};
+use crate::timestamp::now_ms;
+
+const STATIONS_FILE: &str = "radio_stations.json";
+const RADIO_METADATA_EVENT: &str = "radio-metadata";
+const RADIO_RECONNECTING_EVENT: &str = "radio-reconnecting";
+/// 已读数据保留的回看窗口：解码器探测格式时会跳回开头重读
+const HISTORY_BYTES: usize = 256 * 1024;
+/// 暂停时最多缓冲的未读数据，超出后丢弃最旧的部分，恢复播放时仍接近直播进度
+const MAX_AHEAD_BYTES: usize = 8 * 1024 * 1024;
+const MAX_PLAYLIST_BYTES: usize = 64 * 1024;
+const CHUNK_IDLE_TIMEOUT: Duration = Duration::from_secs(15);
+const READ_WAIT_INTERVAL: Duration = Duration::from_millis(200);
+/// 连续重连失败的等待时间；收到足够数据后重新从头计数
+const RECONNECT_DELAYS: [Duration; 5] = [
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    Duration::from_secs(20),
+];
+const STABLE_CONNECTION_BYTES: u64 = 256 * 1024;
+
+/// 所有对 radio_stations.json 的“读-改-写”都在这把锁内完成
+static STATIONS_LOCK: StdMutex<()> = StdMutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RadioStation {
+    pub id: String,
+    pub name: String,
+    /// 直播流地址，或指向 .pls / .m3u 的地址（播放时再解析）
+    pub url: String,
+    pub added_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PlaylistEntry {
+    url: String,
+    title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RadioMetadataEvent {
+    pub url: String,
+    /// icy-name 响应头里的电台名
+    pub station_name: Option<String>,
+    /// ICY StreamTitle，通常是“歌手 - 歌名”
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RadioReconnectingEvent {
+    pub url: String,
+    pub attempt: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum RadioEvent {
+    Metadata(RadioMetadataEvent),
+    Reconnecting(RadioReconnectingEvent),
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+fn looks_like_playlist(url: &str) -> bool {
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .to_ascii_lowercase();
+    path.ends_with(".pls") || path.ends_with(".m3u") || path.ends_with(".m3u8")
+}
+
+fn is_playlist_content_type(content_type: &str) -> bool {
+    let content_type = content_type.to_ascii_lowercase();
+    ["scpls", "mpegurl", "pls+xml"]
+        .iter()
+        .any(|kind| content_type.contains(kind))
+}
+
+/// `[playlist]` 格式：FileN=地址，TitleN=标题
+fn parse_pls(text: &str) -> Vec<PlaylistEntry> {
+    let mut entries: Vec<(u32, PlaylistEntry)> = Vec::new();
+    let mut titles: Vec<(u32, String)> = Vec::new();
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+        if let Some(index) = key.strip_prefix("file").and_then(|n| n.parse().ok()) {
+            entries.push((
+                index,
+                PlaylistEntry {
+                    url: value.to_string(),
+                    title: None,
+                },
+            ));
+        } else if let Some(index) = key.strip_prefix("title").and_then(|n| n.parse().ok()) {
+            titles.push((index, value.to_string()));
+        }
+    }
+    entries.sort_by_key(|(index, _)| *index);
+    entries
+        .into_iter()
+        .map(|(index, mut entry)| {
+            entry.title = titles
+                .iter()
+                .find(|(title_index, _)| *title_index == index)
+                .map(|(_, title)| title.clone())
+                .filter(|title| !title.is_empty());
+            entry
+        })
+        .collect()
+}
+
+/// 每行一个地址，#EXTINF:-1,标题 作为下一条的标题
+fn parse_m3u(text: &str) -> Result<Vec<PlaylistEntry>, String> {
+    if text.contains("#EXT-X-TARGETDURATION") || text.contains("#EXT-X-STREAM-INF") {
+        return Err("HLS streams are not supported".to_string());
+    }
+    let mut entries = Vec::new();
+    let mut title = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            title = info
+                .split_once(',')
+                .map(|(_, title)| title.trim().to_string())
+                .filter(|title| !title.is_empty());
+        } else if !line.is_empty() && !line.starts_with('#') {
+            entries.push(PlaylistEntry {
+                url: line.to_string(),
+                title: title.take(),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_station_playlist(text: &str) -> Result<Vec<PlaylistEntry>, String> {
+    let text = text.trim_start_matches('\u{feff}');
+    let entries = if text
+        .trim_start()
+        .to_ascii_lowercase()
+        .starts_with("[playlist]")
+    {
+        parse_pls(text)
+    } else {
+        parse_m3u(text)?
+    };
+    let entries: Vec<PlaylistEntry> = entries
+        .into_iter()
+        .filter(|entry| is_http_url(&entry.url))
+        .collect();
+    if entries.is_empty() {
+        return Err("no stream found in station file".to_string());
+    }
+    Ok(entries)
+}
+
+/// `StreamTitle='歌手 - 歌名';StreamUrl='';`，标题本身可能包含单引号
+fn parse_stream_title(metadata: &str) -> Option<String> {
+    let start = metadata.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &metadata[start..];
+    let end = rest
+        .find("';")
+        .unwrap_or_else(|| rest.trim_end().trim_end_matches('\'').len());
+    let title = rest[..end].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// 从响应体中拆出 ICY 元数据块：每 metaint 字节音频后跟 1 字节长度（×16）和元数据
+struct IcyDemuxer {
+    metaint: usize,
+    audio_left: usize,
+    meta_left: Option<usize>,
+    meta: Vec<u8>,
+}
+
+impl IcyDemuxer {
+    fn new(metaint: usize) -> Self {
+        Self {
+            metaint,
+            audio_left: metaint,
+            meta_left: None,
+            meta: Vec::new(),
+        }
+    }
+
+    /// 返回本段中的音频数据，以及其中完整元数据块解析出的标题
+    fn push(&mut self, mut chunk: &[u8]) -> (Vec<u8>, Vec<Option<String>>) {
+        let mut audio = Vec::with_capacity(chunk.len());
+        let mut titles = Vec::new();
+        while !chunk.is_empty() {
+            match self.meta_left {
+                None if self.audio_left > 0 => {
+                    let take = self.audio_left.min(chunk.len());
+                    audio.extend_from_slice(&chunk[..take]);
+                    self.audio_left -= take;
+                    chunk = &chunk[take..];
+                }
+                None => {
+                    self.meta_left = Some(chunk[0] as usize * 16);
+                    self.meta.clear();
+                    chunk = &chunk[1..];
+                }
+                Some(left) => {
+                    let take = left.min(chunk.len());
+                    self.meta.extend_from_slice(&chunk[..take]);
+                    chunk = &chunk[take..];
+                    self.meta_left = Some(left - take);
+                }
+            }
+            if self.meta_left == Some(0) {
+                // 长度为 0 表示元数据没有变化
+                if !self.meta.is_empty() {
+                    let text = String::from_utf8_lossy(&self.meta);
+                    titles.push(parse_stream_title(text.trim_end_matches('\0')));
+                }
+                self.meta_left = None;
+                self.audio_left = self.metaint;
+            }
+        }
+        (audio, titles)
+    }
+}
+
+#[derive(Default)]
+struct RadioBufferState {
+    data: VecDeque<u8>,
+    /// data[0] 在整个流中的偏移
+    start: u64,
+    read_pos: u64,
+    finished: bool,
+    error: Option<String>,
+}
+
+/// 下载任务与解码器之间的内存缓冲
+#[derive(Default)]
+pub struct RadioBuffer {
+    state: StdMutex<RadioBufferState>,
+    signal: Condvar,
+    closed: AtomicBool,
+}
+
+impl RadioBuffer {
+    fn push(&self, bytes: &[u8]) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.data.extend(bytes);
+        let end = state.start + state.data.len() as u64;
+        let keep_from = state
+            .read_pos
+            .saturating_sub(HISTORY_BYTES as u64)
+            .max(end.saturating_sub((HISTORY_BYTES + MAX_AHEAD_BYTES) as u64));
+        if keep_from > state.start {
+            let drop_len = (keep_from - state.start) as usize;
+            state.data.drain(..drop_len);
+            state.start = keep_from;
+        }
+        self.signal.notify_all();
+    }
+
+    fn finish(&self, error: Option<String>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.finished = true;
+            state.error = error;
+        }
+        self.signal.notify_all();
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.signal.notify_all();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// 下载端放弃重连时记录的错误
+    pub fn error(&self) -> Option<String> {
+        self.state.lock().ok().and_then(|state| state.error.clone())
+    }
+}
+
+/// 交给解码器的只读流；只能在保留的窗口内回退，不支持跳转到末尾
+pub struct RadioStreamReader {
+    buffer: Arc<RadioBuffer>,
+}
+
+impl RadioStreamReader {
+    pub fn new(buffer: Arc<RadioBuffer>) -> Self {
+        Self { buffer }
+    }
+
+    pub fn shared_buffer(&self) -> Arc<RadioBuffer> {
+        Arc::clone(&self.buffer)
+    }
+}
+
+impl Read for RadioStreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut state = self
+            .buffer
+            .state
+            .lock()
+            .map_err(|_| io::Error::other("radio buffer poisoned"))?;
+        loop {
+            if self.buffer.is_closed() {
+                return Ok(0);
+            }
+            // 暂停太久时最旧的数据已被丢弃，直接跳到仍保留的位置
+            state.read_pos = state.read_pos.max(state.start);
+            let end = state.start + state.data.len() as u64;
+            if state.read_pos < end {
+                let offset = (state.read_pos - state.start) as usize;
+                let len = buf.len().min((end - state.read_pos) as usize);
+                for (target, byte) in buf[..len].iter_mut().zip(state.data.range(offset..)) {
+                    *target = *byte;
+                }
+                state.read_pos += len as u64;
+                return Ok(len);
+            }
+            if state.finished {
+                return Ok(0);
+            }
+            state = self
+                .buffer
+                .signal
+                .wait_timeout(state, READ_WAIT_INTERVAL)
+                .map_err(|_| io::Error::other("radio buffer poisoned"))?
+                .0;
+        }
+    }
+}
+
+impl Seek for RadioStreamReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let mut state = self
+            .buffer
+            .state
+            .lock()
+            .map_err(|_| io::Error::other("radio buffer poisoned"))?;
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => state.read_pos.checked_add_signed(delta),
+            SeekFrom::End(_) => None,
+        };
+        let end = state.start + state.data.len() as u64;
+        match target {
+            Some(target) if target >= state.start && target <= end => {
+                state.read_pos = target;
+                Ok(target)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "radio stream is not seekable",
+            )),
+        }
+    }
+}
+
+impl Drop for RadioStreamReader {
+    fn drop(&mut self) {
+        // 解码器被替换或停止后，下载任务随之退出
+        self.buffer.close();
+    }
+}
+
+async fn fetch_playlist(url: &str) -> Result<Vec<PlaylistEntry>, String> {
+    let client = netease::get_client()?;
+    let response = netease::get_response(client, url.to_string()).await?;
+    playlist_from_response(response).await
+}
+
+async fn playlist_from_response(
+    mut response: reqwest::Response,
+) -> Result<Vec<PlaylistEntry>, String> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("read station file error: {}", e))?
+    {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > MAX_PLAYLIST_BYTES {
+            return Err("station file too large".to_string());
+        }
+    }
+    parse_station_playlist(&String::from_utf8_lossy(&bytes))
+}
+
+/// 给自己拉流的外部播放器（DLNA 渲染器）用：展开 .pls / .m3u，取第一个流地址
+pub async fn resolve_stream_url(url: &str) -> Result<String, String> {
+    if !looks_like_playlist(url) {
+        return Ok(url.to_string());
+    }
+    fetch_playlist(url)
+        .await?
+        .into_iter()
+        .next()
+        .map(|entry| entry.url)
+        .ok_or_else(|| "no stream found".to_string())
+}
+
+/// 连接直播流；地址或响应类型是 .pls / .m3u 时展开后依次尝试其中的流
+async fn connect_stream(url: &str) -> Result<reqwest::Response, String> {
+    let candidates = if looks_like_playlist(url) {
+        fetch_playlist(url).await?
+    } else {
+        vec![PlaylistEntry {
+            url: url.to_string(),
+            title: None,
+        }]
+    };
+    let client = netease::get_client()?;
+    let mut last_error = "no stream found".to_string();
+    for candidate in candidates {
+        let request = client
+            .get(&candidate.url)
+            .header("Icy-MetaData", "1")
+            .send();
+        let response = match tokio::time::timeout(CHUNK_IDLE_TIMEOUT, request).await {
+            Ok(Ok(response)) if response.status().is_success() => response,
+            Ok(Ok(response)) => {
+                last_error = format!("radio stream error: HTTP {}", response.status());
+                continue;
+            }
+            Ok(Err(error)) => {
+                last_error = format!("radio stream error: {}", error);
+                continue;
+            }
+            Err(_) => {
+                last_error = "radio stream connect timed out".to_string();
+                continue;
+            }
+        };
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if !is_playlist_content_type(&content_type) {
+            return Ok(response);
+        }
+        // 没有扩展名的电台文件：再展开一层
+        match playlist_from_response(response).await {
+            Ok(entries) => {
+                for entry in entries {
+                    let request = client.get(&entry.url).header("Icy-MetaData", "1").send();
+                    match tokio::time::timeout(CHUNK_IDLE_TIMEOUT, request).await {
+                        Ok(Ok(response)) if response.status().is_success() => return Ok(response),
+                        Ok(Ok(response)) => {
+                            last_error = format!("radio stream error: HTTP {}", response.status())
+                        }
+                        Ok(Err(error)) => last_error = format!("radio stream error: {}", error),
+                        Err(_) => last_error = "radio stream connect timed out".to_string(),
+                    }
+                }
+            }
+            Err(error) => last_error = error,
+        }
+    }
+    Err(last_error)
+}
+
+fn header_text(response: &reqwest::Response, name: &str) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 读取一次连接直到断开；返回本次收到的音频字节数
+async fn pump_stream(
+    url: &str,
+    mut response: reqwest::Response,
+    buffer: &RadioBuffer,
+    on_event: &(dyn Fn(RadioEvent) + Send + Sync),
+) -> (u64, Result<(), String>) {
+    let station_name = header_text(&response, "icy-name");
+    let mut demuxer = header_text(&response, "icy-metaint")
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|metaint| *metaint > 0)
+        .map(IcyDemuxer::new);
+    on_event(RadioEvent::Metadata(RadioMetadataEvent {
+        url: url.to_string(),
+        station_name: station_name.clone(),
+        title: None,
+    }));
+
+    let mut received = 0u64;
+    loop {
+        if buffer.is_closed() {
+            return (received, Ok(()));
+        }
+        let chunk = match tokio::time::timeout(CHUNK_IDLE_TIMEOUT, response.chunk()).await {
+            Ok(Ok(Some(chunk))) => chunk,
+            Ok(Ok(None)) => return (received, Err("radio stream ended".to_string())),
+            Ok(Err(error)) => return (received, Err(format!("radio stream error: {}", error))),
+            Err(_) => {
+                return (
+                    received,
+                    Err(format!(
+                        "radio stream stalled for {}s",
+                        CHUNK_IDLE_TIMEOUT.as_secs()
+                    )),
+                )
+            }
+        };
+        let (audio, titles) = match demuxer.as_mut() {
+            Some(demuxer) => demuxer.push(&chunk),
+            None => (chunk.to_vec(), Vec::new()),
+        };
+        received += audio.len() as u64;
+        buffer.push(&audio);
+        for title in titles {
+            on_event(RadioEvent::Metadata(RadioMetadataEvent {
+                url: url.to_string(),
+                station_name: station_name.clone(),
+                title,
+            }));
+        }
+    }
+}
+
+/// 后台下载直播流写入缓冲；断流后按退避重连，解码器关闭后退出
+pub fn spawn_radio_download(
+    url: String,
+    buffer: Arc<RadioBuffer>,
+    on_event: Box<dyn Fn(RadioEvent) + Send + Sync>,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut failures = 0usize;
+        loop {
+            if buffer.is_closed() {
+                return;
+            }
+            let error = match connect_stream(&url).await {
+                Ok(response) => {
+                    let (received, result) =
+                        pump_stream(&url, response, &buffer, on_event.as_ref()).await;
+                    if received >= STABLE_CONNECTION_BYTES {
+                        failures = 0;
+                    }
+                    match result {
+                        Ok(()) => return,
+                        Err(error) => error,
+                    }
+                }
+                Err(error) => error,
+            };
+            if buffer.is_closed() {
+                return;
+            }
+            let Some(delay) = RECONNECT_DELAYS.get(failures) else {
+                buffer.finish(Some(error));
+                return;
+            };
+            failures += 1;
+            on_event(RadioEvent::Reconnecting(RadioReconnectingEvent {
+                url: url.clone(),
+                attempt: failures,
+                error,
+            }));
+            tokio::time::sleep(*delay).await;
+        }
+    });
+}
+
+/// 开始播放电台：返回解码器使用的读取端
+pub fn open_radio_stream(app_handle: &AppHandle, url: &str) -> RadioStreamReader {
+    let buffer = Arc::new(RadioBuffer::default());
+    let emitter = app_handle.clone();
+    spawn_radio_download(
+        url.to_string(),
+        Arc::clone(&buffer),
+        Box::new(move |event| {
+            let result = match event {
+                RadioEvent::Metadata(event) => emitter.emit(RADIO_METADATA_EVENT, event),
+                RadioEvent::Reconnecting(event) => emitter.emit(RADIO_RECONNECTING_EVENT, event),
+            };
+            if let Err(error) = result {
+                eprintln!("Failed to emit radio event: {}", error);
+            }
+        }),
+    );
+    RadioStreamReader::new(buffer)
+}
+
+fn stations_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_data_file(app_handle, STATIONS_FILE)
+}
+
+/// 文件损坏时返回错误，不能当成空列表再写回去
+fn read_stations_from_path(path: &Path) -> Result<Vec<RadioStation>, String> {
+    storage::read_json(path)
+}
+
+/// 读取、修改、写回都在锁内完成
+fn update_stations_at<T>(
+    path: &Path,
+    update: impl FnOnce(&mut Vec<RadioStation>) -> Result<T, String>,
+) -> Result<T, String> {
+    let _guard = STATIONS_LOCK
+        .lock()
+        .map_err(|_| "radio stations lock poisoned".to_string())?;
+    let mut stations = read_stations_from_path(path)?;
+    let result = update(&mut stations)?;
+    storage::write_json(path, &stations)?;
+    Ok(result)
+}
+
+fn update_stations<T>(
+    app_handle: &AppHandle,
+    update: impl FnOnce(&mut Vec<RadioStation>) -> Result<T, String>,
+) -> Result<T, String> {
+    update_stations_at(&stations_path(app_handle)?, update)
+}
+
+fn new_station(name: &str, url: &str) -> RadioStation {
+    let name = name.trim();
+    RadioStation {
+        id: format!("radio_{}", &random_token()[..12]),
+        name: if name.is_empty() {
+            url.to_string()
+        } else {
+            name.to_string()
+        },
+        url: url.trim().to_string(),
+        added_at: now_ms(),
+    }
+}
+
+/// 同一地址不重复添加，返回已有或新加的电台
+fn insert_station(stations: &mut Vec<RadioStation>, station: RadioStation) -> RadioStation {
+    if let Some(existing) = stations.iter().find(|existing| existing.url == station.url) {
+        return existing.clone();
+    }
+    stations.push(station.clone());
+    station
+}
+
+#[tauri::command]
+pub fn get_radio_stations(app_handle: AppHandle) -> Result<Vec<RadioStation>, String> {
+    let path = stations_path(&app_handle)?;
+    let _guard = STATIONS_LOCK
+        .lock()
+        .map_err(|_| "radio stations lock poisoned".to_string())?;
+    read_stations_from_path(&path)
+}
+
+/// url 可以是直播流，也可以是在线的 .pls / .m3u；名称为空时使用电台文件里的标题
+#[tauri::command]
+pub async fn add_radio_station(
+    app_handle: AppHandle,
+    name: Option<String>,
+    url: String,
+) -> Result<RadioStation, String> {
+    let url = url.trim().to_string();
+    if !is_http_url(&url) {
+        return Err(format!("invalid radio url: {}", url));
+    }
+    let mut name = name.unwrap_or_default();
+    if name.trim().is_empty() && looks_like_playlist(&url) {
+        name = fetch_playlist(&url)
+            .await?
+            .into_iter()
+            .find_map(|entry| entry.title)
+            .unwrap_or_default();
+    }
+    let station = new_station(&name, &url);
+    update_stations(&app_handle, |stations| {
+        Ok(insert_station(stations, station))
+    })
+}
+
+/// 导入本地 .pls / .m3u 文件中的全部电台
+#[tauri::command]
+pub fn import_radio_stations(
+    app_handle: AppHandle,
+    path: String,
+) -> Result<Vec<RadioStation>, String> {
+    let bytes = std::fs::read(&path).map_err(|e| format!("read station file {}: {}", path, e))?;
+    let entries = parse_station_playlist(&String::from_utf8_lossy(&bytes))?;
+    update_stations(&app_handle, |stations| {
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let station = new_station(entry.title.as_deref().unwrap_or_default(), &entry.url);
+                insert_station(stations, station)
+            })
+            .collect())
+    })
+}
+
+#[tauri::command]
+pub fn rename_radio_station(
+    app_handle: AppHandle,
+    id: String,
+    name: String,
+) -> Result<RadioStation, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("station name is empty".to_string());
+    }
+    update_stations(&app_handle, |stations| {
+        let station = stations
+            .iter_mut()
+            .find(|station| station.id == id)
+            .ok_or_else(|| format!("radio station not found: {}", id))?;
+        station.name = name;
+        Ok(station.clone())
+    })
+}
+
+#[tauri::command]
+pub fn remove_radio_station(app_handle: AppHandle, id: String) -> Result<(), String> {
+    update_stations(&app_handle, |stations| {
+        stations.retain(|station| station.id != id);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{canned, serve};
+    use std::sync::atomic::AtomicUsize;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    #[test]
+    fn parses_pls_and_m3u_station_files() {
+        let pls = "[playlist]\nNumberOfEntries=2\nFile2=http://b.example/stream\nTitle1=Jazz FM\nFile1=http://a.example/stream\nLength1=-1\n";
+        let entries = parse_station_playlist(pls).unwrap();
+        assert_eq!(entries[0].url, "http://a.example/stream");
+        assert_eq!(entries[0].title.as_deref(), Some("Jazz FM"));
+        assert_eq!(entries[1].title, None);
+
+        let m3u =
+            "\u{feff}#EXTM3U\n#EXTINF:-1,Lounge\nhttps://c.example/live.mp3\n\nrelative.mp3\n";
+        let entries = parse_station_playlist(m3u).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title.as_deref(), Some("Lounge"));
+
+        assert!(parse_station_playlist("#EXTM3U\n#EXT-X-TARGETDURATION:10\nseg.ts\n").is_err());
+        assert!(parse_station_playlist("[playlist]\n").is_err());
+        assert!(looks_like_playlist("http://x/listen.pls?sid=1"));
+        assert!(!looks_like_playlist("http://x/stream"));
+    }
+
+    #[test]
+    fn demuxes_icy_metadata_across_chunk_boundaries() {
+        let title = b"StreamTitle='It's Me - Song';StreamUrl='';";
+        let mut meta = title.to_vec();
+        meta.resize(title.len().div_ceil(16) * 16, 0);
+        let mut stream = b"abcd".to_vec();
+        stream.push((meta.len() / 16) as u8);
+        stream.extend_from_slice(&meta);
+        stream.extend_from_slice(b"efgh");
+        stream.push(0);
+        stream.extend_from_slice(b"ij");
+
+        let mut demuxer = IcyDemuxer::new(4);
+        let mut audio = Vec::new();
+        let mut titles = Vec::new();
+        for chunk in stream.chunks(3) {
+            let (bytes, found) = demuxer.push(chunk);
+            audio.extend(bytes);
+            titles.extend(found);
+        }
+        assert_eq!(audio, b"abcdefghij");
+        assert_eq!(titles, vec![Some("It's Me - Song".to_string())]);
+        assert_eq!(parse_stream_title("StreamTitle='';"), None);
+    }
+
+    #[test]
+    fn reader_rewinds_within_window_and_skips_dropped_data() {
+        let buffer = Arc::new(RadioBuffer::default());
+        let mut reader = RadioStreamReader::new(Arc::clone(&buffer));
+        buffer.push(b"hello world");
+        let mut head = [0u8; 5];
+        reader.read_exact(&mut head).unwrap();
+        assert_eq!(&head, b"hello");
+        assert_eq!(reader.seek(SeekFrom::Start(0)).unwrap(), 0);
+        assert!(reader.seek(SeekFrom::End(0)).is_err());
+        assert!(reader.seek(SeekFrom::Start(100)).is_err());
+
+        // 超出缓冲上限时丢弃最旧的数据，读取位置跟着前移
+        buffer.push(&vec![7u8; HISTORY_BYTES + MAX_AHEAD_BYTES]);
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], 7);
+
+        buffer.finish(None);
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest.len(), HISTORY_BYTES + MAX_AHEAD_BYTES - 1);
+        drop(reader);
+        assert!(buffer.is_closed());
+    }
+
+    #[test]
+    fn corrupt_stations_file_is_not_overwritten() {
+        let dir =
+            std::env::temp_dir().join(format!("rmusic-radio-{}-{}", std::process::id(), now_ms()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(STATIONS_FILE);
+        std::fs::write(&path, "[{ broken").unwrap();
+
+        let station = new_station("Jazz", "https://radio.example/jazz");
+        assert!(
+            update_stations_at(&path, |stations| Ok(insert_station(stations, station))).is_err()
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[{ broken");
+
+        std::fs::remove_file(&path).unwrap();
+        let station = new_station("Jazz", "https://radio.example/jazz");
+        update_stations_at(&path, |stations| Ok(insert_station(stations, station))).unwrap();
+        assert_eq!(read_stations_from_path(&path).unwrap().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_drop_and_reports_stream_titles() {
+        let attempts = AtomicUsize::new(0);
+        let server = serve(move |request| {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if request.path() == "/station.pls" {
+                    let body = format!(
+                        "[playlist]\nFile1=http://{}/live\nTitle1=Test FM\n",
+                        request.header("host").unwrap()
+                    );
+                    return canned(200, &[("content-type", "audio/x-scpls")], body);
+                }
+                assert_eq!(request.header("icy-metadata"), Some("1"));
+                let meta = b"StreamTitle='Song';\0\0\0\0\0\0\0\0\0\0\0\0\0";
+                let mut body = format!("{}", attempt % 10).repeat(8).into_bytes();
+                body.push(2);
+                body.extend_from_slice(meta);
+                body.extend_from_slice(b"tail");
+                canned(200, &[("icy-metaint", "8"), ("icy-name", "Test FM")], body)
+            }
+        });
+
+        let buffer = Arc::new(RadioBuffer::default());
+        let (sender, mut events) = unbounded_channel();
+        spawn_radio_download(
+            server.url("/station.pls"),
+            Arc::clone(&buffer),
+            Box::new(move |event| {
+                let _ = sender.send(event);
+            }),
+        );
+
+        let mut titles = Vec::new();
+        let mut reconnects = 0;
+        while reconnects < 1 || titles.len() < 2 {
+            match events.recv().await.unwrap() {
+                RadioEvent::Metadata(event) => {
+                    assert_eq!(event.station_name.as_deref(), Some("Test FM"));
+                    titles.extend(event.title);
+                }
+                RadioEvent::Reconnecting(event) => {
+                    assert_eq!(event.error, "radio stream ended");
+                    reconnects += 1;
+                }
+            }
+        }
+        assert_eq!(titles, vec!["Song", "Song"]);
+
+        let mut reader = RadioStreamReader::new(Arc::clone(&buffer));
+        let mut first = [0u8; 12];
+        reader.read_exact(&mut first).unwrap();
+        assert_eq!(&first, b"11111111tail");
+        drop(reader);
+        assert!(server.requests().len() >= 4);
+    }
+}
@@ -0,0 +1,456 @@
+// 评分写回文件标签：MP3/WAV 写入 ID3 POPM 与 TXXX:FMPS_Rating，FLAC 写入 FMPS_RATING 注释
+
+use id3::frame::{Content, ExtendedText, Popularimeter};
+use id3::{Frame, Tag, TagLike, Version};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::storage::{commit_temp_file, unique_temp_path_for};
+
+/// 多数播放器（Windows、foobar2000、MusicBee）都识别这个 POPM 用户名
+const POPM_USER: &str = "Windows Media Player 9 Series";
+const FMPS_RATING_DESCRIPTION: &str = "FMPS_Rating";
+const FMPS_RATING_FIELD: &str = "FMPS_RATING";
+const FLAC_MARKER: &[u8; 4] = b"fLaC";
+const FLAC_BLOCK_PADDING: u8 = 1;
+const FLAC_BLOCK_VORBIS_COMMENT: u8 = 4;
+const FLAC_PADDING_LEN: usize = 1024;
+
+/// 0 表示未评分；1-5 星按 Windows 的惯例映射到 POPM 的 1-255
+fn popm_value(rating: u8) -> u8 {
+    match rating {
+        0 => 0,
+        1 => 1,
+        2 => 64,
+        3 => 128,
+        4 => 196,
+        _ => 255,
+    }
+}
+
+/// FMPS 评分是 0.0-1.0 的小数
+fn fmps_value(rating: u8) -> String {
+    format!("{:.1}", f64::from(rating.min(5)) / 5.0)
+}
+
+/// 将 0-5 星评分写入文件标签；评分为 0 时移除本应用写入的评分
+pub fn write_rating(path: &Path, rating: u8) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "mp3" | "wav" => write_id3_rating(path, rating),
+        "flac" => write_flac_rating(path, rating),
+        _ => Err(format!(
+            "Rating tags are not supported for .{} files",
+            extension
+        )),
+    }
+}
+
+/// id3 会根据文件格式自动处理 MP3 头部标签与 WAV 的 id3 块
+fn write_id3_rating(path: &Path, rating: u8) -> Result<(), String> {
+    let mut tag = match Tag::read_from_path(path) {
+        Ok(tag) => tag,
+        // 新建的标签使用兼容性最好的 ID3v2.3；已有标签沿用原版本
+        Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => Tag::with_version(Version::Id3v23),
+        Err(e) => return Err(format!("read ID3 tag {}: {}", path.display(), e)),
+    };
+    apply_id3_rating(&mut tag, rating);
+    tag.write_to_path(path, tag.version())
+        .map_err(|e| format!("write ID3 tag {}: {}", path.display(), e))
+}
+
+fn apply_id3_rating(tag: &mut Tag, rating: u8) {
+    // 只替换本应用对应用户名的 POPM，保留其他播放器写入的评分
+    let other_ratings: Vec<Frame> = tag
+        .remove("POPM")
+        .into_iter()
+        .filter(|frame| {
+            !matches!(frame.content(), Content::Popularimeter(popm) if popm.user == POPM_USER)
+        })
+        .collect();
+    for frame in other_ratings {
+        tag.add_frame(frame);
+    }
+    tag.remove_extended_text(Some(FMPS_RATING_DESCRIPTION), None);
+
+    if rating == 0 {
+        return;
+    }
+    tag.add_frame(Frame::with_content(
+        "POPM",
+        Content::Popularimeter(Popularimeter {
+            user: POPM_USER.to_string(),
+            rating: popm_value(rating),
+            counter: 0,
+        }),
+    ));
+    tag.add_frame(ExtendedText {
+        description: FMPS_RATING_DESCRIPTION.to_string(),
+        value: fmps_value(rating),
+    });
+}
+
+struct FlacBlock {
+    block_type: u8,
+    data: Vec<u8>,
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// 解析 VORBIS_COMMENT 块：返回 vendor 字符串与注释列表
+fn parse_vorbis_comment(data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), String> {
+    let malformed = || "malformed FLAC vorbis comment".to_string();
+    let vendor_len = read_u32_le(data, 0).ok_or_else(malformed)? as usize;
+    let vendor = data.get(4..4 + vendor_len).ok_or_else(malformed)?.to_vec();
+    let mut offset = 4 + vendor_len;
+    let count = read_u32_le(data, offset).ok_or_else(malformed)?;
+    offset += 4;
+
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let len = read_u32_le(data, offset).ok_or_else(malformed)? as usize;
+        offset += 4;
+        comments.push(
+            data.get(offset..offset + len)
+                .ok_or_else(malformed)?
+                .to_vec(),
+        );
+        offset += len;
+    }
+    Ok((vendor, comments))
+}
+
+fn build_vorbis_comment(vendor: &[u8], comments: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    data.extend_from_slice(vendor);
+    data.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        data.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        data.extend_from_slice(comment);
+    }
+    data
+}
+
+fn is_fmps_rating_comment(comment: &[u8]) -> bool {
+    comment
+        .iter()
+        .position(|byte| *byte == b'=')
+        .is_some_and(|eq| comment[..eq].eq_ignore_ascii_case(FMPS_RATING_FIELD.as_bytes()))
+}
+
+fn update_flac_blocks(blocks: &mut Vec<FlacBlock>, rating: u8) -> Result<(), String> {
+    let position = blocks
+        .iter()
+        .position(|block| block.block_type == FLAC_BLOCK_VORBIS_COMMENT);
+    let (vendor, mut comments) = match position {
+        Some(index) => parse_vorbis_comment(&blocks[index].data)?,
+        None => (b"rmusic".to_vec(), Vec::new()),
+    };
+    comments.retain(|comment| !is_fmps_rating_comment(comment));
+    if rating > 0 {
+        comments.push(format!("{}={}", FMPS_RATING_FIELD, fmps_value(rating)).into_bytes());
+    }
+
+    let block = FlacBlock {
+        block_type: FLAC_BLOCK_VORBIS_COMMENT,
+        data: build_vorbis_comment(&vendor, &comments),
+    };
+    match position {
+        Some(index) => blocks[index] = block,
+        // STREAMINFO 必须是第一个块
+        None => blocks.insert(1.min(blocks.len()), block),
+    }
+
+    // 填充块由调用方按写入方式重新补上
+    blocks.retain(|block| block.block_type != FLAC_BLOCK_PADDING);
+    Ok(())
+}
+
+/// "fLaC" 标记加所有元数据块（含 4 字节块头）的总长度
+fn flac_metadata_len(blocks: &[FlacBlock]) -> usize {
+    FLAC_MARKER.len()
+        + blocks
+            .iter()
+            .map(|block| 4 + block.data.len())
+            .sum::<usize>()
+}
+
+/// 用填充块把元数据补到原来的长度；剩余空间放不下一个块头时返回 false
+fn pad_flac_blocks_to(blocks: &mut Vec<FlacBlock>, len: usize) -> bool {
+    let Some(remaining) = len.checked_sub(flac_metadata_len(blocks)) else {
+        return false;
+    };
+    match remaining {
+        0 => true,
+        1..=3 => false,
+        _ => {
+            blocks.push(FlacBlock {
+                block_type: FLAC_BLOCK_PADDING,
+                data: vec![0; remaining - 4],
+            });
+            true
+        }
+    }
+}
+
+fn read_flac_blocks(reader: &mut impl Read) -> Result<Vec<FlacBlock>, String> {
+    let mut marker = [0u8; 4];
+    reader
+        .read_exact(&mut marker)
+        .map_err(|e| format!("read FLAC header: {}", e))?;
+    if &marker != FLAC_MARKER {
+        return Err("not a FLAC file".to_string());
+    }
+
+    let mut blocks = Vec::new();
+    loop {
+        let mut header = [0u8; 4];
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| format!("read FLAC metadata: {}", e))?;
+        let is_last = header[0] & 0x80 != 0;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut data = vec![0u8; len];
+        reader
+            .read_exact(&mut data)
+            .map_err(|e| format!("read FLAC metadata: {}", e))?;
+        blocks.push(FlacBlock {
+            block_type: header[0] & 0x7f,
+            data,
+        });
+        if is_last {
+            return Ok(blocks);
+        }
+    }
+}
+
+fn write_flac_blocks(writer: &mut impl Write, blocks: &[FlacBlock]) -> Result<(), String> {
+    writer
+        .write_all(FLAC_MARKER)
+        .map_err(|e| format!("write FLAC header: {}", e))?;
+    for (index, block) in blocks.iter().enumerate() {
+        if block.data.len() >= 1 << 24 {
+            return Err("FLAC metadata block too large".to_string());
+        }
+        let len = (block.data.len() as u32).to_be_bytes();
+        let last_flag = if index + 1 == blocks.len() { 0x80 } else { 0 };
+        writer
+            .write_all(&[block.block_type | last_flag, len[1], len[2], len[3]])
+            .and_then(|_| writer.write_all(&block.data))
+            .map_err(|e| format!("write FLAC metadata: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 新的注释块能放进原有填充时原地覆盖元数据；放不下才拷贝音频帧到临时文件，再原子替换原文件
+fn write_flac_rating(path: &Path, rating: u8) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+    let mut blocks = read_flac_blocks(&mut reader)?;
+    let original_len = flac_metadata_len(&blocks);
+    update_flac_blocks(&mut blocks, rating)?;
+
+    if pad_flac_blocks_to(&mut blocks, original_len) {
+        drop(reader);
+        return overwrite_flac_metadata(path, &blocks);
+    }
+    blocks.push(FlacBlock {
+        block_type: FLAC_BLOCK_PADDING,
+        data: vec![0; FLAC_PADDING_LEN],
+    });
+
+    let tmp_path = unique_temp_path_for(path);
+    let result: Result<(), String> = (|| {
+        let tmp_file = File::create(&tmp_path)
+            .map_err(|e| format!("create temp file {}: {}", tmp_path.display(), e))?;
+        let mut writer = BufWriter::new(tmp_file);
+        write_flac_blocks(&mut writer, &blocks)?;
+        std::io::copy(&mut reader, &mut writer).map_err(|e| format!("copy FLAC frames: {}", e))?;
+        writer
+            .flush()
+            .map_err(|e| format!("flush {}: {}", tmp_path.display(), e))?;
+        writer
+            .get_ref()
+            .sync_all()
+            .map_err(|e| format!("sync {}: {}", tmp_path.display(), e))?;
+        drop(writer);
+        // Windows 上替换前必须先关闭原文件
+        drop(reader);
+        commit_temp_file(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// 元数据总长度不变，从文件头覆盖写入，音频帧原样保留
+fn overwrite_flac_metadata(path: &Path, blocks: &[FlacBlock]) -> Result<(), String> {
+    let mut metadata = Vec::with_capacity(flac_metadata_len(blocks));
+    write_flac_blocks(&mut metadata, blocks)?;
+    let mut file = File::options()
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("open {}: {}", path.display(), e))?;
+    file.write_all(&metadata)
+        .map_err(|e| format!("write FLAC metadata {}: {}", path.display(), e))?;
+    file.sync_all()
+        .map_err(|e| format!("sync {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn rating_values_follow_popm_and_fmps_conventions() {
+        assert_eq!(popm_value(0), 0);
+        assert_eq!(popm_value(3), 128);
+        assert_eq!(popm_value(5), 255);
+        assert_eq!(fmps_value(3), "0.6");
+        assert_eq!(fmps_value(5), "1.0");
+    }
+
+    #[test]
+    fn id3_rating_keeps_other_players_popm_frames() {
+        let mut tag = Tag::new();
+        tag.add_frame(Frame::with_content(
+            "POPM",
+            Content::Popularimeter(Popularimeter {
+                user: "other@example.com".into(),
+                rating: 10,
+                counter: 3,
+            }),
+        ));
+
+        apply_id3_rating(&mut tag, 4);
+        let popms: Vec<&Popularimeter> = tag
+            .frames()
+            .filter_map(|frame| frame.content().popularimeter())
+            .collect();
+        assert_eq!(popms.len(), 2);
+        assert!(popms
+            .iter()
+            .any(|popm| popm.user == POPM_USER && popm.rating == 196));
+        assert!(tag
+            .extended_texts()
+            .any(|text| text.description == FMPS_RATING_DESCRIPTION && text.value == "0.8"));
+
+        apply_id3_rating(&mut tag, 0);
+        assert_eq!(tag.frames().count(), 1);
+    }
+
+    fn unique_test_dir() -> std::path::PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "rmusic-rating-tags-{}-{}",
+            std::process::id(),
+            unique
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn flac_rating_rewrites_vorbis_comment_and_preserves_frames() {
+        let streaminfo = FlacBlock {
+            block_type: 0,
+            data: vec![7; 34],
+        };
+        let comment = FlacBlock {
+            block_type: FLAC_BLOCK_VORBIS_COMMENT,
+            data: build_vorbis_comment(b"vendor", &[b"TITLE=Song".to_vec()]),
+        };
+        let mut bytes = Vec::new();
+        write_flac_blocks(&mut bytes, &[streaminfo, comment]).unwrap();
+        bytes.extend_from_slice(b"AUDIO");
+
+        let dir = unique_test_dir();
+        let path = dir.join("song.flac");
+        fs::write(&path, &bytes).unwrap();
+
+        write_rating(&path, 5).unwrap();
+
+        let written = fs::read(&path).unwrap();
+        assert!(written.ends_with(b"AUDIO"));
+        let blocks = read_flac_blocks(&mut written.as_slice()).unwrap();
+        assert_eq!(blocks[0].data, vec![7; 34]);
+        let (vendor, comments) = parse_vorbis_comment(&blocks[1].data).unwrap();
+        assert_eq!(vendor, b"vendor");
+        assert_eq!(
+            comments,
+            vec![b"TITLE=Song".to_vec(), b"FMPS_RATING=1.0".to_vec()]
+        );
+        let padding = blocks.last().unwrap();
+        assert_eq!(padding.block_type, FLAC_BLOCK_PADDING);
+        assert_eq!(padding.data.len(), FLAC_PADDING_LEN);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn flac_rating_is_written_in_place_when_padding_fits() {
+        let blocks = [
+            FlacBlock {
+                block_type: 0,
+                data: vec![7; 34],
+            },
+            FlacBlock {
+                block_type: FLAC_BLOCK_VORBIS_COMMENT,
+                data: build_vorbis_comment(
+                    b"vendor",
+                    &[b"TITLE=Song".to_vec(), b"fmps_rating=0.2".to_vec()],
+                ),
+            },
+            FlacBlock {
+                block_type: FLAC_BLOCK_PADDING,
+                data: vec![0; 64],
+            },
+        ];
+        let mut bytes = Vec::new();
+        write_flac_blocks(&mut bytes, &blocks).unwrap();
+        let metadata_len = bytes.len();
+        bytes.extend_from_slice(b"AUDIO");
+
+        let dir = unique_test_dir();
+        let path = dir.join("song.flac");
+        fs::write(&path, &bytes).unwrap();
+
+        write_rating(&path, 3).unwrap();
+        let written = fs::read(&path).unwrap();
+        assert_eq!(written.len(), bytes.len());
+        assert_eq!(&written[metadata_len..], b"AUDIO");
+        let blocks = read_flac_blocks(&mut written.as_slice()).unwrap();
+        let (_, comments) = parse_vorbis_comment(&blocks[1].data).unwrap();
+        assert_eq!(
+            comments,
+            vec![b"TITLE=Song".to_vec(), b"FMPS_RATING=0.6".to_vec()]
+        );
+        assert_eq!(flac_metadata_len(&blocks), metadata_len);
+
+        // 清除评分后注释变短，多出的空间并回填充块
+        write_rating(&path, 0).unwrap();
+        let cleared = fs::read(&path).unwrap();
+        assert_eq!(cleared.len(), bytes.len());
+        let blocks = read_flac_blocks(&mut cleared.as_slice()).unwrap();
+        let (_, comments) = parse_vorbis_comment(&blocks[1].data).unwrap();
+        assert_eq!(comments, vec![b"TITLE=Song".to_vec()]);
+        assert_eq!(blocks.last().unwrap().block_type, FLAC_BLOCK_PADDING);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,569 @@
+// 局域网远程控制 API（默认关闭）：带令牌的 HTTP REST 接口与推送播放状态的 WebSocket。
+// 控制命令与 `rmusic ctl` 共用 execute，最终仍落到 Sink / MusicState
+
+use futures_util::{SinkExt, StreamExt};
+use hyper::body::HttpBody;
+use hyper::header::{self, HeaderValue};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::local_server::{RunningServer, ServerSlot, ServerStatus};
+use crate::remote_control::{
+    constant_time_eq, execute, playback_status, random_token, ControlCommand, ControlResponse,
+};
+use crate::storage::{self, app_data_file};
+
+const SETTINGS_FILE: &str = "remote_api.json";
+/// 默认只监听本机；要从手机等设备访问时改成 0.0.0.0 或局域网地址
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:17878";
+const MAX_BODY_BYTES: usize = 16 * 1024;
+const STATE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// 两次轮询之间位置变化超过该值视为跳转；正常播放（最高 2 倍速）不会超过
+const POSITION_JUMP_MS: i64 = 2_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RemoteApiSettings {
+    pub enabled: bool,
+    pub bind_address: String,
+    /// 为空时保存设置会自动生成
+    pub token: String,
+}
+
+impl Default for RemoteApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: DEFAULT_BIND_ADDRESS.to_string(),
+            token: String::new(),
+        }
+    }
+}
+
+impl RemoteApiSettings {
+    /// 校验监听地址并补全令牌
+    fn normalized(mut self) -> Result<Self, String> {
+        self.bind_address = self.bind_address.trim().to_string();
+        if self.bind_address.is_empty() {
+            self.bind_address = DEFAULT_BIND_ADDRESS.to_string();
+        }
+        self.bind_address
+            .parse::<SocketAddr>()
+            .map_err(|e| format!("invalid bind address {}: {}", self.bind_address, e))?;
+        self.token = self.token.trim().to_string();
+        if self.token.is_empty() {
+            self.token = random_token();
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Default)]
+pub struct RemoteApiState {
+    settings: StdMutex<RemoteApiSettings>,
+    server: ServerSlot,
+}
+
+impl RemoteApiState {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let settings = settings_path(app_handle)
+            .map(|path| storage::read_json_or_default(&path))
+            .unwrap_or_default();
+        Self {
+            settings: StdMutex::new(settings),
+            ..Self::default()
+        }
+    }
+
+    fn settings(&self) -> RemoteApiSettings {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn settings_path(app_handle: &AppHandle) -> Result<std::path::PathBuf, String> {
+    app_data_file(app_handle, SETTINGS_FILE)
+}
+
+#[derive(Debug, PartialEq)]
+enum Route {
+    Status,
+    Search,
+    Socket,
+    Command(ControlCommand),
+    Seek,
+    Volume,
+}
+
+fn route(method: &Method, path: &str) -> Option<Route> {
+    let path = path.trim_end_matches('/');
+    let route = match (method, path) {
+        (&Method::GET, "/api/status") => Route::Status,
+        (&Method::GET, "/api/search") => Route::Search,
+        (&Method::GET, "/api/ws") => Route::Socket,
+        (&Method::POST, "/api/play") => Route::Command(ControlCommand::Play),
+        (&Method::POST, "/api/pause") => Route::Command(ControlCommand::Pause),
+        (&Method::POST, "/api/toggle") => Route::Command(ControlCommand::Toggle),
+        (&Method::POST, "/api/next") => Route::Command(ControlCommand::Next),
+        (&Method::POST, "/api/prev") => Route::Command(ControlCommand::Prev),
+        (&Method::POST, "/api/seek") => Route::Seek,
+        (&Method::POST, "/api/volume") => Route::Volume,
+        _ => return None,
+    };
+    Some(route)
+}
+
+fn query_param(query: Option<&str>, name: &str) -> Option<String> {
+    query?.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        (key == name).then(|| {
+            urlencoding::decode(&value.replace('+', " "))
+                .map(|value| value.into_owned())
+                .unwrap_or_default()
+        })
+    })
+}
+
+/// 令牌可放在 `Authorization: Bearer` 头，或（浏览器 WebSocket 无法带头时）放在 `?token=`
+fn is_authorized(request: &Request<Body>, token: &str) -> bool {
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|value| value.trim().to_string())
+        .or_else(|| query_param(request.uri().query(), "token"));
+    provided.is_some_and(|provided| constant_time_eq(provided.as_bytes(), token.as_bytes()))
+}
+
+/// 是否需要向 WebSocket 推送：除位置外的字段变化，或位置发生跳变
+fn state_changed(previous: &Value, next: &Value) -> bool {
+    let position = |value: &Value| value["position_ms"].as_i64().unwrap_or(0);
+    let strip = |value: &Value| {
+        let mut value = value.clone();
+        if let Some(object) = value.as_object_mut() {
+            object.remove("position_ms");
+        }
+        value
+    };
+    strip(previous) != strip(next) || (position(next) - position(previous)).abs() > POSITION_JUMP_MS
+}
+
+fn json_response(status: StatusCode, result: Result<Value, String>) -> Response<Body> {
+    let body = serde_json::to_vec(&ControlResponse::from_result(result)).unwrap_or_default();
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+async fn read_json_body<T: serde::de::DeserializeOwned>(
+    request: Request<Body>,
+) -> Result<T, String> {
+    let mut body = request.into_body();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| format!("read body: {}", e))?;
+        if bytes.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err("request body too large".to_string());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    serde_json::from_slice(&bytes).map_err(|e| format!("invalid body: {}", e))
+}
+
+#[derive(Deserialize)]
+struct SeekBody {
+    position_ms: u64,
+}
+
+#[derive(Deserialize)]
+struct VolumeBody {
+    volume: f32,
+}
+
+struct ServerContext {
+    app_handle: AppHandle,
+    token: String,
+    updates: watch::Receiver<Value>,
+    shutdown: watch::Receiver<bool>,
+}
+
+async fn search(app_handle: &AppHandle, query: Option<&str>) -> Result<Value, String> {
+    let keywords = query_param(query, "q")
+        .filter(|keywords| !keywords.trim().is_empty())
+        .ok_or_else(|| "missing query parameter q".to_string())?;
+    let page = query_param(query, "page").and_then(|page| page.parse().ok());
+    let page_size = query_param(query, "page_size").and_then(|size| size.parse().ok());
+    crate::service::ensure_online_service(app_handle.clone(), app_handle.state()).await?;
+    let result =
+        crate::provider::search_songs(app_handle.state(), keywords, page, page_size, None).await?;
+    serde_json::to_value(result).map_err(|e| format!("encode search result: {}", e))
+}
+
+async fn run_socket(context: Arc<ServerContext>, upgraded: hyper::upgrade::Upgraded) {
+    let mut socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+    let mut updates = context.updates.clone();
+    let mut shutdown = context.shutdown.clone();
+
+    // 连接后先推送一次完整状态
+    if let Ok(status) = playback_status(&context.app_handle).await {
+        if socket
+            .send(Message::Text(status.to_string()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+    loop {
+        tokio::select! {
+            changed = updates.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let status = updates.borrow_and_update().to_string();
+                if socket.send(Message::Text(status)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // 客户端消息目前只用于保活，tungstenite 会自动回复 Ping
+                Some(Ok(_)) => {}
+            },
+            _ = shutdown.changed() => break,
+        }
+    }
+    let _ = socket.close(None).await;
+}
+
+fn upgrade_socket(
+    context: Arc<ServerContext>,
+    mut request: Request<Body>,
+) -> Result<Response<Body>, String> {
+    let is_websocket = request
+        .headers()
+        .get(header::UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+    let key = request
+        .headers()
+        .get(header::SEC_WEBSOCKET_KEY)
+        .map(|key| derive_accept_key(key.as_bytes()));
+    let (true, Some(accept)) = (is_websocket, key) else {
+        return Err("expected a WebSocket upgrade".to_string());
+    };
+
+    tauri::async_runtime::spawn(async move {
+        match hyper::upgrade::on(&mut request).await {
+            Ok(upgraded) => run_socket(context, upgraded).await,
+            Err(error) => eprintln!("WebSocket upgrade failed: {}", error),
+        }
+    });
+
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    let headers = response.headers_mut();
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+    if let Ok(accept) = HeaderValue::from_str(&accept) {
+        headers.insert(header::SEC_WEBSOCKET_ACCEPT, accept);
+    }
+    Ok(response)
+}
+
+async fn handle_request(context: Arc<ServerContext>, request: Request<Body>) -> Response<Body> {
+    // 允许局域网内的网页控制面板跨域调用
+    if request.method() == Method::OPTIONS {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NO_CONTENT;
+        let headers = response.headers_mut();
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("GET, POST, OPTIONS"),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            HeaderValue::from_static("Authorization, Content-Type"),
+        );
+        return response;
+    }
+
+    let Some(route) = route(request.method(), request.uri().path()) else {
+        return json_response(StatusCode::NOT_FOUND, Err("not found".to_string()));
+    };
+    if !is_authorized(&request, &context.token) {
+        return json_response(StatusCode::UNAUTHORIZED, Err("invalid token".to_string()));
+    }
+
+    let app_handle = &context.app_handle;
+    let result = match route {
+        Route::Status => playback_status(app_handle).await,
+        Route::Search => search(app_handle, request.uri().query()).await,
+        Route::Socket => {
+            return upgrade_socket(context.clone(), request)
+                .unwrap_or_else(|error| json_response(StatusCode::BAD_REQUEST, Err(error)));
+        }
+        Route::Command(command) => execute(app_handle, command).await,
+        Route::Seek => match read_json_body::<SeekBody>(request).await {
+            Ok(body) => {
+                let command = ControlCommand::Seek {
+                    position_ms: body.position_ms,
+                };
+                execute(app_handle, command).await
+            }
+            Err(error) => return json_response(StatusCode::BAD_REQUEST, Err(error)),
+        },
+        Route::Volume => match read_json_body::<VolumeBody>(request).await {
+            Ok(body) if body.volume.is_finite() => {
+                let command = ControlCommand::Volume {
+                    volume: body.volume.clamp(0.0, 100.0),
+                };
+                execute(app_handle, command).await
+            }
+            Ok(_) => Err("invalid volume".to_string()),
+            Err(error) => return json_response(StatusCode::BAD_REQUEST, Err(error)),
+        },
+    };
+    let status = if result.is_ok() {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    json_response(status, result)
+}
+
+/// 轮询播放状态，有变化时通知所有 WebSocket 连接
+async fn run_state_poller(
+    app_handle: AppHandle,
+    updates: watch::Sender<Value>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(STATE_POLL_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut previous = Value::Null;
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.changed() => break,
+        }
+        let Ok(status) = playback_status(&app_handle).await else {
+            continue;
+        };
+        // 轮询值总是保存，这样下一次比较的是相邻两次的位置
+        let changed = state_changed(&previous, &status);
+        previous = status.clone();
+        if changed {
+            updates.send_replace(status);
+        }
+    }
+}
+
+async fn start_server(
+    app_handle: &AppHandle,
+    settings: &RemoteApiSettings,
+) -> Result<RunningServer, String> {
+    let address: SocketAddr = settings
+        .bind_address
+        .parse()
+        .map_err(|e| format!("invalid bind address {}: {}", settings.bind_address, e))?;
+    let listener = std::net::TcpListener::bind(address)
+        .map_err(|e| format!("bind remote API on {}: {}", address, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("configure remote API listener: {}", e))?;
+    let address = listener.local_addr().unwrap_or(address);
+    let server = Server::from_tcp(listener).map_err(|e| format!("start remote API: {}", e))?;
+
+    let (shutdown, shutdown_receiver) = watch::channel(false);
+    let (updates, updates_receiver) = watch::channel(Value::Null);
+    tauri::async_runtime::spawn(run_state_poller(
+        app_handle.clone(),
+        updates,
+        shutdown_receiver.clone(),
+    ));
+
+    let context = Arc::new(ServerContext {
+        app_handle: app_handle.clone(),
+        token: settings.token.clone(),
+        updates: updates_receiver,
+        shutdown: shutdown_receiver.clone(),
+    });
+    let make_service = make_service_fn(move |_| {
+        let context = context.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let context = context.clone();
+                async move {
+                    let mut response = handle_request(context, request).await;
+                    response.headers_mut().insert(
+                        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+                        HeaderValue::from_static("*"),
+                    );
+                    Ok::<_, Infallible>(response)
+                }
+            }))
+        }
+    });
+
+    let mut shutdown_signal = shutdown_receiver;
+    let server = server
+        .serve(make_service)
+        .with_graceful_shutdown(async move {
+            let _ = shutdown_signal.changed().await;
+        });
+    let state_app = app_handle.clone();
+    let task = tokio::spawn(async move {
+        if let Err(error) = server.await {
+            eprintln!("Remote API server error: {}", error);
+            state_app
+                .state::<RemoteApiState>()
+                .server
+                .set_last_error(Some(error.to_string()));
+        }
+    });
+    Ok(RunningServer::new(address, shutdown, task))
+}
+
+/// 按当前设置重启服务：先等旧的退出，启用时再绑定新地址
+async fn restart(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<RemoteApiState>();
+    let settings = state.settings();
+    state
+        .server
+        .restart(settings.enabled, || start_server(app_handle, &settings))
+        .await
+}
+
+pub fn start_remote_api(app_handle: &AppHandle) {
+    if !app_handle.state::<RemoteApiState>().settings().enabled {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(error) = restart(&app_handle).await {
+            eprintln!("Failed to start remote API: {}", error);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_remote_api_settings(state: tauri::State<'_, RemoteApiState>) -> RemoteApiSettings {
+    state.settings()
+}
+
+/// 保存设置并立即按新设置重启服务；返回补全令牌后的设置
+#[tauri::command]
+pub async fn set_remote_api_settings(
+    app_handle: AppHandle,
+    settings: RemoteApiSettings,
+) -> Result<RemoteApiSettings, String> {
+    let settings = settings.normalized()?;
+    storage::write_json(&settings_path(&app_handle)?, &settings)?;
+    {
+        let state = app_handle.state::<RemoteApiState>();
+        *state
+            .settings
+            .lock()
+            .map_err(|_| "remote API settings lock poisoned".to_string())? = settings.clone();
+    }
+    restart(&app_handle).await?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn get_remote_api_status(state: tauri::State<'_, RemoteApiState>) -> ServerStatus {
+    state.server.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn routes_rest_endpoints() {
+        assert_eq!(route(&Method::GET, "/api/status/"), Some(Route::Status));
+        assert_eq!(
+            route(&Method::POST, "/api/toggle"),
+            Some(Route::Command(ControlCommand::Toggle))
+        );
+        assert_eq!(route(&Method::POST, "/api/seek"), Some(Route::Seek));
+        assert_eq!(route(&Method::GET, "/api/seek"), None);
+        assert_eq!(route(&Method::GET, "/"), None);
+    }
+
+    #[test]
+    fn accepts_bearer_header_or_query_token() {
+        let request = Request::get("/api/status")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+        assert!(is_authorized(&request, "secret"));
+        assert!(!is_authorized(&request, "other"));
+
+        let request = Request::get("/api/ws?token=secret")
+            .body(Body::empty())
+            .unwrap();
+        assert!(is_authorized(&request, "secret"));
+
+        let request = Request::get("/api/status").body(Body::empty()).unwrap();
+        assert!(!is_authorized(&request, "secret"));
+    }
+
+    #[test]
+    fn decodes_query_parameters() {
+        let query = Some("q=%E7%A8%BB%E9%A6%99+live&page=2&flag");
+        assert_eq!(query_param(query, "q").as_deref(), Some("稻香 live"));
+        assert_eq!(query_param(query, "page").as_deref(), Some("2"));
+        assert_eq!(query_param(query, "flag").as_deref(), Some(""));
+        assert_eq!(query_param(query, "missing"), None);
+    }
+
+    #[test]
+    fn pushes_on_state_change_or_position_jump() {
+        let base = json!({ "is_paused": false, "position_ms": 10_000, "track_id": 1 });
+        let progressed = json!({ "is_paused": false, "position_ms": 10_500, "track_id": 1 });
+        let paused = json!({ "is_paused": true, "position_ms": 10_500, "track_id": 1 });
+        let seeked = json!({ "is_paused": false, "position_ms": 60_000, "track_id": 1 });
+        assert!(!state_changed(&base, &progressed));
+        assert!(state_changed(&base, &paused));
+        assert!(state_changed(&base, &seeked));
+    }
+
+    #[test]
+    fn normalizing_settings_validates_address_and_fills_token() {
+        let settings = RemoteApiSettings {
+            bind_address: " ".to_string(),
+            ..RemoteApiSettings::default()
+        }
+        .normalized()
+        .unwrap();
+        assert_eq!(settings.bind_address, DEFAULT_BIND_ADDRESS);
+        assert_eq!(settings.token.len(), 32);
+
+        let invalid = RemoteApiSettings {
+            bind_address: "localhost".to_string(),
+            ..RemoteApiSettings::default()
+        };
+        assert!(invalid.normalized().is_err());
+    }
+}
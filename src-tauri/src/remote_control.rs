@@ -0,0 +1,527 @@
+// 命令行远程控制：`rmusic ctl <命令>` 经本机回环端口把命令转发给正在运行的实例。
+// 运行中的实例复用 control_playback / seek_to 等现有逻辑执行，并回复一行 JSON
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::launch::{open_paths, resolve_path, OpenMode};
+use crate::music::{get_playback_state, seek_to, NowPlayingState, PlaybackVolumeState};
+use crate::storage::{app_data_file, write_json};
+use crate::{control_playback, PlaybackControlAction};
+
+const ENDPOINT_FILE: &str = "remote_control.json";
+/// 单条请求的上限，防止异常连接一直写入
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+/// enqueue 网易云歌曲时可能需要先启动在线服务，超时放宽
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(20);
+
+pub const USAGE: &str = "usage: rmusic ctl <command>
+
+commands:
+  play | pause | toggle | next | prev
+  seek <seconds | mm:ss>
+  volume <0-100>
+  status
+  enqueue <path | netease:<id> | <id>>";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ControlEndpoint {
+    port: u16,
+    token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    Play,
+    Pause,
+    Toggle,
+    Next,
+    Prev,
+    Seek { position_ms: u64 },
+    Volume { volume: f32 },
+    Status,
+    Enqueue { target: EnqueueTarget },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EnqueueTarget {
+    Path { path: PathBuf },
+    Netease { id: String },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ControlRequest {
+    token: String,
+    #[serde(flatten)]
+    command: ControlCommand,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct ControlResponse {
+    ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl ControlResponse {
+    pub(crate) fn from_result(result: Result<Value, String>) -> Self {
+        match result {
+            Ok(data) => Self {
+                ok: true,
+                data: Some(data),
+                error: None,
+            },
+            Err(error) => Self {
+                ok: false,
+                data: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// 秒数（可带小数）或 mm:ss / hh:mm:ss
+fn parse_position_ms(value: &str) -> Option<u64> {
+    let mut seconds = 0.0f64;
+    for part in value.split(':') {
+        let part: f64 = part.trim().parse().ok()?;
+        if !part.is_finite() || part < 0.0 {
+            return None;
+        }
+        seconds = seconds * 60.0 + part;
+    }
+    Some((seconds * 1000.0).round() as u64)
+}
+
+fn parse_enqueue_target(value: &str, cwd: &Path) -> EnqueueTarget {
+    if let Some(id) = value.strip_prefix("netease:") {
+        return EnqueueTarget::Netease { id: id.to_string() };
+    }
+    let path = resolve_path(value, cwd);
+    // 纯数字且不是已存在的文件时按网易云歌曲 id 处理
+    if !path.exists() && !value.is_empty() && value.chars().all(|ch| ch.is_ascii_digit()) {
+        return EnqueueTarget::Netease {
+            id: value.to_string(),
+        };
+    }
+    EnqueueTarget::Path { path }
+}
+
+/// 解析 `ctl` 之后的参数
+pub fn parse_ctl_args(args: &[String], cwd: &Path) -> Result<ControlCommand, String> {
+    let (name, rest) = args.split_first().ok_or_else(|| USAGE.to_string())?;
+    let argument = |label: &str| {
+        rest.first()
+            .map(|value| value.as_str())
+            .ok_or_else(|| format!("missing {} for `{}`\n\n{}", label, name, USAGE))
+    };
+    let command = match name.as_str() {
+        "play" => ControlCommand::Play,
+        "pause" => ControlCommand::Pause,
+        "toggle" => ControlCommand::Toggle,
+        "next" => ControlCommand::Next,
+        "prev" | "previous" => ControlCommand::Prev,
+        "status" => ControlCommand::Status,
+        "seek" => {
+            let value = argument("position")?;
+            let position_ms =
+                parse_position_ms(value).ok_or_else(|| format!("invalid position: {}", value))?;
+            ControlCommand::Seek { position_ms }
+        }
+        "volume" => {
+            let value = argument("volume")?;
+            let volume = value
+                .parse::<f32>()
+                .ok()
+                .filter(|volume| volume.is_finite())
+                .ok_or_else(|| format!("invalid volume: {}", value))?;
+            ControlCommand::Volume {
+                volume: volume.clamp(0.0, 100.0),
+            }
+        }
+        "enqueue" => ControlCommand::Enqueue {
+            target: parse_enqueue_target(argument("path or id")?, cwd),
+        },
+        other => return Err(format!("unknown command: {}\n\n{}", other, USAGE)),
+    };
+    Ok(command)
+}
+
+/// CLI 模式不启动 Tauri，按配置里的 identifier 自行拼出 app_data_dir
+fn endpoint_file_for_client(identifier: &str) -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join(identifier).join(ENDPOINT_FILE))
+        .ok_or_else(|| "unable to locate app data dir".to_string())
+}
+
+fn send_request(identifier: &str, command: ControlCommand) -> Result<ControlResponse, String> {
+    let endpoint_path = endpoint_file_for_client(identifier)?;
+    let endpoint: ControlEndpoint = std::fs::read(&endpoint_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| "rmusic is not running".to_string())?;
+
+    let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, endpoint.port))
+        .map_err(|_| "rmusic is not running".to_string())?;
+    stream
+        .set_read_timeout(Some(CLIENT_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(CLIENT_TIMEOUT)))
+        .map_err(|e| format!("configure control socket: {}", e))?;
+
+    let request = ControlRequest {
+        token: endpoint.token,
+        command,
+    };
+    let mut line = serde_json::to_string(&request).map_err(|e| format!("encode request: {}", e))?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("send request: {}", e))?;
+
+    let mut response = String::new();
+    BufReader::new(stream)
+        .read_line(&mut response)
+        .map_err(|e| format!("read response: {}", e))?;
+    serde_json::from_str(&response).map_err(|e| format!("parse response: {}", e))
+}
+
+/// release 构建是 GUI 子系统，没有控制台；从终端运行时挂到父进程的控制台上，输出才可见。
+/// 标准输出已被重定向（管道、文件）时句柄本来就有效，挂接失败也无妨
+#[cfg(windows)]
+fn attach_parent_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // SAFETY: AttachConsole 只接收一个进程 id，失败时返回 0，不涉及内存
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// CLI 入口：打印回复的 JSON 并返回进程退出码（0 成功，1 失败，2 参数错误）。
+/// identifier 取自 tauri.conf.json，用于定位运行中实例写下的端点文件
+pub fn run_ctl(identifier: &str, args: &[String]) -> i32 {
+    #[cfg(windows)]
+    attach_parent_console();
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let command = match parse_ctl_args(args, &cwd) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 2;
+        }
+    };
+    let response = send_request(identifier, command)
+        .unwrap_or_else(|error| ControlResponse::from_result(Err(error)));
+    match serde_json::to_string_pretty(&response) {
+        Ok(text) => println!("{}", text),
+        Err(error) => eprintln!("encode response: {}", error),
+    }
+    if response.ok {
+        0
+    } else {
+        1
+    }
+}
+
+fn control(app_handle: &AppHandle, action: PlaybackControlAction) -> Result<(), String> {
+    control_playback(
+        app_handle.state(),
+        app_handle.state(),
+        app_handle.state(),
+        action,
+        None,
+        None,
+        None,
+    )
+}
+
+pub(crate) async fn playback_status(app_handle: &AppHandle) -> Result<Value, String> {
+    let state = get_playback_state(
+        app_handle.state(),
+        app_handle.state(),
+        app_handle.state(),
+        app_handle.state(),
+    )
+    .await?;
+    let track = app_handle
+        .state::<NowPlayingState>()
+        .0
+        .lock()
+        .ok()
+        .and_then(|session| session.as_ref().map(|session| session.track.clone()));
+    let volume = app_handle
+        .state::<PlaybackVolumeState>()
+        .0
+        .lock()
+        .ok()
+        .and_then(|volume| *volume);
+
+    let mut status = serde_json::to_value(state).map_err(|e| format!("encode status: {}", e))?;
+    status["track"] = serde_json::to_value(track).unwrap_or(Value::Null);
+    status["volume"] = json!(volume);
+    Ok(status)
+}
+
+/// 与托盘菜单一致：后端立即切换播放状态，再通知前端同步
+fn set_playing(app_handle: &AppHandle, playing: bool) -> Result<(), String> {
+    if playing {
+        control(app_handle, PlaybackControlAction::Play)?;
+        let _ = app_handle.emit("tray-play", ());
+    } else {
+        control(app_handle, PlaybackControlAction::Pause)?;
+        let _ = app_handle.emit("tray-pause", ());
+    }
+    Ok(())
+}
+
+pub(crate) async fn execute(
+    app_handle: &AppHandle,
+    command: ControlCommand,
+) -> Result<Value, String> {
+    match command {
+        ControlCommand::Play => set_playing(app_handle, true)?,
+        ControlCommand::Pause => set_playing(app_handle, false)?,
+        ControlCommand::Toggle => {
+            let paused = get_playback_state(
+                app_handle.state(),
+                app_handle.state(),
+                app_handle.state(),
+                app_handle.state(),
+            )
+            .await?
+            .is_paused;
+            set_playing(app_handle, paused)?;
+            return Ok(json!({ "is_paused": !paused }));
+        }
+        ControlCommand::Next => {
+            let _ = app_handle.emit("tray-next", ());
+        }
+        ControlCommand::Prev => {
+            let _ = app_handle.emit("tray-prev", ());
+        }
+        ControlCommand::Seek { position_ms } => {
+            let result = seek_to(
+                app_handle.clone(),
+                app_handle.state(),
+                app_handle.state(),
+                position_ms,
+            )
+            .await?;
+            if result.should_play_next {
+                let _ = app_handle.emit("tray-next", ());
+            } else if result.success {
+                let _ = app_handle.emit("remote-seeked", position_ms);
+            }
+            return serde_json::to_value(result).map_err(|e| format!("encode result: {}", e));
+        }
+        ControlCommand::Volume { volume } => {
+            control_playback(
+                app_handle.state(),
+                app_handle.state(),
+                app_handle.state(),
+                PlaybackControlAction::Volume,
+                Some(volume),
+                None,
+                None,
+            )?;
+            let _ = app_handle.emit("remote-volume-changed", volume);
+        }
+        ControlCommand::Status => return playback_status(app_handle).await,
+        ControlCommand::Enqueue {
+            target: EnqueueTarget::Path { path },
+        } => {
+            if !path.exists() {
+                return Err(format!("file not found: {}", path.display()));
+            }
+            open_paths(app_handle, OpenMode::Enqueue, vec![path]);
+        }
+        ControlCommand::Enqueue {
+            target: EnqueueTarget::Netease { id },
+        } => {
+            crate::service::ensure_online_service(app_handle.clone(), app_handle.state()).await?;
+            let api = app_handle.state::<crate::online_api::OnlineApiState>();
+            let song = crate::provider::get_provider(&api, None)?
+                .song_detail(id)
+                .await?;
+            let _ = app_handle.emit("remote-enqueue-song", &song);
+            return serde_json::to_value(song).map_err(|e| format!("encode song: {}", e));
+        }
+    }
+    Ok(Value::Null)
+}
+
+async fn handle_connection(
+    app_handle: AppHandle,
+    stream: tokio::net::TcpStream,
+    token: String,
+) -> Result<(), String> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    tokio::io::BufReader::new(reader.take(MAX_REQUEST_BYTES))
+        .read_line(&mut line)
+        .await
+        .map_err(|e| format!("read control request: {}", e))?;
+
+    let result = match serde_json::from_str::<ControlRequest>(&line) {
+        Ok(request) if constant_time_eq(request.token.as_bytes(), token.as_bytes()) => {
+            execute(&app_handle, request.command).await
+        }
+        Ok(_) => Err("invalid token".to_string()),
+        Err(e) => Err(format!("invalid request: {}", e)),
+    };
+    let mut response = serde_json::to_string(&ControlResponse::from_result(result))
+        .map_err(|e| format!("encode control response: {}", e))?;
+    response.push('\n');
+    writer
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| format!("write control response: {}", e))
+}
+
+pub(crate) fn random_token() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 比较令牌 / 口令时不因第一个不同字节提前返回，避免按响应时间逐字节猜测
+pub(crate) fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// 在回环地址的随机端口上监听，端口与令牌写入 app_data_dir 供 CLI 读取
+pub fn start_remote_control(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await {
+            Ok(listener) => listener,
+            Err(error) => {
+                eprintln!("Failed to start remote control: {}", error);
+                return;
+            }
+        };
+        let endpoint = ControlEndpoint {
+            port: listener.local_addr().map(|addr| addr.port()).unwrap_or(0),
+            token: random_token(),
+        };
+        let written =
+            app_data_file(&app_handle, ENDPOINT_FILE).and_then(|path| write_json(&path, &endpoint));
+        if let Err(error) = written {
+            eprintln!("Failed to publish remote control endpoint: {}", error);
+            return;
+        }
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(error) => {
+                    eprintln!("Remote control accept error: {}", error);
+                    continue;
+                }
+            };
+            let app_handle = app_handle.clone();
+            let token = endpoint.token.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(error) = handle_connection(app_handle, stream, token).await {
+                    eprintln!("{}", error);
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_playback_commands() {
+        let cwd = Path::new("/home/user");
+        assert_eq!(
+            parse_ctl_args(&args(&["toggle"]), cwd),
+            Ok(ControlCommand::Toggle)
+        );
+        assert_eq!(
+            parse_ctl_args(&args(&["seek", "1:30.5"]), cwd),
+            Ok(ControlCommand::Seek {
+                position_ms: 90_500
+            })
+        );
+        assert_eq!(
+            parse_ctl_args(&args(&["volume", "150"]), cwd),
+            Ok(ControlCommand::Volume { volume: 100.0 })
+        );
+        assert!(parse_ctl_args(&args(&["seek", "-3"]), cwd).is_err());
+        assert!(parse_ctl_args(&args(&["volume"]), cwd).is_err());
+        assert!(parse_ctl_args(&args(&["shuffle"]), cwd).is_err());
+        assert!(parse_ctl_args(&[], cwd).is_err());
+    }
+
+    #[test]
+    fn enqueue_distinguishes_paths_and_netease_ids() {
+        let cwd = Path::new("/nonexistent-rmusic-dir");
+        assert_eq!(
+            parse_ctl_args(&args(&["enqueue", "347230"]), cwd),
+            Ok(ControlCommand::Enqueue {
+                target: EnqueueTarget::Netease {
+                    id: "347230".to_string()
+                }
+            })
+        );
+        assert_eq!(
+            parse_ctl_args(&args(&["enqueue", "netease:42"]), cwd),
+            Ok(ControlCommand::Enqueue {
+                target: EnqueueTarget::Netease {
+                    id: "42".to_string()
+                }
+            })
+        );
+        assert_eq!(
+            parse_ctl_args(&args(&["enqueue", "album/01.flac"]), cwd),
+            Ok(ControlCommand::Enqueue {
+                target: EnqueueTarget::Path {
+                    path: cwd.join("album/01.flac")
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn request_line_carries_token_and_flattened_command() {
+        let request = ControlRequest {
+            token: "abc".to_string(),
+            command: ControlCommand::Seek { position_ms: 1000 },
+        };
+        let line = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            line,
+            r#"{"token":"abc","command":"seek","position_ms":1000}"#
+        );
+        let parsed: ControlRequest = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.command, ControlCommand::Seek { position_ms: 1000 });
+    }
+}
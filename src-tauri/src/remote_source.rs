@@ -0,0 +1,836 @@
+// 远程曲库：连接使用 Subsonic API 的自建服务器（Navidrome、Airsonic、装了 Subsonic 插件的 Jellyfin 等）。
+// 服务器列表保存在 remote_servers.json；浏览 / 搜索结果以 SongInfo 形式返回，播放复用在线音频的渐进下载缓存。
+// 封面地址不带认证参数（会随歌曲写进播放列表、历史与会话），改指向 remote-cover 协议，由后端取图时再签名
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex as StdMutex;
+use tauri::http;
+use tauri::{AppHandle, Manager};
+
+use crate::netease::{self, SongInfo};
+use crate::remote_control::random_token;
+use crate::storage::{self, app_data_file};
+
+const SERVERS_FILE: &str = "remote_servers.json";
+const API_VERSION: &str = "1.16.1";
+const CLIENT_NAME: &str = "rmusic";
+const COVER_SIZE: u32 = 512;
+/// 封面代理协议，地址形如 remote-cover://localhost/<server_id>/<coverArt id>
+pub const COVER_SCHEME: &str = "remote-cover";
+/// 给外部程序用的封面缓存目录（位于 app_cache_dir 下）
+const COVER_CACHE_DIR: &str = "remote-covers";
+
+static SERVERS_LOCK: StdMutex<()> = StdMutex::new(());
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RemoteServer {
+    pub id: String,
+    pub name: String,
+    /// 服务器地址，例如 https://music.example.com（不含 /rest）
+    pub base_url: String,
+    pub username: String,
+    pub password: String,
+    /// 不支持 token 认证的服务器（如 LDAP 账号）改用 enc: 十六进制密码
+    pub legacy_auth: bool,
+}
+
+impl RemoteServer {
+    fn normalized(mut self) -> Result<Self, String> {
+        self.base_url = self.base_url.trim().trim_end_matches('/').to_string();
+        if let Some(base) = self.base_url.strip_suffix("/rest") {
+            self.base_url = base.to_string();
+        }
+        if !self.base_url.starts_with("http://") && !self.base_url.starts_with("https://") {
+            return Err(format!("invalid server url: {}", self.base_url));
+        }
+        self.username = self.username.trim().to_string();
+        if self.username.is_empty() {
+            return Err("username is required".to_string());
+        }
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            self.name = self.base_url.clone();
+        }
+        if self.id.trim().is_empty() {
+            self.id = random_token()[..12].to_string();
+        }
+        Ok(self)
+    }
+
+    /// 每次请求使用新的 salt，令牌只对本次请求有效
+    fn auth_query(&self) -> String {
+        let auth = if self.legacy_auth {
+            let hex: String = self
+                .password
+                .bytes()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            format!("p=enc:{}", hex)
+        } else {
+            let salt: String = (0..12)
+                .map(|_| format!("{:x}", rand::thread_rng().gen_range(0..16u8)))
+                .collect();
+            let token = format!("{:x}", md5::compute(format!("{}{}", self.password, salt)));
+            format!("t={}&s={}", token, salt)
+        };
+        format!(
+            "u={}&{}&v={}&c={}&f=json",
+            urlencoding::encode(&self.username),
+            auth,
+            API_VERSION,
+            CLIENT_NAME
+        )
+    }
+
+    fn endpoint_url(&self, endpoint: &str, params: &[(&str, String)]) -> String {
+        let mut url = format!("{}/rest/{}?{}", self.base_url, endpoint, self.auth_query());
+        for (key, value) in params {
+            url.push_str(&format!("&{}={}", key, urlencoding::encode(value)));
+        }
+        url
+    }
+
+    /// 原始文件串流；服务器端不转码，缓存的就是原文件
+    pub fn stream_url(&self, song_id: &str) -> String {
+        self.endpoint_url("stream", &[("id", song_id.to_string())])
+    }
+
+    /// 可持久化的封面地址：只含服务器 id 与 coverArt id
+    fn cover_url(&self, cover_art: Option<&str>) -> String {
+        cover_art
+            .filter(|id| !id.is_empty())
+            .map(|id| cover_proxy_url(&self.id, id))
+            .unwrap_or_default()
+    }
+
+    /// 带认证参数的封面地址，只在取图时临时生成，不要存下来
+    fn signed_cover_url(&self, cover_art: &str) -> String {
+        self.endpoint_url(
+            "getCoverArt",
+            &[
+                ("id", cover_art.to_string()),
+                ("size", COVER_SIZE.to_string()),
+            ],
+        )
+    }
+
+    async fn call(&self, endpoint: &str, params: &[(&str, String)]) -> Result<Value, String> {
+        let client = netease::get_client()?;
+        let response = netease::get_response(client, self.endpoint_url(endpoint, params)).await?;
+        let mut body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Read {} response error: {}", endpoint, e))?;
+        let response = body
+            .get_mut("subsonic-response")
+            .map(Value::take)
+            .ok_or_else(|| format!("{}: not a Subsonic response", endpoint))?;
+        if response["status"] != "ok" {
+            return Err(format!(
+                "Subsonic error {}: {}",
+                response["error"]["code"].as_u64().unwrap_or(0),
+                response["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+            ));
+        }
+        Ok(response)
+    }
+}
+
+/// 远程服务器上的一首歌；序列化后与 SongInfo 字段相同，另带 server_id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredRemoteSong")]
+pub struct RemoteSong {
+    pub server_id: String,
+    #[serde(flatten)]
+    pub song: SongInfo,
+}
+
+#[derive(Deserialize)]
+struct StoredRemoteSong {
+    server_id: String,
+    #[serde(flatten)]
+    song: SongInfo,
+}
+
+/// 读取时顺带清掉旧版本保存的带认证封面地址
+impl From<StoredRemoteSong> for RemoteSong {
+    fn from(stored: StoredRemoteSong) -> Self {
+        let mut song = stored.song;
+        song.pic_url = strip_cover_credentials(&stored.server_id, &song.pic_url);
+        Self {
+            server_id: stored.server_id,
+            song,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteAlbum {
+    pub server_id: String,
+    pub id: String,
+    pub name: String,
+    pub artist: String,
+    pub artist_id: String,
+    pub song_count: u32,
+    pub duration: u64, // ms
+    pub pic_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteArtist {
+    pub server_id: String,
+    pub id: String,
+    pub name: String,
+    pub album_count: u32,
+    pub pic_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoteSearchResult {
+    pub artists: Vec<RemoteArtist>,
+    pub albums: Vec<RemoteAlbum>,
+    pub songs: Vec<RemoteSong>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoteAlbumDetail {
+    pub album: RemoteAlbum,
+    pub songs: Vec<RemoteSong>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoteArtistDetail {
+    pub artist: RemoteArtist,
+    pub albums: Vec<RemoteAlbum>,
+}
+
+/// 在线音频缓存的键，按服务器区分，避免与网易云 id 冲突
+pub fn remote_cache_key(server_id: &str, song_id: &str) -> String {
+    format!("subsonic:{}:{}", server_id, song_id)
+}
+
+/// 与前端 convertFileSrc 的规则一致：Windows / Android 上自定义协议走 http://<scheme>.localhost
+fn cover_proxy_url(server_id: &str, cover_art: &str) -> String {
+    let base = if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost", COVER_SCHEME)
+    } else {
+        format!("{}://localhost", COVER_SCHEME)
+    };
+    format!(
+        "{}/{}/{}",
+        base,
+        urlencoding::encode(server_id),
+        urlencoding::encode(cover_art)
+    )
+}
+
+/// 代理地址的路径部分 /<server_id>/<coverArt id>
+fn parse_cover_path(path: &str) -> Option<(String, String)> {
+    let (server_id, cover_art) = path.trim_start_matches('/').split_once('/')?;
+    let decode = |value: &str| {
+        urlencoding::decode(value)
+            .ok()
+            .map(|value| value.into_owned())
+            .filter(|value| !value.is_empty())
+    };
+    Some((decode(server_id)?, decode(cover_art)?))
+}
+
+/// 旧版本直接保存 getCoverArt 地址（含 t/s 或 p=enc:），换成不带认证的代理地址
+pub fn strip_cover_credentials(server_id: &str, pic_url: &str) -> String {
+    if !pic_url.contains("/rest/getCoverArt") {
+        return pic_url.to_string();
+    }
+    reqwest::Url::parse(pic_url)
+        .ok()
+        .and_then(|url| {
+            url.query_pairs()
+                .find(|(key, _)| key == "id")
+                .map(|(_, id)| cover_proxy_url(server_id, &id))
+        })
+        .unwrap_or_default()
+}
+
+async fn fetch_cover(app_handle: &AppHandle, path: &str) -> Result<(Vec<u8>, String), String> {
+    let (server_id, cover_art) =
+        parse_cover_path(path).ok_or_else(|| format!("invalid cover path: {}", path))?;
+    let server = find_server(app_handle, &server_id)?;
+    let client = netease::get_client()?;
+    let response = netease::get_response(client, server.signed_cover_url(&cover_art)).await?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("image/jpeg")
+        .to_string();
+    // 出错时 Subsonic 仍返回 200，正文是 JSON / XML 错误信息
+    if !content_type.starts_with("image/") {
+        return Err(format!("cover {} not available", cover_art));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Read cover error: {}", e))?;
+    Ok((bytes.to_vec(), content_type))
+}
+
+/// remote-cover 协议的响应：按服务器 id 找到账号，用新的 salt 取图后原样返回
+pub async fn cover_response(app_handle: &AppHandle, path: &str) -> http::Response<Vec<u8>> {
+    let response = match fetch_cover(app_handle, path).await {
+        Ok((bytes, content_type)) => http::Response::builder()
+            .header(http::header::CONTENT_TYPE, content_type)
+            .header(http::header::CACHE_CONTROL, "max-age=86400")
+            .body(bytes),
+        Err(error) => {
+            eprintln!("Failed to load remote cover: {}", error);
+            http::Response::builder()
+                .status(http::StatusCode::NOT_FOUND)
+                .body(Vec::new())
+        }
+    };
+    response.unwrap_or_default()
+}
+
+/// 缓存文件名只由服务器 id 与 coverArt id 决定，不含任何认证信息
+fn cover_cache_path(
+    app_handle: &AppHandle,
+    path: &str,
+    content_type: &str,
+) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("unable to get app cache dir: {}", e))?
+        .join(COVER_CACHE_DIR);
+    let extension = match content_type {
+        "image/png" => "png",
+        "image/webp" => "webp",
+        "image/gif" => "gif",
+        _ => "jpg",
+    };
+    let mut hasher = Sha1::new();
+    hasher.update(path.as_bytes());
+    Ok(dir.join(format!("{:x}.{}", hasher.finalize(), extension)))
+}
+
+fn cached_cover(app_handle: &AppHandle, path: &str) -> Option<PathBuf> {
+    ["image/jpeg", "image/png", "image/webp", "image/gif"]
+        .iter()
+        .filter_map(|content_type| cover_cache_path(app_handle, path, content_type).ok())
+        .find(|path| path.is_file())
+}
+
+/// MPRIS 等外部程序无法访问代理协议；签名地址里可能带着 enc: 形式的密码，不能交给会话总线上的其他程序，
+/// 所以先把封面下载到缓存目录，再给出 file:// 地址
+pub async fn external_cover_url(app_handle: &AppHandle, pic_url: &str) -> Option<String> {
+    let path = pic_url
+        .strip_prefix(&format!("{}://localhost", COVER_SCHEME))
+        .or_else(|| pic_url.strip_prefix(&format!("http://{}.localhost", COVER_SCHEME)))?;
+    let path = path.trim_start_matches('/');
+    let cover_path = match cached_cover(app_handle, path) {
+        Some(cover_path) => cover_path,
+        None => {
+            let result = async {
+                let (bytes, content_type) = fetch_cover(app_handle, path).await?;
+                let cover_path = cover_cache_path(app_handle, path, &content_type)?;
+                if let Some(parent) = cover_path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("create {}: {}", parent.display(), e))?;
+                }
+                let tmp_path = storage::unique_temp_path_for(&cover_path);
+                fs::write(&tmp_path, &bytes)
+                    .map_err(|e| format!("write {}: {}", tmp_path.display(), e))?;
+                storage::commit_temp_file(&tmp_path, &cover_path)?;
+                Ok::<_, String>(cover_path)
+            }
+            .await;
+            match result {
+                Ok(cover_path) => cover_path,
+                Err(error) => {
+                    eprintln!("Failed to cache remote cover: {}", error);
+                    return None;
+                }
+            }
+        }
+    };
+    Some(format!("file://{}", cover_path.display()))
+}
+
+fn text(value: &Value, key: &str) -> String {
+    match &value[key] {
+        Value::String(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// 单个结果在部分服务器上不是数组而是对象
+fn items<'a>(value: &'a Value, key: &str) -> Vec<&'a Value> {
+    match &value[key] {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(_) => vec![&value[key]],
+        _ => Vec::new(),
+    }
+}
+
+fn song_from_json(server: &RemoteServer, song: &Value) -> Option<RemoteSong> {
+    let id = text(song, "id");
+    if id.is_empty() || song["isDir"].as_bool() == Some(true) {
+        return None;
+    }
+    let artist = text(song, "artist");
+    Some(RemoteSong {
+        server_id: server.id.clone(),
+        song: SongInfo {
+            id: id.clone(),
+            name: Some(text(song, "title"))
+                .filter(|title| !title.is_empty())
+                .unwrap_or_else(|| "unknown".to_string()),
+            artists: if artist.is_empty() {
+                vec!["unknown artist".to_string()]
+            } else {
+                vec![artist]
+            },
+            album: text(song, "album"),
+            duration: song["duration"].as_u64().unwrap_or(0) * 1000,
+            pic_url: server.cover_url(song["coverArt"].as_str()),
+            file_hash: id,
+            // 远程歌曲靠 server_id 区分，不经过 MusicProvider
+            provider: "subsonic".to_string(),
+        },
+    })
+}
+
+fn album_from_json(server: &RemoteServer, album: &Value) -> RemoteAlbum {
+    RemoteAlbum {
+        server_id: server.id.clone(),
+        id: text(album, "id"),
+        name: Some(text(album, "name"))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| text(album, "title")),
+        artist: text(album, "artist"),
+        artist_id: text(album, "artistId"),
+        song_count: album["songCount"].as_u64().unwrap_or(0) as u32,
+        duration: album["duration"].as_u64().unwrap_or(0) * 1000,
+        pic_url: server.cover_url(album["coverArt"].as_str()),
+    }
+}
+
+fn artist_from_json(server: &RemoteServer, artist: &Value) -> RemoteArtist {
+    RemoteArtist {
+        server_id: server.id.clone(),
+        id: text(artist, "id"),
+        name: text(artist, "name"),
+        album_count: artist["albumCount"].as_u64().unwrap_or(0) as u32,
+        pic_url: server.cover_url(artist["coverArt"].as_str()),
+    }
+}
+
+pub async fn ping(server: &RemoteServer) -> Result<(), String> {
+    server.call("ping", &[]).await.map(|_| ())
+}
+
+pub async fn search(
+    server: &RemoteServer,
+    query: &str,
+    page: u32,
+    pagesize: u32,
+) -> Result<RemoteSearchResult, String> {
+    let offset = page.saturating_sub(1) * pagesize;
+    let response = server
+        .call(
+            "search3",
+            &[
+                ("query", query.to_string()),
+                ("songCount", pagesize.to_string()),
+                ("songOffset", offset.to_string()),
+                // 歌手 / 专辑只在第一页返回
+                (
+                    "artistCount",
+                    if offset == 0 { "6" } else { "0" }.to_string(),
+                ),
+                (
+                    "albumCount",
+                    if offset == 0 { "6" } else { "0" }.to_string(),
+                ),
+            ],
+        )
+        .await?;
+    let result = &response["searchResult3"];
+    Ok(RemoteSearchResult {
+        artists: items(result, "artist")
+            .into_iter()
+            .map(|artist| artist_from_json(server, artist))
+            .collect(),
+        albums: items(result, "album")
+            .into_iter()
+            .map(|album| album_from_json(server, album))
+            .collect(),
+        songs: items(result, "song")
+            .into_iter()
+            .filter_map(|song| song_from_json(server, song))
+            .collect(),
+    })
+}
+
+pub async fn album_list(
+    server: &RemoteServer,
+    list_type: &str,
+    offset: u32,
+    size: u32,
+) -> Result<Vec<RemoteAlbum>, String> {
+    let response = server
+        .call(
+            "getAlbumList2",
+            &[
+                ("type", list_type.to_string()),
+                ("offset", offset.to_string()),
+                ("size", size.to_string()),
+            ],
+        )
+        .await?;
+    Ok(items(&response["albumList2"], "album")
+        .into_iter()
+        .map(|album| album_from_json(server, album))
+        .collect())
+}
+
+pub async fn album(server: &RemoteServer, album_id: &str) -> Result<RemoteAlbumDetail, String> {
+    let response = server
+        .call("getAlbum", &[("id", album_id.to_string())])
+        .await?;
+    let album = &response["album"];
+    Ok(RemoteAlbumDetail {
+        album: album_from_json(server, album),
+        songs: items(album, "song")
+            .into_iter()
+            .filter_map(|song| song_from_json(server, song))
+            .collect(),
+    })
+}
+
+pub async fn artist(server: &RemoteServer, artist_id: &str) -> Result<RemoteArtistDetail, String> {
+    let response = server
+        .call("getArtist", &[("id", artist_id.to_string())])
+        .await?;
+    let artist = &response["artist"];
+    Ok(RemoteArtistDetail {
+        artist: artist_from_json(server, artist),
+        albums: items(artist, "album")
+            .into_iter()
+            .map(|album| album_from_json(server, album))
+            .collect(),
+    })
+}
+
+fn servers_path(app_handle: &AppHandle) -> Result<std::path::PathBuf, String> {
+    app_data_file(app_handle, SERVERS_FILE)
+}
+
+/// 文件损坏时返回错误，不能当成空列表再写回去
+fn read_servers_from_path(path: &Path) -> Result<Vec<RemoteServer>, String> {
+    storage::read_json(path)
+}
+
+/// 加锁读改写 remote_servers.json；不要在持锁期间发网络请求
+fn update_servers_at<T>(
+    path: &Path,
+    update: impl FnOnce(&mut Vec<RemoteServer>) -> T,
+) -> Result<T, String> {
+    let _guard = SERVERS_LOCK
+        .lock()
+        .map_err(|_| "remote servers lock poisoned".to_string())?;
+    let mut servers = read_servers_from_path(path)?;
+    let result = update(&mut servers);
+    storage::write_json(path, &servers)?;
+    Ok(result)
+}
+
+pub fn find_server(app_handle: &AppHandle, server_id: &str) -> Result<RemoteServer, String> {
+    read_servers_from_path(&servers_path(app_handle)?)?
+        .into_iter()
+        .find(|server| server.id == server_id)
+        .ok_or_else(|| format!("remote server not found: {}", server_id))
+}
+
+#[tauri::command]
+pub fn get_remote_servers(app_handle: AppHandle) -> Result<Vec<RemoteServer>, String> {
+    read_servers_from_path(&servers_path(&app_handle)?)
+}
+
+/// 先用 ping 验证地址与账号，再新增或按 id 覆盖；返回补全 id 后的配置
+#[tauri::command]
+pub async fn save_remote_server(
+    app_handle: AppHandle,
+    server: RemoteServer,
+) -> Result<RemoteServer, String> {
+    let server = server.normalized()?;
+    ping(&server).await?;
+    update_servers_at(&servers_path(&app_handle)?, |servers| {
+        match servers.iter_mut().find(|existing| existing.id == server.id) {
+            Some(existing) => *existing = server.clone(),
+            None => servers.push(server.clone()),
+        }
+    })?;
+    Ok(server)
+}
+
+#[tauri::command]
+pub fn delete_remote_server(app_handle: AppHandle, id: String) -> Result<(), String> {
+    update_servers_at(&servers_path(&app_handle)?, |servers| {
+        servers.retain(|server| server.id != id);
+    })
+}
+
+#[tauri::command]
+pub async fn search_remote_songs(
+    app_handle: AppHandle,
+    server_id: String,
+    keywords: String,
+    page: Option<u32>,
+    pagesize: Option<u32>,
+) -> Result<RemoteSearchResult, String> {
+    let server = find_server(&app_handle, &server_id)?;
+    search(
+        &server,
+        &keywords,
+        page.unwrap_or(1),
+        pagesize.unwrap_or(20),
+    )
+    .await
+}
+
+/// list_type 同 Subsonic getAlbumList2：newest、random、alphabeticalByName 等
+#[tauri::command]
+pub async fn get_remote_album_list(
+    app_handle: AppHandle,
+    server_id: String,
+    list_type: Option<String>,
+    offset: Option<u32>,
+    size: Option<u32>,
+) -> Result<Vec<RemoteAlbum>, String> {
+    let server = find_server(&app_handle, &server_id)?;
+    album_list(
+        &server,
+        list_type.as_deref().unwrap_or("newest"),
+        offset.unwrap_or(0),
+        size.unwrap_or(30),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_remote_album(
+    app_handle: AppHandle,
+    server_id: String,
+    album_id: String,
+) -> Result<RemoteAlbumDetail, String> {
+    let server = find_server(&app_handle, &server_id)?;
+    album(&server, &album_id).await
+}
+
+#[tauri::command]
+pub async fn get_remote_artist(
+    app_handle: AppHandle,
+    server_id: String,
+    artist_id: String,
+) -> Result<RemoteArtistDetail, String> {
+    let server = find_server(&app_handle, &server_id)?;
+    artist(&server, &artist_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{canned, serve, RecordedRequest};
+    use hyper::{Body, Response};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    const PASSWORD: &str = "sesame";
+
+    /// 校验 salted token 后按接口返回固定数据，模拟 Subsonic 服务器
+    fn mock_response(request: &RecordedRequest) -> Response<Body> {
+        let params: HashMap<String, String> = request
+            .uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| {
+                (
+                    key.to_string(),
+                    urlencoding::decode(value).unwrap().into_owned(),
+                )
+            })
+            .collect();
+        let expected = format!("{:x}", md5::compute(format!("{}{}", PASSWORD, params["s"])));
+        let body = if params["u"] != "alice" || params["t"] != expected {
+            json!({ "status": "failed", "error": { "code": 40, "message": "Wrong username or password" } })
+        } else {
+            match request.path() {
+                "/rest/ping" => json!({ "status": "ok" }),
+                "/rest/search3" => {
+                    assert_eq!(params["query"], "sky high");
+                    assert_eq!(params["songOffset"], "20");
+                    json!({ "status": "ok", "searchResult3": {
+                        "song": { "id": 7, "title": "Sky", "artist": "Beyond", "album": "Live",
+                                  "duration": 181, "coverArt": "al-1" }
+                    } })
+                }
+                "/rest/getAlbum" => json!({ "status": "ok", "album": {
+                    "id": "al-1", "name": "Live", "artist": "Beyond", "artistId": "ar-1",
+                    "songCount": 2, "duration": 300,
+                    "song": [
+                        { "id": "7", "title": "Sky", "artist": "Beyond", "duration": 181 },
+                        { "id": "dir", "isDir": true },
+                        { "id": "8", "title": "", "duration": 119 }
+                    ]
+                } }),
+                _ => json!({ "status": "failed", "error": { "code": 0, "message": "unknown" } }),
+            }
+        };
+        canned(200, &[], json!({ "subsonic-response": body }).to_string())
+    }
+
+    fn start_mock_server() -> String {
+        let server = serve(|request| async move { mock_response(&request) });
+        server.url("/rest/")
+    }
+
+    fn server(base_url: String, password: &str) -> RemoteServer {
+        RemoteServer {
+            base_url,
+            username: " alice ".to_string(),
+            password: password.to_string(),
+            ..RemoteServer::default()
+        }
+        .normalized()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn searches_and_browses_with_salted_token_auth() {
+        let server = server(start_mock_server(), PASSWORD);
+        assert!(!server.base_url.ends_with("/rest"));
+        ping(&server).await.unwrap();
+
+        let result = search(&server, "sky high", 2, 20).await.unwrap();
+        assert_eq!(result.songs.len(), 1);
+        let song = &result.songs[0];
+        assert_eq!(song.song.id, "7");
+        assert_eq!(song.song.artists, vec!["Beyond".to_string()]);
+        assert_eq!(song.song.duration, 181_000);
+        // 封面地址不带认证参数，取图时再签名
+        assert_eq!(song.song.pic_url, cover_proxy_url(&server.id, "al-1"));
+        assert!(!song.song.pic_url.contains("t="));
+        assert!(server
+            .signed_cover_url("al-1")
+            .contains("/rest/getCoverArt?u=alice&t="));
+        assert_eq!(song.server_id, server.id);
+
+        let detail = album(&server, "al-1").await.unwrap();
+        assert_eq!(detail.album.name, "Live");
+        assert_eq!(detail.album.duration, 300_000);
+        let titles: Vec<_> = detail
+            .songs
+            .iter()
+            .map(|song| song.song.name.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Sky", "unknown"]);
+    }
+
+    #[tokio::test]
+    async fn surfaces_subsonic_errors() {
+        let server = server(start_mock_server(), "wrong");
+        let error = ping(&server).await.unwrap_err();
+        assert_eq!(error, "Subsonic error 40: Wrong username or password");
+    }
+
+    #[test]
+    fn cover_urls_round_trip_and_legacy_credentials_are_stripped() {
+        let url = cover_proxy_url("srv 1", "al/1");
+        let path = url.split_once("localhost").unwrap().1;
+        assert_eq!(
+            parse_cover_path(path),
+            Some(("srv 1".to_string(), "al/1".to_string()))
+        );
+        assert_eq!(parse_cover_path("/srv"), None);
+
+        let legacy = "http://music.local/rest/getCoverArt?u=alice&t=abc&s=def&v=1.16.1&c=rmusic&f=json&id=al-1&size=512";
+        assert_eq!(
+            strip_cover_credentials("srv", legacy),
+            cover_proxy_url("srv", "al-1")
+        );
+        let song: RemoteSong = serde_json::from_value(json!({
+            "server_id": "srv", "id": "9", "name": "T", "artists": [], "album": "",
+            "duration": 0, "pic_url": legacy, "file_hash": "9"
+        }))
+        .unwrap();
+        assert_eq!(song.song.pic_url, cover_proxy_url("srv", "al-1"));
+        assert_eq!(
+            strip_cover_credentials("srv", "https://p1.music.126.net/a.jpg"),
+            "https://p1.music.126.net/a.jpg"
+        );
+    }
+
+    #[test]
+    fn song_serializes_like_song_info_with_server_id() {
+        let base = RemoteServer {
+            id: "srv".to_string(),
+            base_url: "http://music.local".to_string(),
+            username: "alice".to_string(),
+            legacy_auth: true,
+            password: "ab".to_string(),
+            ..RemoteServer::default()
+        };
+        assert!(base.stream_url("9").contains("p=enc:6162"));
+        let song = song_from_json(&base, &json!({ "id": "9", "title": "T" })).unwrap();
+        let value = serde_json::to_value(&song).unwrap();
+        assert_eq!(value["server_id"], "srv");
+        assert_eq!(value["name"], "T");
+        assert_eq!(value["pic_url"], "");
+        let back: RemoteSong = serde_json::from_value(value).unwrap();
+        assert_eq!(back.song.id, "9");
+
+        assert!(RemoteServer {
+            base_url: "music.local".to_string(),
+            username: "a".to_string(),
+            ..RemoteServer::default()
+        }
+        .normalized()
+        .is_err());
+    }
+
+    #[test]
+    fn corrupt_servers_file_is_not_overwritten() {
+        let dir = std::env::temp_dir().join(format!(
+            "rmusic-remote-servers-{}-{}",
+            std::process::id(),
+            crate::timestamp::now_ms()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SERVERS_FILE);
+        fs::write(&path, "[{ broken").unwrap();
+        assert!(update_servers_at(&path, |servers| servers.clear()).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[{ broken");
+
+        fs::remove_file(&path).unwrap();
+        let server = RemoteServer {
+            id: "srv".to_string(),
+            ..RemoteServer::default()
+        };
+        update_servers_at(&path, |servers| servers.push(server.clone())).unwrap();
+        update_servers_at(&path, |servers| servers.retain(|s| s.id != "other")).unwrap();
+        assert_eq!(read_servers_from_path(&path).unwrap(), vec![server]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,1276 @@
+// 曲目提交（scrobble）：向 ListenBrainz 与 Last.fm 兼容服务提交“正在播放”和有效收听。
+// 有效收听先写入持久化的离线队列，再由后台任务按退避与服务端限流批量提交。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+use crate::history::{HistoryItem, PlayHistoryEntry};
+use crate::music::PlaybackTrack;
+use crate::remote_source::RemoteSong;
+use crate::storage;
+use crate::timestamp::now_ms;
+
+const SCROBBLER_SETTINGS_FILE: &str = "scrobbler.json";
+const SCROBBLE_QUEUE_FILE: &str = "scrobble-queue.json";
+const DEFAULT_LISTENBRAINZ_API_BASE: &str = "https://api.listenbrainz.org";
+const DEFAULT_LASTFM_API_BASE: &str = "https://ws.audioscrobbler.com/2.0/";
+const CLIENT_NAME: &str = "rmusic";
+/// 两个服务单次批量提交的上限都是 50
+const SUBMIT_BATCH_SIZE: usize = 50;
+/// 离线队列上限，超出后丢弃最旧的记录
+const MAX_QUEUE_LEN: usize = 5000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(6 * 60 * 60);
+const DEFAULT_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+const IDLE_WAKE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+static SCROBBLE_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrobbleSourceFlags {
+    pub local: bool,
+    pub online: bool,
+}
+
+impl Default for ScrobbleSourceFlags {
+    fn default() -> Self {
+        Self {
+            local: true,
+            online: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListenBrainzSettings {
+    pub enabled: bool,
+    pub api_base: String,
+    pub token: String,
+    pub sources: ScrobbleSourceFlags,
+}
+
+impl Default for ListenBrainzSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_base: DEFAULT_LISTENBRAINZ_API_BASE.to_string(),
+            token: String::new(),
+            sources: ScrobbleSourceFlags::default(),
+        }
+    }
+}
+
+/// Last.fm 以及 Libre.fm 等兼容 Audioscrobbler 2.0 协议的服务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastfmSettings {
+    pub enabled: bool,
+    pub api_base: String,
+    pub api_key: String,
+    pub api_secret: String,
+    pub session_key: String,
+    pub username: String,
+    pub sources: ScrobbleSourceFlags,
+}
+
+impl Default for LastfmSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_base: DEFAULT_LASTFM_API_BASE.to_string(),
+            api_key: String::new(),
+            api_secret: String::new(),
+            session_key: String::new(),
+            username: String::new(),
+            sources: ScrobbleSourceFlags::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrobblerSettings {
+    pub listenbrainz: ListenBrainzSettings,
+    pub lastfm: LastfmSettings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrobbleService {
+    Listenbrainz,
+    Lastfm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrobbleSource {
+    Local,
+    Online,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScrobbleTrack {
+    pub artist: String,
+    /// 在线曲目有结构化的歌手列表时记录第一位；本地标签里的歌手名原样提交
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_artist: Option<String>,
+    pub title: String,
+    pub album: Option<String>,
+    pub duration_ms: u64,
+    pub source: ScrobbleSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedListen {
+    pub id: u64,
+    pub service: ScrobbleService,
+    pub track: ScrobbleTrack,
+    /// 开始播放的 Unix 时间（秒）
+    pub listened_at: u64,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub next_attempt_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScrobbleStatus {
+    pub pending: usize,
+    pub pending_listenbrainz: usize,
+    pub pending_lastfm: usize,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum SubmitError {
+    /// 网络错误、服务端 5xx 或限流：保留在队列中稍后重试
+    Retry {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// token 或 session 失效：保留队列，暂停该服务直到设置更新
+    Auth(String),
+    /// 服务端拒绝了这批记录本身的数据：重试也不会成功，直接丢弃
+    Permanent(String),
+}
+
+impl SubmitError {
+    fn message(&self) -> &str {
+        match self {
+            SubmitError::Retry { message, .. }
+            | SubmitError::Auth(message)
+            | SubmitError::Permanent(message) => message,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ScrobblerState {
+    settings: Mutex<ScrobblerSettings>,
+    queue: Mutex<Vec<QueuedListen>>,
+    blocked_until_ms: Mutex<HashMap<ScrobbleService, u64>>,
+    last_error: Mutex<Option<String>>,
+    /// 队列文件读取失败时不再写回，避免用空队列覆盖原文件
+    queue_load_error: Option<String>,
+    wake: Notify,
+}
+
+impl ScrobbleSourceFlags {
+    fn allows(&self, source: ScrobbleSource) -> bool {
+        match source {
+            ScrobbleSource::Local => self.local,
+            ScrobbleSource::Online => self.online,
+        }
+    }
+}
+
+impl ScrobblerSettings {
+    fn services_for(&self, source: ScrobbleSource) -> Vec<ScrobbleService> {
+        let mut services = Vec::new();
+        if self.listenbrainz.enabled
+            && !self.listenbrainz.token.trim().is_empty()
+            && self.listenbrainz.sources.allows(source)
+        {
+            services.push(ScrobbleService::Listenbrainz);
+        }
+        if self.lastfm.enabled
+            && !self.lastfm.session_key.trim().is_empty()
+            && self.lastfm.sources.allows(source)
+        {
+            services.push(ScrobbleService::Lastfm);
+        }
+        services
+    }
+
+    fn is_service_active(&self, service: ScrobbleService) -> bool {
+        match service {
+            ScrobbleService::Listenbrainz => {
+                self.listenbrainz.enabled && !self.listenbrainz.token.trim().is_empty()
+            }
+            ScrobbleService::Lastfm => {
+                self.lastfm.enabled && !self.lastfm.session_key.trim().is_empty()
+            }
+        }
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+/// 下载的文件按 “歌手 - 歌名.ext” 命名，缺少标签时据此回退
+fn split_artist_title_from_file_name(file_name: &str) -> (Option<String>, String) {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(file_name)
+        .trim();
+    match stem.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => {
+            (Some(artist.trim().to_string()), title.trim().to_string())
+        }
+        _ => (None, stem.to_string()),
+    }
+}
+
+impl ScrobbleTrack {
+    /// 缺少歌手或歌名时无法提交，返回 None
+    fn from_history_item(item: &HistoryItem, duration_ms: u64) -> Option<Self> {
+        match item {
+            HistoryItem::Local {
+                file_name,
+                title,
+                artist,
+                album,
+                ..
+            } => {
+                let (file_artist, file_title) = split_artist_title_from_file_name(file_name);
+                let artist = non_empty(artist.as_deref()).or(file_artist)?;
+                let title = non_empty(title.as_deref()).unwrap_or(file_title);
+                if title.is_empty() {
+                    return None;
+                }
+                Some(Self {
+                    artist,
+                    primary_artist: None,
+                    title,
+                    album: non_empty(album.as_deref()),
+                    duration_ms,
+                    source: ScrobbleSource::Local,
+                })
+            }
+            // 远程服务器的曲目同样是串流播放，按在线来源的开关处理
+            HistoryItem::Online { song }
+            | HistoryItem::Remote {
+                song: RemoteSong { song, .. },
+            } => {
+                let artists: Vec<&str> = song
+                    .artists
+                    .iter()
+                    .map(|artist| artist.trim())
+                    .filter(|artist| !artist.is_empty())
+                    .collect();
+                let title = non_empty(Some(&song.name))?;
+                if artists.is_empty() {
+                    return None;
+                }
+                Some(Self {
+                    artist: artists.join(", "),
+                    primary_artist: artists.first().map(|artist| artist.to_string()),
+                    title,
+                    album: non_empty(Some(&song.album)),
+                    duration_ms: if duration_ms > 0 {
+                        duration_ms
+                    } else {
+                        song.duration
+                    },
+                    source: ScrobbleSource::Online,
+                })
+            }
+        }
+    }
+
+    /// Last.fm 只接受单个歌手名；歌手名里本身可能带逗号，所以不拆分 artist 字符串
+    fn primary_artist(&self) -> &str {
+        self.primary_artist.as_deref().unwrap_or(&self.artist)
+    }
+}
+
+fn scrobble_client() -> Result<reqwest::Client, String> {
+    if let Some(client) = SCROBBLE_CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = reqwest::Client::builder()
+        .user_agent(format!("{}/{}", CLIENT_NAME, env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(8))
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to build scrobble HTTP client: {}", e))?;
+    Ok(SCROBBLE_CLIENT.get_or_init(|| client).clone())
+}
+
+fn retry_after_from_headers(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    ["x-ratelimit-reset-in", "retry-after"]
+        .iter()
+        .filter_map(|name| headers.get(*name))
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .next()
+}
+
+fn classify_http_status(
+    status: reqwest::StatusCode,
+    retry_after: Option<Duration>,
+    body: &str,
+) -> SubmitError {
+    let preview: String = body.chars().take(200).collect();
+    let message = format!("HTTP {}: {}", status, preview);
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        SubmitError::Retry {
+            message,
+            retry_after: Some(retry_after.unwrap_or(DEFAULT_RATE_LIMIT_DELAY)),
+        }
+    } else if status == reqwest::StatusCode::UNAUTHORIZED
+        || status == reqwest::StatusCode::FORBIDDEN
+    {
+        SubmitError::Auth(message)
+    } else if matches!(status.as_u16(), 400 | 413 | 422) {
+        SubmitError::Permanent(message)
+    } else {
+        // 5xx、超时以及 404 等地址或配置问题都与这批记录无关，稍后重试
+        SubmitError::Retry {
+            message,
+            retry_after,
+        }
+    }
+}
+
+fn listenbrainz_payload(
+    listen_type: &str,
+    listens: &[(Option<u64>, &ScrobbleTrack)],
+) -> serde_json::Value {
+    let payload: Vec<serde_json::Value> = listens
+        .iter()
+        .map(|(listened_at, track)| {
+            let mut additional_info = serde_json::json!({
+                "media_player": CLIENT_NAME,
+                "submission_client": CLIENT_NAME,
+                "submission_client_version": env!("CARGO_PKG_VERSION"),
+            });
+            if track.duration_ms > 0 {
+                additional_info["duration_ms"] = serde_json::json!(track.duration_ms);
+            }
+            let mut metadata = serde_json::json!({
+                "artist_name": track.artist,
+                "track_name": track.title,
+                "additional_info": additional_info,
+            });
+            if let Some(album) = &track.album {
+                metadata["release_name"] = serde_json::json!(album);
+            }
+            let mut listen = serde_json::json!({ "track_metadata": metadata });
+            if let Some(listened_at) = listened_at {
+                listen["listened_at"] = serde_json::json!(listened_at);
+            }
+            listen
+        })
+        .collect();
+    serde_json::json!({ "listen_type": listen_type, "payload": payload })
+}
+
+async fn submit_listenbrainz(
+    client: &reqwest::Client,
+    settings: &ListenBrainzSettings,
+    listen_type: &str,
+    listens: &[(Option<u64>, &ScrobbleTrack)],
+) -> Result<(), SubmitError> {
+    let url = format!(
+        "{}/1/submit-listens",
+        settings.api_base.trim_end_matches('/')
+    );
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Token {}", settings.token.trim()))
+        .json(&listenbrainz_payload(listen_type, listens))
+        .send()
+        .await
+        .map_err(|e| SubmitError::Retry {
+            message: format!("ListenBrainz request error: {}", e),
+            retry_after: None,
+        })?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let retry_after = retry_after_from_headers(response.headers());
+    let body = response.text().await.unwrap_or_default();
+    Err(classify_http_status(status, retry_after, &body))
+}
+
+/// Audioscrobbler 2.0 签名：参数按名称排序后拼接 name+value，末尾加 secret 取 md5
+fn lastfm_signature(params: &[(String, String)], secret: &str) -> String {
+    let mut sorted: Vec<&(String, String)> = params
+        .iter()
+        .filter(|(name, _)| name != "format" && name != "callback")
+        .collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut raw = String::new();
+    for (name, value) in sorted {
+        raw.push_str(name);
+        raw.push_str(value);
+    }
+    raw.push_str(secret);
+    format!("{:x}", md5::compute(raw.as_bytes()))
+}
+
+fn signed_lastfm_params(
+    settings: &LastfmSettings,
+    mut params: Vec<(String, String)>,
+) -> Vec<(String, String)> {
+    params.push(("api_key".into(), settings.api_key.trim().to_string()));
+    let signature = lastfm_signature(&params, settings.api_secret.trim());
+    params.push(("api_sig".into(), signature));
+    params.push(("format".into(), "json".into()));
+    params
+}
+
+fn lastfm_error_from_code(code: u64, message: String) -> SubmitError {
+    match code {
+        // 11: Service Offline, 16: temporarily unavailable
+        11 | 16 => SubmitError::Retry {
+            message,
+            retry_after: None,
+        },
+        // 29: Rate limit exceeded
+        29 => SubmitError::Retry {
+            message,
+            retry_after: Some(DEFAULT_RATE_LIMIT_DELAY),
+        },
+        // 4: Authentication Failed, 9: Invalid session key, 10: Invalid API key,
+        // 13: Invalid method signature, 14: Unauthorized Token, 26: Suspended API key
+        4 | 9 | 10 | 13 | 14 | 26 => SubmitError::Auth(message),
+        // 6: Invalid parameters，即这批记录本身的数据有问题
+        6 => SubmitError::Permanent(message),
+        _ => SubmitError::Retry {
+            message,
+            retry_after: None,
+        },
+    }
+}
+
+async fn call_lastfm(
+    client: &reqwest::Client,
+    settings: &LastfmSettings,
+    params: Vec<(String, String)>,
+) -> Result<serde_json::Value, SubmitError> {
+    let params = signed_lastfm_params(settings, params);
+    let response = client
+        .post(settings.api_base.trim())
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| SubmitError::Retry {
+            message: format!("Last.fm request error: {}", e),
+            retry_after: None,
+        })?;
+    let status = response.status();
+    let retry_after = retry_after_from_headers(response.headers());
+    let body = response.text().await.unwrap_or_default();
+
+    // Last.fm 的错误响应可能是任意 HTTP 状态码，优先解析 JSON 中的 error 字段
+    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&body) {
+        if let Some(code) = json.get("error").and_then(|v| v.as_u64()) {
+            let message = json
+                .get("message")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown error");
+            return Err(lastfm_error_from_code(
+                code,
+                format!("Last.fm error {}: {}", code, message),
+            ));
+        }
+        if status.is_success() {
+            return Ok(json);
+        }
+    }
+    Err(classify_http_status(status, retry_after, &body))
+}
+
+fn lastfm_track_params(index: Option<usize>, track: &ScrobbleTrack) -> Vec<(String, String)> {
+    let key = |name: &str| match index {
+        Some(index) => format!("{}[{}]", name, index),
+        None => name.to_string(),
+    };
+    let mut params = vec![
+        (key("artist"), track.primary_artist().to_string()),
+        (key("track"), track.title.clone()),
+    ];
+    if let Some(album) = &track.album {
+        params.push((key("album"), album.clone()));
+    }
+    if track.duration_ms > 0 {
+        params.push((key("duration"), (track.duration_ms / 1000).to_string()));
+    }
+    params
+}
+
+async fn submit_lastfm_scrobbles(
+    client: &reqwest::Client,
+    settings: &LastfmSettings,
+    listens: &[(u64, &ScrobbleTrack)],
+) -> Result<(), SubmitError> {
+    let mut params = vec![
+        ("method".to_string(), "track.scrobble".to_string()),
+        ("sk".to_string(), settings.session_key.trim().to_string()),
+    ];
+    for (index, (listened_at, track)) in listens.iter().enumerate() {
+        params.extend(lastfm_track_params(Some(index), track));
+        params.push((format!("timestamp[{}]", index), listened_at.to_string()));
+    }
+    call_lastfm(client, settings, params).await.map(|_| ())
+}
+
+async fn submit_lastfm_now_playing(
+    client: &reqwest::Client,
+    settings: &LastfmSettings,
+    track: &ScrobbleTrack,
+) -> Result<(), SubmitError> {
+    let mut params = vec![
+        ("method".to_string(), "track.updateNowPlaying".to_string()),
+        ("sk".to_string(), settings.session_key.trim().to_string()),
+    ];
+    params.extend(lastfm_track_params(None, track));
+    call_lastfm(client, settings, params).await.map(|_| ())
+}
+
+fn retry_delay(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1).min(16));
+    RETRY_BASE_DELAY.saturating_mul(factor).min(MAX_RETRY_DELAY)
+}
+
+fn settings_path(app_handle: &AppHandle) -> Result<std::path::PathBuf, String> {
+    storage::app_data_file(app_handle, SCROBBLER_SETTINGS_FILE)
+}
+
+fn queue_path(app_handle: &AppHandle) -> Result<std::path::PathBuf, String> {
+    storage::app_data_file(app_handle, SCROBBLE_QUEUE_FILE)
+}
+
+impl ScrobblerState {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let settings = settings_path(app_handle)
+            .map(|path| storage::read_json_or_default(&path))
+            .unwrap_or_default();
+        let (queue, queue_load_error) =
+            match queue_path(app_handle).and_then(|path| storage::read_json(&path)) {
+                Ok(queue) => (queue, None),
+                Err(e) => {
+                    eprintln!("Failed to load scrobble queue: {}", e);
+                    (Vec::new(), Some(e))
+                }
+            };
+        Self {
+            settings: Mutex::new(settings),
+            queue: Mutex::new(queue),
+            last_error: Mutex::new(queue_load_error.clone()),
+            queue_load_error,
+            ..Self::default()
+        }
+    }
+
+    fn settings(&self) -> ScrobblerSettings {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    fn set_last_error(&self, error: Option<String>) {
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = error;
+        }
+    }
+
+    fn persist_queue(&self, app_handle: &AppHandle) {
+        if let Some(error) = &self.queue_load_error {
+            eprintln!(
+                "Scrobble queue not saved, queue file is unreadable: {}",
+                error
+            );
+            return;
+        }
+        let Ok(queue) = self.queue.lock().map(|queue| queue.clone()) else {
+            return;
+        };
+        if let Err(e) = queue_path(app_handle).and_then(|path| storage::write_json(&path, &queue)) {
+            eprintln!("Failed to persist scrobble queue: {}", e);
+        }
+    }
+
+    /// 取出某个服务当前到期的一批记录
+    fn due_batch(&self, service: ScrobbleService, now: u64) -> Vec<QueuedListen> {
+        let blocked_until = self
+            .blocked_until_ms
+            .lock()
+            .ok()
+            .and_then(|blocked| blocked.get(&service).copied())
+            .unwrap_or(0);
+        if blocked_until > now {
+            return vec![];
+        }
+        self.queue
+            .lock()
+            .map(|queue| {
+                queue
+                    .iter()
+                    .filter(|listen| listen.service == service && listen.next_attempt_ms <= now)
+                    .take(SUBMIT_BATCH_SIZE)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn apply_batch_result(
+        &self,
+        service: ScrobbleService,
+        ids: &[u64],
+        result: &Result<(), SubmitError>,
+        now: u64,
+    ) {
+        let Ok(mut queue) = self.queue.lock() else {
+            return;
+        };
+        match result {
+            Ok(()) | Err(SubmitError::Permanent(_)) => {
+                queue.retain(|listen| !ids.contains(&listen.id));
+            }
+            Err(SubmitError::Auth(_)) => {
+                // 记录原样保留；更新设置或手动重试时解除
+                if let Ok(mut blocked) = self.blocked_until_ms.lock() {
+                    blocked.insert(service, u64::MAX);
+                }
+            }
+            Err(SubmitError::Retry { retry_after, .. }) => {
+                for listen in queue.iter_mut().filter(|listen| ids.contains(&listen.id)) {
+                    listen.attempts = listen.attempts.saturating_add(1);
+                    let delay = retry_after.unwrap_or_else(|| retry_delay(listen.attempts));
+                    listen.next_attempt_ms = now + delay.as_millis() as u64;
+                }
+                if let Some(retry_after) = retry_after {
+                    if let Ok(mut blocked) = self.blocked_until_ms.lock() {
+                        blocked.insert(service, now + retry_after.as_millis() as u64);
+                    }
+                }
+            }
+        }
+    }
+
+    fn unblock_services(&self) {
+        if let Ok(mut blocked) = self.blocked_until_ms.lock() {
+            blocked.clear();
+        }
+    }
+
+    fn next_wake_delay(&self, now: u64) -> Duration {
+        let settings = self.settings();
+        let blocked = self
+            .blocked_until_ms
+            .lock()
+            .map(|blocked| blocked.clone())
+            .unwrap_or_default();
+        self.queue
+            .lock()
+            .ok()
+            .and_then(|queue| {
+                queue
+                    .iter()
+                    .filter(|listen| settings.is_service_active(listen.service))
+                    .map(|listen| {
+                        let blocked_until = blocked.get(&listen.service).copied().unwrap_or(0);
+                        listen.next_attempt_ms.max(blocked_until)
+                    })
+                    .min()
+            })
+            .map(|next| Duration::from_millis(next.saturating_sub(now)))
+            .unwrap_or(IDLE_WAKE_INTERVAL)
+            .clamp(Duration::from_secs(1), IDLE_WAKE_INTERVAL)
+    }
+}
+
+fn push_to_queue(queue: &mut Vec<QueuedListen>, listens: Vec<QueuedListen>) {
+    queue.extend(listens);
+    if queue.len() > MAX_QUEUE_LEN {
+        let overflow = queue.len() - MAX_QUEUE_LEN;
+        queue.drain(..overflow);
+    }
+}
+
+async fn submit_batch(
+    client: &reqwest::Client,
+    settings: &ScrobblerSettings,
+    service: ScrobbleService,
+    batch: &[QueuedListen],
+) -> Result<(), SubmitError> {
+    match service {
+        ScrobbleService::Listenbrainz => {
+            let listens: Vec<(Option<u64>, &ScrobbleTrack)> = batch
+                .iter()
+                .map(|listen| (Some(listen.listened_at), &listen.track))
+                .collect();
+            let listen_type = if listens.len() == 1 {
+                "single"
+            } else {
+                "import"
+            };
+            submit_listenbrainz(client, &settings.listenbrainz, listen_type, &listens).await
+        }
+        ScrobbleService::Lastfm => {
+            let listens: Vec<(u64, &ScrobbleTrack)> = batch
+                .iter()
+                .map(|listen| (listen.listened_at, &listen.track))
+                .collect();
+            submit_lastfm_scrobbles(client, &settings.lastfm, &listens).await
+        }
+    }
+}
+
+async fn flush_due_listens(app_handle: &AppHandle, state: &ScrobblerState) {
+    let client = match scrobble_client() {
+        Ok(client) => client,
+        Err(e) => {
+            state.set_last_error(Some(e));
+            return;
+        }
+    };
+    let settings = state.settings();
+    let mut changed = false;
+
+    for service in [ScrobbleService::Listenbrainz, ScrobbleService::Lastfm] {
+        if !settings.is_service_active(service) {
+            continue;
+        }
+        loop {
+            let batch = state.due_batch(service, now_ms());
+            if batch.is_empty() {
+                break;
+            }
+            let mut result = submit_batch(&client, &settings, service, &batch).await;
+            if matches!(result, Err(SubmitError::Permanent(_))) && batch.len() > 1 {
+                // 整批被拒时逐条重交，只丢弃服务端拒绝的那几条
+                for listen in &batch {
+                    result =
+                        submit_batch(&client, &settings, service, std::slice::from_ref(listen))
+                            .await;
+                    state.apply_batch_result(service, &[listen.id], &result, now_ms());
+                    if matches!(
+                        result,
+                        Err(SubmitError::Retry { .. } | SubmitError::Auth(_))
+                    ) {
+                        break;
+                    }
+                }
+            } else {
+                let ids: Vec<u64> = batch.iter().map(|listen| listen.id).collect();
+                state.apply_batch_result(service, &ids, &result, now_ms());
+            }
+            changed = true;
+            match result {
+                Ok(()) => state.set_last_error(None),
+                Err(error) => {
+                    eprintln!("Scrobble submission failed: {}", error.message());
+                    state.set_last_error(Some(error.message().to_string()));
+                    if !matches!(error, SubmitError::Permanent(_)) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    if changed {
+        state.persist_queue(app_handle);
+    }
+}
+
+/// 启动后台提交任务；需要在 ScrobblerState 注册到 app 之后调用
+pub fn start_scrobbler(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app_handle.try_state::<ScrobblerState>() else {
+            return;
+        };
+        loop {
+            flush_due_listens(&app_handle, &state).await;
+            let delay = state.next_wake_delay(now_ms());
+            tokio::select! {
+                _ = state.wake.notified() => {}
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+    });
+}
+
+/// 有效收听写入离线队列，由后台任务提交
+pub fn enqueue_listen(app_handle: &AppHandle, entry: &PlayHistoryEntry) {
+    let Some(state) = app_handle.try_state::<ScrobblerState>() else {
+        return;
+    };
+    let Some(track) = ScrobbleTrack::from_history_item(&entry.item, entry.duration_ms) else {
+        return;
+    };
+    let services = state.settings().services_for(track.source);
+    if services.is_empty() {
+        return;
+    }
+
+    let listens = services
+        .into_iter()
+        .map(|service| QueuedListen {
+            id: rand::random(),
+            service,
+            track: track.clone(),
+            listened_at: entry.played_at_ms / 1000,
+            attempts: 0,
+            next_attempt_ms: 0,
+        })
+        .collect();
+    if let Ok(mut queue) = state.queue.lock() {
+        push_to_queue(&mut queue, listens);
+    }
+    state.persist_queue(app_handle);
+    state.wake.notify_one();
+}
+
+/// “正在播放”只是瞬时状态，失败不进入离线队列
+pub fn submit_now_playing(app_handle: &AppHandle, track: &PlaybackTrack) {
+    let Some(state) = app_handle.try_state::<ScrobblerState>() else {
+        return;
+    };
+    let Some(track) =
+        ScrobbleTrack::from_history_item(&HistoryItem::from(track), track.duration_ms())
+    else {
+        return;
+    };
+    let settings = state.settings();
+    let services = settings.services_for(track.source);
+    if services.is_empty() {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let Ok(client) = scrobble_client() else {
+            return;
+        };
+        for service in services {
+            let result = match service {
+                ScrobbleService::Listenbrainz => {
+                    submit_listenbrainz(
+                        &client,
+                        &settings.listenbrainz,
+                        "playing_now",
+                        &[(None, &track)],
+                    )
+                    .await
+                }
+                ScrobbleService::Lastfm => {
+                    submit_lastfm_now_playing(&client, &settings.lastfm, &track).await
+                }
+            };
+            if let Err(error) = result {
+                eprintln!("Now playing submission failed: {}", error.message());
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_scrobbler_settings(state: tauri::State<'_, ScrobblerState>) -> ScrobblerSettings {
+    state.settings()
+}
+
+#[tauri::command]
+pub fn set_scrobbler_settings(
+    app_handle: AppHandle,
+    state: tauri::State<'_, ScrobblerState>,
+    settings: ScrobblerSettings,
+) -> Result<(), String> {
+    storage::write_json(&settings_path(&app_handle)?, &settings)?;
+    *state
+        .settings
+        .lock()
+        .map_err(|_| "scrobbler settings lock poisoned".to_string())? = settings;
+    state.unblock_services();
+    state.wake.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_scrobble_status(state: tauri::State<'_, ScrobblerState>) -> ScrobbleStatus {
+    let (pending_listenbrainz, pending_lastfm) = state
+        .queue
+        .lock()
+        .map(|queue| {
+            (
+                queue
+                    .iter()
+                    .filter(|listen| listen.service == ScrobbleService::Listenbrainz)
+                    .count(),
+                queue
+                    .iter()
+                    .filter(|listen| listen.service == ScrobbleService::Lastfm)
+                    .count(),
+            )
+        })
+        .unwrap_or((0, 0));
+    ScrobbleStatus {
+        pending: pending_listenbrainz + pending_lastfm,
+        pending_listenbrainz,
+        pending_lastfm,
+        last_error: state.last_error.lock().ok().and_then(|error| error.clone()),
+    }
+}
+
+/// 忽略退避时间，立即重试队列中的全部记录
+#[tauri::command]
+pub fn flush_scrobble_queue(state: tauri::State<'_, ScrobblerState>) -> Result<(), String> {
+    if let Ok(mut queue) = state.queue.lock() {
+        for listen in queue.iter_mut() {
+            listen.next_attempt_ms = 0;
+        }
+    }
+    state.unblock_services();
+    state.wake.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_scrobble_queue(
+    app_handle: AppHandle,
+    state: tauri::State<'_, ScrobblerState>,
+) -> Result<(), String> {
+    state
+        .queue
+        .lock()
+        .map_err(|_| "scrobble queue lock poisoned".to_string())?
+        .clear();
+    state.set_last_error(None);
+    state.persist_queue(&app_handle);
+    Ok(())
+}
+
+/// 通过 auth.getMobileSession 用账号密码换取 session key 并保存
+#[tauri::command]
+pub async fn lastfm_authenticate(
+    app_handle: AppHandle,
+    state: tauri::State<'_, ScrobblerState>,
+    username: String,
+    password: String,
+) -> Result<(), String> {
+    let mut settings = state.settings();
+    if settings.lastfm.api_key.trim().is_empty() || settings.lastfm.api_secret.trim().is_empty() {
+        return Err("Last.fm api_key and api_secret are required".to_string());
+    }
+    let client = scrobble_client()?;
+    let params = vec![
+        ("method".to_string(), "auth.getMobileSession".to_string()),
+        ("username".to_string(), username.trim().to_string()),
+        ("password".to_string(), password),
+    ];
+    let json = call_lastfm(&client, &settings.lastfm, params)
+        .await
+        .map_err(|error| error.message().to_string())?;
+    let session_key = json["session"]["key"]
+        .as_str()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| "No session key in Last.fm response".to_string())?;
+
+    settings.lastfm.session_key = session_key.to_string();
+    settings.lastfm.username = json["session"]["name"]
+        .as_str()
+        .unwrap_or(username.trim())
+        .to_string();
+    storage::write_json(&settings_path(&app_handle)?, &settings)?;
+    *state
+        .settings
+        .lock()
+        .map_err(|_| "scrobbler settings lock poisoned".to_string())? = settings;
+    state.unblock_services();
+    state.wake.notify_one();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::netease::SongInfo;
+    use crate::test_server::serve_canned;
+
+    fn test_client() -> reqwest::Client {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    fn track() -> ScrobbleTrack {
+        ScrobbleTrack {
+            artist: "周杰伦, 费玉清".into(),
+            primary_artist: Some("周杰伦".into()),
+            title: "千里之外".into(),
+            album: Some("依然范特西".into()),
+            duration_ms: 255_000,
+            source: ScrobbleSource::Online,
+        }
+    }
+
+    #[test]
+    fn local_track_falls_back_to_file_name_when_tags_are_missing() {
+        let item = HistoryItem::Local {
+            key: "k".into(),
+            file_name: "Album/Artist - Song.mp3".into(),
+            title: None,
+            artist: None,
+            album: Some(" ".into()),
+        };
+        let track = ScrobbleTrack::from_history_item(&item, 1000).unwrap();
+        assert_eq!(track.artist, "Artist");
+        assert_eq!(track.primary_artist(), "Artist");
+        assert_eq!(track.title, "Song");
+        assert_eq!(track.album, None);
+
+        let tagged = HistoryItem::Local {
+            key: "k".into(),
+            file_name: "September.mp3".into(),
+            title: Some("September".into()),
+            artist: Some("Earth, Wind & Fire".into()),
+            album: None,
+        };
+        let track = ScrobbleTrack::from_history_item(&tagged, 1000).unwrap();
+        assert_eq!(track.primary_artist(), "Earth, Wind & Fire");
+
+        let untagged = HistoryItem::Local {
+            key: "k".into(),
+            file_name: "untitled.mp3".into(),
+            title: None,
+            artist: None,
+            album: None,
+        };
+        assert!(ScrobbleTrack::from_history_item(&untagged, 1000).is_none());
+    }
+
+    #[test]
+    fn online_track_joins_artists_and_uses_song_duration() {
+        let item = HistoryItem::Online {
+            song: SongInfo {
+                id: "1".into(),
+                name: "Song".into(),
+                artists: vec!["A".into(), " ".into(), "B".into()],
+                album: "".into(),
+                duration: 180_000,
+                pic_url: String::new(),
+                file_hash: "1".into(),
+                provider: "netease".into(),
+            },
+        };
+        let track = ScrobbleTrack::from_history_item(&item, 0).unwrap();
+        assert_eq!(track.artist, "A, B");
+        assert_eq!(track.primary_artist(), "A");
+        assert_eq!(track.duration_ms, 180_000);
+        assert_eq!(track.album, None);
+    }
+
+    #[test]
+    fn lastfm_signature_sorts_params_and_skips_format() {
+        let params = vec![
+            ("track".to_string(), "b".to_string()),
+            ("artist".to_string(), "a".to_string()),
+            ("format".to_string(), "json".to_string()),
+        ];
+        assert_eq!(
+            lastfm_signature(&params, "secret"),
+            format!("{:x}", md5::compute("artistatrackbsecret"))
+        );
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially_and_is_capped() {
+        assert_eq!(retry_delay(1), RETRY_BASE_DELAY);
+        assert_eq!(retry_delay(3), RETRY_BASE_DELAY * 4);
+        assert_eq!(retry_delay(30), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn retry_results_reschedule_and_block_the_service() {
+        let state = ScrobblerState::default();
+        let listen = QueuedListen {
+            id: 7,
+            service: ScrobbleService::Lastfm,
+            track: track(),
+            listened_at: 1,
+            attempts: 0,
+            next_attempt_ms: 0,
+        };
+        push_to_queue(&mut state.queue.lock().unwrap(), vec![listen]);
+
+        assert_eq!(state.due_batch(ScrobbleService::Lastfm, 1_000).len(), 1);
+        state.apply_batch_result(
+            ScrobbleService::Lastfm,
+            &[7],
+            &Err(SubmitError::Retry {
+                message: "rate limited".into(),
+                retry_after: Some(Duration::from_secs(10)),
+            }),
+            1_000,
+        );
+        assert!(state.due_batch(ScrobbleService::Lastfm, 5_000).is_empty());
+        assert_eq!(state.due_batch(ScrobbleService::Lastfm, 11_000).len(), 1);
+
+        state.apply_batch_result(
+            ScrobbleService::Lastfm,
+            &[7],
+            &Err(SubmitError::Auth("bad session".into())),
+            11_000,
+        );
+        assert_eq!(state.queue.lock().unwrap().len(), 1);
+        assert!(state
+            .due_batch(ScrobbleService::Lastfm, u64::MAX - 1)
+            .is_empty());
+        state.unblock_services();
+        assert_eq!(state.due_batch(ScrobbleService::Lastfm, 11_000).len(), 1);
+
+        state.apply_batch_result(
+            ScrobbleService::Lastfm,
+            &[7],
+            &Err(SubmitError::Permanent("invalid parameters".into())),
+            11_000,
+        );
+        assert!(state.queue.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn auth_failures_are_not_treated_as_rejected_listens() {
+        let status = |code: u16| reqwest::StatusCode::from_u16(code).unwrap();
+        assert!(matches!(
+            classify_http_status(status(401), None, ""),
+            SubmitError::Auth(_)
+        ));
+        assert!(matches!(
+            classify_http_status(status(400), None, ""),
+            SubmitError::Permanent(_)
+        ));
+        assert!(matches!(
+            classify_http_status(status(404), None, ""),
+            SubmitError::Retry { .. }
+        ));
+        assert!(matches!(
+            lastfm_error_from_code(9, String::new()),
+            SubmitError::Auth(_)
+        ));
+        assert!(matches!(
+            lastfm_error_from_code(6, String::new()),
+            SubmitError::Permanent(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn listenbrainz_submission_posts_token_and_metadata() {
+        let server = serve_canned(200, &[], "{\"status\":\"ok\"}");
+        let settings = ListenBrainzSettings {
+            enabled: true,
+            api_base: server.url("/"),
+            token: "secret-token".into(),
+            sources: ScrobbleSourceFlags::default(),
+        };
+        let track = track();
+
+        submit_listenbrainz(
+            &test_client(),
+            &settings,
+            "single",
+            &[(Some(1_700_000_000), &track)],
+        )
+        .await
+        .unwrap();
+
+        let request = server.requests().remove(0);
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path(), "/1/submit-listens");
+        assert_eq!(request.header("authorization"), Some("Token secret-token"));
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["listen_type"], "single");
+        assert_eq!(body["payload"][0]["listened_at"], 1_700_000_000u64);
+        assert_eq!(
+            body["payload"][0]["track_metadata"]["track_name"],
+            "千里之外"
+        );
+        assert_eq!(
+            body["payload"][0]["track_metadata"]["release_name"],
+            "依然范特西"
+        );
+    }
+
+    #[tokio::test]
+    async fn listenbrainz_rate_limit_uses_reset_header() {
+        let server = serve_canned(429, &[("X-RateLimit-Reset-In", "7")], "");
+        let settings = ListenBrainzSettings {
+            enabled: true,
+            api_base: server.base(),
+            token: "t".into(),
+            sources: ScrobbleSourceFlags::default(),
+        };
+        let track = track();
+
+        let error = submit_listenbrainz(&test_client(), &settings, "single", &[(Some(1), &track)])
+            .await
+            .unwrap_err();
+        assert_eq!(server.requests().len(), 1);
+        assert!(matches!(
+            error,
+            SubmitError::Retry {
+                retry_after: Some(delay),
+                ..
+            } if delay == Duration::from_secs(7)
+        ));
+    }
+
+    #[tokio::test]
+    async fn lastfm_scrobble_sends_signed_batch_and_maps_error_codes() {
+        let server = serve_canned(
+            200,
+            &[],
+            "{\"error\":9,\"message\":\"Invalid session key\"}",
+        );
+        let settings = LastfmSettings {
+            enabled: true,
+            api_base: server.url("/2.0/"),
+            api_key: "key".into(),
+            api_secret: "secret".into(),
+            session_key: "sk".into(),
+            username: "user".into(),
+            sources: ScrobbleSourceFlags::default(),
+        };
+        let track = track();
+
+        let error = submit_lastfm_scrobbles(&test_client(), &settings, &[(42, &track)])
+            .await
+            .unwrap_err();
+        let request = server.requests().remove(0);
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path(), "/2.0/");
+        let body = request.body_text();
+        assert!(body.contains("method=track.scrobble"));
+        assert!(body.contains("timestamp%5B0%5D=42"));
+        assert!(body.contains("api_sig="));
+        assert!(matches!(error, SubmitError::Auth(message) if message.contains("error 9")));
+    }
+}
@@ -1,12 +1,196 @@
+// 内置 NeteaseCloudMusicApi sidecar 的监管：启动前检查默认端口，被占用时换一个空闲端口（PORT 环境变量）并告知 online_api；
+// stdout / stderr 写入 app_log_dir 下滚动的 online-service.log，进程意外退出时通知前端并按退避间隔自动重启
+
+use crate::online_api::OnlineApiState;
+use crate::timestamp::{iso8601, now_ms};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tauri::{Emitter, Manager};
-use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent, TerminatedPayload};
 use tauri_plugin_shell::ShellExt;
 
+pub const DEFAULT_SERVICE_PORT: u16 = 3000;
+const LOG_FILE: &str = "online-service.log";
+/// 单个日志文件的上限，超出后滚动为 .1、.2
+const MAX_LOG_BYTES: u64 = 512 * 1024;
+const ROTATED_LOG_COUNT: usize = 2;
+const DEFAULT_LOG_LINES: usize = 200;
+/// 意外退出后的重启间隔，连续崩溃时逐级拉长
+const RESTART_BACKOFF: [Duration; 5] = [
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+    Duration::from_secs(15),
+    Duration::from_secs(30),
+];
+/// 运行超过这个时长再退出，不算连续崩溃
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
 #[derive(Clone, Default)]
 pub struct OnlineServiceProcess {
     child: Arc<Mutex<Option<CommandChild>>>,
+    // 每次启动或主动停止都加一；崩溃重启前核对，避免和手动重启、退出应用冲突
+    generation: Arc<AtomicU64>,
+    crash_streak: Arc<AtomicU32>,
+    log: Arc<Mutex<ServiceLog>>,
+}
+
+/// 发给前端的 online-service-exited 事件
+#[derive(Debug, Clone, Serialize)]
+pub struct OnlineServiceExit {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub restart_in_ms: u64,
+}
+
+/// 追加写入的滚动日志；打开失败时只打印到控制台，不影响服务运行
+#[derive(Default)]
+struct ServiceLog {
+    path: Option<PathBuf>,
+    file: Option<File>,
+    size: u64,
+}
+
+fn rotated_log_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+impl ServiceLog {
+    fn set_path(&mut self, path: PathBuf) {
+        if self.path.as_ref() != Some(&path) {
+            self.path = Some(path);
+            self.file = None;
+        }
+    }
+
+    fn open(&mut self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("create {}: {}", parent.display(), e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("open {}: {}", path.display(), e))?;
+        self.size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self, path: &Path) -> Result<(), String> {
+        self.file = None;
+        for index in (1..ROTATED_LOG_COUNT).rev() {
+            let from = rotated_log_path(path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_log_path(path, index + 1))
+                    .map_err(|e| format!("rotate {}: {}", from.display(), e))?;
+            }
+        }
+        fs::rename(path, rotated_log_path(path, 1))
+            .map_err(|e| format!("rotate {}: {}", path.display(), e))?;
+        self.open(path)
+    }
+
+    fn append(&mut self, stream: &str, line: &str) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let entry = format!("{} [{}] {}\n", iso8601(now_ms()), stream, line.trim_end());
+        let result = (|| {
+            if self.file.is_none() {
+                self.open(&path)?;
+            }
+            if self.size > 0 && self.size + entry.len() as u64 > MAX_LOG_BYTES {
+                self.rotate(&path)?;
+            }
+            let file = self.file.as_mut().ok_or("log file not open")?;
+            file.write_all(entry.as_bytes())
+                .map_err(|e| format!("write {}: {}", path.display(), e))?;
+            self.size += entry.len() as u64;
+            Ok::<(), String>(())
+        })();
+        if let Err(error) = result {
+            self.file = None;
+            eprintln!("Online service log error: {}", error);
+        }
+    }
+}
+
+impl OnlineServiceProcess {
+    fn log(&self, stream: &str, line: &str) {
+        if let Ok(mut log) = self.log.lock() {
+            log.append(stream, line);
+        }
+    }
+
+    /// 当前子进程还是 pid 时取走它；返回 false 说明已被 shutdown_service 主动取走
+    fn take_if_current(&self, pid: u32) -> bool {
+        let Ok(mut current) = self.child.lock() else {
+            return false;
+        };
+        if current.as_ref().map(CommandChild::pid) == Some(pid) {
+            current.take();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn log_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("app_log_dir: {}", e))?;
+    Ok(dir.join(LOG_FILE))
+}
+
+fn port_is_free(port: u16) -> bool {
+    // 其他程序可能只监听回环地址或所有地址，两个都要能绑定
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok()
+        && TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok()
+}
+
+/// 默认端口可用时用默认端口，否则让系统分配一个空闲端口
+fn choose_service_port(preferred: u16) -> Result<u16, String> {
+    if port_is_free(preferred) {
+        return Ok(preferred);
+    }
+    TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("Failed to find a free port for online service: {}", e))
+}
+
+/// 日志末尾的 lines 行，按时间顺序包含滚动出去的旧文件
+fn read_log_tail(path: &Path, lines: usize) -> String {
+    let mut tail = VecDeque::with_capacity(lines);
+    let files = (1..=ROTATED_LOG_COUNT)
+        .rev()
+        .map(|index| rotated_log_path(path, index))
+        .chain(std::iter::once(path.to_path_buf()));
+    for file in files {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        for line in content.lines() {
+            if tail.len() == lines {
+                tail.pop_front();
+            }
+            tail.push_back(line.to_string());
+        }
+    }
+    Vec::from(tail).join("\n")
 }
 
 /// 返回当前平台的 sidecar 名称（与 build.rs / lib.rs 中使用的名称一致）
@@ -35,15 +219,18 @@ pub async fn ensure_online_service(
     app_handle: tauri::AppHandle,
     process: tauri::State<'_, OnlineServiceProcess>,
 ) -> Result<(), String> {
-    let service_name = sidecar_name_for_current_platform();
-    let window = app_handle.get_webview_window("main");
-    spawn_service(&app_handle, service_name, window, process.inner())?;
-    wait_until_service_ready().await
+    // 只用自建实例时不启动内置服务
+    if app_handle.state::<OnlineApiState>().uses_bundled_service() {
+        let service_name = sidecar_name_for_current_platform();
+        let window = app_handle.get_webview_window("main");
+        spawn_service(&app_handle, service_name, window, process.inner())?;
+    }
+    wait_until_service_ready(&app_handle.state::<OnlineApiState>()).await
 }
 
 /// Sidecar 进程创建成功不代表 HTTP 服务已经开始监听。这里用有上限的退避轮询
 /// 建立真正的就绪屏障，避免冷启动期间立即发出的搜索请求撞上未监听端口。
-async fn wait_until_service_ready() -> Result<(), String> {
+async fn wait_until_service_ready(api: &OnlineApiState) -> Result<(), String> {
     const READY_TIMEOUT: Duration = Duration::from_secs(10);
     const RETRY_DELAYS: [Duration; 5] = [
         Duration::from_millis(120),
@@ -56,7 +243,7 @@ async fn wait_until_service_ready() -> Result<(), String> {
     let wait = async {
         let mut attempt = 0usize;
         loop {
-            let status = crate::netease::check_online_service_status().await?;
+            let status = crate::netease::online_service_status(api).await?;
             if status.available {
                 return Ok(());
             }
@@ -92,10 +279,25 @@ fn spawn_service(
         return Ok(());
     }
 
+    if let Ok(mut log) = process.log.lock() {
+        match log_path(app_handle) {
+            Ok(path) => log.set_path(path),
+            Err(error) => eprintln!("Online service log unavailable: {}", error),
+        }
+    }
+    let port = choose_service_port(DEFAULT_SERVICE_PORT)?;
+    if port != DEFAULT_SERVICE_PORT {
+        process.log(
+            "supervisor",
+            &format!("port {} is in use, using {}", DEFAULT_SERVICE_PORT, port),
+        );
+    }
+
     let app_sidecar_command = app_handle
         .shell()
         .sidecar(service_name)
-        .map_err(|e| format!("Failed to get sidecar command for {}: {}", service_name, e))?;
+        .map_err(|e| format!("Failed to get sidecar command for {}: {}", service_name, e))?
+        .env("PORT", port.to_string());
 
     let (mut rx, child) = app_sidecar_command
         .spawn()
@@ -106,38 +308,105 @@ fn spawn_service(
         .lock()
         .map_err(|_| "Online service process lock poisoned".to_string())?
         .replace(child);
+    process.generation.fetch_add(1, Ordering::SeqCst);
+    app_handle.state::<OnlineApiState>().set_bundled_port(port);
+    process.log(
+        "supervisor",
+        &format!("started {} (pid {}) on port {}", service_name, pid, port),
+    );
 
-    let child_state = Arc::clone(&process.child);
+    let app_handle = app_handle.clone();
+    let process = process.clone();
+    let service_name = service_name.to_string();
+    let started_at = Instant::now();
     tauri::async_runtime::spawn(async move {
+        let mut exit = None;
         // 读取诸如 stdout 之类的事件
         while let Some(event) = rx.recv().await {
             match event {
                 CommandEvent::Stdout(line) => {
+                    process.log("stdout", &String::from_utf8_lossy(&line));
                     if let Some(window) = &window {
                         if let Err(e) = window.emit("message", Some(format!("{:?}", line))) {
                             eprintln!("Failed to emit event: {}", e);
                         }
                     }
                 }
-                CommandEvent::Terminated(_) => {
-                    if let Ok(mut current) = child_state.lock() {
-                        if current.as_ref().map(CommandChild::pid) == Some(pid) {
-                            current.take();
-                        }
-                    }
+                CommandEvent::Stderr(line) => {
+                    process.log("stderr", &String::from_utf8_lossy(&line));
+                }
+                CommandEvent::Error(error) => process.log("supervisor", &error),
+                CommandEvent::Terminated(payload) => {
+                    exit = Some(payload);
                 }
                 _ => {}
             }
         }
-        if let Ok(mut current) = child_state.lock() {
-            if current.as_ref().map(CommandChild::pid) == Some(pid) {
-                current.take();
-            }
+        let payload = exit.unwrap_or(TerminatedPayload {
+            code: None,
+            signal: None,
+        });
+        process.log(
+            "supervisor",
+            &format!(
+                "pid {} exited (code {:?}, signal {:?})",
+                pid, payload.code, payload.signal
+            ),
+        );
+        // 主动停止时子进程已被 shutdown_service 取走，只有意外退出才重启
+        if process.take_if_current(pid) {
+            schedule_restart(
+                app_handle,
+                process,
+                service_name,
+                started_at.elapsed(),
+                payload,
+            );
         }
     });
     Ok(())
 }
 
+/// 意外退出：通知前端，按连续崩溃次数退避后重新启动；期间有手动启动 / 停止则放弃
+fn schedule_restart(
+    app_handle: AppHandle,
+    process: OnlineServiceProcess,
+    service_name: String,
+    ran_for: Duration,
+    payload: TerminatedPayload,
+) {
+    if ran_for >= STABLE_RUN {
+        process.crash_streak.store(0, Ordering::SeqCst);
+    }
+    let attempt = process.crash_streak.fetch_add(1, Ordering::SeqCst) as usize;
+    let delay = RESTART_BACKOFF[attempt.min(RESTART_BACKOFF.len() - 1)];
+    let generation = process.generation.load(Ordering::SeqCst);
+    process.log(
+        "supervisor",
+        &format!("unexpected exit, restarting in {}s", delay.as_secs()),
+    );
+    let _ = app_handle.emit(
+        "online-service-exited",
+        OnlineServiceExit {
+            code: payload.code,
+            signal: payload.signal,
+            restart_in_ms: delay.as_millis() as u64,
+        },
+    );
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+        if process.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let window = app_handle.get_webview_window("main");
+        if let Err(error) = spawn_service(&app_handle, &service_name, window, &process) {
+            process.log("supervisor", &format!("restart failed: {}", error));
+            eprintln!("Failed to restart online service: {}", error);
+        }
+    });
+}
+
 #[tauri::command]
 pub async fn restart_online_service(
     app_handle: tauri::AppHandle,
@@ -145,23 +414,100 @@ pub async fn restart_online_service(
 ) -> Result<(), String> {
     let service_name = sidecar_name_for_current_platform();
     shutdown_service(process.inner())?;
+    process.crash_streak.store(0, Ordering::SeqCst);
     tokio::time::sleep(Duration::from_millis(300)).await;
     let window = app_handle.get_webview_window("main");
     spawn_service(&app_handle, service_name, window, process.inner())?;
-    wait_until_service_ready().await
+    wait_until_service_ready(&app_handle.state::<OnlineApiState>()).await
 }
 
 /// shutdown the service for the sidecar
 pub fn shutdown_service(process: &OnlineServiceProcess) -> Result<(), String> {
+    process.generation.fetch_add(1, Ordering::SeqCst);
     let child = process
         .child
         .lock()
         .map_err(|_| "Online service process lock poisoned".to_string())?
         .take();
     if let Some(child) = child {
+        process.log("supervisor", &format!("stopping pid {}", child.pid()));
         child
             .kill()
             .map_err(|e| format!("Failed to terminate online service: {}", e))?;
     }
     Ok(())
 }
+
+/// 内置服务日志的最后若干行（默认 200 行）
+#[tauri::command]
+pub fn get_online_service_logs(
+    app_handle: tauri::AppHandle,
+    lines: Option<usize>,
+) -> Result<String, String> {
+    let path = log_path(&app_handle)?;
+    Ok(read_log_tail(
+        &path,
+        lines.unwrap_or(DEFAULT_LOG_LINES).max(1),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rmusic-service-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_ms()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn log_rotates_and_tail_spans_rotated_files() {
+        let dir = temp_dir("log");
+        let path = dir.join(LOG_FILE);
+        let mut log = ServiceLog::default();
+        log.set_path(path.clone());
+        let line = "x".repeat(1024);
+        let total = (MAX_LOG_BYTES / 1024) as usize * 3;
+        for index in 0..total {
+            log.append("stderr", &format!("{} {}", index, line));
+        }
+
+        assert!(fs::metadata(&path).unwrap().len() <= MAX_LOG_BYTES);
+        assert!(rotated_log_path(&path, 1).exists());
+        assert!(rotated_log_path(&path, 2).exists());
+        assert!(!rotated_log_path(&path, 3).exists());
+
+        let tail = read_log_tail(&path, 3);
+        let tail: Vec<&str> = tail.lines().collect();
+        assert_eq!(tail.len(), 3);
+        assert!(tail[2].contains(&format!("[stderr] {} x", total - 1)));
+        // 当前文件不足 lines 行时从滚动出去的旧文件补齐，顺序不乱
+        let long_tail = read_log_tail(&path, 1000);
+        let indexes: Vec<usize> = long_tail
+            .lines()
+            .filter_map(|line| line.split("] ").nth(1)?.split(' ').next()?.parse().ok())
+            .collect();
+        assert!(indexes.windows(2).all(|pair| pair[1] == pair[0] + 1));
+        assert_eq!(*indexes.last().unwrap(), total - 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn busy_default_port_falls_back_to_a_free_one() {
+        let busy = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let busy_port = busy.local_addr().unwrap().port();
+        let port = choose_service_port(busy_port).unwrap();
+        assert_ne!(port, busy_port);
+        assert!(port_is_free(port));
+
+        drop(busy);
+        assert_eq!(choose_service_port(busy_port).unwrap(), busy_port);
+    }
+}
@@ -0,0 +1,413 @@
+// 播放会话持久化：定期及退出时保存当前曲目、进度、音量、队列与播放模式，下次启动时以暂停状态恢复
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::Sender;
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::music::{
+    self, MusicFile, MusicState, PlayStartResult, PlaybackDurationState, PlaybackSource,
+    PlaybackTrack, PlaybackVolumeState,
+};
+use crate::netease::SongInfo;
+use crate::remote_source::{remote_cache_key, RemoteSong};
+use crate::storage;
+use crate::time_stretch::PlaybackRateState;
+use crate::timestamp::now_ms;
+
+const PLAYBACK_SESSION_FILE: &str = "playback-session.json";
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlayMode {
+    #[default]
+    Sequential,
+    Random,
+    RepeatOne,
+}
+
+/// 播放队列与模式由前端维护，变化时通过 update_session_queue 同步过来
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionQueue {
+    pub play_mode: PlayMode,
+    pub playlist_id: Option<String>,
+    pub local_queue: Vec<MusicFile>,
+    pub online_queue: Vec<QueuedSong>,
+}
+
+/// 在线队列里的歌曲；远程服务器的歌曲多一个 server_id，需要原样保留
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum QueuedSong {
+    Remote(RemoteSong),
+    Online(SongInfo),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTrack {
+    pub source: PlaybackSource,
+    pub track: Option<PlaybackTrack>,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaybackSession {
+    pub current: Option<SessionTrack>,
+    /// 前端音量滑块的值（0-100）
+    pub volume: Option<f32>,
+    pub queue: SessionQueue,
+    pub saved_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoredSession {
+    pub session: PlaybackSession,
+    /// 当前曲目已以暂停状态载入 sink；为 None 表示文件不存在或在线音频未缓存，需要重新播放
+    pub playback: Option<PlayStartResult>,
+}
+
+#[derive(Default)]
+pub struct SessionState {
+    current: StdMutex<Option<(PlaybackSource, Option<PlaybackTrack>)>>,
+    queue: StdMutex<SessionQueue>,
+    last_saved: StdMutex<String>,
+    /// 每次启动只恢复一次；前端重新加载时直接返回第一次的结果
+    restored: Mutex<Option<Option<RestoredSession>>>,
+}
+
+fn session_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    storage::app_data_file(app_handle, PLAYBACK_SESSION_FILE)
+}
+
+/// play_track 成功载入新曲目后调用
+pub fn set_current_track(
+    app_handle: &AppHandle,
+    source: PlaybackSource,
+    track: Option<PlaybackTrack>,
+) {
+    let Some(state) = app_handle.try_state::<SessionState>() else {
+        return;
+    };
+    if let Ok(mut current) = state.current.lock() {
+        *current = Some((source, track));
+    };
+}
+
+/// 退出时必须拿到锁：在运行时线程上（如 MPRIS 的退出命令）先让出工作线程，否则 blocking_lock 会 panic
+fn lock_blocking<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::task::block_in_place(|| mutex.blocking_lock())
+    } else {
+        mutex.blocking_lock()
+    }
+}
+
+/// 读取当前播放状态；wait 为 false 时 sink 正被占用就返回 None，由调用方稍后重试
+fn capture_session(app_handle: &AppHandle, wait: bool) -> Option<PlaybackSession> {
+    let state = app_handle.try_state::<SessionState>()?;
+    let sink = app_handle.try_state::<Arc<Mutex<rodio::Sink>>>()?;
+    let duration = app_handle.try_state::<PlaybackDurationState>()?;
+    let playback_rate = app_handle.try_state::<PlaybackRateState>()?;
+
+    let (position_ms, is_empty) = {
+        let sink = if wait {
+            lock_blocking(&sink)
+        } else {
+            sink.try_lock().ok()?
+        };
+        (playback_rate.position().as_millis() as u64, sink.empty())
+    };
+    let duration_ms = if wait {
+        *lock_blocking(&duration.0)
+    } else {
+        *duration.0.try_lock().ok()?
+    };
+    let current = state
+        .current
+        .lock()
+        .ok()?
+        .clone()
+        .map(|(source, track)| SessionTrack {
+            source,
+            track,
+            // 曲目已播放完时从头恢复
+            position_ms: if is_empty { 0 } else { position_ms },
+            duration_ms,
+        });
+    let volume = app_handle
+        .try_state::<PlaybackVolumeState>()
+        .and_then(|volume| volume.0.lock().ok().and_then(|volume| *volume));
+    let queue = state.queue.lock().ok()?.clone();
+
+    Some(PlaybackSession {
+        current,
+        volume,
+        queue,
+        saved_at_ms: 0,
+    })
+}
+
+/// 保存会话快照；内容与上次写入相同时跳过
+pub fn save_session(app_handle: &AppHandle) {
+    write_session(app_handle, false);
+}
+
+/// 退出前保存：等待 sink 锁而不是跳过；会话尚未恢复（或正在恢复）时不覆盖上次保存的内容
+pub fn save_session_on_quit(app_handle: &AppHandle) {
+    let restored = match app_handle.try_state::<SessionState>() {
+        Some(state) => state
+            .restored
+            .try_lock()
+            .is_ok_and(|restored| restored.is_some()),
+        None => return,
+    };
+    if restored {
+        write_session(app_handle, true);
+    }
+}
+
+fn write_session(app_handle: &AppHandle, wait: bool) {
+    let Some(state) = app_handle.try_state::<SessionState>() else {
+        return;
+    };
+    let Some(mut session) = capture_session(app_handle, wait) else {
+        return;
+    };
+    let Ok(fingerprint) = serde_json::to_string(&session) else {
+        return;
+    };
+    let Ok(mut last_saved) = state.last_saved.lock() else {
+        return;
+    };
+    if *last_saved == fingerprint {
+        return;
+    }
+
+    session.saved_at_ms = now_ms();
+    match session_path(app_handle).and_then(|path| storage::write_json(&path, &session)) {
+        Ok(()) => *last_saved = fingerprint,
+        Err(e) => eprintln!("Failed to save playback session: {}", e),
+    }
+}
+
+pub fn start_session_autosave(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SESSION_SAVE_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            // 恢复完成前不覆盖上次保存的会话
+            let restored = match app_handle.try_state::<SessionState>() {
+                Some(state) => state.restored.lock().await.is_some(),
+                None => return,
+            };
+            if restored {
+                save_session(&app_handle);
+            }
+        }
+    });
+}
+
+/// 本地文件需要仍然存在；在线曲目只从已完整缓存的音频恢复，避免启动时联网
+fn restorable_path(
+    source: &PlaybackSource,
+    cached_online_path: impl Fn(&str) -> Option<PathBuf>,
+) -> Option<PathBuf> {
+    match source {
+        PlaybackSource::Local { path, .. } => {
+            let path = Path::new(path);
+            path.is_file().then(|| path.to_path_buf())
+        }
+        PlaybackSource::Online { cache_key, .. } => cached_online_path(cache_key),
+        PlaybackSource::Remote { server_id, song_id } => {
+            cached_online_path(&remote_cache_key(server_id, song_id))
+        }
+        // 直播无法从暂停位置恢复；播客单集的进度单独保存，重新播放时续播
+        PlaybackSource::Radio { .. } | PlaybackSource::Podcast { .. } => None,
+    }
+}
+
+async fn restore_saved_session(
+    app_handle: &AppHandle,
+    state: &SessionState,
+) -> Option<RestoredSession> {
+    let path = session_path(app_handle).ok()?;
+    let session: PlaybackSession = storage::read_json_or_default(&path);
+    if session.current.is_none()
+        && session.volume.is_none()
+        && session.queue.local_queue.is_empty()
+        && session.queue.online_queue.is_empty()
+    {
+        return None;
+    }
+
+    if let Ok(mut queue) = state.queue.lock() {
+        *queue = session.queue.clone();
+    }
+    if let Some(volume) = session.volume {
+        if let Some(volume_state) = app_handle.try_state::<PlaybackVolumeState>() {
+            if let Ok(mut current) = volume_state.0.lock() {
+                *current = Some(volume);
+            }
+        }
+        if let Some(sender) = app_handle.try_state::<Sender<MusicState>>() {
+            let _ = sender.send(MusicState::Volume(volume));
+        }
+    }
+
+    let mut playback = None;
+    if let Some(current) = &session.current {
+        if let Ok(mut state_current) = state.current.lock() {
+            *state_current = Some((current.source.clone(), current.track.clone()));
+        }
+        let cached = |cache_key: &str| music::cached_online_audio_path(app_handle, cache_key);
+        if let Some(audio_path) = restorable_path(&current.source, cached) {
+            let (local_path, range) = match &current.source {
+                PlaybackSource::Local { path, range } => (Some(path.clone()), *range),
+                PlaybackSource::Online { .. }
+                | PlaybackSource::Remote { .. }
+                | PlaybackSource::Radio { .. }
+                | PlaybackSource::Podcast { .. } => (None, None),
+            };
+            match music::load_paused_track(
+                app_handle,
+                &audio_path,
+                range,
+                current.position_ms,
+                current.track.clone(),
+                local_path,
+            )
+            .await
+            {
+                Ok(result) => playback = Some(result),
+                Err(e) => eprintln!("Failed to restore playback session: {}", e),
+            }
+        }
+    }
+
+    Some(RestoredSession { session, playback })
+}
+
+/// 前端启动时调用：恢复上次会话（仅一次），返回恢复后的状态供界面同步
+#[tauri::command]
+pub async fn restore_session(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SessionState>,
+) -> Result<Option<RestoredSession>, String> {
+    let mut restored = state.restored.lock().await;
+    if let Some(result) = restored.as_ref() {
+        return Ok(result.clone());
+    }
+    let result = restore_saved_session(&app_handle, &state).await;
+    *restored = Some(result.clone());
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn update_session_queue(
+    state: tauri::State<'_, SessionState>,
+    queue: SessionQueue,
+) -> Result<(), String> {
+    *state
+        .queue
+        .lock()
+        .map_err(|_| "session queue lock poisoned".to_string())? = queue;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn only_existing_local_files_and_cached_online_tracks_are_restorable() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir =
+            std::env::temp_dir().join(format!("rmusic-session-{}-{}", std::process::id(), unique));
+        fs::create_dir_all(&dir).unwrap();
+        let existing = dir.join("song.mp3");
+        fs::write(&existing, b"audio").unwrap();
+
+        let no_cache = |_: &str| None;
+        let local = PlaybackSource::Local {
+            path: existing.to_string_lossy().to_string(),
+            range: None,
+        };
+        assert_eq!(restorable_path(&local, no_cache), Some(existing.clone()));
+        let missing = PlaybackSource::Local {
+            path: dir.join("gone.mp3").to_string_lossy().to_string(),
+            range: None,
+        };
+        assert_eq!(restorable_path(&missing, no_cache), None);
+
+        let online = PlaybackSource::Online {
+            url: "https://expired.example/song.mp3".into(),
+            cache_key: "42".into(),
+        };
+        assert_eq!(restorable_path(&online, no_cache), None);
+        let cached_path = dir.join("42.audio");
+        let cached = |key: &str| (key == "42").then(|| cached_path.clone());
+        assert_eq!(restorable_path(&online, cached), Some(dir.join("42.audio")));
+        let remote = PlaybackSource::Remote {
+            server_id: "srv".into(),
+            song_id: "42".into(),
+        };
+        let cached = |key: &str| (key == "subsonic:srv:42").then(|| cached_path.clone());
+        assert_eq!(restorable_path(&remote, cached), Some(dir.join("42.audio")));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn session_round_trips_with_frontend_play_mode_names() {
+        let json = r#"{
+            "current": {
+                "source": { "type": "online", "url": "", "cache_key": "7" },
+                "track": null,
+                "position_ms": 1500,
+                "duration_ms": 200000
+            },
+            "volume": 35,
+            "queue": {
+                "play_mode": "repeat-one",
+                "playlist_id": "pl_1",
+                "online_queue": [
+                    { "id": "1", "name": "a", "artists": [], "album": "", "duration": 0,
+                      "pic_url": "", "file_hash": "1" },
+                    { "server_id": "srv", "id": "2", "name": "b", "artists": [], "album": "",
+                      "duration": 0, "pic_url": "", "file_hash": "2" }
+                ]
+            }
+        }"#;
+        let session: PlaybackSession = serde_json::from_str(json).unwrap();
+        assert_eq!(session.queue.play_mode, PlayMode::RepeatOne);
+        assert_eq!(session.current.as_ref().unwrap().position_ms, 1500);
+        assert!(session.queue.local_queue.is_empty());
+        assert!(matches!(
+            session.queue.online_queue[0],
+            QueuedSong::Online(_)
+        ));
+        assert!(matches!(
+            &session.queue.online_queue[1],
+            QueuedSong::Remote(song) if song.server_id == "srv"
+        ));
+
+        let value = serde_json::to_value(&session).unwrap();
+        assert_eq!(value["queue"]["play_mode"], "repeat-one");
+        assert_eq!(value["current"]["source"]["cache_key"], "7");
+        assert_eq!(value["queue"]["online_queue"][1]["server_id"], "srv");
+    }
+}
@@ -0,0 +1,386 @@
+// 睡眠定时器：到时（或播完指定曲目数）前逐渐降低音量，然后暂停并恢复原音量
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::broadcast::Sender;
+use tokio::sync::Mutex;
+
+use crate::music::{MusicState, PlaybackDurationState, PlaybackTrackIdState, PlaybackVolumeState};
+use crate::time_stretch::PlaybackRateState;
+use crate::timestamp::now_ms;
+
+const SLEEP_TIMER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// 每隔多少次轮询推送一次剩余时间
+const STATUS_EMIT_EVERY_POLLS: u32 = 4;
+const DEFAULT_FADE_SECONDS: u32 = 10;
+const MAX_FADE_SECONDS: u32 = 60;
+/// 按曲目计时时在曲目结束前这么久暂停，避免前端收到 playback-ended 后自动切到下一首
+const TRACK_END_GUARD_MS: u64 = 500;
+/// 前端尚未同步音量时 sink 的默认音量 1.0 对应的滑块值（平方曲线 * 2）
+const DEFAULT_SLIDER_VOLUME: f32 = 70.710_68;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SleepTimerMode {
+    /// N 分钟后暂停
+    Duration { minutes: u32 },
+    /// 当前曲目结束时暂停
+    EndOfTrack,
+    /// 播完 N 首（包括当前曲目）后暂停
+    Tracks { count: u32 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SleepTimerStatus {
+    pub active: bool,
+    pub mode: Option<SleepTimerMode>,
+    /// 距离暂停的剩余毫秒；按曲目计时且还有多首未播时未知
+    pub remaining_ms: Option<u64>,
+    pub tracks_remaining: Option<u32>,
+    pub fading: bool,
+}
+
+#[derive(Debug, Clone)]
+struct ActiveSleepTimer {
+    id: u64,
+    mode: SleepTimerMode,
+    deadline_ms: Option<u64>,
+    tracks_remaining: u32,
+    fade_ms: u64,
+    remaining_ms: Option<u64>,
+    fading: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct SleepTimerState {
+    active: Arc<StdMutex<Option<ActiveSleepTimer>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl SleepTimerStatus {
+    fn inactive() -> Self {
+        Self {
+            active: false,
+            mode: None,
+            remaining_ms: None,
+            tracks_remaining: None,
+            fading: false,
+        }
+    }
+}
+
+impl From<&ActiveSleepTimer> for SleepTimerStatus {
+    fn from(timer: &ActiveSleepTimer) -> Self {
+        Self {
+            active: true,
+            mode: Some(timer.mode),
+            remaining_ms: timer.remaining_ms,
+            tracks_remaining: match timer.mode {
+                SleepTimerMode::Duration { .. } => None,
+                _ => Some(timer.tracks_remaining),
+            },
+            fading: timer.fading,
+        }
+    }
+}
+
+/// 剩余时间内音量的比例：淡出窗口之前为 1，之后线性降到 0
+fn fade_factor(remaining_ms: u64, fade_ms: u64) -> f32 {
+    if fade_ms == 0 || remaining_ms >= fade_ms {
+        1.0
+    } else {
+        remaining_ms as f32 / fade_ms as f32
+    }
+}
+
+/// 按曲目计时：最后一首的剩余时间（扣除结束保护）；还有多首未播时未知
+fn track_remaining_ms(tracks_remaining: u32, position_ms: u64, duration_ms: u64) -> Option<u64> {
+    if tracks_remaining > 1 || duration_ms == 0 {
+        return None;
+    }
+    Some(
+        duration_ms
+            .saturating_sub(position_ms)
+            .saturating_sub(TRACK_END_GUARD_MS),
+    )
+}
+
+impl SleepTimerState {
+    fn status(&self) -> SleepTimerStatus {
+        self.active
+            .lock()
+            .ok()
+            .and_then(|active| active.as_ref().map(SleepTimerStatus::from))
+            .unwrap_or_else(SleepTimerStatus::inactive)
+    }
+
+    fn is_current(&self, id: u64) -> bool {
+        self.active
+            .lock()
+            .ok()
+            .is_some_and(|active| active.as_ref().is_some_and(|timer| timer.id == id))
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut ActiveSleepTimer)) -> Option<SleepTimerStatus> {
+        let mut active = self.active.lock().ok()?;
+        let timer = active.as_mut().filter(|timer| timer.id == id)?;
+        f(timer);
+        Some(SleepTimerStatus::from(&*timer))
+    }
+
+    fn clear(&self, id: Option<u64>) -> bool {
+        let Ok(mut active) = self.active.lock() else {
+            return false;
+        };
+        if active
+            .as_ref()
+            .is_some_and(|timer| id.is_none_or(|id| timer.id == id))
+        {
+            *active = None;
+            return true;
+        }
+        false
+    }
+}
+
+fn user_volume(app_handle: &AppHandle) -> f32 {
+    app_handle
+        .try_state::<PlaybackVolumeState>()
+        .and_then(|state| state.0.lock().ok().and_then(|volume| *volume))
+        .unwrap_or(DEFAULT_SLIDER_VOLUME)
+}
+
+fn send_music_state(app_handle: &AppHandle, state: MusicState) {
+    if let Some(sender) = app_handle.try_state::<Sender<MusicState>>() {
+        let _ = sender.send(state);
+    }
+}
+
+fn emit_status(app_handle: &AppHandle, status: &SleepTimerStatus) {
+    let _ = app_handle.emit("sleep-timer-updated", status);
+}
+
+async fn run_sleep_timer(app_handle: AppHandle, state: SleepTimerState, id: u64) {
+    let (Some(sink), Some(duration), Some(track_id), Some(playback_rate)) = (
+        app_handle
+            .try_state::<Arc<Mutex<rodio::Sink>>>()
+            .map(|state| Arc::clone(&state)),
+        app_handle
+            .try_state::<PlaybackDurationState>()
+            .map(|state| state.inner().clone()),
+        app_handle
+            .try_state::<PlaybackTrackIdState>()
+            .map(|state| state.inner().clone()),
+        app_handle
+            .try_state::<PlaybackRateState>()
+            .map(|state| state.inner().clone()),
+    ) else {
+        return;
+    };
+
+    let mut last_track_id = *track_id.0.lock().await;
+    let mut applied_factor = 1.0f32;
+    let mut polls = 0u32;
+
+    loop {
+        tokio::time::sleep(SLEEP_TIMER_POLL_INTERVAL).await;
+        if !state.is_current(id) {
+            break;
+        }
+
+        let current_track_id = *track_id.0.lock().await;
+        let (position_ms, is_empty) = {
+            let sink = sink.lock().await;
+            (playback_rate.position().as_millis() as u64, sink.empty())
+        };
+        let rate = playback_rate.rate();
+        let duration_ms = *duration.0.lock().await;
+        let track_changed = current_track_id != last_track_id;
+        last_track_id = current_track_id;
+
+        let Some(status) = state.update(id, |timer| {
+            match timer.mode {
+                SleepTimerMode::Duration { .. } => {
+                    timer.remaining_ms = timer
+                        .deadline_ms
+                        .map(|deadline| deadline.saturating_sub(now_ms()));
+                }
+                SleepTimerMode::EndOfTrack | SleepTimerMode::Tracks { .. } => {
+                    if track_changed {
+                        timer.tracks_remaining = timer.tracks_remaining.saturating_sub(1);
+                    }
+                    let last_track_ended = timer.tracks_remaining == 1 && is_empty;
+                    timer.remaining_ms = if timer.tracks_remaining == 0 || last_track_ended {
+                        // 最后一首已经结束（轮询错过了结束保护窗口），立即暂停
+                        Some(0)
+                    } else {
+                        // 曲目位置是源时间，按播放速度换算成实际剩余时间
+                        track_remaining_ms(timer.tracks_remaining, position_ms, duration_ms)
+                            .map(|remaining| (remaining as f32 / rate) as u64)
+                    };
+                }
+            }
+            timer.fading = timer
+                .remaining_ms
+                .is_some_and(|remaining| remaining < timer.fade_ms);
+        }) else {
+            break;
+        };
+
+        let remaining_ms = status.remaining_ms;
+        let fade_ms = state
+            .active
+            .lock()
+            .ok()
+            .and_then(|active| active.as_ref().map(|timer| timer.fade_ms))
+            .unwrap_or(0);
+
+        if remaining_ms == Some(0) {
+            if state.clear(Some(id)) {
+                send_music_state(&app_handle, MusicState::Pause);
+                send_music_state(&app_handle, MusicState::Volume(user_volume(&app_handle)));
+                let _ = app_handle.emit("sleep-timer-finished", ());
+                emit_status(&app_handle, &SleepTimerStatus::inactive());
+            }
+            return;
+        }
+
+        let factor = remaining_ms.map_or(1.0, |remaining| fade_factor(remaining, fade_ms));
+        if factor < 1.0 || applied_factor < 1.0 {
+            send_music_state(
+                &app_handle,
+                MusicState::Volume(user_volume(&app_handle) * factor),
+            );
+            applied_factor = factor;
+        }
+
+        polls = polls.wrapping_add(1);
+        if polls.is_multiple_of(STATUS_EMIT_EVERY_POLLS) || status.fading {
+            emit_status(&app_handle, &status);
+        }
+    }
+
+    // 被取消或被新的定时器替换：撤销已经做过的淡出
+    if applied_factor < 1.0 {
+        send_music_state(&app_handle, MusicState::Volume(user_volume(&app_handle)));
+    }
+}
+
+pub fn start(
+    app_handle: &AppHandle,
+    mode: SleepTimerMode,
+    fade_seconds: Option<u32>,
+) -> Result<SleepTimerStatus, String> {
+    let state = app_handle
+        .try_state::<SleepTimerState>()
+        .ok_or_else(|| "sleep timer state not available".to_string())?
+        .inner()
+        .clone();
+    let (deadline_ms, tracks_remaining) = match mode {
+        SleepTimerMode::Duration { minutes } if minutes > 0 => {
+            (Some(now_ms() + u64::from(minutes) * 60_000), 0)
+        }
+        SleepTimerMode::EndOfTrack => (None, 1),
+        SleepTimerMode::Tracks { count } if count > 0 => (None, count),
+        _ => return Err("Sleep timer needs a positive duration or track count".to_string()),
+    };
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let timer = ActiveSleepTimer {
+        id,
+        mode,
+        deadline_ms,
+        tracks_remaining,
+        fade_ms: u64::from(
+            fade_seconds
+                .unwrap_or(DEFAULT_FADE_SECONDS)
+                .min(MAX_FADE_SECONDS),
+        ) * 1000,
+        remaining_ms: deadline_ms.map(|deadline| deadline.saturating_sub(now_ms())),
+        fading: false,
+    };
+    let status = SleepTimerStatus::from(&timer);
+    *state
+        .active
+        .lock()
+        .map_err(|_| "sleep timer state poisoned".to_string())? = Some(timer);
+
+    emit_status(app_handle, &status);
+    tauri::async_runtime::spawn(run_sleep_timer(app_handle.clone(), state, id));
+    Ok(status)
+}
+
+pub fn cancel(app_handle: &AppHandle) {
+    let Some(state) = app_handle.try_state::<SleepTimerState>() else {
+        return;
+    };
+    if state.clear(None) {
+        emit_status(app_handle, &SleepTimerStatus::inactive());
+    }
+}
+
+#[tauri::command]
+pub fn start_sleep_timer(
+    app_handle: AppHandle,
+    mode: SleepTimerMode,
+    fade_seconds: Option<u32>,
+) -> Result<SleepTimerStatus, String> {
+    start(&app_handle, mode, fade_seconds)
+}
+
+#[tauri::command]
+pub fn cancel_sleep_timer(app_handle: AppHandle) {
+    cancel(&app_handle);
+}
+
+#[tauri::command]
+pub fn get_sleep_timer_status(state: tauri::State<'_, SleepTimerState>) -> SleepTimerStatus {
+    state.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fade_factor_ramps_down_inside_fade_window() {
+        assert_eq!(fade_factor(20_000, 10_000), 1.0);
+        assert_eq!(fade_factor(5_000, 10_000), 0.5);
+        assert_eq!(fade_factor(0, 10_000), 0.0);
+        assert_eq!(fade_factor(0, 0), 1.0);
+    }
+
+    #[test]
+    fn track_remaining_is_known_only_for_the_last_track() {
+        assert_eq!(track_remaining_ms(2, 0, 180_000), None);
+        assert_eq!(track_remaining_ms(1, 0, 0), None);
+        assert_eq!(
+            track_remaining_ms(1, 100_000, 180_000),
+            Some(80_000 - TRACK_END_GUARD_MS)
+        );
+        assert_eq!(track_remaining_ms(1, 180_000, 180_000), Some(0));
+    }
+
+    #[test]
+    fn clearing_only_affects_the_matching_timer() {
+        let state = SleepTimerState::default();
+        *state.active.lock().unwrap() = Some(ActiveSleepTimer {
+            id: 2,
+            mode: SleepTimerMode::EndOfTrack,
+            deadline_ms: None,
+            tracks_remaining: 1,
+            fade_ms: 0,
+            remaining_ms: None,
+            fading: false,
+        });
+
+        assert!(!state.clear(Some(1)));
+        assert!(state.status().active);
+        assert_eq!(state.status().tracks_remaining, Some(1));
+        assert!(state.clear(None));
+        assert!(!state.status().active);
+    }
+}
@@ -0,0 +1,140 @@
+// 应用数据目录下 JSON 文件的通用读写：读取失败回退默认值，写入走临时文件 + rename 保证原子性
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+pub fn app_data_file(app_handle: &AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("app_data_dir: {}", e))?;
+    Ok(dir.join(file_name))
+}
+
+/// 与目标同目录的临时文件路径，rename 时不会跨文件系统
+pub(crate) fn unique_temp_path_for(target_path: &Path) -> PathBuf {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    let file_name = target_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("data.json");
+
+    target_path.with_file_name(format!(
+        "{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        unique
+    ))
+}
+
+/// 用临时文件替换目标；Windows 上 rename 不能覆盖已有文件，需要先删除
+pub(crate) fn commit_temp_file(tmp_path: &Path, target_path: &Path) -> Result<(), String> {
+    #[cfg(windows)]
+    if target_path.exists() {
+        fs::remove_file(target_path)
+            .map_err(|e| format!("replace {}: {}", target_path.display(), e))?;
+    }
+
+    fs::rename(tmp_path, target_path)
+        .map_err(|e| format!("commit {}: {}", target_path.display(), e))
+}
+
+/// 文件不存在时返回默认值；内容损坏时返回错误，由调用方决定是否回退
+pub fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let file = File::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| format!("parse {}: {}", path.display(), e))
+}
+
+/// 读取失败（包括内容损坏）时打印日志并回退默认值，适合设置类文件
+pub fn read_json_or_default<T: DeserializeOwned + Default>(path: &Path) -> T {
+    read_json(path).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        T::default()
+    })
+}
+
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create {}: {}", parent.display(), e))?;
+    }
+
+    let tmp_path = unique_temp_path_for(path);
+    let result: Result<(), String> = (|| {
+        let file = File::create(&tmp_path)
+            .map_err(|e| format!("create temp file {}: {}", tmp_path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, value)
+            .map_err(|e| format!("serialize {}: {}", path.display(), e))?;
+        writer
+            .flush()
+            .map_err(|e| format!("flush {}: {}", path.display(), e))?;
+        writer
+            .get_ref()
+            .sync_all()
+            .map_err(|e| format!("sync {}: {}", path.display(), e))?;
+        drop(writer);
+        commit_temp_file(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn json_round_trips_and_missing_file_reads_default() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir =
+            std::env::temp_dir().join(format!("rmusic-storage-{}-{}", std::process::id(), unique));
+        let path = dir.join("nested").join("settings.json");
+
+        let empty: BTreeMap<String, u32> = read_json(&path).unwrap();
+        assert!(empty.is_empty());
+
+        let value = BTreeMap::from([("volume".to_string(), 42u32)]);
+        write_json(&path, &value).unwrap();
+        let restored: BTreeMap<String, u32> = read_json(&path).unwrap();
+        assert_eq!(restored, value);
+
+        fs::write(&path, b"{broken").unwrap();
+        assert!(read_json::<BTreeMap<String, u32>>(&path).is_err());
+        assert!(read_json_or_default::<BTreeMap<String, u32>>(&path).is_empty());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn unique_temp_path_stays_next_to_target() {
+        let target = Path::new("/tmp/rmusic-data/playlists.json");
+        let tmp = unique_temp_path_for(target);
+
+        assert_eq!(tmp.parent(), target.parent());
+        assert_ne!(tmp, target);
+        assert!(tmp
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| { name.starts_with("playlists.json.") && name.ends_with(".tmp") }));
+    }
+}
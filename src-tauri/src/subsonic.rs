@@ -0,0 +1,1371 @@
+// Subsonic 兼容服务（默认关闭）：把本地媒体库（扫描索引）与 playlists.json 提供给手机上的 Subsonic 客户端。
+// 实现核心接口与 salted token 认证；响应统一先构造成 JSON，再按 f 参数输出 JSON 或 XML。
+// 限制：CUE 分轨不对外提供——Subsonic 客户端按歌曲 id 串流整个文件，无法只播放其中一段
+
+use hyper::body::HttpBody;
+use hyper::header::{self, HeaderValue};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha1::{Digest, Sha1};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::file::{
+    find_cover_file, library_paths, lyric_file_path, modified_ms, read_library_index,
+};
+use crate::local_server::{
+    content_type, escape_xml, file_response, RunningServer, ServerSlot, ServerStatus,
+};
+use crate::music::MusicFile;
+use crate::playlist::{playlists_path, read_playlists_from_path, Playlist, PlaylistItem};
+use crate::remote_control::constant_time_eq;
+use crate::storage::{self, app_data_file};
+use crate::timestamp::{iso8601, now_ms};
+
+const SETTINGS_FILE: &str = "subsonic_server.json";
+/// 供手机访问，默认监听所有网卡；只有启用并设置了账号密码才会启动
+const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0:4533";
+const API_VERSION: &str = "1.16.1";
+const XML_NAMESPACE: &str = "http://subsonic.org/restapi";
+const ROOT_DIRECTORY_ID: &str = "dir-root";
+const MAX_FORM_BYTES: usize = 64 * 1024;
+const MAX_LIST_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SubsonicServerSettings {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub username: String,
+    /// token 认证需要明文密码（md5(password + salt)），这是 Subsonic 协议本身的要求
+    pub password: String,
+    /// 媒体库根目录，与前端的 defaultDirectory 一致；为空时使用应用数据目录
+    pub library_root: Option<String>,
+}
+
+impl Default for SubsonicServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: DEFAULT_BIND_ADDRESS.to_string(),
+            username: String::new(),
+            password: String::new(),
+            library_root: None,
+        }
+    }
+}
+
+impl SubsonicServerSettings {
+    fn normalized(mut self) -> Result<Self, String> {
+        self.bind_address = self.bind_address.trim().to_string();
+        if self.bind_address.is_empty() {
+            self.bind_address = DEFAULT_BIND_ADDRESS.to_string();
+        }
+        self.bind_address
+            .parse::<SocketAddr>()
+            .map_err(|e| format!("invalid bind address {}: {}", self.bind_address, e))?;
+        self.username = self.username.trim().to_string();
+        self.library_root = self
+            .library_root
+            .map(|root| root.trim().to_string())
+            .filter(|root| !root.is_empty());
+        if self.enabled && (self.username.is_empty() || self.password.is_empty()) {
+            return Err("username and password are required".to_string());
+        }
+        Ok(self)
+    }
+}
+
+/// 服务读取的文件位置；测试时指向临时目录
+#[derive(Debug, Clone)]
+pub struct SubsonicLibrary {
+    /// 扫描目录（媒体库根目录下的 music/）
+    pub scan_path: PathBuf,
+    /// 媒体库根目录，封面与歌词在其下的 cover/、lyrics/
+    pub base_dir: PathBuf,
+    pub index_path: PathBuf,
+    pub playlists_path: PathBuf,
+}
+
+/// 客户端上报的播放
+#[derive(Debug, Clone)]
+pub struct SubsonicScrobble {
+    pub file: MusicFile,
+    pub path: PathBuf,
+    pub played_at_ms: u64,
+}
+
+pub struct SubsonicContext {
+    library: SubsonicLibrary,
+    username: String,
+    password: String,
+    scrobbles: UnboundedSender<SubsonicScrobble>,
+    /// 按索引文件修改时间缓存解析结果，避免每个请求都重新解析整个索引
+    cache: StdMutex<Option<(u64, Arc<Vec<MusicFile>>)>>,
+}
+
+impl SubsonicContext {
+    pub fn new(
+        library: SubsonicLibrary,
+        username: String,
+        password: String,
+    ) -> (Arc<Self>, UnboundedReceiver<SubsonicScrobble>) {
+        let (scrobbles, receiver) = unbounded_channel();
+        let context = Arc::new(Self {
+            library,
+            username,
+            password,
+            scrobbles,
+            cache: StdMutex::new(None),
+        });
+        (context, receiver)
+    }
+
+    /// 只含实际文件；CUE 虚拟分轨会被过滤（见文件开头的限制说明）
+    fn files(&self) -> Arc<Vec<MusicFile>> {
+        let modified = modified_ms(&self.library.index_path);
+        if let Ok(cache) = self.cache.lock() {
+            if let Some((cached_modified, files)) = cache.as_ref() {
+                if *cached_modified == modified {
+                    return files.clone();
+                }
+            }
+        }
+        let files: Arc<Vec<MusicFile>> = Arc::new(
+            read_library_index(&self.library.index_path, &self.library.scan_path)
+                .into_iter()
+                .filter(|file| file.cue.is_none())
+                .collect(),
+        );
+        if let Ok(mut cache) = self.cache.lock() {
+            *cache = Some((modified, files.clone()));
+        }
+        files
+    }
+
+    fn absolute_path(&self, file: &MusicFile) -> PathBuf {
+        self.library.scan_path.join(&file.relative_path)
+    }
+}
+
+#[derive(Default)]
+pub struct SubsonicServerState {
+    settings: StdMutex<SubsonicServerSettings>,
+    server: ServerSlot,
+}
+
+impl SubsonicServerState {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let settings = settings_path(app_handle)
+            .map(|path| storage::read_json_or_default(&path))
+            .unwrap_or_default();
+        Self {
+            settings: StdMutex::new(settings),
+            ..Self::default()
+        }
+    }
+
+    fn settings(&self) -> SubsonicServerSettings {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn settings_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_data_file(app_handle, SETTINGS_FILE)
+}
+
+/// Subsonic 错误码
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ApiError {
+    Generic,
+    MissingParameter,
+    WrongCredentials,
+    NotFound,
+}
+
+impl ApiError {
+    fn code(self) -> u32 {
+        match self {
+            ApiError::Generic => 0,
+            ApiError::MissingParameter => 10,
+            ApiError::WrongCredentials => 40,
+            ApiError::NotFound => 70,
+        }
+    }
+}
+
+type ApiResult = Result<Value, (ApiError, String)>;
+
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    fn parse(query: &str) -> Vec<(String, String)> {
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                let decode = |value: &str| {
+                    urlencoding::decode(&value.replace('+', " "))
+                        .map(|value| value.into_owned())
+                        .unwrap_or_default()
+                };
+                (decode(key), decode(value))
+            })
+            .collect()
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn all(&self, name: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    fn require(&self, name: &str) -> Result<&str, (ApiError, String)> {
+        self.get(name).ok_or_else(|| {
+            (
+                ApiError::MissingParameter,
+                format!("Required parameter is missing: {}", name),
+            )
+        })
+    }
+
+    fn number(&self, name: &str, default: usize) -> usize {
+        self.get(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+}
+
+fn decode_hex_password(value: &str) -> Option<String> {
+    let bytes = value
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// 支持 token（t = md5(password + s)）与旧式明文 / enc: 十六进制密码
+fn authenticate(params: &Params, username: &str, password: &str) -> Result<(), (ApiError, String)> {
+    let user = params.require("u")?;
+    let valid = if let (Some(token), Some(salt)) = (params.get("t"), params.get("s")) {
+        let expected = format!("{:x}", md5::compute(format!("{}{}", password, salt)));
+        constant_time_eq(expected.as_bytes(), token.to_ascii_lowercase().as_bytes())
+    } else {
+        let provided = params.require("p")?;
+        let provided = match provided.strip_prefix("enc:") {
+            Some(hex) => decode_hex_password(hex).unwrap_or_default(),
+            None => provided.to_string(),
+        };
+        constant_time_eq(provided.as_bytes(), password.as_bytes())
+    };
+    if user == username && valid {
+        Ok(())
+    } else {
+        Err((
+            ApiError::WrongCredentials,
+            "Wrong username or password".to_string(),
+        ))
+    }
+}
+
+fn short_hash(prefix: &str, value: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(value.as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    format!("{}-{}", prefix, &digest[..16])
+}
+
+fn song_id(file: &MusicFile) -> String {
+    format!("tr-{}", file.key)
+}
+
+fn parent_dir(relative_path: &str) -> String {
+    Path::new(relative_path)
+        .parent()
+        .map(|parent| parent.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default()
+}
+
+fn directory_id(relative_dir: &str) -> String {
+    if relative_dir.is_empty() {
+        ROOT_DIRECTORY_ID.to_string()
+    } else {
+        short_hash("dir", relative_dir)
+    }
+}
+
+fn title_of(file: &MusicFile) -> String {
+    file.title.clone().unwrap_or_else(|| {
+        Path::new(&file.file_name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| file.file_name.clone())
+    })
+}
+
+fn artist_of(file: &MusicFile) -> String {
+    file.artist
+        .clone()
+        .filter(|artist| !artist.trim().is_empty())
+        .unwrap_or_else(|| "Unknown Artist".to_string())
+}
+
+fn album_of(file: &MusicFile) -> String {
+    file.album
+        .clone()
+        .filter(|album| !album.trim().is_empty())
+        .unwrap_or_else(|| "Unknown Album".to_string())
+}
+
+fn artist_id(artist: &str) -> String {
+    short_hash("ar", artist)
+}
+
+fn album_id(file: &MusicFile) -> String {
+    short_hash("al", &format!("{}\u{0}{}", artist_of(file), album_of(file)))
+}
+
+fn song_json(context: &SubsonicContext, file: &MusicFile) -> Value {
+    let id = song_id(file);
+    let size = std::fs::metadata(context.absolute_path(file))
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let artist = artist_of(file);
+    json!({
+        "id": id,
+        "parent": directory_id(&parent_dir(&file.relative_path)),
+        "isDir": false,
+        "title": title_of(file),
+        "album": album_of(file),
+        "artist": artist,
+        "coverArt": id,
+        "size": size,
+        "contentType": content_type(&file.extension),
+        "suffix": file.extension,
+        "duration": file.duration_ms / 1000,
+        "path": file.relative_path,
+        "albumId": album_id(file),
+        "artistId": artist_id(&artist),
+        "type": "music",
+        "created": iso8601(file.modified_ms),
+    })
+}
+
+struct Album<'a> {
+    id: String,
+    name: String,
+    artist: String,
+    songs: Vec<&'a MusicFile>,
+}
+
+impl Album<'_> {
+    fn created_ms(&self) -> u64 {
+        self.songs
+            .iter()
+            .map(|song| song.modified_ms)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "name": self.name,
+            "artist": self.artist,
+            "artistId": artist_id(&self.artist),
+            "coverArt": self.songs.first().map(|song| song_id(song)),
+            "songCount": self.songs.len(),
+            "duration": self.songs.iter().map(|song| song.duration_ms / 1000).sum::<u64>(),
+            "created": iso8601(self.created_ms()),
+        })
+    }
+}
+
+/// 按（艺术家，专辑名）分组，专辑内按文件名排序
+fn albums(files: &[MusicFile]) -> Vec<Album<'_>> {
+    let mut grouped: BTreeMap<(String, String), Vec<&MusicFile>> = BTreeMap::new();
+    for file in files {
+        grouped
+            .entry((artist_of(file), album_of(file)))
+            .or_default()
+            .push(file);
+    }
+    grouped
+        .into_iter()
+        .map(|((artist, name), mut songs)| {
+            songs.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+            Album {
+                id: album_id(songs[0]),
+                name,
+                artist,
+                songs,
+            }
+        })
+        .collect()
+}
+
+fn not_found(what: &str) -> (ApiError, String) {
+    (ApiError::NotFound, format!("{} not found", what))
+}
+
+fn find_song<'a>(files: &'a [MusicFile], id: &str) -> Option<&'a MusicFile> {
+    files.iter().find(|file| song_id(file) == id)
+}
+
+fn get_music_folders() -> ApiResult {
+    Ok(json!({ "musicFolders": { "musicFolder": [{ "id": 1, "name": "rmusic" }] } }))
+}
+
+/// 根目录的子目录作为“艺术家”索引，根目录下的文件作为 child
+fn get_indexes(context: &SubsonicContext) -> ApiResult {
+    let files = context.files();
+    let mut top_dirs: BTreeMap<String, ()> = BTreeMap::new();
+    let mut root_songs = Vec::new();
+    for file in files.iter() {
+        match file.relative_path.replace('\\', "/").split_once('/') {
+            Some((top, _)) => {
+                top_dirs.insert(top.to_string(), ());
+            }
+            None => root_songs.push(song_json(context, file)),
+        }
+    }
+    let mut indexes: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for name in top_dirs.keys() {
+        let letter = name
+            .chars()
+            .next()
+            .filter(|ch| ch.is_ascii_alphabetic())
+            .map(|ch| ch.to_ascii_uppercase().to_string())
+            .unwrap_or_else(|| "#".to_string());
+        indexes
+            .entry(letter)
+            .or_default()
+            .push(json!({ "id": directory_id(name), "name": name }));
+    }
+    let index: Vec<Value> = indexes
+        .into_iter()
+        .map(|(name, artist)| json!({ "name": name, "artist": artist }))
+        .collect();
+    Ok(json!({
+        "indexes": {
+            "lastModified": modified_ms(&context.library.index_path),
+            "ignoredArticles": "",
+            "index": index,
+            "child": root_songs,
+        }
+    }))
+}
+
+fn get_music_directory(context: &SubsonicContext, params: &Params) -> ApiResult {
+    let id = params.require("id")?;
+    let files = context.files();
+    let mut directories: BTreeMap<String, ()> = BTreeMap::new();
+    for file in files.iter() {
+        let mut dir = parent_dir(&file.relative_path);
+        while !dir.is_empty() {
+            let parent = parent_dir(&dir);
+            directories.insert(dir, ());
+            dir = parent;
+        }
+    }
+    let relative_dir = if id == ROOT_DIRECTORY_ID {
+        String::new()
+    } else {
+        directories
+            .keys()
+            .find(|dir| directory_id(dir) == id)
+            .cloned()
+            .ok_or_else(|| not_found("Directory"))?
+    };
+
+    let mut children: Vec<Value> = directories
+        .keys()
+        .filter(|dir| parent_dir(dir) == relative_dir)
+        .map(|dir| {
+            json!({
+                "id": directory_id(dir),
+                "parent": id,
+                "isDir": true,
+                "title": Path::new(dir).file_name().map(|name| name.to_string_lossy().to_string()),
+            })
+        })
+        .collect();
+    children.extend(
+        files
+            .iter()
+            .filter(|file| parent_dir(&file.relative_path) == relative_dir)
+            .map(|file| song_json(context, file)),
+    );
+    let name = Path::new(&relative_dir)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "rmusic".to_string());
+    let mut directory = json!({ "id": id, "name": name, "child": children });
+    if !relative_dir.is_empty() {
+        directory["parent"] = json!(directory_id(&parent_dir(&relative_dir)));
+    }
+    Ok(json!({ "directory": directory }))
+}
+
+fn get_album_list2(context: &SubsonicContext, params: &Params) -> ApiResult {
+    let list_type = params.require("type")?;
+    let size = params.number("size", 10).min(MAX_LIST_SIZE);
+    let offset = params.number("offset", 0);
+    let files = context.files();
+    let mut albums = albums(&files);
+    match list_type {
+        "random" => albums.shuffle(&mut rand::thread_rng()),
+        "alphabeticalByName" => albums.sort_by_key(|album| album.name.to_lowercase()),
+        "alphabeticalByArtist" => albums.sort_by_key(|album| album.artist.to_lowercase()),
+        // 没有单独的播放统计，recent/frequent/highest 退化为最新入库
+        "newest" | "recent" | "frequent" | "highest" | "starred" => {
+            albums.sort_by_key(|album| std::cmp::Reverse(album.created_ms()))
+        }
+        "byYear" | "byGenre" => albums.clear(),
+        other => {
+            return Err((
+                ApiError::Generic,
+                format!("Unsupported list type: {}", other),
+            ));
+        }
+    }
+    let album: Vec<Value> = albums
+        .iter()
+        .skip(offset)
+        .take(size)
+        .map(Album::to_json)
+        .collect();
+    Ok(json!({ "albumList2": { "album": album } }))
+}
+
+fn get_album(context: &SubsonicContext, params: &Params) -> ApiResult {
+    let id = params.require("id")?;
+    let files = context.files();
+    let albums = albums(&files);
+    let album = albums
+        .iter()
+        .find(|album| album.id == id)
+        .ok_or_else(|| not_found("Album"))?;
+    let mut value = album.to_json();
+    value["song"] = Value::Array(
+        album
+            .songs
+            .iter()
+            .map(|song| song_json(context, song))
+            .collect(),
+    );
+    Ok(json!({ "album": value }))
+}
+
+/// 客户端常把关键词加引号；空查询返回全部（部分客户端用它同步整个库）
+fn search_terms(query: &str) -> Vec<String> {
+    query
+        .trim()
+        .trim_matches('"')
+        .to_lowercase()
+        .split_whitespace()
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn search3(context: &SubsonicContext, params: &Params) -> ApiResult {
+    let terms = search_terms(params.get("query").unwrap_or_default());
+    let matches = |text: &str| {
+        let text = text.to_lowercase();
+        terms.iter().all(|term| text.contains(term))
+    };
+    let files = context.files();
+    let albums = albums(&files);
+
+    let mut artists: BTreeMap<String, usize> = BTreeMap::new();
+    for album in &albums {
+        if matches(&album.artist) {
+            *artists.entry(album.artist.clone()).or_default() += 1;
+        }
+    }
+    let artist: Vec<Value> = artists
+        .into_iter()
+        .skip(params.number("artistOffset", 0))
+        .take(params.number("artistCount", 20).min(MAX_LIST_SIZE))
+        .map(|(name, album_count)| {
+            json!({ "id": artist_id(&name), "name": name, "albumCount": album_count })
+        })
+        .collect();
+    let album: Vec<Value> = albums
+        .iter()
+        .filter(|album| matches(&format!("{} {}", album.name, album.artist)))
+        .skip(params.number("albumOffset", 0))
+        .take(params.number("albumCount", 20).min(MAX_LIST_SIZE))
+        .map(Album::to_json)
+        .collect();
+    let song: Vec<Value> = files
+        .iter()
+        .filter(|file| {
+            matches(&format!(
+                "{} {} {} {}",
+                file.search_text,
+                title_of(file),
+                artist_of(file),
+                album_of(file)
+            ))
+        })
+        .skip(params.number("songOffset", 0))
+        .take(params.number("songCount", 20).min(MAX_LIST_SIZE))
+        .map(|file| song_json(context, file))
+        .collect();
+    Ok(json!({ "searchResult3": { "artist": artist, "album": album, "song": song } }))
+}
+
+/// 去掉 LRC 的时间标签与元信息行，只保留歌词文本
+fn plain_lyrics(lrc: &str) -> String {
+    lrc.lines()
+        .filter_map(|line| {
+            let mut rest = line.trim();
+            let mut tagged = false;
+            while let Some(stripped) = rest.strip_prefix('[') {
+                let (tag, after) = stripped.split_once(']')?;
+                // [ar:xxx] 之类的元信息行整行丢弃
+                if !tag.starts_with(|ch: char| ch.is_ascii_digit()) {
+                    return None;
+                }
+                rest = after.trim_start();
+                tagged = true;
+            }
+            (tagged || !rest.is_empty()).then(|| rest.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn get_lyrics(context: &SubsonicContext, params: &Params) -> ApiResult {
+    let artist = params.get("artist").map(str::to_lowercase);
+    let title = params.get("title").map(str::to_lowercase);
+    let files = context.files();
+    let file = files.iter().find(|file| {
+        title
+            .as_ref()
+            .is_some_and(|title| title_of(file).to_lowercase() == *title)
+            && artist
+                .as_ref()
+                .is_none_or(|artist| artist_of(file).to_lowercase() == *artist)
+    });
+    let lyrics = file.and_then(|file| {
+        let text =
+            std::fs::read_to_string(lyric_file_path(&context.library.base_dir, &file.file_name))
+                .ok()?;
+        Some(json!({
+            "artist": artist_of(file),
+            "title": title_of(file),
+            "value": plain_lyrics(&text),
+        }))
+    });
+    Ok(json!({ "lyrics": lyrics.unwrap_or_else(|| json!({})) }))
+}
+
+fn read_playlists(context: &SubsonicContext) -> Result<Vec<Playlist>, (ApiError, String)> {
+    read_playlists_from_path(&context.library.playlists_path)
+        .map_err(|error| (ApiError::Generic, error))
+}
+
+/// 只包含本地曲目；在线（网易云）与远程服务器条目客户端无法串流，略过
+fn playlist_entries<'a>(playlist: &Playlist, files: &'a [MusicFile]) -> Vec<&'a MusicFile> {
+    let by_name: HashMap<&str, &MusicFile> = files
+        .iter()
+        .map(|file| (file.file_name.as_str(), file))
+        .collect();
+    playlist
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            PlaylistItem::Local { file_name } => by_name.get(file_name.as_str()).copied(),
+            PlaylistItem::Online { .. } | PlaylistItem::Remote { .. } => None,
+        })
+        .collect()
+}
+
+fn playlist_json(context: &SubsonicContext, playlist: &Playlist, entries: &[&MusicFile]) -> Value {
+    json!({
+        "id": playlist.id,
+        "name": playlist.name,
+        "owner": context.username,
+        "public": false,
+        "songCount": entries.len(),
+        "duration": entries.iter().map(|file| file.duration_ms / 1000).sum::<u64>(),
+        "created": iso8601(playlist.created_at),
+        "changed": iso8601(playlist.created_at),
+        "coverArt": entries.first().map(|file| song_id(file)),
+    })
+}
+
+fn get_playlists(context: &SubsonicContext) -> ApiResult {
+    let files = context.files();
+    let playlist: Vec<Value> = read_playlists(context)?
+        .iter()
+        .map(|playlist| playlist_json(context, playlist, &playlist_entries(playlist, &files)))
+        .collect();
+    Ok(json!({ "playlists": { "playlist": playlist } }))
+}
+
+fn get_playlist(context: &SubsonicContext, params: &Params) -> ApiResult {
+    let id = params.require("id")?;
+    let files = context.files();
+    let playlists = read_playlists(context)?;
+    let playlist = playlists
+        .iter()
+        .find(|playlist| playlist.id == id)
+        .ok_or_else(|| not_found("Playlist"))?;
+    let entries = playlist_entries(playlist, &files);
+    let mut value = playlist_json(context, playlist, &entries);
+    value["entry"] = Value::Array(
+        entries
+            .iter()
+            .map(|file| song_json(context, file))
+            .collect(),
+    );
+    Ok(json!({ "playlist": value }))
+}
+
+/// submission=false 的“正在播放”通知不记录
+fn scrobble(context: &SubsonicContext, params: &Params) -> ApiResult {
+    let ids = params.all("id");
+    if ids.is_empty() {
+        params.require("id")?;
+    }
+    if params.get("submission") == Some("false") {
+        return Ok(json!({}));
+    }
+    let times = params.all("time");
+    let files = context.files();
+    for (index, id) in ids.iter().enumerate() {
+        let file = find_song(&files, id).ok_or_else(|| not_found("Song"))?;
+        let played_at_ms = times
+            .get(index)
+            .and_then(|time| time.parse().ok())
+            .unwrap_or_else(now_ms);
+        let _ = context.scrobbles.send(SubsonicScrobble {
+            file: file.clone(),
+            path: context.absolute_path(file),
+            played_at_ms,
+        });
+    }
+    Ok(json!({}))
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// JSON 到 XML 的通用映射：标量是属性，对象是子元素，数组是重复的子元素，"value" 是文本内容
+fn write_xml_element(out: &mut String, name: &str, value: &Map<String, Value>) {
+    out.push('<');
+    out.push_str(name);
+    for (key, value) in value {
+        if key == "value" {
+            continue;
+        }
+        if let Some(text) = scalar_text(value) {
+            out.push_str(&format!(" {}=\"{}\"", key, escape_xml(&text)));
+        }
+    }
+    let text = value.get("value").and_then(scalar_text);
+    let children: Vec<(&String, &Value)> = value
+        .iter()
+        .filter(|(_, value)| value.is_object() || value.is_array())
+        .collect();
+    if text.is_none() && children.is_empty() {
+        out.push_str("/>");
+        return;
+    }
+    out.push('>');
+    if let Some(text) = text {
+        out.push_str(&escape_xml(&text));
+    }
+    for (key, child) in children {
+        match child {
+            Value::Object(object) => write_xml_element(out, key, object),
+            Value::Array(items) => {
+                for item in items.iter().filter_map(Value::as_object) {
+                    write_xml_element(out, key, item);
+                }
+            }
+            _ => {}
+        }
+    }
+    out.push_str(&format!("</{}>", name));
+}
+
+fn envelope(result: ApiResult) -> Map<String, Value> {
+    let mut body = Map::new();
+    match result {
+        Ok(Value::Object(payload)) => {
+            body.insert("status".to_string(), json!("ok"));
+            body.insert("version".to_string(), json!(API_VERSION));
+            body.insert("type".to_string(), json!("rmusic"));
+            body.extend(payload);
+        }
+        Ok(_) => {
+            body.insert("status".to_string(), json!("ok"));
+            body.insert("version".to_string(), json!(API_VERSION));
+        }
+        Err((error, message)) => {
+            body.insert("status".to_string(), json!("failed"));
+            body.insert("version".to_string(), json!(API_VERSION));
+            body.insert(
+                "error".to_string(),
+                json!({ "code": error.code(), "message": message }),
+            );
+        }
+    }
+    body
+}
+
+fn api_response(format: Option<&str>, result: ApiResult) -> Response<Body> {
+    let body = envelope(result);
+    let (content_type, text) = match format {
+        Some("json") => (
+            "application/json",
+            json!({ "subsonic-response": body }).to_string(),
+        ),
+        _ => {
+            let mut body = body;
+            body.insert("xmlns".to_string(), json!(XML_NAMESPACE));
+            let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+            write_xml_element(&mut xml, "subsonic-response", &body);
+            ("text/xml; charset=utf-8", xml)
+        }
+    };
+    let mut response = Response::new(Body::from(text));
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+async fn stream(context: &SubsonicContext, params: &Params, range: Option<&str>) -> Response<Body> {
+    let format = params.get("f");
+    let id = match params.require("id") {
+        Ok(id) => id,
+        Err(error) => return api_response(format, Err(error)),
+    };
+    let files = context.files();
+    let Some(file) = find_song(&files, id) else {
+        return api_response(format, Err(not_found("Song")));
+    };
+    file_response(
+        &context.absolute_path(file),
+        content_type(&file.extension),
+        range,
+    )
+    .await
+    .unwrap_or_else(|_| api_response(format, Err(not_found("File"))))
+}
+
+/// 封面 id 可以是曲目、专辑或播放列表里出现的曲目 id
+async fn get_cover_art(context: &SubsonicContext, params: &Params) -> Response<Body> {
+    let format = params.get("f");
+    let id = match params.require("id") {
+        Ok(id) => id,
+        Err(error) => return api_response(format, Err(error)),
+    };
+    let files = context.files();
+    let file = find_song(&files, id).or_else(|| files.iter().find(|file| album_id(file) == id));
+    let cover = file.and_then(|file| find_cover_file(&context.library.base_dir, &file.file_name));
+    let Some(cover) = cover else {
+        return api_response(format, Err(not_found("Cover art")));
+    };
+    let extension = cover
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_default();
+    file_response(&cover, content_type(&extension), None)
+        .await
+        .unwrap_or_else(|_| api_response(format, Err(not_found("Cover art"))))
+}
+
+async fn request_params(request: Request<Body>) -> (Params, Option<String>) {
+    let mut params = Params::parse(request.uri().query().unwrap_or_default());
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+    let is_form = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    // 部分客户端用 POST 表单提交参数
+    if request.method() == Method::POST && is_form {
+        let mut body = request.into_body();
+        let mut bytes = Vec::new();
+        while let Some(Ok(chunk)) = body.data().await {
+            if bytes.len() + chunk.len() > MAX_FORM_BYTES {
+                break;
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        params.extend(Params::parse(&String::from_utf8_lossy(&bytes)));
+    }
+    (Params(params), range)
+}
+
+async fn handle_request(context: Arc<SubsonicContext>, request: Request<Body>) -> Response<Body> {
+    let path = request.uri().path().to_string();
+    let Some(endpoint) = path.strip_prefix("/rest/") else {
+        let mut response = Response::new(Body::from("Not Found"));
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    };
+    let endpoint = endpoint.trim_end_matches(".view").to_string();
+    let (params, range) = request_params(request).await;
+    let format = params.get("f").map(ToOwned::to_owned);
+    let format = format.as_deref();
+    if let Err(error) = authenticate(&params, &context.username, &context.password) {
+        return api_response(format, Err(error));
+    }
+
+    let context = context.as_ref();
+    let result = match endpoint.as_str() {
+        "ping" => Ok(json!({})),
+        "getLicense" => Ok(json!({ "license": { "valid": true } })),
+        "getMusicFolders" => get_music_folders(),
+        "getIndexes" => get_indexes(context),
+        "getMusicDirectory" => get_music_directory(context, &params),
+        "getAlbumList2" => get_album_list2(context, &params),
+        "getAlbum" => get_album(context, &params),
+        "search3" => search3(context, &params),
+        "getLyrics" => get_lyrics(context, &params),
+        "getPlaylists" => get_playlists(context),
+        "getPlaylist" => get_playlist(context, &params),
+        "scrobble" => scrobble(context, &params),
+        "stream" | "download" => return stream(context, &params, range.as_deref()).await,
+        "getCoverArt" => return get_cover_art(context, &params).await,
+        other => Err((
+            ApiError::Generic,
+            format!("Unsupported endpoint: {}", other),
+        )),
+    };
+    api_response(format, result)
+}
+
+/// 在已绑定的监听器上启动服务，shutdown 置为 true 时停止；返回的任务结束时监听器已关闭
+pub fn spawn_server(
+    listener: std::net::TcpListener,
+    context: Arc<SubsonicContext>,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<JoinHandle<()>, String> {
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("configure Subsonic listener: {}", e))?;
+    let server = Server::from_tcp(listener).map_err(|e| format!("start Subsonic server: {}", e))?;
+    let make_service = make_service_fn(move |_| {
+        let context = context.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let context = context.clone();
+                async move { Ok::<_, Infallible>(handle_request(context, request).await) }
+            }))
+        }
+    });
+    let server = server
+        .serve(make_service)
+        .with_graceful_shutdown(async move {
+            let _ = shutdown.changed().await;
+        });
+    Ok(tokio::spawn(async move {
+        if let Err(error) = server.await {
+            eprintln!("Subsonic server error: {}", error);
+        }
+    }))
+}
+
+fn forward_scrobbles(app_handle: AppHandle, mut receiver: UnboundedReceiver<SubsonicScrobble>) {
+    tokio::spawn(async move {
+        while let Some(scrobble) = receiver.recv().await {
+            let result = crate::history::record_external_play(
+                &app_handle,
+                &scrobble.file,
+                &scrobble.path,
+                scrobble.played_at_ms,
+            );
+            if let Err(error) = result {
+                eprintln!("Failed to record Subsonic scrobble: {}", error);
+            }
+        }
+    });
+}
+
+async fn start_server(
+    app_handle: &AppHandle,
+    settings: &SubsonicServerSettings,
+) -> Result<RunningServer, String> {
+    let (scan_path, base_dir, index_path) =
+        library_paths(app_handle, settings.library_root.clone())?;
+    let library = SubsonicLibrary {
+        scan_path,
+        base_dir,
+        index_path,
+        playlists_path: playlists_path(app_handle)?,
+    };
+    let address: SocketAddr = settings
+        .bind_address
+        .parse()
+        .map_err(|e| format!("invalid bind address {}: {}", settings.bind_address, e))?;
+    let listener = std::net::TcpListener::bind(address)
+        .map_err(|e| format!("bind Subsonic server on {}: {}", address, e))?;
+    let address = listener.local_addr().unwrap_or(address);
+
+    let (context, scrobbles) = SubsonicContext::new(
+        library,
+        settings.username.clone(),
+        settings.password.clone(),
+    );
+    let (shutdown, shutdown_receiver) = watch::channel(false);
+    let task = spawn_server(listener, context, shutdown_receiver)?;
+    forward_scrobbles(app_handle.clone(), scrobbles);
+    Ok(RunningServer::new(address, shutdown, task))
+}
+
+async fn restart(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SubsonicServerState>();
+    let settings = state.settings();
+    state
+        .server
+        .restart(settings.enabled, || start_server(app_handle, &settings))
+        .await
+}
+
+pub fn start_subsonic_server(app_handle: &AppHandle) {
+    if !app_handle.state::<SubsonicServerState>().settings().enabled {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(error) = restart(&app_handle).await {
+            eprintln!("Failed to start Subsonic server: {}", error);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_subsonic_server_settings(
+    state: tauri::State<'_, SubsonicServerState>,
+) -> SubsonicServerSettings {
+    state.settings()
+}
+
+#[tauri::command]
+pub async fn set_subsonic_server_settings(
+    app_handle: AppHandle,
+    settings: SubsonicServerSettings,
+) -> Result<(), String> {
+    let settings = settings.normalized()?;
+    storage::write_json(&settings_path(&app_handle)?, &settings)?;
+    {
+        let state = app_handle.state::<SubsonicServerState>();
+        *state
+            .settings
+            .lock()
+            .map_err(|_| "Subsonic settings lock poisoned".to_string())? = settings;
+    }
+    restart(&app_handle).await
+}
+
+#[tauri::command]
+pub fn get_subsonic_server_status(state: tauri::State<'_, SubsonicServerState>) -> ServerStatus {
+    state.server.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    struct Fixture {
+        dir: PathBuf,
+        base_url: String,
+        scrobbles: UnboundedReceiver<SubsonicScrobble>,
+        _shutdown: watch::Sender<bool>,
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn music_file(relative_path: &str, title: &str, artist: &str, album: &str) -> Value {
+        let file_name = Path::new(relative_path)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        json!({
+            "file_name": relative_path,
+            "key": short_hash("key", relative_path),
+            "relative_path": relative_path,
+            "extension": "mp3",
+            "modified_ms": 1_700_000_000_000u64,
+            "search_text": file_name.to_lowercase(),
+            "title": title,
+            "artist": artist,
+            "album": album,
+            "duration_ms": 180_000,
+        })
+    }
+
+    async fn start_fixture(name: &str) -> Fixture {
+        let dir =
+            std::env::temp_dir().join(format!("rmusic-subsonic-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let scan_path = dir.join("music");
+        fs::create_dir_all(scan_path.join("Beyond")).unwrap();
+        fs::create_dir_all(dir.join("cover/Beyond")).unwrap();
+        fs::create_dir_all(dir.join("lyrics/Beyond")).unwrap();
+        fs::write(scan_path.join("Beyond/01 Sky.mp3"), b"0123456789").unwrap();
+        fs::write(scan_path.join("Loose.mp3"), b"loose").unwrap();
+        fs::write(dir.join("cover/Beyond/01 Sky.jpg"), b"JPEG").unwrap();
+        fs::write(
+            dir.join("lyrics/Beyond/01 Sky.lrc"),
+            "[ar:Beyond]\n[00:01.00]Line one\n[00:02.00]Line two\n",
+        )
+        .unwrap();
+
+        let index_path = dir.join("index.json");
+        let index = json!({
+            "version": 4,
+            "root": scan_path.to_string_lossy(),
+            "files": [
+                music_file("Beyond/01 Sky.mp3", "Sky", "Beyond", "Live"),
+                music_file("Loose.mp3", "Loose", "Someone", "Singles"),
+            ],
+        });
+        fs::write(&index_path, index.to_string()).unwrap();
+        let playlists_path = dir.join("playlists.json");
+        let playlists = json!([{
+            "id": "pl-1",
+            "name": "Favourites",
+            "items": [
+                { "type": "local", "file_name": "Beyond/01 Sky.mp3" },
+                { "type": "online", "song": {
+                    "id": "1", "name": "x", "artists": [], "album": "", "duration": 0,
+                    "pic_url": "", "file_hash": ""
+                } }
+            ],
+            "createdAt": 1_700_000_000_000u64,
+        }]);
+        fs::write(&playlists_path, playlists.to_string()).unwrap();
+
+        let library = SubsonicLibrary {
+            scan_path,
+            base_dir: dir.clone(),
+            index_path,
+            playlists_path,
+        };
+        let (context, scrobbles) =
+            SubsonicContext::new(library, "alice".to_string(), "sesame".to_string());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/rest", listener.local_addr().unwrap());
+        let (shutdown, shutdown_receiver) = watch::channel(false);
+        spawn_server(listener, context, shutdown_receiver).unwrap();
+        Fixture {
+            dir,
+            base_url,
+            scrobbles,
+            _shutdown: shutdown,
+        }
+    }
+
+    fn auth() -> String {
+        let token = format!("{:x}", md5::compute("sesamec0ffee"));
+        format!("u=alice&t={}&s=c0ffee&v=1.16.1&c=test&f=json", token)
+    }
+
+    async fn get_json(url: String) -> Value {
+        let response = reqwest::get(url).await.unwrap();
+        let body: Value = response.json().await.unwrap();
+        body["subsonic-response"].clone()
+    }
+
+    #[tokio::test]
+    async fn authenticates_with_salted_token_and_rejects_wrong_password() {
+        let fixture = start_fixture("auth").await;
+        let ok = get_json(format!("{}/ping.view?{}", fixture.base_url, auth())).await;
+        assert_eq!(ok["status"], "ok");
+
+        let wrong = get_json(format!(
+            "{}/ping?u=alice&p=enc:{}&f=json",
+            fixture.base_url, "77726f6e67"
+        ))
+        .await;
+        assert_eq!(wrong["status"], "failed");
+        assert_eq!(wrong["error"]["code"], 40);
+
+        // 默认输出 XML
+        let xml = reqwest::get(format!("{}/ping?u=alice&p=sesame", fixture.base_url))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(xml.contains(r#"<subsonic-response status="ok""#));
+    }
+
+    #[tokio::test]
+    async fn browses_searches_and_lists_albums() {
+        let fixture = start_fixture("browse").await;
+        let root = get_json(format!(
+            "{}/getMusicDirectory?id={}&{}",
+            fixture.base_url,
+            ROOT_DIRECTORY_ID,
+            auth()
+        ))
+        .await;
+        let children = root["directory"]["child"].as_array().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0]["isDir"], true);
+        assert_eq!(children[0]["title"], "Beyond");
+        assert_eq!(children[1]["title"], "Loose");
+
+        let search = get_json(format!(
+            "{}/search3?query=%22sky%22&{}",
+            fixture.base_url,
+            auth()
+        ))
+        .await;
+        let songs = search["searchResult3"]["song"].as_array().unwrap();
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0]["artist"], "Beyond");
+        assert_eq!(songs[0]["size"], 10);
+
+        let albums = get_json(format!(
+            "{}/getAlbumList2?type=alphabeticalByName&{}",
+            fixture.base_url,
+            auth()
+        ))
+        .await;
+        let albums = albums["albumList2"]["album"].as_array().unwrap();
+        assert_eq!(albums.len(), 2);
+        assert_eq!(albums[0]["name"], "Live");
+
+        let album = get_json(format!(
+            "{}/getAlbum?id={}&{}",
+            fixture.base_url,
+            albums[0]["id"].as_str().unwrap(),
+            auth()
+        ))
+        .await;
+        assert_eq!(album["album"]["song"][0]["title"], "Sky");
+    }
+
+    #[tokio::test]
+    async fn streams_ranges_and_serves_cover_lyrics_and_playlists() {
+        let mut fixture = start_fixture("stream").await;
+        let search = get_json(format!("{}/search3?query=sky&{}", fixture.base_url, auth())).await;
+        let id = search["searchResult3"]["song"][0]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let client = reqwest::Client::new();
+        let partial = client
+            .get(format!("{}/stream?id={}&{}", fixture.base_url, id, auth()))
+            .header("Range", "bytes=2-5")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(partial.status(), 206);
+        assert_eq!(
+            partial.headers()["content-range"].to_str().unwrap(),
+            "bytes 2-5/10"
+        );
+        assert_eq!(partial.bytes().await.unwrap().as_ref(), b"2345");
+
+        let cover = reqwest::get(format!(
+            "{}/getCoverArt?id={}&{}",
+            fixture.base_url,
+            id,
+            auth()
+        ))
+        .await
+        .unwrap();
+        assert_eq!(cover.headers()["content-type"], "image/jpeg");
+        assert_eq!(cover.bytes().await.unwrap().as_ref(), b"JPEG");
+
+        let lyrics = get_json(format!(
+            "{}/getLyrics?artist=beyond&title=sky&{}",
+            fixture.base_url,
+            auth()
+        ))
+        .await;
+        assert_eq!(lyrics["lyrics"]["value"], "Line one\nLine two");
+
+        let playlists = get_json(format!("{}/getPlaylists?{}", fixture.base_url, auth())).await;
+        assert_eq!(playlists["playlists"]["playlist"][0]["songCount"], 1);
+
+        let scrobbled = get_json(format!(
+            "{}/scrobble?id={}&time=1700000000123&{}",
+            fixture.base_url,
+            id,
+            auth()
+        ))
+        .await;
+        assert_eq!(scrobbled["status"], "ok");
+        let scrobble = fixture.scrobbles.recv().await.unwrap();
+        assert_eq!(scrobble.played_at_ms, 1_700_000_000_123);
+        assert_eq!(scrobble.file.title.as_deref(), Some("Sky"));
+    }
+
+    #[test]
+    fn maps_json_payload_to_subsonic_xml() {
+        let mut xml = String::new();
+        let body = envelope(Ok(json!({
+            "lyrics": { "artist": "A & B", "value": "<la>" },
+            "albumList2": { "album": [{ "id": "1" }, { "id": "2" }] },
+        })));
+        write_xml_element(&mut xml, "subsonic-response", &body);
+        assert!(xml.contains(r#"<lyrics artist="A &amp; B">&lt;la&gt;</lyrics>"#));
+        assert!(xml.contains(r#"<albumList2><album id="1"/><album id="2"/></albumList2>"#));
+    }
+
+    #[test]
+    fn token_comparison_ignores_hex_case() {
+        let token = format!("{:x}", md5::compute("sesamesalt"));
+        let params = |query: String| Params(Params::parse(&query));
+        let check = |query: String| authenticate(&params(query), "alice", "sesame").is_ok();
+        assert!(check(format!("u=alice&t={}&s=salt", token)));
+        assert!(check(format!("u=alice&t={}&s=salt", token.to_uppercase())));
+        assert!(!check(format!("u=alice&t={}&s=pepper", token)));
+        assert!(!check(format!("u=alice&t={}&s=salt", &token[1..])));
+        assert!(check("u=alice&p=sesame".to_string()));
+        assert!(!check("u=alice&p=sesam".to_string()));
+    }
+}
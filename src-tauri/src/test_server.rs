@@ -0,0 +1,109 @@
+// 测试用的本地 HTTP 服务：模拟网易云 API、Subsonic 服务器、电台、播客源、DLNA 设备与 scrobble 服务。
+// 每个请求先读完请求体并记录下来，再交给测试给出的处理函数生成响应；需要在 tokio 运行时里启动
+
+use hyper::header::HeaderMap;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode, Uri};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex as StdMutex};
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    pub fn path(&self) -> &str {
+        self.uri.path()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+pub struct TestServer {
+    pub address: SocketAddr,
+    requests: Arc<StdMutex<Vec<RecordedRequest>>>,
+}
+
+impl TestServer {
+    /// "http://127.0.0.1:端口"，不带末尾的 /
+    pub fn base(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base(), path)
+    }
+
+    /// 按到达顺序返回已收到的请求
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// 启动服务，每个请求都交给 handler 生成响应
+pub fn serve<F, Fut>(handler: F) -> TestServer
+where
+    F: Fn(RecordedRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Response<Body>> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let address = listener.local_addr().unwrap();
+    let requests = Arc::new(StdMutex::new(Vec::new()));
+    let recorded = Arc::clone(&requests);
+    let handler = Arc::new(handler);
+    let server = Server::from_tcp(listener)
+        .unwrap()
+        .serve(make_service_fn(move |_| {
+            let (recorded, handler) = (Arc::clone(&recorded), Arc::clone(&handler));
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let (recorded, handler) = (Arc::clone(&recorded), Arc::clone(&handler));
+                    async move {
+                        let (parts, body) = request.into_parts();
+                        let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+                        let request = RecordedRequest {
+                            method: parts.method,
+                            uri: parts.uri,
+                            headers: parts.headers,
+                            body: body.to_vec(),
+                        };
+                        recorded.lock().unwrap().push(request.clone());
+                        Ok::<_, Infallible>(handler(request).await)
+                    }
+                }))
+            }
+        }));
+    tokio::spawn(server);
+    TestServer { address, requests }
+}
+
+/// 所有请求都返回同一个固定响应
+pub fn serve_canned(
+    status: u16,
+    headers: &'static [(&'static str, &'static str)],
+    body: &'static str,
+) -> TestServer {
+    serve(move |_| async move { canned(status, headers, body) })
+}
+
+/// 由状态码、响应头和正文拼出响应，处理函数里按路径分别返回时使用
+pub fn canned(status: u16, headers: &[(&str, &str)], body: impl Into<Body>) -> Response<Body> {
+    let mut response = Response::builder().status(StatusCode::from_u16(status).unwrap());
+    for (name, value) in headers {
+        response = response.header(*name, *value);
+    }
+    response.body(body.into()).unwrap()
+}
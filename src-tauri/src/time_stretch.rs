@@ -0,0 +1,553 @@
+// 变速播放：WSOLA 时间伸缩（保持音高）或直接重采样（音高随速度变化），并以源时间记录播放位置
+
+use rodio::source::SeekError;
+use rodio::Source;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const MIN_PLAYBACK_RATE: f32 = 0.5;
+pub const MAX_PLAYBACK_RATE: f32 = 2.0;
+/// 重采样模式下每隔多少帧重新读取一次速度设置
+const SETTINGS_REFRESH_FRAMES: usize = 256;
+/// WSOLA 分析帧长（秒），合成步长为帧长的一半
+const WSOLA_FRAME_SECONDS: f64 = 0.03;
+/// WSOLA 在名义位置前后搜索最佳拼接点的范围（秒）
+const WSOLA_SEEK_SECONDS: f64 = 0.008;
+/// 计算相关度时对单声道混合信号做抽取，降低搜索开销
+const WSOLA_CORRELATION_STRIDE: usize = 4;
+
+pub struct PlaybackRateControl {
+    rate_bits: AtomicU32,
+    preserve_pitch: AtomicBool,
+    /// 每次载入新曲目递增；只有最新的 TimeStretch 才能更新播放位置
+    generation: AtomicU64,
+    position_ms: AtomicU64,
+}
+
+/// 播放速度设置与当前曲目的源时间位置，由 sink 中的 TimeStretch 共享
+#[derive(Clone)]
+pub struct PlaybackRateState(pub Arc<PlaybackRateControl>);
+
+impl Default for PlaybackRateState {
+    fn default() -> Self {
+        Self(Arc::new(PlaybackRateControl {
+            rate_bits: AtomicU32::new(1.0f32.to_bits()),
+            preserve_pitch: AtomicBool::new(true),
+            generation: AtomicU64::new(0),
+            position_ms: AtomicU64::new(0),
+        }))
+    }
+}
+
+pub fn clamp_rate(rate: f32) -> f32 {
+    if rate.is_finite() {
+        rate.clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE)
+    } else {
+        1.0
+    }
+}
+
+impl PlaybackRateState {
+    /// 设置播放速度，返回限制到 0.5-2.0 之后的值
+    pub fn set(&self, rate: f32, preserve_pitch: bool) -> f32 {
+        let rate = clamp_rate(rate);
+        self.0.rate_bits.store(rate.to_bits(), Ordering::Relaxed);
+        self.0
+            .preserve_pitch
+            .store(preserve_pitch, Ordering::Relaxed);
+        rate
+    }
+
+    pub fn rate(&self) -> f32 {
+        f32::from_bits(self.0.rate_bits.load(Ordering::Relaxed))
+    }
+
+    pub fn preserve_pitch(&self) -> bool {
+        self.0.preserve_pitch.load(Ordering::Relaxed)
+    }
+
+    /// 当前曲目的播放位置（源时间，不受播放速度影响）
+    pub fn position(&self) -> Duration {
+        Duration::from_millis(self.0.position_ms.load(Ordering::Relaxed))
+    }
+
+    /// 包装新曲目的音源；之前包装的音源不再更新播放位置
+    pub fn wrap<S>(&self, source: S) -> TimeStretch<S>
+    where
+        S: Source<Item = f32>,
+    {
+        let generation = self.0.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        self.0.position_ms.store(0, Ordering::Relaxed);
+        TimeStretch::new(source, Arc::clone(&self.0), generation)
+    }
+
+    /// 改由外部输出（DLNA 渲染器）报告播放位置：之前包装的音源不再更新位置，返回的代号用于后续上报
+    pub fn detach(&self) -> u64 {
+        let generation = self.0.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        self.0.position_ms.store(0, Ordering::Relaxed);
+        generation
+    }
+
+    /// 外部输出上报的位置；其间已换成新包装的音源时忽略
+    pub fn set_external_position(&self, generation: u64, position: Duration) -> bool {
+        if self.0.generation.load(Ordering::Relaxed) != generation {
+            return false;
+        }
+        self.0
+            .position_ms
+            .store(position.as_millis() as u64, Ordering::Relaxed);
+        true
+    }
+}
+
+struct Wsola {
+    frame_len: usize,
+    hop: usize,
+    seek: usize,
+    window: Vec<f32>,
+    /// 下一个分析帧的名义源位置（帧）
+    analysis_pos: f64,
+    prev_chosen: Option<u64>,
+    overlap: Vec<f32>,
+    pending: VecDeque<f32>,
+}
+
+impl Wsola {
+    fn new(sample_rate: u32, channels: usize, analysis_pos: f64) -> Self {
+        let hop = ((sample_rate as f64 * WSOLA_FRAME_SECONDS / 2.0).round() as usize).max(8);
+        let frame_len = hop * 2;
+        let seek = ((sample_rate as f64 * WSOLA_SEEK_SECONDS).round() as usize).max(1);
+        // 周期 Hann 窗，50% 重叠时叠加恒为 1
+        let window = (0..frame_len)
+            .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f64 / frame_len as f64).cos()) as f32)
+            .collect();
+        Self {
+            frame_len,
+            hop,
+            seek,
+            window,
+            analysis_pos,
+            prev_chosen: None,
+            overlap: vec![0.0; frame_len * channels],
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+pub struct TimeStretch<S> {
+    inner: S,
+    channels: usize,
+    sample_rate: u32,
+    control: Arc<PlaybackRateControl>,
+    generation: u64,
+    /// 已读入但尚未丢弃的交错采样，input_start 为其第一帧的源帧序号
+    input: VecDeque<f32>,
+    input_start: u64,
+    inner_done: bool,
+    /// 下一输出帧对应的源位置（帧）
+    read_pos: f64,
+    rate: f64,
+    frames_until_refresh: usize,
+    frame: Vec<f32>,
+    frame_index: usize,
+    wsola: Option<Wsola>,
+}
+
+impl<S> TimeStretch<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(inner: S, control: Arc<PlaybackRateControl>, generation: u64) -> Self {
+        let channels = (inner.channels() as usize).max(1);
+        let sample_rate = inner.sample_rate().max(1);
+        Self {
+            inner,
+            channels,
+            sample_rate,
+            control,
+            generation,
+            input: VecDeque::new(),
+            input_start: 0,
+            inner_done: false,
+            read_pos: 0.0,
+            rate: 1.0,
+            frames_until_refresh: 0,
+            frame: vec![0.0; channels],
+            frame_index: channels,
+            wsola: None,
+        }
+    }
+
+    fn input_end(&self) -> u64 {
+        self.input_start + (self.input.len() / self.channels) as u64
+    }
+
+    /// 从内部音源读入，直到输入覆盖到 end_frame（不含）或音源结束
+    fn fill_input(&mut self, end_frame: u64) {
+        while !self.inner_done && self.input_end() < end_frame {
+            for _ in 0..self.channels {
+                match self.inner.next() {
+                    Some(sample) => self.input.push_back(sample),
+                    None => {
+                        self.inner_done = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if self.inner_done {
+            // 丢弃末尾不完整的帧
+            let complete = self.input.len() - self.input.len() % self.channels;
+            self.input.truncate(complete);
+        }
+    }
+
+    fn discard_before(&mut self, frame: u64) {
+        if frame <= self.input_start {
+            return;
+        }
+        let available = (self.input.len() / self.channels) as u64;
+        let count = (frame - self.input_start).min(available);
+        self.input.drain(..count as usize * self.channels);
+        self.input_start += count;
+    }
+
+    fn sample(&self, frame: u64, channel: usize) -> f32 {
+        if frame < self.input_start {
+            return 0.0;
+        }
+        let index = (frame - self.input_start) as usize * self.channels + channel;
+        self.input.get(index).copied().unwrap_or(0.0)
+    }
+
+    fn mono(&self, frame: u64) -> f32 {
+        (0..self.channels)
+            .map(|channel| self.sample(frame, channel))
+            .sum::<f32>()
+    }
+
+    fn publish_position(&self) {
+        if self.control.generation.load(Ordering::Relaxed) != self.generation {
+            return;
+        }
+        let position_ms = (self.read_pos.max(0.0) * 1000.0 / self.sample_rate as f64) as u64;
+        self.control
+            .position_ms
+            .store(position_ms, Ordering::Relaxed);
+    }
+
+    fn refresh_settings(&mut self) {
+        // WSOLA 只在两个合成步之间切换参数，保证已合成的输出连续
+        match &self.wsola {
+            Some(wsola) if !wsola.pending.is_empty() => return,
+            None if self.frames_until_refresh > 0 => {
+                self.frames_until_refresh -= 1;
+                return;
+            }
+            _ => {}
+        }
+        self.frames_until_refresh = SETTINGS_REFRESH_FRAMES;
+
+        let rate = clamp_rate(f32::from_bits(
+            self.control.rate_bits.load(Ordering::Relaxed),
+        ));
+        let preserve_pitch = self.control.preserve_pitch.load(Ordering::Relaxed);
+        self.rate = rate as f64;
+        let use_wsola = preserve_pitch && (rate - 1.0).abs() > 1e-3;
+        match (self.wsola.take(), use_wsola) {
+            (Some(wsola), false) => {
+                // 从上一个合成帧内容的自然延续处继续，避免跳变
+                self.read_pos = wsola
+                    .prev_chosen
+                    .map(|chosen| (chosen + wsola.hop as u64) as f64)
+                    .unwrap_or(wsola.analysis_pos);
+            }
+            (Some(wsola), true) => self.wsola = Some(wsola),
+            (None, true) => {
+                self.wsola = Some(Wsola::new(self.sample_rate, self.channels, self.read_pos));
+            }
+            (None, false) => {}
+        }
+    }
+
+    /// 线性插值重采样；速度为 1 时逐帧原样输出
+    fn produce_resampled_frame(&mut self) -> bool {
+        let base = self.read_pos.floor();
+        let t = (self.read_pos - base) as f32;
+        let base = base as u64;
+        self.fill_input(base + 2);
+        let input_end = self.input_end();
+        if base >= input_end {
+            return false;
+        }
+        self.discard_before(base);
+        let next = if base + 1 < input_end { base + 1 } else { base };
+        for channel in 0..self.channels {
+            let a = self.sample(base, channel);
+            let b = self.sample(next, channel);
+            self.frame[channel] = a + (b - a) * t;
+        }
+        true
+    }
+
+    /// 在名义位置附近找与上一帧自然延续最相似的拼接点
+    fn best_offset(&self, wsola: &Wsola, nominal: u64, natural: u64) -> u64 {
+        let overlap_len = wsola.frame_len - wsola.hop;
+        let low = nominal
+            .saturating_sub(wsola.seek as u64)
+            .max(self.input_start);
+        let high = nominal + wsola.seek as u64;
+        let target: Vec<f32> = (0..overlap_len)
+            .step_by(WSOLA_CORRELATION_STRIDE)
+            .map(|i| self.mono(natural + i as u64))
+            .collect();
+        let region: Vec<f32> = (low..high + overlap_len as u64)
+            .map(|frame| self.mono(frame))
+            .collect();
+
+        let mut best = nominal;
+        let mut best_score = f32::MIN;
+        for candidate in low..=high {
+            let start = (candidate - low) as usize;
+            let (mut dot, mut energy) = (0.0f32, 0.0f32);
+            for (j, i) in (0..overlap_len)
+                .step_by(WSOLA_CORRELATION_STRIDE)
+                .enumerate()
+            {
+                let x = region[start + i];
+                dot += x * target[j];
+                energy += x * x;
+            }
+            let score = if energy > 0.0 {
+                dot / energy.sqrt()
+            } else {
+                0.0
+            };
+            if score > best_score {
+                best_score = score;
+                best = candidate;
+            }
+        }
+        best
+    }
+
+    /// 合成一个步长的输出；音源结束时返回 false
+    fn wsola_step(&mut self, wsola: &mut Wsola) -> bool {
+        let nominal = wsola.analysis_pos.max(0.0).round() as u64;
+        self.fill_input(nominal + (wsola.seek + wsola.frame_len) as u64 + 1);
+        if nominal >= self.input_end() {
+            return false;
+        }
+        let chosen = match wsola.prev_chosen {
+            Some(prev) => self.best_offset(wsola, nominal, prev + wsola.hop as u64),
+            None => nominal,
+        };
+
+        for i in 0..wsola.frame_len {
+            // 第一帧不做淡入，避免切换速度时音量下陷
+            let gain = if wsola.prev_chosen.is_none() && i < wsola.hop {
+                1.0
+            } else {
+                wsola.window[i]
+            };
+            for channel in 0..self.channels {
+                wsola.overlap[i * self.channels + channel] +=
+                    self.sample(chosen + i as u64, channel) * gain;
+            }
+        }
+        let ready = wsola.hop * self.channels;
+        wsola.pending.extend(wsola.overlap.drain(..ready));
+        wsola.overlap.resize(wsola.frame_len * self.channels, 0.0);
+        wsola.prev_chosen = Some(chosen);
+        wsola.analysis_pos += wsola.hop as f64 * self.rate;
+
+        let next_nominal = wsola.analysis_pos.max(0.0) as u64;
+        let keep_from = next_nominal
+            .saturating_sub(wsola.seek as u64)
+            .min(chosen + wsola.hop as u64);
+        self.discard_before(keep_from);
+        true
+    }
+
+    fn produce_wsola_frame(&mut self) -> bool {
+        let Some(mut wsola) = self.wsola.take() else {
+            return false;
+        };
+        let produced = wsola.pending.len() >= self.channels || self.wsola_step(&mut wsola);
+        if produced {
+            for channel in 0..self.channels {
+                self.frame[channel] = wsola.pending.pop_front().unwrap_or(0.0);
+            }
+        }
+        self.wsola = Some(wsola);
+        produced
+    }
+
+    fn produce_frame(&mut self) -> bool {
+        self.refresh_settings();
+        let produced = if self.wsola.is_some() {
+            self.produce_wsola_frame()
+        } else {
+            self.produce_resampled_frame()
+        };
+        if produced {
+            self.publish_position();
+            self.read_pos += self.rate;
+        }
+        produced
+    }
+}
+
+impl<S> Iterator for TimeStretch<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.frame_index >= self.frame.len() {
+            if !self.produce_frame() {
+                return None;
+            }
+            self.frame_index = 0;
+        }
+        let sample = self.frame[self.frame_index];
+        self.frame_index += 1;
+        Some(sample)
+    }
+}
+
+impl<S> Source for TimeStretch<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)?;
+        let frame = (pos.as_secs_f64() * self.sample_rate as f64).round();
+        self.input.clear();
+        self.input_start = frame as u64;
+        self.inner_done = false;
+        self.read_pos = frame;
+        if self.wsola.is_some() {
+            self.wsola = Some(Wsola::new(self.sample_rate, self.channels, frame));
+        }
+        // 补齐当前帧剩余声道，保持声道对齐
+        for sample in &mut self.frame[self.frame_index..] {
+            *sample = 0.0;
+        }
+        self.publish_position();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    const SAMPLE_RATE: u32 = 16_000;
+
+    fn sine(frequency: f32, seconds: f32, channels: u16) -> SamplesBuffer<f32> {
+        let frames = (SAMPLE_RATE as f32 * seconds) as usize;
+        let samples = (0..frames)
+            .flat_map(|i| {
+                let value =
+                    (2.0 * std::f32::consts::PI * frequency * i as f32 / SAMPLE_RATE as f32).sin();
+                std::iter::repeat_n(value, channels as usize)
+            })
+            .collect::<Vec<_>>();
+        SamplesBuffer::new(channels, SAMPLE_RATE, samples)
+    }
+
+    /// 以上升过零次数估计单声道信号的频率
+    fn estimated_frequency(samples: &[f32]) -> f32 {
+        let crossings = samples
+            .windows(2)
+            .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+            .count();
+        crossings as f32 * SAMPLE_RATE as f32 / samples.len() as f32
+    }
+
+    #[test]
+    fn normal_speed_passes_samples_through_unchanged() {
+        let state = PlaybackRateState::default();
+        let input: Vec<f32> = sine(440.0, 0.2, 2).collect();
+        let output: Vec<f32> = state.wrap(sine(440.0, 0.2, 2)).collect();
+        assert_eq!(output, input);
+        assert_eq!(state.position().as_millis(), 199);
+    }
+
+    #[test]
+    fn preserving_pitch_changes_duration_but_not_frequency() {
+        let state = PlaybackRateState::default();
+        state.set(2.0, true);
+        let output: Vec<f32> = state.wrap(sine(440.0, 2.0, 1)).collect();
+        let seconds = output.len() as f32 / SAMPLE_RATE as f32;
+        assert!((seconds - 1.0).abs() < 0.05, "output lasted {}s", seconds);
+        let frequency = estimated_frequency(&output);
+        assert!((frequency - 440.0).abs() < 20.0, "frequency {}", frequency);
+
+        state.set(0.5, true);
+        let output: Vec<f32> = state.wrap(sine(440.0, 1.0, 1)).collect();
+        let seconds = output.len() as f32 / SAMPLE_RATE as f32;
+        assert!((seconds - 2.0).abs() < 0.05, "output lasted {}s", seconds);
+        let frequency = estimated_frequency(&output);
+        assert!((frequency - 440.0).abs() < 20.0, "frequency {}", frequency);
+    }
+
+    #[test]
+    fn chipmunk_mode_shifts_pitch_with_speed() {
+        let state = PlaybackRateState::default();
+        state.set(2.0, false);
+        let output: Vec<f32> = state.wrap(sine(440.0, 2.0, 1)).collect();
+        let seconds = output.len() as f32 / SAMPLE_RATE as f32;
+        assert!((seconds - 1.0).abs() < 0.01, "output lasted {}s", seconds);
+        let frequency = estimated_frequency(&output);
+        assert!((frequency - 880.0).abs() < 20.0, "frequency {}", frequency);
+    }
+
+    #[test]
+    fn position_and_seek_stay_in_source_time() {
+        let state = PlaybackRateState::default();
+        state.set(2.0, true);
+        let mut source = state.wrap(sine(440.0, 4.0, 2));
+        // 1 秒输出对应 2 秒源时间
+        for _ in 0..SAMPLE_RATE * 2 {
+            source.next();
+        }
+        let position = state.position().as_millis() as i64;
+        assert!((position - 2000).abs() < 50, "position {}", position);
+
+        source.try_seek(Duration::from_millis(500)).unwrap();
+        assert_eq!(state.position().as_millis(), 500);
+        source.next();
+        source.next();
+        assert_eq!(state.position().as_millis(), 500);
+
+        // 新曲目接管位置，旧音源不再更新
+        let _next_track = state.wrap(sine(440.0, 1.0, 2));
+        for _ in 0..1000 {
+            source.next();
+        }
+        assert_eq!(state.position().as_millis(), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use tauri::menu::MenuBuilder;
+use tauri::menu::{MenuBuilder, SubmenuBuilder};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::Emitter;
 use tauri::Manager;
@@ -6,10 +6,15 @@ use tauri::{App, AppHandle};
 use tauri_plugin_window_state::{AppHandleExt, StateFlags};
 use tokio::sync::broadcast::Sender;
 
+use crate::history;
 use crate::music::MusicState;
 use crate::service;
+use crate::session;
+use crate::sleep_timer::{self, SleepTimerMode};
 
 pub fn quit_app(app: &AppHandle) {
+    session::save_session_on_quit(app);
+    history::finish_current_listen(app);
     if let Err(e) = app.save_window_state(StateFlags::all()) {
         eprintln!("Failed to save window state: {}", e);
     }
@@ -24,12 +29,22 @@ pub fn quit_app(app: &AppHandle) {
 /// set up the tray
 pub fn setup_tray(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
     // 使用 MenuBuilder 构建托盘菜单：播放控制、上一曲/下一曲、分隔符、显示/隐藏、退出
+    let sleep_menu = SubmenuBuilder::new(app, "Sleep Timer")
+        .text("sleep_15", "15 Minutes")
+        .text("sleep_30", "30 Minutes")
+        .text("sleep_60", "60 Minutes")
+        .text("sleep_end_of_track", "End of Current Track")
+        .separator()
+        .text("sleep_cancel", "Cancel Sleep Timer")
+        .build()?;
     let menu = MenuBuilder::new(app)
         .text("play", "Play")
         .text("pause", "Pause")
         .text("prev", "Previous")
         .text("next", "Next")
         .separator()
+        .item(&sleep_menu)
+        .separator()
         .text("show_hide", "Show / Hide")
         .separator()
         .text("quit", "Quit")
@@ -71,6 +86,18 @@ pub fn setup_tray(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
             "next" => {
                 let _ = app.emit("tray-next", ());
             }
+            "sleep_15" | "sleep_30" | "sleep_60" | "sleep_end_of_track" => {
+                let mode = match event.id.as_ref() {
+                    "sleep_15" => SleepTimerMode::Duration { minutes: 15 },
+                    "sleep_30" => SleepTimerMode::Duration { minutes: 30 },
+                    "sleep_60" => SleepTimerMode::Duration { minutes: 60 },
+                    _ => SleepTimerMode::EndOfTrack,
+                };
+                if let Err(e) = sleep_timer::start(app, mode, None) {
+                    eprintln!("Failed to start sleep timer: {}", e);
+                }
+            }
+            "sleep_cancel" => sleep_timer::cancel(app),
             "show_hide" => {
                 if let Some(window) = app.get_webview_window("main") {
                     match window.is_visible() {
@@ -0,0 +1,524 @@
+// 用户曲目元数据：评分、喜欢、播放/跳过次数、最近播放时间与自定义标签，保存在 app_data_dir/user-metadata.json
+// 本地曲目以 MusicFile::key 为键，在线曲目以网易云 id 为键，远程曲目以服务器 id + 曲目 id 为键；本地文件改名后通过内容指纹找回原记录
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+use crate::music::PlaybackTrack;
+use crate::{provider, rating_tags, storage};
+
+const USER_METADATA_FILE: &str = "user-metadata.json";
+const MAX_RATING: u8 = 5;
+const MAX_TAG_LEN: usize = 64;
+/// 指纹只取文件尾部的音频数据：写入 ID3v2 / FLAC 元数据块只会改动文件头，不影响指纹
+const FINGERPRINT_WINDOW: u64 = 64 * 1024;
+const ID3V1_TAG_LEN: u64 = 128;
+
+static USER_METADATA_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum TrackRef {
+    Local {
+        key: String,
+        /// 本地文件的绝对路径，用于计算内容指纹和写回评分标签
+        #[serde(default)]
+        path: Option<String>,
+    },
+    Online {
+        id: String,
+        /// 旧数据没有来源字段，视为网易云
+        #[serde(default = "provider::default_provider")]
+        provider: String,
+    },
+    Remote {
+        server_id: String,
+        id: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackUserMetadata {
+    pub rating: u8,
+    pub loved: bool,
+    pub play_count: u64,
+    pub skip_count: u64,
+    pub last_played_ms: Option<u64>,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserMetadataSettings {
+    /// 修改评分时同步写入文件的 POPM / FMPS_RATING 标签
+    pub sync_rating_tags: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserMetadataStore {
+    pub settings: UserMetadataSettings,
+    pub local: BTreeMap<String, TrackUserMetadata>,
+    /// 在线曲目，以歌曲缓存键为键（网易云为裸 id，其他来源为 "来源:id"）
+    pub online: BTreeMap<String, TrackUserMetadata>,
+    /// 远程服务器曲目，以 "服务器 id:曲目 id" 为键
+    pub remote: BTreeMap<String, TrackUserMetadata>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TrackUserMetadataUpdate {
+    pub rating: Option<u8>,
+    pub loved: Option<bool>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackUserMetadataResult {
+    pub track: TrackRef,
+    pub metadata: TrackUserMetadata,
+    /// 元数据已保存，但写回文件标签失败时的错误信息
+    pub tag_sync_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ListenOutcome {
+    Played { at_ms: u64 },
+    Skipped,
+}
+
+#[derive(Clone, Serialize)]
+struct UserMetadataChangedEvent {
+    track: TrackRef,
+    metadata: TrackUserMetadata,
+}
+
+impl TrackRef {
+    pub fn from_playback_track(track: &PlaybackTrack, local_path: Option<&str>) -> Self {
+        match track {
+            // CUE 分轨共用整轨文件，不关联路径，避免评分标签写进整张专辑、指纹互相串号
+            PlaybackTrack::Local { file } => TrackRef::Local {
+                key: file.key.clone(),
+                path: local_path
+                    .filter(|_| file.cue.is_none())
+                    .map(ToOwned::to_owned),
+            },
+            PlaybackTrack::Online { song } => TrackRef::Online {
+                id: song.id.clone(),
+                provider: song.provider.clone(),
+            },
+            PlaybackTrack::Remote { song } => TrackRef::Remote {
+                server_id: song.server_id.clone(),
+                id: song.song.id.clone(),
+            },
+        }
+    }
+
+    fn local_path(&self) -> Option<&Path> {
+        match self {
+            TrackRef::Local {
+                path: Some(path), ..
+            } if !path.trim().is_empty() => Some(Path::new(path)),
+            _ => None,
+        }
+    }
+}
+
+/// 文件尾部（去掉 ID3v1 标签）最多 64 KiB 的 SHA-256
+pub fn content_fingerprint(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+    let mut end = file
+        .metadata()
+        .map_err(|e| format!("stat {}: {}", path.display(), e))?
+        .len();
+
+    if end >= ID3V1_TAG_LEN {
+        let mut marker = [0u8; 3];
+        file.seek(SeekFrom::Start(end - ID3V1_TAG_LEN))
+            .and_then(|_| file.read_exact(&mut marker))
+            .map_err(|e| format!("read {}: {}", path.display(), e))?;
+        if &marker == b"TAG" {
+            end -= ID3V1_TAG_LEN;
+        }
+    }
+
+    let start = end.saturating_sub(FINGERPRINT_WINDOW);
+    let mut window = vec![0u8; (end - start) as usize];
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut window))
+        .map_err(|e| format!("read {}: {}", path.display(), e))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(window);
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag: String = tag.trim().chars().take(MAX_TAG_LEN).collect();
+        if !tag.is_empty()
+            && !normalized
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(&tag))
+        {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// 本地曲目的键不存在时，尝试用内容指纹匹配原路径已失效的旧记录，并迁移到新键下
+fn relink_renamed_entry(
+    store: &mut UserMetadataStore,
+    key: &str,
+    path: &Path,
+    fingerprint: impl Fn(&Path) -> Result<String, String>,
+) -> bool {
+    if store.local.contains_key(key) {
+        return false;
+    }
+    let orphaned: Vec<(String, String)> = store
+        .local
+        .iter()
+        .filter_map(|(old_key, metadata)| {
+            let hash = metadata.content_hash.as_ref()?;
+            let old_path = metadata.path.as_ref()?;
+            (!Path::new(old_path).exists()).then(|| (old_key.clone(), hash.clone()))
+        })
+        .collect();
+    if orphaned.is_empty() {
+        return false;
+    }
+
+    let Ok(hash) = fingerprint(path) else {
+        return false;
+    };
+    let Some((old_key, _)) = orphaned.into_iter().find(|(_, old_hash)| *old_hash == hash) else {
+        return false;
+    };
+    let Some(mut metadata) = store.local.remove(&old_key) else {
+        return false;
+    };
+    metadata.path = Some(path.to_string_lossy().to_string());
+    store.local.insert(key.to_string(), metadata);
+    true
+}
+
+fn lookup(store: &mut UserMetadataStore, track: &TrackRef) -> (Option<TrackUserMetadata>, bool) {
+    match track {
+        TrackRef::Local { key, .. } => {
+            let relinked = track
+                .local_path()
+                .is_some_and(|path| relink_renamed_entry(store, key, path, content_fingerprint));
+            (store.local.get(key).cloned(), relinked)
+        }
+        TrackRef::Online { id, provider } => {
+            (store.online.get(&online_key(provider, id)).cloned(), false)
+        }
+        TrackRef::Remote { server_id, id } => {
+            (store.remote.get(&remote_key(server_id, id)).cloned(), false)
+        }
+    }
+}
+
+/// 取出（必要时创建）曲目记录；本地曲目顺带补全路径与内容指纹
+fn entry_mut<'a>(store: &'a mut UserMetadataStore, track: &TrackRef) -> &'a mut TrackUserMetadata {
+    match track {
+        TrackRef::Local { key, .. } => {
+            if let Some(path) = track.local_path() {
+                relink_renamed_entry(store, key, path, content_fingerprint);
+            }
+            let metadata = store.local.entry(key.clone()).or_default();
+            if let Some(path) = track.local_path() {
+                let path_string = path.to_string_lossy().to_string();
+                if metadata.content_hash.is_none() || metadata.path.as_deref() != Some(&path_string)
+                {
+                    metadata.content_hash = content_fingerprint(path).ok();
+                }
+                metadata.path = Some(path_string);
+            }
+            metadata
+        }
+        TrackRef::Online { id, provider } => {
+            store.online.entry(online_key(provider, id)).or_default()
+        }
+        TrackRef::Remote { server_id, id } => {
+            store.remote.entry(remote_key(server_id, id)).or_default()
+        }
+    }
+}
+
+/// 在线曲目的键与歌曲缓存键一致：网易云沿用裸 id（兼容旧数据），其他来源加前缀
+pub(crate) fn online_key(provider: &str, id: &str) -> String {
+    provider::song_cache_key(provider, id)
+}
+
+pub(crate) fn remote_key(server_id: &str, id: &str) -> String {
+    format!("{}:{}", server_id, id)
+}
+
+/// 返回评分是否发生变化
+fn apply_update(
+    metadata: &mut TrackUserMetadata,
+    update: TrackUserMetadataUpdate,
+) -> Result<bool, String> {
+    let mut rating_changed = false;
+    if let Some(rating) = update.rating {
+        if rating > MAX_RATING {
+            return Err(format!("Rating must be between 0 and {}", MAX_RATING));
+        }
+        rating_changed = metadata.rating != rating;
+        metadata.rating = rating;
+    }
+    if let Some(loved) = update.loved {
+        metadata.loved = loved;
+    }
+    if let Some(tags) = update.tags {
+        metadata.tags = normalize_tags(tags);
+    }
+    Ok(rating_changed)
+}
+
+fn apply_listen(metadata: &mut TrackUserMetadata, outcome: ListenOutcome) {
+    match outcome {
+        ListenOutcome::Played { at_ms } => {
+            metadata.play_count = metadata.play_count.saturating_add(1);
+            metadata.last_played_ms = Some(metadata.last_played_ms.unwrap_or(0).max(at_ms));
+        }
+        ListenOutcome::Skipped => {
+            metadata.skip_count = metadata.skip_count.saturating_add(1);
+        }
+    }
+}
+
+/// 加锁读改写；closure 返回 true 表示需要写回
+fn with_store<T>(
+    app_handle: &AppHandle,
+    f: impl FnOnce(&mut UserMetadataStore) -> Result<(T, bool), String>,
+) -> Result<T, String> {
+    let _guard = USER_METADATA_LOCK
+        .lock()
+        .map_err(|_| "user metadata lock poisoned".to_string())?;
+    let path = storage::app_data_file(app_handle, USER_METADATA_FILE)?;
+    let mut store: UserMetadataStore = storage::read_json(&path)?;
+    let (value, changed) = f(&mut store)?;
+    if changed {
+        storage::write_json(&path, &store)?;
+    }
+    Ok(value)
+}
+
+fn emit_changed(app_handle: &AppHandle, track: &TrackRef, metadata: &TrackUserMetadata) {
+    let _ = app_handle.emit(
+        "user-metadata-changed",
+        UserMetadataChangedEvent {
+            track: track.clone(),
+            metadata: metadata.clone(),
+        },
+    );
+}
+
+/// 收听会话结束时更新播放/跳过次数，由播放历史模块调用
+pub fn record_listen(app_handle: &AppHandle, track: &TrackRef, outcome: ListenOutcome) {
+    let result = with_store(app_handle, |store| {
+        let metadata = entry_mut(store, track);
+        apply_listen(metadata, outcome);
+        Ok((metadata.clone(), true))
+    });
+    match result {
+        Ok(metadata) => emit_changed(app_handle, track, &metadata),
+        Err(e) => eprintln!("Failed to record listen in user metadata: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn get_track_user_metadata(
+    app_handle: AppHandle,
+    track: TrackRef,
+) -> Result<TrackUserMetadata, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        with_store(&app_handle, |store| {
+            let (metadata, relinked) = lookup(store, &track);
+            Ok((metadata.unwrap_or_default(), relinked))
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to join user metadata task: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_all_user_metadata(app_handle: AppHandle) -> Result<UserMetadataStore, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        with_store(&app_handle, |store| Ok((store.clone(), false)))
+    })
+    .await
+    .map_err(|e| format!("Failed to join user metadata task: {}", e))?
+}
+
+#[tauri::command]
+pub async fn update_track_user_metadata(
+    app_handle: AppHandle,
+    track: TrackRef,
+    update: TrackUserMetadataUpdate,
+) -> Result<TrackUserMetadataResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (mut metadata, sync_rating) = with_store(&app_handle, |store| {
+            let sync_enabled = store.settings.sync_rating_tags;
+            let metadata = entry_mut(store, &track);
+            let rating_changed = apply_update(metadata, update)?;
+            Ok(((metadata.clone(), sync_enabled && rating_changed), true))
+        })?;
+
+        let mut tag_sync_error = None;
+        if let (true, Some(path)) = (sync_rating, track.local_path()) {
+            match rating_tags::write_rating(path, metadata.rating) {
+                // 部分格式（如 WAV 的 id3 块）写在文件末尾，写入后需要刷新指纹
+                Ok(()) => {
+                    let content_hash = content_fingerprint(path).ok();
+                    metadata = with_store(&app_handle, |store| {
+                        let metadata = entry_mut(store, &track);
+                        metadata.content_hash = content_hash;
+                        Ok((metadata.clone(), true))
+                    })?;
+                }
+                Err(e) => tag_sync_error = Some(e),
+            }
+        }
+
+        emit_changed(&app_handle, &track, &metadata);
+        Ok(TrackUserMetadataResult {
+            track,
+            metadata,
+            tag_sync_error,
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to join user metadata task: {}", e))?
+}
+
+#[tauri::command]
+pub fn get_user_metadata_settings(app_handle: AppHandle) -> Result<UserMetadataSettings, String> {
+    with_store(&app_handle, |store| Ok((store.settings.clone(), false)))
+}
+
+#[tauri::command]
+pub fn set_user_metadata_settings(
+    app_handle: AppHandle,
+    settings: UserMetadataSettings,
+) -> Result<(), String> {
+    with_store(&app_handle, |store| {
+        store.settings = settings;
+        Ok(((), true))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_test_dir(prefix: &str) -> std::path::PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "rmusic-{}-{}-{}",
+            prefix,
+            std::process::id(),
+            unique
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn fingerprint_ignores_header_and_id3v1_changes() {
+        let dir = unique_test_dir("fingerprint");
+        let audio = vec![42u8; 100 * 1024];
+        let plain = dir.join("plain.mp3");
+        fs::write(&plain, &audio).unwrap();
+
+        let tagged = dir.join("tagged.mp3");
+        let mut bytes = b"ID3 header bytes".to_vec();
+        bytes.extend_from_slice(&audio);
+        bytes.extend_from_slice(b"TAG");
+        bytes.extend_from_slice(&[0u8; 125]);
+        fs::write(&tagged, &bytes).unwrap();
+
+        assert_eq!(
+            content_fingerprint(&plain).unwrap(),
+            content_fingerprint(&tagged).unwrap()
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn renamed_local_track_keeps_its_metadata() {
+        let dir = unique_test_dir("user-meta-rename");
+        let old_path = dir.join("old.mp3");
+        let new_path = dir.join("new.mp3");
+        fs::write(&old_path, b"same audio").unwrap();
+
+        let mut store = UserMetadataStore::default();
+        let old_track = TrackRef::Local {
+            key: "old".into(),
+            path: Some(old_path.to_string_lossy().to_string()),
+        };
+        let metadata = entry_mut(&mut store, &old_track);
+        apply_update(
+            metadata,
+            TrackUserMetadataUpdate {
+                rating: Some(4),
+                loved: Some(true),
+                tags: Some(vec![" chill ".into(), "Chill".into(), "".into()]),
+            },
+        )
+        .unwrap();
+        assert_eq!(store.local["old"].tags, vec!["chill".to_string()]);
+
+        fs::rename(&old_path, &new_path).unwrap();
+        let new_track = TrackRef::Local {
+            key: "new".into(),
+            path: Some(new_path.to_string_lossy().to_string()),
+        };
+        let (metadata, relinked) = lookup(&mut store, &new_track);
+        assert!(relinked);
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata.rating, 4);
+        assert!(metadata.loved);
+        assert!(!store.local.contains_key("old"));
+
+        apply_listen(
+            entry_mut(&mut store, &new_track),
+            ListenOutcome::Played { at_ms: 10 },
+        );
+        apply_listen(entry_mut(&mut store, &new_track), ListenOutcome::Skipped);
+        assert_eq!(store.local["new"].play_count, 1);
+        assert_eq!(store.local["new"].skip_count, 1);
+        assert_eq!(store.local["new"].last_played_ms, Some(10));
+
+        assert!(apply_update(
+            entry_mut(&mut store, &new_track),
+            TrackUserMetadataUpdate {
+                rating: Some(6),
+                ..Default::default()
+            }
+        )
+        .is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,409 @@
+// 可视化数据：播放链中的旁路 Source 采集单声道采样，有订阅者时按约 30 fps 推送频谱分段与 RMS/峰值电平
+
+use rodio::source::SeekError;
+use rodio::Source;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+const FFT_SIZE: usize = 2048;
+const VISUALIZER_FRAME_INTERVAL: Duration = Duration::from_millis(33);
+const DEFAULT_BAND_COUNT: usize = 32;
+const MAX_BAND_COUNT: usize = 128;
+const MIN_BAND_HZ: f32 = 20.0;
+const MAX_BAND_HZ: f32 = 16_000.0;
+/// 频谱归一化的下限，低于它的分量显示为 0
+const MIN_DB: f32 = -80.0;
+/// 旁路每攒够这么多帧才加锁写入一次共享缓冲
+const TAP_FLUSH_FRAMES: usize = 256;
+/// 旁路每隔这么多采样检查一次是否有订阅者，没有订阅者时只做计数
+const TAP_CHECK_SAMPLES: usize = 4096;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VisualizerFrame {
+    /// 对数分布的频段能量，0-1
+    pub bands: Vec<f32>,
+    /// 本帧间隔内的 RMS 与峰值（线性幅度）
+    pub rms: f32,
+    pub peak: f32,
+}
+
+struct SampleWindow {
+    ring: VecDeque<f32>,
+    sample_rate: u32,
+    /// 上次计算之后新到的帧数
+    fresh: usize,
+    /// 暂停后已经推送过一帧静音
+    idle_sent: bool,
+}
+
+struct VisualizerShared {
+    subscribers: AtomicUsize,
+    band_count: AtomicUsize,
+    window: StdMutex<SampleWindow>,
+    wake: Notify,
+}
+
+#[derive(Clone)]
+pub struct VisualizerState(Arc<VisualizerShared>);
+
+impl Default for VisualizerState {
+    fn default() -> Self {
+        Self(Arc::new(VisualizerShared {
+            subscribers: AtomicUsize::new(0),
+            band_count: AtomicUsize::new(DEFAULT_BAND_COUNT),
+            window: StdMutex::new(SampleWindow {
+                ring: VecDeque::with_capacity(FFT_SIZE),
+                sample_rate: 44_100,
+                fresh: 0,
+                idle_sent: true,
+            }),
+            wake: Notify::new(),
+        }))
+    }
+}
+
+impl VisualizerState {
+    pub fn tap<S>(&self, source: S) -> VisualizerTap<S>
+    where
+        S: Source<Item = f32>,
+    {
+        let channels = (source.channels() as usize).max(1);
+        VisualizerTap {
+            inner: source,
+            shared: Arc::clone(&self.0),
+            channels,
+            channel: 0,
+            frame_sum: 0.0,
+            pending: Vec::with_capacity(TAP_FLUSH_FRAMES),
+            active: false,
+            samples_until_check: 0,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.0.subscribers.load(Ordering::Relaxed) > 0
+    }
+
+    /// 取出一帧可视化数据；暂停（没有新采样）时只推送一次静音帧
+    fn take_frame(&self) -> Option<VisualizerFrame> {
+        let band_count = self.0.band_count.load(Ordering::Relaxed);
+        let (samples, fresh, sample_rate) = {
+            let mut window = self.0.window.lock().ok()?;
+            if window.fresh == 0 {
+                if window.idle_sent {
+                    return None;
+                }
+                window.idle_sent = true;
+                return Some(VisualizerFrame {
+                    bands: vec![0.0; band_count],
+                    rms: 0.0,
+                    peak: 0.0,
+                });
+            }
+            let fresh = window.fresh.min(window.ring.len());
+            window.fresh = 0;
+            window.idle_sent = false;
+            (
+                window.ring.iter().copied().collect::<Vec<_>>(),
+                fresh,
+                window.sample_rate,
+            )
+        };
+
+        let (rms, peak) = levels(&samples[samples.len() - fresh..]);
+        Some(VisualizerFrame {
+            bands: spectrum_bands(&samples, sample_rate, band_count),
+            rms,
+            peak,
+        })
+    }
+}
+
+pub struct VisualizerTap<S> {
+    inner: S,
+    shared: Arc<VisualizerShared>,
+    channels: usize,
+    channel: usize,
+    frame_sum: f32,
+    pending: Vec<f32>,
+    active: bool,
+    samples_until_check: usize,
+}
+
+impl<S> VisualizerTap<S>
+where
+    S: Source<Item = f32>,
+{
+    fn flush(&mut self) {
+        let sample_rate = self.inner.sample_rate();
+        if let Ok(mut window) = self.shared.window.lock() {
+            window.sample_rate = sample_rate;
+            window.fresh += self.pending.len();
+            window.ring.extend(self.pending.drain(..));
+            let overflow = window.ring.len().saturating_sub(FFT_SIZE);
+            window.ring.drain(..overflow);
+        }
+        self.pending.clear();
+    }
+}
+
+impl<S> Iterator for VisualizerTap<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next()?;
+        if self.samples_until_check == 0 {
+            self.samples_until_check = TAP_CHECK_SAMPLES;
+            self.active = self.shared.subscribers.load(Ordering::Relaxed) > 0;
+            if !self.active {
+                self.pending.clear();
+            }
+        } else {
+            self.samples_until_check -= 1;
+        }
+
+        if self.active {
+            self.frame_sum += sample;
+        }
+        self.channel += 1;
+        if self.channel == self.channels {
+            self.channel = 0;
+            if self.active {
+                self.pending.push(self.frame_sum / self.channels as f32);
+                self.frame_sum = 0.0;
+                if self.pending.len() >= TAP_FLUSH_FRAMES {
+                    self.flush();
+                }
+            }
+        }
+        Some(sample)
+    }
+}
+
+impl<S> Source for VisualizerTap<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
+
+/// 原地基 2 FFT，长度必须是 2 的幂
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        let (w_im, w_re) = angle.sin_cos();
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0f32, 0.0f32);
+            for k in 0..len / 2 {
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+fn levels(samples: &[f32]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let (sum, peak) = samples
+        .iter()
+        .fold((0.0f32, 0.0f32), |(sum, peak), sample| {
+            (sum + sample * sample, peak.max(sample.abs()))
+        });
+    ((sum / samples.len() as f32).sqrt(), peak)
+}
+
+/// 对最近 FFT_SIZE 个采样加 Hann 窗做 FFT，按对数频段取最大幅度并归一化到 0-1
+fn spectrum_bands(samples: &[f32], sample_rate: u32, band_count: usize) -> Vec<f32> {
+    let mut re = vec![0.0f32; FFT_SIZE];
+    let mut im = vec![0.0f32; FFT_SIZE];
+    let offset = FFT_SIZE - samples.len().min(FFT_SIZE);
+    for (i, sample) in samples.iter().rev().take(FFT_SIZE).rev().enumerate() {
+        let index = offset + i;
+        let window = 0.5 - 0.5 * (2.0 * PI * index as f32 / FFT_SIZE as f32).cos();
+        re[index] = sample * window;
+    }
+    fft(&mut re, &mut im);
+
+    let nyquist = sample_rate as f32 / 2.0;
+    let max_hz = MAX_BAND_HZ.min(nyquist);
+    let bin_hz = sample_rate as f32 / FFT_SIZE as f32;
+    // Hann 窗的相干增益为 0.5，单边谱再乘 2
+    let scale = 4.0 / FFT_SIZE as f32;
+    (0..band_count)
+        .map(|band| {
+            let low = MIN_BAND_HZ * (max_hz / MIN_BAND_HZ).powf(band as f32 / band_count as f32);
+            let high =
+                MIN_BAND_HZ * (max_hz / MIN_BAND_HZ).powf((band + 1) as f32 / band_count as f32);
+            let first = ((low / bin_hz).floor() as usize).clamp(1, FFT_SIZE / 2 - 1);
+            let last = ((high / bin_hz).ceil() as usize).clamp(first + 1, FFT_SIZE / 2);
+            let magnitude = (first..last)
+                .map(|bin| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt() * scale)
+                .fold(0.0f32, f32::max);
+            let db = 20.0 * magnitude.max(1e-9).log10();
+            ((db - MIN_DB) / -MIN_DB).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+pub fn start_visualizer(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app_handle
+            .try_state::<VisualizerState>()
+            .map(|state| state.inner().clone())
+        else {
+            return;
+        };
+        loop {
+            if !state.is_active() {
+                // 没有订阅者时挂起，不占用任何计算
+                state.0.wake.notified().await;
+                continue;
+            }
+            tokio::time::sleep(VISUALIZER_FRAME_INTERVAL).await;
+            if let Some(frame) = state.take_frame() {
+                let _ = app_handle.emit("visualizer-frame", frame);
+            }
+        }
+    });
+}
+
+/// 开始接收 visualizer-frame 事件；每次订阅都需要对应一次取消订阅
+#[tauri::command]
+pub fn subscribe_visualizer(state: tauri::State<'_, VisualizerState>, bands: Option<usize>) {
+    if let Some(bands) = bands {
+        state
+            .0
+            .band_count
+            .store(bands.clamp(1, MAX_BAND_COUNT), Ordering::Relaxed);
+    }
+    state.0.subscribers.fetch_add(1, Ordering::Relaxed);
+    state.0.wake.notify_one();
+}
+
+#[tauri::command]
+pub fn unsubscribe_visualizer(state: tauri::State<'_, VisualizerState>) {
+    let _ = state
+        .0
+        .subscribers
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+            count.checked_sub(1)
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn sine(frequency: f32, sample_rate: u32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (2.0 * PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn spectrum_peaks_in_the_band_containing_the_tone() {
+        let sample_rate = 44_100;
+        let bands = spectrum_bands(&sine(1_000.0, sample_rate, FFT_SIZE), sample_rate, 32);
+        let loudest = bands
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(index, _)| index)
+            .unwrap();
+        let low = MIN_BAND_HZ * (MAX_BAND_HZ / MIN_BAND_HZ).powf(loudest as f32 / 32.0);
+        let high = MIN_BAND_HZ * (MAX_BAND_HZ / MIN_BAND_HZ).powf((loudest + 1) as f32 / 32.0);
+        assert!(low <= 1_000.0 && 1_000.0 <= high, "{}..{}", low, high);
+        assert!(bands[loudest] > 0.9);
+        assert!(bands[2] < 0.3);
+
+        let (rms, peak) = levels(&sine(1_000.0, sample_rate, FFT_SIZE));
+        assert!((rms - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+        assert!((peak - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn tap_passes_audio_through_and_only_collects_with_subscribers() {
+        let state = VisualizerState::default();
+        let samples: Vec<f32> = sine(440.0, 8_000, 4_000)
+            .into_iter()
+            .flat_map(|sample| [sample, sample])
+            .collect();
+
+        let output: Vec<f32> = state
+            .tap(SamplesBuffer::new(2, 8_000, samples.clone()))
+            .collect();
+        assert_eq!(output, samples);
+        assert!(state.take_frame().is_none());
+
+        state.0.subscribers.store(1, Ordering::Relaxed);
+        let output: Vec<f32> = state
+            .tap(SamplesBuffer::new(2, 8_000, samples.clone()))
+            .collect();
+        assert_eq!(output, samples);
+        let frame = state.take_frame().unwrap();
+        assert_eq!(frame.bands.len(), DEFAULT_BAND_COUNT);
+        assert!(frame.peak > 0.9);
+
+        // 暂停后推送一帧静音，之后不再推送
+        let idle = state.take_frame().unwrap();
+        assert_eq!(idle.peak, 0.0);
+        assert!(state.take_frame().is_none());
+    }
+}
@@ -0,0 +1,284 @@
+// 进度条波形：后台解码整首曲目（CUE 分轨只解码所在区间）生成 min/max 峰值数组，按曲目 key + 修改时间缓存到磁盘
+
+use rodio::{Decoder, Source};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::file::{modified_ms, path_key};
+use crate::music::{cached_online_audio_path, PlaybackRange, RangedSource};
+use crate::storage::{read_json, write_json};
+
+const MIN_RESOLUTION: usize = 16;
+const MAX_RESOLUTION: usize = 4096;
+/// 解码时先按固定帧数聚合成小块，再按请求的分辨率重新分桶，避免事先知道总长度
+const BLOCK_FRAMES: usize = 256;
+const WAVEFORM_CACHE_VERSION: &str = "v1";
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WaveformSource {
+    /// 本地文件，缓存 key 与 MusicFile::key 一致
+    Local {
+        path: String,
+        /// CUE 分轨在整轨文件中的区间
+        #[serde(default)]
+        range: Option<PlaybackRange>,
+    },
+    /// 已完整缓存的在线曲目
+    Online { cache_key: String },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct WaveformPeaks {
+    pub duration_ms: u64,
+    /// 每个桶的 [min, max]，按 -127..127 量化
+    pub peaks: Vec<[i8; 2]>,
+}
+
+fn waveform_cache_path(
+    app_handle: &AppHandle,
+    key: &str,
+    modified_ms: u64,
+    resolution: usize,
+) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("unable to get app cache dir: {}", e))?
+        .join("waveforms");
+    let mut hasher = Sha1::new();
+    hasher.update(
+        format!(
+            "{}:{}:{}:{}",
+            WAVEFORM_CACHE_VERSION, key, modified_ms, resolution
+        )
+        .as_bytes(),
+    );
+    Ok(dir.join(format!("{:x}.json", hasher.finalize())))
+}
+
+/// CUE 分轨共用整轨文件，缓存 key 带上区间，各分轨分别缓存
+fn ranged_key(key: String, range: Option<PlaybackRange>) -> String {
+    match range {
+        Some(range) => format!(
+            "{}#{}-{}",
+            key,
+            range.start_ms,
+            range.end_ms.map(|end| end.to_string()).unwrap_or_default()
+        ),
+        None => key,
+    }
+}
+
+fn resolve_source(
+    app_handle: &AppHandle,
+    source: &WaveformSource,
+) -> Result<(PathBuf, String), String> {
+    match source {
+        WaveformSource::Local { path, range } => {
+            let path = PathBuf::from(path);
+            if !path.is_file() {
+                return Err(format!("audio file not found: {}", path.display()));
+            }
+            let key = ranged_key(path_key(&path), *range);
+            Ok((path, key))
+        }
+        WaveformSource::Online { cache_key } => cached_online_audio_path(app_handle, cache_key)
+            .map(|path| (path, format!("online:{}", cache_key)))
+            .ok_or_else(|| "online track is not cached yet".to_string()),
+    }
+}
+
+fn quantize(sample: f32) -> i8 {
+    (sample.clamp(-1.0, 1.0) * 127.0).round() as i8
+}
+
+/// 把解码出的交错采样聚合成 min/max 峰值；所有声道合并到同一组峰值
+fn compute_peaks<S>(source: S, resolution: usize) -> WaveformPeaks
+where
+    S: Source<Item = f32>,
+{
+    let channels = (source.channels() as usize).max(1);
+    let sample_rate = source.sample_rate().max(1) as u64;
+    let block_samples = BLOCK_FRAMES * channels;
+
+    let mut blocks: Vec<(f32, f32)> = Vec::new();
+    let mut current = (f32::MAX, f32::MIN);
+    let mut in_block = 0;
+    let mut total_samples: u64 = 0;
+    for sample in source {
+        current.0 = current.0.min(sample);
+        current.1 = current.1.max(sample);
+        in_block += 1;
+        total_samples += 1;
+        if in_block == block_samples {
+            blocks.push(current);
+            current = (f32::MAX, f32::MIN);
+            in_block = 0;
+        }
+    }
+    if in_block > 0 {
+        blocks.push(current);
+    }
+
+    let duration_ms = total_samples / channels as u64 * 1000 / sample_rate;
+    if blocks.is_empty() {
+        return WaveformPeaks {
+            duration_ms,
+            peaks: vec![[0, 0]; resolution],
+        };
+    }
+
+    let peaks = (0..resolution)
+        .map(|bucket| {
+            let start = bucket * blocks.len() / resolution;
+            let end = ((bucket + 1) * blocks.len() / resolution).max(start + 1);
+            let (min, max) = blocks[start..end.min(blocks.len())]
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(min, max), block| {
+                    (min.min(block.0), max.max(block.1))
+                });
+            [quantize(min), quantize(max)]
+        })
+        .collect();
+    WaveformPeaks { duration_ms, peaks }
+}
+
+fn decode_peaks(
+    path: &Path,
+    range: Option<PlaybackRange>,
+    resolution: usize,
+) -> Result<WaveformPeaks, String> {
+    let file =
+        File::open(path).map_err(|e| format!("open audio file error {}: {}", path.display(), e))?;
+    let decoder = Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("decode audio file error: {}", e))?;
+    let samples = decoder.convert_samples();
+    match range {
+        Some(range) => Ok(compute_peaks(
+            RangedSource::new(samples, range)?,
+            resolution,
+        )),
+        None => Ok(compute_peaks(samples, resolution)),
+    }
+}
+
+/// 返回曲目的波形峰值；解码在阻塞线程池中进行，不影响播放
+#[tauri::command]
+pub async fn get_waveform_peaks(
+    app_handle: AppHandle,
+    source: WaveformSource,
+    resolution: usize,
+) -> Result<WaveformPeaks, String> {
+    let resolution = resolution.clamp(MIN_RESOLUTION, MAX_RESOLUTION);
+    let (path, key) = resolve_source(&app_handle, &source)?;
+    let range = match source {
+        WaveformSource::Local { range, .. } => range,
+        WaveformSource::Online { .. } => None,
+    };
+    let cache_path = waveform_cache_path(&app_handle, &key, modified_ms(&path), resolution)?;
+
+    tokio::task::spawn_blocking(move || {
+        if cache_path.exists() {
+            match read_json::<WaveformPeaks>(&cache_path) {
+                Ok(peaks) if peaks.peaks.len() == resolution => return Ok(peaks),
+                Ok(_) => {}
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+        let peaks = decode_peaks(&path, range, resolution)?;
+        if let Err(error) = write_json(&cache_path, &peaks) {
+            eprintln!("write waveform cache: {}", error);
+        }
+        Ok(peaks)
+    })
+    .await
+    .map_err(|e| format!("waveform task failed: {}", e))?
+}
+
+/// 清空波形缓存，随在线音频缓存一起清理
+pub(crate) fn clear_waveform_cache(app_handle: &AppHandle) -> Result<(), String> {
+    let dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("unable to get app cache dir: {}", e))?
+        .join("waveforms");
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("clear waveform cache: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn peaks_follow_signal_envelope() {
+        // 前半段静音，后半段满幅方波，双声道
+        let frames = BLOCK_FRAMES * 64;
+        let samples: Vec<f32> = (0..frames)
+            .flat_map(|frame| {
+                let value = if frame < frames / 2 {
+                    0.0
+                } else if frame % 2 == 0 {
+                    1.0
+                } else {
+                    -1.0
+                };
+                [value, value]
+            })
+            .collect();
+        let peaks = compute_peaks(SamplesBuffer::new(2, 8_000, samples), 16);
+
+        assert_eq!(peaks.peaks.len(), 16);
+        assert_eq!(peaks.duration_ms, frames as u64 * 1000 / 8_000);
+        assert!(peaks.peaks[..8].iter().all(|peak| *peak == [0, 0]));
+        assert!(peaks.peaks[8..].iter().all(|peak| *peak == [-127, 127]));
+    }
+
+    #[test]
+    fn short_tracks_still_fill_every_bucket() {
+        let peaks = compute_peaks(SamplesBuffer::new(1, 8_000, vec![0.5f32; 100]), 32);
+        assert_eq!(peaks.peaks.len(), 32);
+        assert!(peaks.peaks.iter().all(|peak| *peak == [64, 64]));
+
+        let empty = compute_peaks(SamplesBuffer::new(1, 8_000, Vec::<f32>::new()), 32);
+        assert_eq!(empty.peaks, vec![[0, 0]; 32]);
+    }
+
+    #[test]
+    fn cue_tracks_only_cover_their_range() {
+        // 第 1 秒静音，第 2 秒满幅；分轨从 1000ms 开始、到 2000ms 结束
+        let mut samples = vec![0.0f32; 8_000];
+        samples.extend(vec![1.0f32; 8_000]);
+        samples.extend(vec![0.0f32; 8_000]);
+        let range = PlaybackRange {
+            start_ms: 1_000,
+            end_ms: Some(2_000),
+        };
+        let source = RangedSource::new(SamplesBuffer::new(1, 8_000, samples), range).unwrap();
+        let peaks = compute_peaks(source, 16);
+        assert_eq!(peaks.duration_ms, 1_000);
+        assert!(peaks.peaks.iter().all(|peak| *peak == [127, 127]));
+
+        let key = ranged_key("album.flac".to_string(), Some(range));
+        assert_eq!(key, "album.flac#1000-2000");
+        assert_ne!(
+            key,
+            ranged_key(
+                "album.flac".to_string(),
+                Some(PlaybackRange {
+                    start_ms: 2_000,
+                    end_ms: None,
+                })
+            )
+        );
+        assert_eq!(ranged_key("song.mp3".to_string(), None), "song.mp3");
+    }
+}
@@ -0,0 +1,34 @@
+import type {
+  ChannelOptions,
+  OutputDeviceInfo,
+  OutputDeviceStatus,
+} from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function listOutputDevices(): Promise<OutputDeviceInfo[]> {
+  return await invokeCommand("list_output_devices");
+}
+
+export async function getOutputDevice(): Promise<OutputDeviceStatus> {
+  return await invokeCommand("get_output_device");
+}
+
+// device 为 null 时跟随系统默认设备
+export async function setOutputDevice(device: string | null): Promise<OutputDeviceStatus> {
+  return await invokeCommand("set_output_device", { device });
+}
+
+export async function getChannelOptions(): Promise<ChannelOptions> {
+  return await invokeCommand("get_channel_options");
+}
+
+// 未传入的字段保持不变，修改即时生效并持久化
+export async function setChannelOptions(
+  options: Partial<ChannelOptions>
+): Promise<ChannelOptions> {
+  return await invokeCommand("set_channel_options", {
+    balance: options.balance ?? null,
+    mono: options.mono ?? null,
+    swapChannels: options.swap_channels ?? null,
+  });
+}
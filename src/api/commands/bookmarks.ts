@@ -0,0 +1,48 @@
+import type { AbLoopStatus, Bookmark, LoopPoint, TrackRef } from "@/types/model";
+import { invokeCommand } from "../client";
+import type { SeekResult } from "./music";
+
+// 不传 positionMs 时使用当前播放位置
+export async function setLoopPoint(point: LoopPoint, positionMs?: number): Promise<AbLoopStatus> {
+  return await invokeCommand("set_loop_point", { point, positionMs: positionMs ?? null });
+}
+
+export async function clearAbLoop(): Promise<AbLoopStatus> {
+  return await invokeCommand("clear_ab_loop");
+}
+
+export async function getAbLoop(): Promise<AbLoopStatus> {
+  return await invokeCommand("get_ab_loop");
+}
+
+export async function getTrackBookmarks(track: TrackRef): Promise<Bookmark[]> {
+  return await invokeCommand("get_track_bookmarks", { track });
+}
+
+export async function addBookmark(
+  track: TrackRef,
+  name?: string,
+  positionMs?: number
+): Promise<Bookmark> {
+  return await invokeCommand("add_bookmark", {
+    track,
+    name: name ?? null,
+    positionMs: positionMs ?? null,
+  });
+}
+
+export async function renameBookmark(
+  track: TrackRef,
+  bookmarkId: string,
+  name: string
+): Promise<Bookmark> {
+  return await invokeCommand("rename_bookmark", { track, bookmarkId, name });
+}
+
+export async function removeBookmark(track: TrackRef, bookmarkId: string): Promise<void> {
+  await invokeCommand("remove_bookmark", { track, bookmarkId });
+}
+
+export async function jumpToBookmark(track: TrackRef, bookmarkId: string): Promise<SeekResult> {
+  return await invokeCommand("jump_to_bookmark", { track, bookmarkId });
+}
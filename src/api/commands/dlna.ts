@@ -0,0 +1,19 @@
+import type { DlnaRenderer } from "@/types/model";
+import { invokeCommand } from "../client";
+
+/** SSDP 搜索局域网里的渲染器，约需 3 秒 */
+export async function discoverDlnaRenderers(): Promise<DlnaRenderer[]> {
+  return await invokeCommand("discover_dlna_renderers");
+}
+
+/** 当前输出；null 表示本机 */
+export async function getPlaybackOutput(): Promise<DlnaRenderer | null> {
+  return await invokeCommand("get_playback_output");
+}
+
+/** 切换输出后需要在新输出上重新播放当前曲目 */
+export async function setPlaybackOutput(
+  rendererId: string | null
+): Promise<DlnaRenderer | null> {
+  return await invokeCommand("set_playback_output", { rendererId });
+}
@@ -0,0 +1,38 @@
+import type {
+  AlbumStat,
+  ArtistStat,
+  HistoryRange,
+  ListeningTotals,
+  PlayHistoryEntry,
+  TrackStat,
+} from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function getRecentlyPlayed(limit?: number): Promise<PlayHistoryEntry[]> {
+  return await invokeCommand("get_recently_played", { limit });
+}
+
+export async function getTopTracks(
+  range?: HistoryRange,
+  limit?: number
+): Promise<TrackStat[]> {
+  return await invokeCommand("get_top_tracks", { range, limit });
+}
+
+export async function getTopArtists(
+  range?: HistoryRange,
+  limit?: number
+): Promise<ArtistStat[]> {
+  return await invokeCommand("get_top_artists", { range, limit });
+}
+
+export async function getTopAlbums(
+  range?: HistoryRange,
+  limit?: number
+): Promise<AlbumStat[]> {
+  return await invokeCommand("get_top_albums", { range, limit });
+}
+
+export async function getListeningTotals(range?: HistoryRange): Promise<ListeningTotals> {
+  return await invokeCommand("get_listening_totals", { range });
+}
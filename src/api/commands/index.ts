@@ -1,5 +1,20 @@
+export * as audioOutputCommands from "./audioOutput";
+export * as bookmarkCommands from "./bookmarks";
+export * as dlnaCommands from "./dlna";
 export * as fileCommands from "./file";
+export * as historyCommands from "./history";
 export * as musicCommands from "./music";
 export * as neteaseCommands from "./netease";
 export * as playlistCommands from "./playlist";
+export * as podcastCommands from "./podcast";
+export * as radioCommands from "./radio";
+export * as remoteApiCommands from "./remoteApi";
+export * as remoteSourceCommands from "./remoteSource";
+export * as scrobbleCommands from "./scrobble";
+export * as sessionCommands from "./session";
+export * as sleepTimerCommands from "./sleepTimer";
+export * as subsonicCommands from "./subsonic";
 export * as systemCommands from "./system";
+export * as userMetaCommands from "./userMeta";
+export * as visualizerCommands from "./visualizer";
+export * as waveformCommands from "./waveform";
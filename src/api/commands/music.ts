@@ -1,4 +1,9 @@
-import type { PlaybackSource, PlaySongResult, PlayStartResult } from "@/types/model";
+import type {
+  PlaybackSource,
+  PlaybackTrack,
+  PlaySongResult,
+  PlayStartResult,
+} from "@/types/model";
 import { invokeCommand } from "../client";
 import type { HandleEventAction } from "../types";
 
@@ -12,26 +17,38 @@ export async function handleEvent(
   });
 }
 
+export async function setPlaybackRate(rate: number, preservePitch?: boolean) {
+  return await invokeCommand("control_playback", {
+    action: "rate",
+    volume: null,
+    rate,
+    preservePitch: preservePitch ?? null,
+  });
+}
+
 export async function playNeteaseSong(args: {
   id: string;
   name: string;
   artist: string;
   picUrl?: string;
+  provider?: string;
 }): Promise<PlaySongResult> {
   return await invokeCommand("play_netease_song", args);
 }
 
 export async function playTrack(
   source: PlaybackSource,
-  requestId: number
+  requestId: number,
+  track?: PlaybackTrack
 ): Promise<PlayStartResult> {
-  return await invokeCommand("play_track", { source, requestId });
+  return await invokeCommand("play_track", { source, requestId, track });
 }
 
 export async function preparePlaybackRequest(requestId: number): Promise<void> {
   await invokeCommand("prepare_playback_request", { requestId });
 }
 
+/** id 为在线音频缓存键，见 getOnlineCacheKey */
 export async function prefetchNeteaseSong(id: string): Promise<void> {
   await invokeCommand("prefetch_netease_song", { id });
 }
@@ -53,6 +70,7 @@ export async function downloadMusic(args: {
   songName: string;
   artist: string;
   defaultDirectory: string | null;
+  provider?: string;
 }): Promise<string> {
   return await invokeCommand("download_music", args);
 }
@@ -64,6 +82,8 @@ export interface PlaybackState {
   is_paused: boolean;
   has_track: boolean;
   track_id: number;
+  playback_rate: number;
+  preserve_pitch: boolean;
 }
 
 export interface SeekResult {
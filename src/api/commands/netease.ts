@@ -1,5 +1,7 @@
 import type {
+  AlbumResult,
   ArtistSongsResult,
+  OnlineApiSettings,
   OnlineServiceStatus,
   SearchMixResult,
 } from "@/types/model";
@@ -11,6 +13,7 @@ export async function searchOnlineMix(args: {
   pagesize: number;
   songLimit?: number;
   artistLimit?: number;
+  provider?: string;
 }): Promise<SearchMixResult> {
   return await invokeCommand("search_online_mix", args);
 }
@@ -18,18 +21,39 @@ export async function searchOnlineMix(args: {
 export async function getArtistTopSongs(args: {
   id: string;
   limit: number;
+  provider?: string;
 }): Promise<ArtistSongsResult> {
   return await invokeCommand("get_artist_top_songs", args);
 }
 
-export async function getSongLyric(args: { id: string }): Promise<string> {
+export async function getSongLyric(args: {
+  id: string;
+  provider?: string;
+}): Promise<string> {
   return await invokeCommand("get_song_lyric", args);
 }
 
+export async function getOnlineAlbum(args: {
+  id: string;
+  provider?: string;
+}): Promise<AlbumResult> {
+  return await invokeCommand("get_online_album", args);
+}
+
 export async function checkOnlineServiceStatus(): Promise<OnlineServiceStatus> {
   return await invokeCommand("check_online_service_status");
 }
 
+export async function getOnlineApiSettings(): Promise<OnlineApiSettings> {
+  return await invokeCommand("get_online_api_settings");
+}
+
+export async function setOnlineApiSettings(
+  settings: OnlineApiSettings
+): Promise<OnlineApiSettings> {
+  return await invokeCommand("set_online_api_settings", { settings });
+}
+
 export async function ensureOnlineService(): Promise<void> {
   return await invokeCommand("ensure_online_service");
 }
@@ -37,3 +61,8 @@ export async function ensureOnlineService(): Promise<void> {
 export async function restartOnlineService(): Promise<void> {
   return await invokeCommand("restart_online_service");
 }
+
+/** 内置服务日志的最后 lines 行（默认 200） */
+export async function getOnlineServiceLogs(lines?: number): Promise<string> {
+  return await invokeCommand("get_online_service_logs", { lines });
+}
@@ -1,4 +1,4 @@
-import type { Playlist } from "@/types/model";
+import type { Playlist, PlaylistItem, PlaylistMutationResult } from "@/types/model";
 import { invokeCommand } from "../client";
 
 export async function readPlaylists(): Promise<Playlist[]> {
@@ -6,6 +6,55 @@ export async function readPlaylists(): Promise<Playlist[]> {
   return Array.isArray(list) ? list : [];
 }
 
-export async function writePlaylists(playlists: Playlist[]): Promise<void> {
-  await invokeCommand("write_playlists", { playlists });
+export async function createPlaylist(name: string): Promise<PlaylistMutationResult> {
+  return await invokeCommand("create_playlist", { name });
+}
+
+export async function renamePlaylist(args: {
+  id: string;
+  name: string;
+  expectedRevision?: number;
+}): Promise<PlaylistMutationResult> {
+  return await invokeCommand("rename_playlist", args);
+}
+
+export async function deletePlaylist(args: {
+  id: string;
+  expectedRevision?: number;
+}): Promise<PlaylistMutationResult> {
+  return await invokeCommand("delete_playlist", args);
+}
+
+export async function addPlaylistItems(args: {
+  id: string;
+  items: PlaylistItem[];
+  index?: number;
+  expectedRevision?: number;
+}): Promise<PlaylistMutationResult> {
+  return await invokeCommand("add_playlist_items", args);
+}
+
+export async function removePlaylistItems(args: {
+  id: string;
+  indices: number[];
+  expectedRevision?: number;
+}): Promise<PlaylistMutationResult> {
+  return await invokeCommand("remove_playlist_items", args);
+}
+
+export async function reorderPlaylistItem(args: {
+  id: string;
+  fromIndex: number;
+  toIndex: number;
+  expectedRevision?: number;
+}): Promise<PlaylistMutationResult> {
+  return await invokeCommand("reorder_playlist_item", args);
+}
+
+export async function duplicatePlaylist(args: {
+  id: string;
+  name?: string;
+  expectedRevision?: number;
+}): Promise<PlaylistMutationResult> {
+  return await invokeCommand("duplicate_playlist", args);
 }
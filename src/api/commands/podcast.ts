@@ -0,0 +1,65 @@
+import type {
+  PodcastEpisode,
+  PodcastFeed,
+  PodcastRefreshResult,
+  PodcastSettings,
+} from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function getPodcasts(): Promise<PodcastFeed[]> {
+  return await invokeCommand("get_podcasts");
+}
+
+/** 已订阅的地址直接返回已有节目 */
+export async function subscribePodcast(url: string): Promise<PodcastFeed> {
+  return await invokeCommand("subscribe_podcast", { url });
+}
+
+/** 同时删除该节目已下载的单集 */
+export async function unsubscribePodcast(feedId: string): Promise<void> {
+  await invokeCommand("unsubscribe_podcast", { feedId });
+}
+
+/** 不传 feedId 时刷新全部订阅，单个节目的失败记录在结果的 error 里 */
+export async function refreshPodcasts(feedId?: string | null): Promise<PodcastRefreshResult[]> {
+  return await invokeCommand("refresh_podcasts", { feedId: feedId ?? null });
+}
+
+export async function getPodcastSettings(): Promise<PodcastSettings> {
+  return await invokeCommand("get_podcast_settings");
+}
+
+export async function setPodcastSettings(settings: PodcastSettings): Promise<PodcastSettings> {
+  return await invokeCommand("set_podcast_settings", { settings });
+}
+
+export async function setPodcastEpisodePlayed(args: {
+  feedId: string;
+  episodeId: string;
+  played: boolean;
+}): Promise<PodcastEpisode> {
+  return await invokeCommand("set_podcast_episode_played", args);
+}
+
+/** rate 为 null 时沿用当前播放速度 */
+export async function setPodcastPlaybackRate(
+  feedId: string,
+  rate: number | null
+): Promise<PodcastFeed> {
+  return await invokeCommand("set_podcast_playback_rate", { feedId, rate });
+}
+
+/** 下载进度通过 podcast-download-progress 事件推送 */
+export async function downloadPodcastEpisode(args: {
+  feedId: string;
+  episodeId: string;
+}): Promise<PodcastEpisode> {
+  return await invokeCommand("download_podcast_episode", args);
+}
+
+export async function deletePodcastDownload(args: {
+  feedId: string;
+  episodeId: string;
+}): Promise<PodcastEpisode> {
+  return await invokeCommand("delete_podcast_download", args);
+}
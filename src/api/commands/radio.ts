@@ -0,0 +1,27 @@
+import type { RadioStation } from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function getRadioStations(): Promise<RadioStation[]> {
+  return await invokeCommand("get_radio_stations");
+}
+
+/** 同一地址不会重复添加；名称留空时使用 .pls / .m3u 里的标题 */
+export async function addRadioStation(args: {
+  name?: string | null;
+  url: string;
+}): Promise<RadioStation> {
+  return await invokeCommand("add_radio_station", args);
+}
+
+/** 导入本地 .pls / .m3u 文件中的全部电台 */
+export async function importRadioStations(path: string): Promise<RadioStation[]> {
+  return await invokeCommand("import_radio_stations", { path });
+}
+
+export async function renameRadioStation(id: string, name: string): Promise<RadioStation> {
+  return await invokeCommand("rename_radio_station", { id, name });
+}
+
+export async function removeRadioStation(id: string): Promise<void> {
+  await invokeCommand("remove_radio_station", { id });
+}
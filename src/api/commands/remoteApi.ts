@@ -0,0 +1,17 @@
+import type { RemoteApiSettings, RemoteApiStatus } from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function getRemoteApiSettings(): Promise<RemoteApiSettings> {
+  return await invokeCommand("get_remote_api_settings");
+}
+
+/** 保存并按新设置重启服务，返回补全令牌后的设置 */
+export async function setRemoteApiSettings(
+  settings: RemoteApiSettings
+): Promise<RemoteApiSettings> {
+  return await invokeCommand("set_remote_api_settings", { settings });
+}
+
+export async function getRemoteApiStatus(): Promise<RemoteApiStatus> {
+  return await invokeCommand("get_remote_api_status");
+}
@@ -0,0 +1,54 @@
+import type {
+  RemoteAlbum,
+  RemoteAlbumDetail,
+  RemoteArtistDetail,
+  RemoteSearchResult,
+  RemoteServer,
+} from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function getRemoteServers(): Promise<RemoteServer[]> {
+  return await invokeCommand("get_remote_servers");
+}
+
+/** 先连接验证再保存；新建时 id 留空，返回补全 id 后的配置 */
+export async function saveRemoteServer(server: RemoteServer): Promise<RemoteServer> {
+  return await invokeCommand("save_remote_server", { server });
+}
+
+export async function deleteRemoteServer(id: string): Promise<void> {
+  await invokeCommand("delete_remote_server", { id });
+}
+
+export async function searchRemoteSongs(args: {
+  serverId: string;
+  keywords: string;
+  page?: number;
+  pagesize?: number;
+}): Promise<RemoteSearchResult> {
+  return await invokeCommand("search_remote_songs", args);
+}
+
+/** listType 同 Subsonic getAlbumList2：newest、random、alphabeticalByName 等 */
+export async function getRemoteAlbumList(args: {
+  serverId: string;
+  listType?: string;
+  offset?: number;
+  size?: number;
+}): Promise<RemoteAlbum[]> {
+  return await invokeCommand("get_remote_album_list", args);
+}
+
+export async function getRemoteAlbum(args: {
+  serverId: string;
+  albumId: string;
+}): Promise<RemoteAlbumDetail> {
+  return await invokeCommand("get_remote_album", args);
+}
+
+export async function getRemoteArtist(args: {
+  serverId: string;
+  artistId: string;
+}): Promise<RemoteArtistDetail> {
+  return await invokeCommand("get_remote_artist", args);
+}
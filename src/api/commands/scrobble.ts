@@ -0,0 +1,26 @@
+import type { ScrobblerSettings, ScrobbleStatus } from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function getScrobblerSettings(): Promise<ScrobblerSettings> {
+  return await invokeCommand("get_scrobbler_settings");
+}
+
+export async function setScrobblerSettings(settings: ScrobblerSettings): Promise<void> {
+  await invokeCommand("set_scrobbler_settings", { settings });
+}
+
+export async function getScrobbleStatus(): Promise<ScrobbleStatus> {
+  return await invokeCommand("get_scrobble_status");
+}
+
+export async function flushScrobbleQueue(): Promise<void> {
+  await invokeCommand("flush_scrobble_queue");
+}
+
+export async function clearScrobbleQueue(): Promise<void> {
+  await invokeCommand("clear_scrobble_queue");
+}
+
+export async function lastfmAuthenticate(username: string, password: string): Promise<void> {
+  await invokeCommand("lastfm_authenticate", { username, password });
+}
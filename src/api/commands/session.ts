@@ -0,0 +1,10 @@
+import type { RestoredSession, SessionQueue } from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function restoreSession(): Promise<RestoredSession | null> {
+  return await invokeCommand("restore_session");
+}
+
+export async function updateSessionQueue(queue: SessionQueue): Promise<void> {
+  await invokeCommand("update_session_queue", { queue });
+}
@@ -0,0 +1,17 @@
+import type { SleepTimerMode, SleepTimerStatus } from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function startSleepTimer(
+  mode: SleepTimerMode,
+  fadeSeconds?: number
+): Promise<SleepTimerStatus> {
+  return await invokeCommand("start_sleep_timer", { mode, fadeSeconds: fadeSeconds ?? null });
+}
+
+export async function cancelSleepTimer(): Promise<void> {
+  await invokeCommand("cancel_sleep_timer");
+}
+
+export async function getSleepTimerStatus(): Promise<SleepTimerStatus> {
+  return await invokeCommand("get_sleep_timer_status");
+}
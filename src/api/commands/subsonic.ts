@@ -0,0 +1,17 @@
+import type { SubsonicServerSettings, SubsonicServerStatus } from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function getSubsonicServerSettings(): Promise<SubsonicServerSettings> {
+  return await invokeCommand("get_subsonic_server_settings");
+}
+
+/** 保存并按新设置重启服务；启用时必须填写用户名和密码 */
+export async function setSubsonicServerSettings(
+  settings: SubsonicServerSettings
+): Promise<void> {
+  await invokeCommand("set_subsonic_server_settings", { settings });
+}
+
+export async function getSubsonicServerStatus(): Promise<SubsonicServerStatus> {
+  return await invokeCommand("get_subsonic_server_status");
+}
@@ -1,5 +1,11 @@
+import type { OpenFilesRequest } from "@/types/model";
 import { invokeCommand } from "../client";
 
 export async function quitApp(): Promise<void> {
   await invokeCommand("quit_app");
 }
+
+// 首次启动时命令行传入的文件；之后的请求通过 open-files 事件推送
+export async function takePendingOpenFiles(): Promise<OpenFilesRequest | null> {
+  return await invokeCommand("take_pending_open_files");
+}
@@ -0,0 +1,32 @@
+import type {
+  TrackRef,
+  TrackUserMetadata,
+  TrackUserMetadataResult,
+  TrackUserMetadataUpdate,
+  UserMetadataSettings,
+  UserMetadataStore,
+} from "@/types/model";
+import { invokeCommand } from "../client";
+
+export async function getTrackUserMetadata(track: TrackRef): Promise<TrackUserMetadata> {
+  return await invokeCommand("get_track_user_metadata", { track });
+}
+
+export async function getAllUserMetadata(): Promise<UserMetadataStore> {
+  return await invokeCommand("get_all_user_metadata");
+}
+
+export async function updateTrackUserMetadata(
+  track: TrackRef,
+  update: TrackUserMetadataUpdate
+): Promise<TrackUserMetadataResult> {
+  return await invokeCommand("update_track_user_metadata", { track, update });
+}
+
+export async function getUserMetadataSettings(): Promise<UserMetadataSettings> {
+  return await invokeCommand("get_user_metadata_settings");
+}
+
+export async function setUserMetadataSettings(settings: UserMetadataSettings): Promise<void> {
+  await invokeCommand("set_user_metadata_settings", { settings });
+}
@@ -0,0 +1,10 @@
+import { invokeCommand } from "../client";
+
+// 订阅后后端开始推送 visualizer-frame 事件；每次订阅都需要对应一次取消订阅
+export async function subscribeVisualizer(bands?: number): Promise<void> {
+  await invokeCommand("subscribe_visualizer", { bands: bands ?? null });
+}
+
+export async function unsubscribeVisualizer(): Promise<void> {
+  await invokeCommand("unsubscribe_visualizer");
+}
@@ -0,0 +1,10 @@
+import type { WaveformPeaks, WaveformSource } from "@/types/model";
+import { invokeCommand } from "../client";
+
+// resolution 为桶数，后端限制在 16-4096；结果按曲目与修改时间缓存
+export async function getWaveformPeaks(
+  source: WaveformSource,
+  resolution: number
+): Promise<WaveformPeaks> {
+  return await invokeCommand("get_waveform_peaks", { source, resolution });
+}
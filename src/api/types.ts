@@ -1,10 +1,57 @@
 import type {
+  AbLoopStatus,
+  Bookmark,
+  ChannelOptions,
+  DlnaRenderer,
+  LoopPoint,
+  AlbumResult,
+  AlbumStat,
   ArtistSongsResult,
+  ArtistStat,
+  HistoryRange,
+  ListeningTotals,
+  PlayHistoryEntry,
+  PlaybackTrack,
+  TrackStat,
   MusicFile,
+  OpenFilesRequest,
+  OutputDeviceInfo,
+  OutputDeviceStatus,
   Playlist,
+  PlaylistItem,
+  PlaylistMutationResult,
+  RemoteApiSettings,
+  RemoteApiStatus,
+  RemoteAlbum,
+  RemoteAlbumDetail,
+  RemoteArtistDetail,
+  RemoteSearchResult,
+  RemoteServer,
   PlaybackSource,
   PlayStartResult,
+  PodcastEpisode,
+  PodcastFeed,
+  PodcastRefreshResult,
+  PodcastSettings,
+  RadioStation,
   PlaySongResult,
+  RestoredSession,
+  SessionQueue,
+  SleepTimerMode,
+  SubsonicServerSettings,
+  SubsonicServerStatus,
+  SleepTimerStatus,
+  ScrobblerSettings,
+  ScrobbleStatus,
+  TrackRef,
+  TrackUserMetadata,
+  TrackUserMetadataResult,
+  TrackUserMetadataUpdate,
+  UserMetadataSettings,
+  UserMetadataStore,
+  WaveformPeaks,
+  WaveformSource,
+  OnlineApiSettings,
   OnlineServiceStatus,
   SearchMixResult,
 } from "@/types/model";
@@ -21,6 +68,8 @@ interface PlaybackStateResult extends PlaybackProgressResult {
   is_paused: boolean;
   has_track: boolean;
   track_id: number;
+  playback_rate: number;
+  preserve_pitch: boolean;
 }
 
 interface SeekResult {
@@ -30,27 +79,41 @@ interface SeekResult {
 
 export interface TauriCommandParamsMap {
   quit_app: void;
+  take_pending_open_files: void;
   scan_files: { path: string | null; defaultDirectory: string | null };
   load_cached_music_files: { path: string | null; defaultDirectory: string | null };
   control_playback: {
-    action: "play" | "pause" | "volume";
+    action: "play" | "pause" | "volume" | "rate";
     volume: number | null;
+    // 播放速度 0.5-2.0；preservePitch 为 false 时音高随速度变化
+    rate?: number | null;
+    preservePitch?: boolean | null;
   };
-  play_track: { source: PlaybackSource; requestId: number };
+  play_track: { source: PlaybackSource; requestId: number; track?: PlaybackTrack };
   prepare_playback_request: { requestId: number };
   prefetch_netease_song: { id: string };
   get_online_audio_cache_size: void;
   get_online_audio_cache_path: void;
   clear_online_audio_cache: void;
   check_online_service_status: void;
+  get_online_api_settings: void;
+  set_online_api_settings: { settings: OnlineApiSettings };
   ensure_online_service: void;
   restart_online_service: void;
-  play_netease_song: { id: string; name: string; artist: string; picUrl?: string };
+  get_online_service_logs: { lines?: number };
+  play_netease_song: {
+    id: string;
+    name: string;
+    artist: string;
+    picUrl?: string;
+    provider?: string;
+  };
   download_music: {
     songHash: string;
     songName: string;
     artist: string;
     defaultDirectory: string | null;
+    provider?: string;
   };
   search_online_mix: {
     keywords: string;
@@ -58,21 +121,122 @@ export interface TauriCommandParamsMap {
     pagesize: number;
     songLimit?: number;
     artistLimit?: number;
+    provider?: string;
   };
-  get_artist_top_songs: { id: string; limit: number };
+  get_artist_top_songs: { id: string; limit: number; provider?: string };
   get_default_music_dir: void;
-  get_song_lyric: { id: string };
+  get_song_lyric: { id: string; provider?: string };
+  get_online_album: { id: string; provider?: string };
   load_local_cover_path: { fileName: string; defaultDirectory: string | null };
   load_local_lyric: { fileName: string; defaultDirectory: string | null };
   get_playback_state: void;
   import_music: { files: string[]; defaultDirectory: string | null };
   read_playlists: void;
-  write_playlists: { playlists: Playlist[] };
+  create_playlist: { name: string };
+  rename_playlist: { id: string; name: string; expectedRevision?: number };
+  delete_playlist: { id: string; expectedRevision?: number };
+  add_playlist_items: {
+    id: string;
+    items: PlaylistItem[];
+    index?: number;
+    expectedRevision?: number;
+  };
+  remove_playlist_items: { id: string; indices: number[]; expectedRevision?: number };
+  reorder_playlist_item: {
+    id: string;
+    fromIndex: number;
+    toIndex: number;
+    expectedRevision?: number;
+  };
+  duplicate_playlist: { id: string; name?: string; expectedRevision?: number };
+  get_recently_played: { limit?: number };
+  get_top_tracks: { range?: HistoryRange; limit?: number };
+  get_top_artists: { range?: HistoryRange; limit?: number };
+  get_top_albums: { range?: HistoryRange; limit?: number };
+  get_listening_totals: { range?: HistoryRange };
+  get_scrobbler_settings: void;
+  set_scrobbler_settings: { settings: ScrobblerSettings };
+  get_scrobble_status: void;
+  flush_scrobble_queue: void;
+  clear_scrobble_queue: void;
+  lastfm_authenticate: { username: string; password: string };
+  get_track_user_metadata: { track: TrackRef };
+  get_all_user_metadata: void;
+  update_track_user_metadata: { track: TrackRef; update: TrackUserMetadataUpdate };
+  get_user_metadata_settings: void;
+  set_user_metadata_settings: { settings: UserMetadataSettings };
+  restore_session: void;
+  update_session_queue: { queue: SessionQueue };
+  start_sleep_timer: { mode: SleepTimerMode; fadeSeconds?: number | null };
+  cancel_sleep_timer: void;
+  get_sleep_timer_status: void;
+  set_loop_point: { point: LoopPoint; positionMs?: number | null };
+  clear_ab_loop: void;
+  get_ab_loop: void;
+  get_track_bookmarks: { track: TrackRef };
+  add_bookmark: { track: TrackRef; name?: string | null; positionMs?: number | null };
+  rename_bookmark: { track: TrackRef; bookmarkId: string; name: string };
+  remove_bookmark: { track: TrackRef; bookmarkId: string };
+  jump_to_bookmark: { track: TrackRef; bookmarkId: string };
+  list_output_devices: void;
+  get_output_device: void;
+  set_output_device: { device: string | null };
+  get_channel_options: void;
+  set_channel_options: {
+    balance?: number | null;
+    mono?: boolean | null;
+    swapChannels?: boolean | null;
+  };
+  subscribe_visualizer: { bands?: number | null };
+  unsubscribe_visualizer: void;
+  get_waveform_peaks: { source: WaveformSource; resolution: number };
+  get_remote_api_settings: void;
+  set_remote_api_settings: { settings: RemoteApiSettings };
+  get_remote_api_status: void;
+  get_subsonic_server_settings: void;
+  set_subsonic_server_settings: { settings: SubsonicServerSettings };
+  get_subsonic_server_status: void;
+  get_remote_servers: void;
+  save_remote_server: { server: RemoteServer };
+  delete_remote_server: { id: string };
+  search_remote_songs: {
+    serverId: string;
+    keywords: string;
+    page?: number;
+    pagesize?: number;
+  };
+  get_remote_album_list: {
+    serverId: string;
+    listType?: string;
+    offset?: number;
+    size?: number;
+  };
+  get_remote_album: { serverId: string; albumId: string };
+  get_remote_artist: { serverId: string; artistId: string };
+  get_radio_stations: void;
+  add_radio_station: { name?: string | null; url: string };
+  import_radio_stations: { path: string };
+  rename_radio_station: { id: string; name: string };
+  remove_radio_station: { id: string };
+  get_podcasts: void;
+  subscribe_podcast: { url: string };
+  unsubscribe_podcast: { feedId: string };
+  refresh_podcasts: { feedId?: string | null };
+  get_podcast_settings: void;
+  set_podcast_settings: { settings: PodcastSettings };
+  set_podcast_episode_played: { feedId: string; episodeId: string; played: boolean };
+  set_podcast_playback_rate: { feedId: string; rate: number | null };
+  download_podcast_episode: { feedId: string; episodeId: string };
+  delete_podcast_download: { feedId: string; episodeId: string };
+  discover_dlna_renderers: void;
+  get_playback_output: void;
+  set_playback_output: { rendererId: string | null };
   seek_to: { positionMs: number };
 }
 
 export interface TauriCommandResultMap {
   quit_app: void;
+  take_pending_open_files: OpenFilesRequest | null;
   scan_files: MusicFile[];
   load_cached_music_files: MusicFile[];
   control_playback: void;
@@ -83,20 +247,98 @@ export interface TauriCommandResultMap {
   get_online_audio_cache_path: string;
   clear_online_audio_cache: void;
   check_online_service_status: OnlineServiceStatus;
+  get_online_api_settings: OnlineApiSettings;
+  set_online_api_settings: OnlineApiSettings;
   ensure_online_service: void;
   restart_online_service: void;
+  get_online_service_logs: string;
   play_netease_song: PlaySongResult;
   download_music: string;
   search_online_mix: SearchMixResult;
   get_artist_top_songs: ArtistSongsResult;
   get_default_music_dir: string;
   get_song_lyric: string;
+  get_online_album: AlbumResult;
   load_local_cover_path: string | null;
   load_local_lyric: string;
   get_playback_state: PlaybackStateResult;
   import_music: string;
   read_playlists: Playlist[];
-  write_playlists: void;
+  create_playlist: PlaylistMutationResult;
+  rename_playlist: PlaylistMutationResult;
+  delete_playlist: PlaylistMutationResult;
+  add_playlist_items: PlaylistMutationResult;
+  remove_playlist_items: PlaylistMutationResult;
+  reorder_playlist_item: PlaylistMutationResult;
+  duplicate_playlist: PlaylistMutationResult;
+  get_recently_played: PlayHistoryEntry[];
+  get_top_tracks: TrackStat[];
+  get_top_artists: ArtistStat[];
+  get_top_albums: AlbumStat[];
+  get_listening_totals: ListeningTotals;
+  get_scrobbler_settings: ScrobblerSettings;
+  set_scrobbler_settings: void;
+  get_scrobble_status: ScrobbleStatus;
+  flush_scrobble_queue: void;
+  clear_scrobble_queue: void;
+  lastfm_authenticate: void;
+  get_track_user_metadata: TrackUserMetadata;
+  get_all_user_metadata: UserMetadataStore;
+  update_track_user_metadata: TrackUserMetadataResult;
+  get_user_metadata_settings: UserMetadataSettings;
+  set_user_metadata_settings: void;
+  restore_session: RestoredSession | null;
+  update_session_queue: void;
+  start_sleep_timer: SleepTimerStatus;
+  cancel_sleep_timer: void;
+  get_sleep_timer_status: SleepTimerStatus;
+  set_loop_point: AbLoopStatus;
+  clear_ab_loop: AbLoopStatus;
+  get_ab_loop: AbLoopStatus;
+  get_track_bookmarks: Bookmark[];
+  add_bookmark: Bookmark;
+  rename_bookmark: Bookmark;
+  remove_bookmark: void;
+  jump_to_bookmark: SeekResult;
+  list_output_devices: OutputDeviceInfo[];
+  get_output_device: OutputDeviceStatus;
+  set_output_device: OutputDeviceStatus;
+  get_channel_options: ChannelOptions;
+  set_channel_options: ChannelOptions;
+  subscribe_visualizer: void;
+  unsubscribe_visualizer: void;
+  get_waveform_peaks: WaveformPeaks;
+  get_remote_api_settings: RemoteApiSettings;
+  set_remote_api_settings: RemoteApiSettings;
+  get_remote_api_status: RemoteApiStatus;
+  get_subsonic_server_settings: SubsonicServerSettings;
+  set_subsonic_server_settings: void;
+  get_subsonic_server_status: SubsonicServerStatus;
+  get_remote_servers: RemoteServer[];
+  save_remote_server: RemoteServer;
+  delete_remote_server: void;
+  search_remote_songs: RemoteSearchResult;
+  get_remote_album_list: RemoteAlbum[];
+  get_remote_album: RemoteAlbumDetail;
+  get_remote_artist: RemoteArtistDetail;
+  get_radio_stations: RadioStation[];
+  add_radio_station: RadioStation;
+  import_radio_stations: RadioStation[];
+  rename_radio_station: RadioStation;
+  remove_radio_station: void;
+  get_podcasts: PodcastFeed[];
+  subscribe_podcast: PodcastFeed;
+  unsubscribe_podcast: void;
+  refresh_podcasts: PodcastRefreshResult[];
+  get_podcast_settings: PodcastSettings;
+  set_podcast_settings: PodcastSettings;
+  set_podcast_episode_played: PodcastEpisode;
+  set_podcast_playback_rate: PodcastFeed;
+  download_podcast_episode: PodcastEpisode;
+  delete_podcast_download: PodcastEpisode;
+  discover_dlna_renderers: DlnaRenderer[];
+  get_playback_output: DlnaRenderer | null;
+  set_playback_output: DlnaRenderer | null;
   seek_to: SeekResult;
 }
 
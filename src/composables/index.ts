@@ -5,6 +5,7 @@ export { useCoverBrightness } from "./useCoverBrightness";
 export { useCoverLoader } from "./useCoverLoader";
 export { useLocalCoverCache } from "./useLocalCoverCache";
 export type { UseLocalCoverCacheOptions } from "./useLocalCoverCache";
+export { useOpenFilesEvents } from "./useOpenFilesEvents";
 export { usePlaybackClock } from "./usePlaybackClock";
 export { usePlaybackProgressSlider } from "./usePlaybackProgressSlider";
 export { usePlaybackQueue } from "./usePlaybackQueue";
@@ -12,8 +13,11 @@ export type { PlayOnlineOptions } from "./usePlaybackQueue";
 export { usePlaybackVolume } from "./usePlaybackVolume";
 export { useOnlinePlaylistActions } from "./useOnlinePlaylistActions";
 export { usePlatform } from "./usePlatform";
+export { useRadioEvents } from "./useRadioEvents";
+export { useRemoteControlEvents } from "./useRemoteControlEvents";
 export { useStorageThemeSync } from "./useStorageThemeSync";
 export { useTrayPlaybackEvents } from "./useTrayPlaybackEvents";
+export { useVisualizer } from "./useVisualizer";
 export { useVirtualListWhenLong } from "./useVirtualListWhenLong";
 export type { UseVirtualListWhenLongOptions } from "./useVirtualListWhenLong";
 export { useWindowControls } from "./useWindowControls";
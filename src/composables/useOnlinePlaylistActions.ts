@@ -20,6 +20,7 @@ export function useOnlinePlaylistActions() {
         songName: song.name,
         artist: song.artists.join(", "),
         defaultDirectory: localStore.defaultDirectory,
+        provider: song.provider,
       });
       await localStore.refreshCurrentDirectory();
       ElMessage.success(t("download.done", { fileName }));
@@ -33,8 +34,9 @@ export function useOnlinePlaylistActions() {
     try {
       const playlistId =
         command === "new"
-          ? playlistStore.createPlaylist(t("playlist.newPlaylist")).id
+          ? (await playlistStore.createPlaylist(t("playlist.newPlaylist")))?.id
           : command;
+      if (!playlistId) return;
 
       let fileName: string | null = getLocalFileNameForSong(song, localStore.musicFiles);
       let didDownload = false;
@@ -47,6 +49,7 @@ export function useOnlinePlaylistActions() {
             songName: song.name,
             artist: song.artists?.join(", ") ?? "",
             defaultDirectory: localStore.defaultDirectory,
+            provider: song.provider,
           });
           didDownload = true;
           await localStore.loadMusicFiles();
@@ -68,7 +71,7 @@ export function useOnlinePlaylistActions() {
         return;
       }
 
-      const added = playlistStore.addToPlaylist(playlistId, {
+      const added = await playlistStore.addToPlaylist(playlistId, {
         type: "local",
         file_name: fileName,
       });
@@ -0,0 +1,26 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { takePendingOpenFiles } from "@/api/commands/system";
+import type { OpenFilesRequest } from "@/types/model";
+
+/** 命令行与“打开方式”传入的文件：先领取首次启动时暂存的请求，再监听运行期间的 open-files 事件 */
+export function useOpenFilesEvents(options: {
+  onOpen: (request: OpenFilesRequest) => void | Promise<void>;
+}) {
+  let unlisten: UnlistenFn | null = null;
+
+  async function start() {
+    stop();
+    unlisten = await listen<OpenFilesRequest>("open-files", (event) => {
+      void options.onOpen(event.payload);
+    });
+    const pending = await takePendingOpenFiles();
+    if (pending) await options.onOpen(pending);
+  }
+
+  function stop() {
+    unlisten?.();
+    unlisten = null;
+  }
+
+  return { start, stop };
+}
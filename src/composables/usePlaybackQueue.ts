@@ -1,5 +1,6 @@
 import { ref } from "vue";
 import { PlayMode, type Playlist, type SongInfo } from "@/types/model";
+import { getOnlineCacheKey, isRemoteSong } from "@/utils/songUtils";
 
 const MAX_PREFETCHED_ONLINE_SONG_IDS = 300;
 const MAX_CONCURRENT_ONLINE_PREFETCHES = 2;
@@ -98,8 +99,9 @@ export function usePlaybackQueue(options: {
 
   async function prefetchNextOnlineSong(song: SongInfo) {
     const nextSong = getNextOnlineSongForPrefetch(song);
-    if (!nextSong) return;
-    await prefetchOnlineSong(nextSong.id);
+    // 预取走网易云接口，远程服务器歌曲在播放时再缓存
+    if (!nextSong || isRemoteSong(nextSong)) return;
+    await prefetchOnlineSong(getOnlineCacheKey(nextSong));
   }
 
   async function prefetchOnlineSong(id: string) {
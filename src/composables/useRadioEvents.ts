@@ -0,0 +1,37 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { RadioMetadataEvent, RadioReconnectingEvent } from "@/types/model";
+
+/** 网络电台：ICY 元数据中的正在播放标题，以及断流后的自动重连提示 */
+export function useRadioEvents(options: {
+  onMetadata: (event: RadioMetadataEvent) => void;
+  onReconnecting: (event: RadioReconnectingEvent) => void;
+}) {
+  const unlisteners: UnlistenFn[] = [];
+
+  async function start() {
+    stop();
+    try {
+      unlisteners.push(
+        await listen<RadioMetadataEvent>("radio-metadata", (event) =>
+          options.onMetadata(event.payload)
+        )
+      );
+      unlisteners.push(
+        await listen<RadioReconnectingEvent>("radio-reconnecting", (event) =>
+          options.onReconnecting(event.payload)
+        )
+      );
+    } catch (error) {
+      stop();
+      throw error;
+    }
+  }
+
+  function stop() {
+    while (unlisteners.length > 0) {
+      unlisteners.pop()?.();
+    }
+  }
+
+  return { start, stop };
+}
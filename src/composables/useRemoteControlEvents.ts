@@ -0,0 +1,39 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { SongInfo } from "@/types/model";
+
+/** `rmusic ctl` 远程控制：后端已执行跳转 / 音量，前端同步显示；按 id 加入的在线歌曲交给队列处理 */
+export function useRemoteControlEvents(options: {
+  onSeeked: () => void;
+  onVolumeChanged: (volume: number) => void;
+  onEnqueueSong: (song: SongInfo) => void | Promise<void>;
+}) {
+  const unlisteners: UnlistenFn[] = [];
+
+  async function start() {
+    stop();
+    try {
+      unlisteners.push(await listen("remote-seeked", options.onSeeked));
+      unlisteners.push(
+        await listen<number>("remote-volume-changed", (event) =>
+          options.onVolumeChanged(event.payload)
+        )
+      );
+      unlisteners.push(
+        await listen<SongInfo>("remote-enqueue-song", (event) => {
+          void options.onEnqueueSong(event.payload);
+        })
+      );
+    } catch (error) {
+      stop();
+      throw error;
+    }
+  }
+
+  function stop() {
+    while (unlisteners.length > 0) {
+      unlisteners.pop()?.();
+    }
+  }
+
+  return { start, stop };
+}
@@ -16,6 +16,10 @@ export function useTrayPlaybackEvents(options: {
       unlisteners.push(await listen("tray-next", options.onNext));
       unlisteners.push(await listen("tray-play", options.onPlay));
       unlisteners.push(await listen("tray-pause", options.onPause));
+      // 睡眠定时器到时后端已暂停 sink，前端同步暂停状态
+      unlisteners.push(await listen("sleep-timer-finished", options.onPause));
+      // 输出设备断开时后端已暂停播放
+      unlisteners.push(await listen("output-device-lost", options.onPause));
       unlisteners.push(await listen("tray-quit", options.onQuit));
     } catch (error) {
       stop();
@@ -0,0 +1,50 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { onBeforeUnmount, onMounted, ref } from "vue";
+import { visualizerCommands } from "@/api/commands";
+import type { VisualizerFrame } from "@/types/model";
+
+/** 组件挂载期间订阅后端的频谱/电平数据，卸载时自动取消订阅 */
+export function useVisualizer(bandCount = 32) {
+  const bands = ref<number[]>(new Array(bandCount).fill(0));
+  const rms = ref(0);
+  const peak = ref(0);
+  let unlisten: UnlistenFn | null = null;
+  let subscribed = false;
+  let disposed = false;
+
+  async function start() {
+    const stopListening = await listen<VisualizerFrame>("visualizer-frame", (event) => {
+      bands.value = event.payload.bands;
+      rms.value = event.payload.rms;
+      peak.value = event.payload.peak;
+    });
+    if (disposed) {
+      stopListening();
+      return;
+    }
+    unlisten = stopListening;
+    await visualizerCommands.subscribeVisualizer(bandCount);
+    subscribed = true;
+    if (disposed) stop();
+  }
+
+  function stop() {
+    unlisten?.();
+    unlisten = null;
+    if (subscribed) {
+      subscribed = false;
+      void visualizerCommands.unsubscribeVisualizer().catch(() => {});
+    }
+  }
+
+  onMounted(() => {
+    void start().catch((error) => console.error("subscribe visualizer failed:", error));
+  });
+
+  onBeforeUnmount(() => {
+    disposed = true;
+    stop();
+  });
+
+  return { bands, rms, peak };
+}
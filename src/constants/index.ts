@@ -12,10 +12,6 @@ export const STORAGE_KEY_PLAYER_VOLUME = "player_volume";
 /** 单模式（本地/在线）最多保留条数 */
 export const SEARCH_HISTORY_MAX_ITEMS = 6;
 
-/* ---------- 播放列表 ---------- */
-/** 防抖写入延迟（ms），避免连续多次写入后端 */
-export const PLAYLIST_SAVE_DEBOUNCE_MS = 300;
-
 /* ---------- 列表与虚拟滚动 ---------- */
 /** 默认封面图路径（对应 public/icon.png） */
 export const DEFAULT_COVER_URL = "/icon.png";
@@ -168,6 +168,10 @@ export default {
     loadMusicFailed: "Failed to load music files",
     playFailed: "Failed to play music",
     playFailedOnline: "Playback failed",
+    playRadioFailed: "Failed to play radio station",
+    radioReconnecting: "Radio stream interrupted, reconnecting (attempt {attempt})",
+    switchOutputFailed: "Failed to switch playback output",
+    dlnaRendererUnreachable: "{name} is not responding, playback paused",
     downloadFailed: "Failed to download",
     fileAlreadyExists: "File already exists, no need to download again",
     fileAlreadyExistsWithPath: "File already exists: {fileName}",
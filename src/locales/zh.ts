@@ -168,6 +168,10 @@ export default {
     loadMusicFailed: "加载音乐文件失败",
     playFailed: "播放音乐失败",
     playFailedOnline: "播放失败",
+    playRadioFailed: "电台播放失败",
+    radioReconnecting: "电台连接中断，正在重连（第 {attempt} 次）",
+    switchOutputFailed: "切换播放输出失败",
+    dlnaRendererUnreachable: "无法连接 {name}，已暂停播放",
     downloadFailed: "下载歌曲失败",
     fileAlreadyExists: "文件已存在，无需重复下载",
     fileAlreadyExistsWithPath: "文件已存在: {fileName}",
@@ -27,14 +27,14 @@ const routes = [
     path: "/playlist/new",
     name: "PlaylistNew",
     component: PlaylistView,
-    beforeEnter: (
+    beforeEnter: async (
       _to: RouteLocationNormalized,
       _from: RouteLocationNormalized,
       next: NavigationGuardNext
     ) => {
       const store = usePlaylistStore();
-      const list = store.createPlaylist("");
-      next({ path: `/playlist/${list.id}`, replace: true });
+      const list = await store.createPlaylist("");
+      next(list ? { path: `/playlist/${list.id}`, replace: true } : false);
     },
   },
   {
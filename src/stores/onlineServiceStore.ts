@@ -1,10 +1,18 @@
 import { computed, ref } from "vue";
 import { defineStore } from "pinia";
-import type { OnlineServiceStatus } from "@/types/model";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type {
+  OnlineApiSettings,
+  OnlineEndpointStatus,
+  OnlineServiceExitEvent,
+  OnlineServiceStatus,
+} from "@/types/model";
 import {
   checkOnlineServiceStatus,
   ensureOnlineService,
+  getOnlineApiSettings,
   restartOnlineService,
+  setOnlineApiSettings,
 } from "@/api/commands/netease";
 
 type ServiceState = "checking" | "restarting" | "available" | "unavailable";
@@ -17,6 +25,8 @@ export const useOnlineServiceStore = defineStore("onlineService", () => {
   const message = ref("");
   const statusCode = ref<number | null>(null);
   const lastCheckedAt = ref<number | null>(null);
+  const endpoints = ref<OnlineEndpointStatus[]>([]);
+  const apiSettings = ref<OnlineApiSettings>({ use_bundled_service: true, endpoints: [] });
   const isChecking = ref(false);
   const isRestarting = ref(false);
   let timer: number | null = null;
@@ -25,6 +35,7 @@ export const useOnlineServiceStore = defineStore("onlineService", () => {
   let checkPromise: Promise<boolean> | null = null;
   let restartPromise: Promise<void> | null = null;
   let failureStreak = 0;
+  const unlisteners: UnlistenFn[] = [];
 
   const isAvailable = computed(() => state.value === "available");
 
@@ -32,6 +43,7 @@ export const useOnlineServiceStore = defineStore("onlineService", () => {
     state.value = status.available ? "available" : "unavailable";
     message.value = status.message;
     statusCode.value = status.status_code;
+    endpoints.value = status.endpoints;
     lastCheckedAt.value = Date.now();
     failureStreak = status.available ? 0 : failureStreak + 1;
   }
@@ -117,6 +129,49 @@ export const useOnlineServiceStore = defineStore("onlineService", () => {
     return restartPromise;
   }
 
+  async function loadApiSettings() {
+    apiSettings.value = await getOnlineApiSettings();
+  }
+
+  /** 保存端点设置并立即重新探测各端点 */
+  async function updateApiSettings(next: OnlineApiSettings) {
+    apiSettings.value = await setOnlineApiSettings(next);
+    await checkNow();
+  }
+
+  /** 内置服务崩溃后由后端自动重启；这里先标记不可用，等重启完成后再探测 */
+  function handleServiceExit(event: OnlineServiceExitEvent) {
+    if (isRestarting.value) return;
+    state.value = "unavailable";
+    message.value =
+      event.code !== null
+        ? `Online service exited with code ${event.code}`
+        : "Online service exited";
+    statusCode.value = null;
+    lastCheckedAt.value = Date.now();
+    if (!started) return;
+    clearTimer();
+    timer = window.setTimeout(() => {
+      timer = null;
+      void checkNow();
+    }, event.restart_in_ms + 1_000);
+  }
+
+  async function startEventListening() {
+    stopEventListening();
+    unlisteners.push(
+      await listen<OnlineServiceExitEvent>("online-service-exited", (event) =>
+        handleServiceExit(event.payload)
+      )
+    );
+  }
+
+  function stopEventListening() {
+    while (unlisteners.length > 0) {
+      unlisteners.pop()?.();
+    }
+  }
+
   function handleVisibilityChange() {
     if (document.visibilityState === "hidden") {
       clearTimer();
@@ -187,12 +242,18 @@ export const useOnlineServiceStore = defineStore("onlineService", () => {
     message,
     statusCode,
     lastCheckedAt,
+    endpoints,
+    apiSettings,
     isChecking,
     isRestarting,
     isAvailable,
     checkNow,
     ensureStarted,
     restartService,
+    loadApiSettings,
+    updateApiSettings,
+    startEventListening,
+    stopEventListening,
     start,
     stop,
   };
@@ -0,0 +1,80 @@
+import { ref } from "vue";
+import { defineStore } from "pinia";
+import { ElMessage } from "element-plus";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { DlnaRenderer, DlnaRendererErrorEvent } from "@/types/model";
+import { i18n } from "@/i18n";
+import {
+  discoverDlnaRenderers,
+  getPlaybackOutput,
+  setPlaybackOutput,
+} from "@/api/commands/dlna";
+import { usePlayerStore } from "./playerStore";
+
+/** 播放输出：本机或局域网里的 DLNA 渲染器 */
+export const usePlaybackOutputStore = defineStore("playbackOutput", () => {
+  const renderers = ref<DlnaRenderer[]>([]);
+  // null 表示本机输出
+  const output = ref<DlnaRenderer | null>(null);
+  const isDiscovering = ref(false);
+  const unlisteners: UnlistenFn[] = [];
+
+  async function load() {
+    output.value = await getPlaybackOutput();
+  }
+
+  async function discover() {
+    isDiscovering.value = true;
+    try {
+      renderers.value = await discoverDlnaRenderers();
+    } finally {
+      isDiscovering.value = false;
+    }
+  }
+
+  /** 切换后在新输出上从当前位置继续播放 */
+  async function select(rendererId: string | null) {
+    if ((output.value?.id ?? null) === rendererId) return;
+    try {
+      output.value = await setPlaybackOutput(rendererId);
+    } catch (error) {
+      ElMessage.error(`${i18n.global.t("errors.switchOutputFailed")}: ${error}`);
+      return;
+    }
+    await usePlayerStore().resumeOnCurrentOutput();
+  }
+
+  async function startEventListening() {
+    stopEventListening();
+    try {
+      unlisteners.push(
+        await listen<DlnaRendererErrorEvent>("dlna-renderer-error", (event) => {
+          if (output.value?.id !== event.payload.renderer_id) return;
+          ElMessage.warning(
+            i18n.global.t("errors.dlnaRendererUnreachable", { name: event.payload.name })
+          );
+        })
+      );
+    } catch (error) {
+      stopEventListening();
+      throw error;
+    }
+  }
+
+  function stopEventListening() {
+    while (unlisteners.length > 0) {
+      unlisteners.pop()?.();
+    }
+  }
+
+  return {
+    renderers,
+    output,
+    isDiscovering,
+    load,
+    discover,
+    select,
+    startEventListening,
+    stopEventListening,
+  };
+});
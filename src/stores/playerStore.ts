@@ -1,17 +1,28 @@
-import { ref, computed } from "vue";
+import { ref, computed, watch } from "vue";
 import { defineStore } from "pinia";
 import { ElMessage } from "element-plus";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
   MusicFile,
+  OpenFilesRequest,
   PlaybackPhase,
   PlaybackQueueItem,
+  PlayStartResult,
+  PodcastEpisode,
+  PodcastFeed,
+  RadioMetadataEvent,
+  RadioStation,
   SongInfo,
 } from "@/types/model";
 import { PlayMode } from "@/types/model";
 import { i18n } from "@/i18n";
 import { joinPathSegment } from "@/utils/pathUtils";
-import { getLocalMusicDisplayInfo } from "@/utils/songUtils";
+import {
+  getLocalMusicDisplayInfo,
+  getOnlineCacheKey,
+  getSongProvider,
+  isRemoteSong,
+} from "@/utils/songUtils";
 import { getPlaybackStep, getSequentialIndex } from "@/utils/playbackQueue";
 import {
   handleEvent,
@@ -21,7 +32,9 @@ import {
   prefetchNeteaseSong,
   getPlaybackState,
   seekTo,
+  setPlaybackRate,
 } from "@/api/commands/music";
+import { restoreSession, updateSessionQueue } from "@/api/commands/session";
 import { usePlaybackClock } from "@/composables/usePlaybackClock";
 import { usePlaybackQueue, type PlayOnlineOptions } from "@/composables/usePlaybackQueue";
 import { usePlaybackVolume } from "@/composables/usePlaybackVolume";
@@ -47,6 +60,8 @@ interface PlaybackEndedPayload {
 interface PlaybackSnapshot {
   music: MusicFile | null;
   onlineSong: SongInfo | null;
+  radioStation: RadioStation | null;
+  podcast: CurrentPodcastEpisode | null;
   localQueue: MusicFile[];
   onlineQueue: SongInfo[];
   playlistId: string | null;
@@ -56,12 +71,18 @@ interface PlaybackSnapshot {
   backendTrackId: number;
 }
 
+interface CurrentPodcastEpisode {
+  feed: PodcastFeed;
+  episode: PodcastEpisode;
+}
+
 interface PlayLocalOptions {
   fromPlaylistId?: string;
   queue?: MusicFile[];
 }
 
 const MAX_SHUFFLE_HISTORY_SIZE = 200;
+const SESSION_QUEUE_SYNC_DELAY_MS = 1000;
 
 export const usePlayerStore = defineStore("player", () => {
   const viewStore = useViewStore();
@@ -73,6 +94,10 @@ export const usePlayerStore = defineStore("player", () => {
 
   const currentMusic = ref<MusicFile | null>(null);
   const currentOnlineSong = ref<SongInfo | null>(null);
+  const currentRadioStation = ref<RadioStation | null>(null);
+  // 电台 ICY 元数据中的正在播放标题
+  const radioStreamTitle = ref<string | null>(null);
+  const currentPodcast = ref<CurrentPodcastEpisode | null>(null);
   const isPlaying = ref(false);
   const isLoadingSong = ref(false);
   const playbackPhase = ref<PlaybackPhase>("idle");
@@ -90,6 +115,9 @@ export const usePlayerStore = defineStore("player", () => {
   // 乘以 1000 为同一毫秒内的连续切歌预留递增空间，数值仍在 JS 安全整数范围内。
   let playbackRequestId = Date.now() * 1000;
   let fallbackPlaybackSnapshot: PlaybackSnapshot | null = null;
+  // 会话恢复时音频未能载入（如在线音频未缓存）：首次播放时重新加载并跳到该位置
+  let pendingResumePositionMs: number | null = null;
+  let sessionQueueSyncTimer: ReturnType<typeof setTimeout> | null = null;
   /** 当前从播放列表播放时记录列表 id，用于上一曲/下一曲 */
   const currentPlaylistId = ref<string | null>(null);
 
@@ -111,7 +139,11 @@ export const usePlayerStore = defineStore("player", () => {
   const currentOnlineQueue = playbackQueue.currentOnlineQueue;
 
   const hasCurrentTrack = computed(
-    () => currentMusic.value !== null || currentOnlineSong.value !== null
+    () =>
+      currentMusic.value !== null ||
+      currentOnlineSong.value !== null ||
+      currentRadioStation.value !== null ||
+      currentPodcast.value !== null
   );
   const localMusicByFileName = computed(() => {
     const map = new Map<string, MusicFile>();
@@ -124,10 +156,26 @@ export const usePlayerStore = defineStore("player", () => {
   const currentTrackDuration = computed(() => {
     if (currentTrackDurationMs.value > 0) return currentTrackDurationMs.value;
     if (currentOnlineSong.value?.duration) return currentOnlineSong.value.duration;
+    if (currentPodcast.value) return currentPodcast.value.episode.duration_ms;
     return 0;
   });
 
   const currentTrackInfo = computed(() => {
+    if (currentPodcast.value) {
+      const { feed, episode } = currentPodcast.value;
+      return {
+        name: episode.title,
+        artist: feed.author || feed.title,
+        picUrl: episode.image_url || feed.image_url || "",
+      };
+    }
+    if (currentRadioStation.value) {
+      return {
+        name: radioStreamTitle.value || currentRadioStation.value.name,
+        artist: currentRadioStation.value.name,
+        picUrl: "",
+      };
+    }
     if (currentOnlineSong.value) {
       return {
         name: currentOnlineSong.value.name,
@@ -150,7 +198,7 @@ export const usePlayerStore = defineStore("player", () => {
     if (currentPlaylistId.value) {
       const playlist = playlistStore.getPlaylist(currentPlaylistId.value);
       return (playlist?.items ?? []).map((item, sourceIndex) => {
-        if (item.type === "online") {
+        if (item.type !== "local") {
           return {
             key: `online:${sourceIndex}:${item.song.id}`,
             title: item.song.name,
@@ -228,6 +276,8 @@ export const usePlayerStore = defineStore("player", () => {
     return {
       music: currentMusic.value,
       onlineSong: currentOnlineSong.value,
+      radioStation: currentRadioStation.value,
+      podcast: currentPodcast.value,
       localQueue: [...currentLocalQueue.value],
       onlineQueue: [...currentOnlineQueue.value],
       playlistId: currentPlaylistId.value,
@@ -241,6 +291,8 @@ export const usePlayerStore = defineStore("player", () => {
   function restorePlaybackSnapshot(snapshot: PlaybackSnapshot) {
     currentMusic.value = snapshot.music;
     currentOnlineSong.value = snapshot.onlineSong;
+    currentRadioStation.value = snapshot.radioStation;
+    currentPodcast.value = snapshot.podcast;
     currentLocalQueue.value = snapshot.localQueue;
     currentOnlineQueue.value = snapshot.onlineQueue;
     currentPlaylistId.value = snapshot.playlistId;
@@ -250,7 +302,13 @@ export const usePlayerStore = defineStore("player", () => {
     isPlaying.value = snapshot.isPlaying;
     isLoadingSong.value = false;
     playbackPhase.value = "idle";
-    if (snapshot.isPlaying && (snapshot.music || snapshot.onlineSong)) {
+    if (
+      snapshot.isPlaying &&
+      (snapshot.music ||
+        snapshot.onlineSong ||
+        snapshot.radioStation ||
+        snapshot.podcast)
+    ) {
       startPlayTimeTracking();
     } else {
       stopPlayTimeTracking();
@@ -258,6 +316,7 @@ export const usePlayerStore = defineStore("player", () => {
   }
 
   function beginPlaybackRequest(): number {
+    pendingResumePositionMs = null;
     if (!isLoadingSong.value || fallbackPlaybackSnapshot === null) {
       fallbackPlaybackSnapshot = capturePlaybackSnapshot();
     }
@@ -337,6 +396,8 @@ export const usePlayerStore = defineStore("player", () => {
       isPlaying.value = false;
       playbackClock.stop({ updatePosition: false });
 
+      // 电台重连失败或播客单集播完后停止，不切到队列中的下一首
+      if (currentRadioStation.value || currentPodcast.value) return;
       if (playMode.value === PlayMode.REPEAT_ONE) {
         await replayCurrentSong();
       } else {
@@ -393,7 +454,7 @@ export const usePlayerStore = defineStore("player", () => {
   }
 
   function prefetchOnlineSong(song: SongInfo) {
-    return playbackQueue.prefetchOnlineSong(song.id);
+    return playbackQueue.prefetchOnlineSong(getOnlineCacheKey(song));
   }
 
   function stopPlayTimeTracking() {
@@ -420,12 +481,23 @@ export const usePlayerStore = defineStore("player", () => {
 
       currentMusic.value = music;
       currentOnlineSong.value = null;
+      clearStandaloneSources();
       playbackPhase.value = "buffering";
       await preparePlaybackRequest(requestId);
       if (!isCurrentPlaybackRequest(requestId)) return;
 
-      const fullPath = joinPathSegment(localStore.currentDirectory, music.file_name);
-      const playResult = await playTrack({ type: "local", path: fullPath }, requestId);
+      // CUE 分轨播放整轨文件中的一段
+      // 库外文件直接使用绝对路径
+      const fullPath =
+        music.path ??
+        joinPathSegment(localStore.currentDirectory, music.cue?.source_path ?? music.file_name);
+      const range = music.cue
+        ? { start_ms: music.cue.start_ms, end_ms: music.cue.end_ms }
+        : null;
+      const playResult = await playTrack({ type: "local", path: fullPath, range }, requestId, {
+        type: "local",
+        file: music,
+      });
       if (!isCurrentPlaybackRequest(requestId)) return;
       currentBackendTrackId.value = playResult.track_id;
       updateProgressFromBackend(playResult);
@@ -470,29 +542,143 @@ export const usePlayerStore = defineStore("player", () => {
 
       currentOnlineSong.value = song;
       currentMusic.value = null;
+      clearStandaloneSources();
       currentLocalQueue.value = [];
       playbackPhase.value = "resolving";
       await preparePlaybackRequest(requestId);
       if (!isCurrentPlaybackRequest(requestId)) return;
-      await onlineServiceStore.ensureStarted();
+
+      let resolvedPicUrl = "";
+      let startResult: PlayStartResult;
+      if (isRemoteSong(song)) {
+        // 远程服务器歌曲由后端按服务器配置生成串流地址，不经过网易云服务
+        playbackPhase.value = "buffering";
+        startResult = await playTrack(
+          { type: "remote", server_id: song.server_id, song_id: song.id },
+          requestId,
+          { type: "remote", song }
+        );
+      } else {
+        const provider = getSongProvider(song);
+        // 只有网易云来源依赖内置的 API 服务
+        if (provider === "netease") {
+          await onlineServiceStore.ensureStarted();
+          if (!isCurrentPlaybackRequest(requestId)) return;
+        }
+
+        const playResult = await playNeteaseSong({
+          id: song.id,
+          name: song.name,
+          artist: song.artists.join(", "),
+          picUrl: song.pic_url || undefined,
+          provider,
+        });
+        if (!isCurrentPlaybackRequest(requestId)) return;
+
+        debugPlaybackLog("[播放控制] 获取到播放URL，准备播放");
+        playbackPhase.value = "buffering";
+        resolvedPicUrl = playResult.pic_url;
+        startResult = await playTrack(
+          {
+            type: "online",
+            url: playResult.url,
+            cache_key: getOnlineCacheKey(song),
+          },
+          requestId,
+          { type: "online", song }
+        );
+      }
       if (!isCurrentPlaybackRequest(requestId)) return;
+      currentBackendTrackId.value = startResult.track_id;
+      updateProgressFromBackend(startResult);
 
-      const playResult = await playNeteaseSong({
-        id: song.id,
-        name: song.name,
-        artist: song.artists.join(", "),
-        picUrl: song.pic_url || undefined,
-      });
+      if (!completePlaybackRequest(requestId)) return;
+      isPlaying.value = true;
+      startPlayTimeTracking();
+
+      if (resolvedPicUrl && currentOnlineSong.value) {
+        currentOnlineSong.value.pic_url = resolvedPicUrl;
+      }
+      debugPlaybackLog(`[播放控制] 在线歌曲播放成功: ${song.name}`);
+      void playbackQueue.prefetchNextOnlineSong(song);
+    } catch (error) {
+      if (!isCurrentPlaybackRequest(requestId)) return;
+      if (isSupersededPlaybackRequest(error)) {
+        debugPlaybackLog("[播放控制] 在线播放请求已被更新请求替代");
+        failPlaybackRequest(requestId);
+        return;
+      }
+      console.error("[播放控制] 播放在线歌曲失败:", error);
+      ElMessage.error(`${i18n.global.t("errors.playFailedOnline")}: ${error}`);
+      failPlaybackRequest(requestId);
+    }
+  }
+
+  /** 电台与播客不属于播放队列，切回普通曲目时清除 */
+  function clearStandaloneSources() {
+    currentRadioStation.value = null;
+    radioStreamTitle.value = null;
+    currentPodcast.value = null;
+  }
+
+  /** 直播没有时长和队列：切到电台时清空本地 / 在线队列 */
+  async function playRadioStation(station: RadioStation) {
+    const requestId = beginPlaybackRequest();
+    try {
+      debugPlaybackLog(`[播放控制] 开始播放电台: ${station.name}`);
+      currentRadioStation.value = station;
+      radioStreamTitle.value = null;
+      currentPodcast.value = null;
+      currentMusic.value = null;
+      currentOnlineSong.value = null;
+      currentLocalQueue.value = [];
+      currentPlaylistId.value = null;
+      playbackQueue.clearOnlineQueue();
+      playbackPhase.value = "buffering";
+      await preparePlaybackRequest(requestId);
+      if (!isCurrentPlaybackRequest(requestId)) return;
+
+      const startResult = await playTrack({ type: "radio", url: station.url }, requestId);
+      if (!isCurrentPlaybackRequest(requestId)) return;
+      currentBackendTrackId.value = startResult.track_id;
+      updateProgressFromBackend(startResult);
+
+      if (!completePlaybackRequest(requestId)) return;
+      isPlaying.value = true;
+      startPlayTimeTracking();
+      debugPlaybackLog(`[播放控制] 电台播放成功: ${station.name}`);
+    } catch (error) {
       if (!isCurrentPlaybackRequest(requestId)) return;
+      if (isSupersededPlaybackRequest(error)) {
+        debugPlaybackLog("[播放控制] 电台播放请求已被更新请求替代");
+        failPlaybackRequest(requestId);
+        return;
+      }
+      console.error("[播放控制] 播放电台失败:", error);
+      ElMessage.error(`${i18n.global.t("errors.playRadioFailed")}: ${error}`);
+      failPlaybackRequest(requestId);
+    }
+  }
 
-      debugPlaybackLog("[播放控制] 获取到播放URL，准备播放");
+  /** 播客单集：从上次的位置续播，并应用该节目的默认播放速度 */
+  async function playPodcastEpisode(feed: PodcastFeed, episode: PodcastEpisode) {
+    const requestId = beginPlaybackRequest();
+    try {
+      debugPlaybackLog(`[播放控制] 开始播放播客: ${feed.title} - ${episode.title}`);
+      clearStandaloneSources();
+      currentPodcast.value = { feed, episode };
+      currentMusic.value = null;
+      currentOnlineSong.value = null;
+      currentLocalQueue.value = [];
+      currentPlaylistId.value = null;
+      playbackQueue.clearOnlineQueue();
       playbackPhase.value = "buffering";
+      await preparePlaybackRequest(requestId);
+      if (!isCurrentPlaybackRequest(requestId)) return;
+
+      if (feed.playback_rate) await setPlaybackRate(feed.playback_rate);
       const startResult = await playTrack(
-        {
-          type: "online",
-          url: playResult.url,
-          cache_key: song.id,
-        },
+        { type: "podcast", feed_id: feed.id, episode_id: episode.id },
         requestId
       );
       if (!isCurrentPlaybackRequest(requestId)) return;
@@ -502,25 +688,28 @@ export const usePlayerStore = defineStore("player", () => {
       if (!completePlaybackRequest(requestId)) return;
       isPlaying.value = true;
       startPlayTimeTracking();
-
-      if (playResult.pic_url && currentOnlineSong.value) {
-        currentOnlineSong.value.pic_url = playResult.pic_url;
+      if (!episode.played && episode.position_ms > 0) {
+        await seekToPosition(episode.position_ms);
       }
-      debugPlaybackLog(`[播放控制] 在线歌曲播放成功: ${song.name}`);
-      void playbackQueue.prefetchNextOnlineSong(song);
+      debugPlaybackLog(`[播放控制] 播客播放成功: ${episode.title}`);
     } catch (error) {
       if (!isCurrentPlaybackRequest(requestId)) return;
       if (isSupersededPlaybackRequest(error)) {
-        debugPlaybackLog("[播放控制] 在线播放请求已被更新请求替代");
+        debugPlaybackLog("[播放控制] 播客播放请求已被更新请求替代");
         failPlaybackRequest(requestId);
         return;
       }
-      console.error("[播放控制] 播放在线歌曲失败:", error);
+      console.error("[播放控制] 播放播客失败:", error);
       ElMessage.error(`${i18n.global.t("errors.playFailedOnline")}: ${error}`);
       failPlaybackRequest(requestId);
     }
   }
 
+  function updateRadioMetadata(event: RadioMetadataEvent) {
+    if (currentRadioStation.value?.url !== event.url) return;
+    radioStreamTitle.value = event.title;
+  }
+
   async function playFromPlaylist(playlistId: string, index: number) {
     const list = playlistStore.getPlaylist(playlistId);
     if (!list || index < 0 || index >= list.items.length) return;
@@ -563,6 +752,13 @@ export const usePlayerStore = defineStore("player", () => {
         return;
       }
 
+      if (!isPlaying.value && pendingResumePositionMs !== null) {
+        const positionMs = pendingResumePositionMs;
+        await replayCurrentSong();
+        if (positionMs > 0 && isPlaying.value) await seekToPosition(positionMs);
+        return;
+      }
+
       debugPlaybackLog(`[播放控制] ${isPlaying.value ? "暂停" : "恢复"}播放`);
       if (isPlaying.value) {
         await handleEvent("pause", {});
@@ -724,7 +920,7 @@ export const usePlayerStore = defineStore("player", () => {
               currentIndex = i;
               break;
             }
-            if (it.type === "online" && currentOnlineSong.value?.id === it.song.id) {
+            if (it.type !== "local" && currentOnlineSong.value?.id === it.song.id) {
               currentIndex = i;
               break;
             }
@@ -817,7 +1013,12 @@ export const usePlayerStore = defineStore("player", () => {
   }
 
   async function replayCurrentSong() {
-    if (currentMusic.value) {
+    if (currentPodcast.value) {
+      const { feed, episode } = currentPodcast.value;
+      await playPodcastEpisode(feed, { ...episode, position_ms: 0 });
+    } else if (currentRadioStation.value) {
+      await playRadioStation(currentRadioStation.value);
+    } else if (currentMusic.value) {
       await playMusic(
         currentMusic.value,
         currentPlaylistId.value
@@ -834,6 +1035,18 @@ export const usePlayerStore = defineStore("player", () => {
     }
   }
 
+  /** 切换播放输出（本机 / DLNA 渲染器）后从原位置重新播放；原本暂停的保持暂停 */
+  async function resumeOnCurrentOutput() {
+    if (!hasCurrentTrack.value || isLoadingSong.value) return;
+    const positionMs = currentPlayTime.value;
+    const wasPlaying = isPlaying.value;
+    await replayCurrentSong();
+    if (!currentRadioStation.value && positionMs > 0 && isPlaying.value) {
+      await seekToPosition(positionMs);
+    }
+    if (!wasPlaying && isPlaying.value) await togglePlay();
+  }
+
   function showImmersive() {
     if (currentOnlineSong.value || currentMusic.value) {
       viewStore.showImmersive();
@@ -882,10 +1095,90 @@ export const usePlayerStore = defineStore("player", () => {
     }
   }
 
+  /** 命令行 / 打开方式传入的文件：立即播放，或在本地队列播放时追加到队尾 */
+  async function openExternalFiles(request: OpenFilesRequest) {
+    if (request.files.length === 0) return;
+    const canEnqueue =
+      request.mode === "enqueue" && currentMusic.value !== null && !currentPlaylistId.value;
+    if (!canEnqueue) {
+      await playMusic(request.files[0], { queue: request.files });
+      return;
+    }
+
+    const queue = currentLocalQueue.value.length
+      ? currentLocalQueue.value
+      : [...localStore.musicFiles];
+    const queuedKeys = new Set(queue.map(getLocalTrackKey));
+    currentLocalQueue.value = [
+      ...queue,
+      ...request.files.filter((file) => !queuedKeys.has(getLocalTrackKey(file))),
+    ];
+  }
+
+  /** `rmusic ctl enqueue <id>`：在线队列播放时追加到队尾，否则直接播放 */
+  async function enqueueOnlineSong(song: SongInfo) {
+    const current = currentOnlineSong.value;
+    if (!current || currentPlaylistId.value) {
+      await playOnlineSong(song, { queue: [song] });
+      return;
+    }
+    const queue = currentOnlineQueue.value.length ? currentOnlineQueue.value : [current];
+    if (queue.some((item) => item.id === song.id)) return;
+    currentOnlineQueue.value = [...queue, song];
+  }
+
+  /** 启动时恢复上次的曲目、进度、队列与播放模式（保持暂停） */
+  async function restoreLastSession() {
+    const restored = await restoreSession();
+    if (!restored || hasCurrentTrack.value || isLoadingSong.value) return;
+    const { session, playback } = restored;
+
+    playMode.value = session.queue.play_mode;
+    currentPlaylistId.value = session.queue.playlist_id;
+    currentLocalQueue.value = session.queue.local_queue;
+    currentOnlineQueue.value = session.queue.online_queue;
+    if (session.volume !== null) await adjustVolume(session.volume);
+
+    const current = session.current;
+    if (!current?.track) return;
+    if (current.track.type === "local") {
+      currentMusic.value = current.track.file;
+    } else {
+      currentOnlineSong.value = current.track.song;
+    }
+    isPlaying.value = false;
+    if (playback) {
+      currentBackendTrackId.value = playback.track_id;
+      updateProgressFromBackend(playback);
+    } else {
+      currentTrackDurationMs.value = current.duration_ms;
+      currentPlayTime.value = clampPlayTime(current.position_ms);
+      pendingResumePositionMs = current.position_ms;
+    }
+  }
+
+  watch([playMode, currentPlaylistId, currentLocalQueue, currentOnlineQueue], () => {
+    if (sessionQueueSyncTimer) clearTimeout(sessionQueueSyncTimer);
+    sessionQueueSyncTimer = setTimeout(() => {
+      sessionQueueSyncTimer = null;
+      updateSessionQueue({
+        play_mode: playMode.value,
+        playlist_id: currentPlaylistId.value,
+        local_queue: currentLocalQueue.value,
+        online_queue: currentOnlineQueue.value,
+      }).catch((error) => {
+        console.error("[播放控制] 同步播放队列失败:", error);
+      });
+    }, SESSION_QUEUE_SYNC_DELAY_MS);
+  });
+
   return {
     playMode,
     currentMusic,
     currentOnlineSong,
+    currentRadioStation,
+    radioStreamTitle,
+    currentPodcast,
     isPlaying,
     isLoadingSong,
     playbackPhase,
@@ -907,6 +1200,9 @@ export const usePlayerStore = defineStore("player", () => {
     stopPlaybackEventListening,
     playMusic,
     playOnlineSong,
+    playRadioStation,
+    playPodcastEpisode,
+    updateRadioMetadata,
     prefetchOnlineSong,
     playFromPlaylist,
     playQueueItem,
@@ -917,10 +1213,14 @@ export const usePlayerStore = defineStore("player", () => {
     getPlayStep,
     togglePlayMode,
     replayCurrentSong,
+    resumeOnCurrentOutput,
     showImmersive,
     exitImmersive,
     syncPlaybackStateFromTray,
     syncProgressFromBackend,
     seekToPosition,
+    restoreLastSession,
+    openExternalFiles,
+    enqueueOnlineSong,
   };
 });
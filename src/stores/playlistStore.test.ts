@@ -1,39 +1,140 @@
 import { createPinia, setActivePinia } from "pinia";
 import { beforeEach, describe, expect, it, vi } from "vitest";
+import type { Playlist, PlaylistsChangedEvent } from "@/types/model";
 import { usePlaylistStore } from "./playlistStore";
 
 const playlistApi = vi.hoisted(() => ({
   readPlaylists: vi.fn(),
-  writePlaylists: vi.fn(),
+  createPlaylist: vi.fn(),
+  renamePlaylist: vi.fn(),
+  deletePlaylist: vi.fn(),
+  addPlaylistItems: vi.fn(),
+  removePlaylistItems: vi.fn(),
+  reorderPlaylistItem: vi.fn(),
+}));
+
+const eventApi = vi.hoisted(() => ({
+  handler: null as ((event: { payload: PlaylistsChangedEvent }) => void) | null,
 }));
 
 vi.mock("@/api/commands/playlist", () => playlistApi);
+vi.mock("@tauri-apps/api/event", () => ({
+  listen: vi.fn(async (_event: string, handler: typeof eventApi.handler) => {
+    eventApi.handler = handler;
+    return () => {
+      eventApi.handler = null;
+    };
+  }),
+}));
+
+function work(): Playlist {
+  return { id: "pl_a", name: "Work", items: [], createdAt: 1, revision: 1 };
+}
 
 describe("playlistStore", () => {
   beforeEach(() => {
     setActivePinia(createPinia());
-    playlistApi.readPlaylists.mockReset().mockResolvedValue([]);
-    playlistApi.writePlaylists.mockReset().mockResolvedValue(undefined);
+    for (const mock of Object.values(playlistApi)) mock.mockReset();
+    playlistApi.readPlaylists.mockResolvedValue([]);
   });
 
-  it("deduplicates local tracks and reorders entries", async () => {
+  it("sends expected revisions and applies the returned changes", async () => {
     const store = usePlaylistStore();
     await store.loadPlaylists();
-    const playlist = store.createPlaylist("Work");
+    playlistApi.createPlaylist.mockResolvedValue({
+      playlist: work(),
+      change: { kind: "created", playlist: work() },
+    });
+    const playlist = await store.createPlaylist("Work");
+    expect(playlist?.id).toBe("pl_a");
 
-    expect(store.addToPlaylist(playlist.id, { type: "local", file_name: "A.mp3" })).toBe(
-      true
+    playlistApi.addPlaylistItems.mockResolvedValue({
+      playlist: null,
+      change: {
+        kind: "items_added",
+        id: "pl_a",
+        index: 0,
+        items: [
+          { type: "local", file_name: "A.mp3" },
+          { type: "local", file_name: "B.mp3" },
+        ],
+        revision: 2,
+      },
+    });
+    expect(
+      await store.addItemsToPlaylist("pl_a", [
+        { type: "local", file_name: "A.mp3" },
+        { type: "local", file_name: "B.mp3" },
+      ])
+    ).toBe(2);
+    expect(playlistApi.addPlaylistItems).toHaveBeenCalledWith(
+      expect.objectContaining({ id: "pl_a", expectedRevision: 1 })
     );
-    expect(store.addToPlaylist(playlist.id, { type: "local", file_name: "A.mp3" })).toBe(
+    // 已存在的歌曲不再提交
+    expect(await store.addToPlaylist("pl_a", { type: "local", file_name: "A.mp3" })).toBe(
       false
     );
-    store.addToPlaylist(playlist.id, { type: "local", file_name: "B.mp3" });
-    store.reorderPlaylist(playlist.id, 1, 0);
+    expect(playlistApi.addPlaylistItems).toHaveBeenCalledOnce();
 
-    expect(store.getPlaylist(playlist.id)?.items).toEqual([
-      { type: "local", file_name: "B.mp3" },
-      { type: "local", file_name: "A.mp3" },
+    playlistApi.reorderPlaylistItem.mockResolvedValue({
+      playlist: null,
+      change: { kind: "item_moved", id: "pl_a", from_index: 1, to_index: 0, revision: 3 },
+    });
+    await store.reorderPlaylist("pl_a", 1, 0);
+    expect(playlistApi.reorderPlaylistItem).toHaveBeenCalledWith({
+      id: "pl_a",
+      fromIndex: 1,
+      toIndex: 0,
+      expectedRevision: 2,
+    });
+    expect(store.getPlaylist("pl_a")).toMatchObject({
+      revision: 3,
+      items: [
+        { type: "local", file_name: "B.mp3" },
+        { type: "local", file_name: "A.mp3" },
+      ],
+    });
+  });
+
+  it("reloads after a revision conflict", async () => {
+    playlistApi.readPlaylists.mockResolvedValue([work()]);
+    const store = usePlaylistStore();
+    await store.loadPlaylists();
+    playlistApi.renamePlaylist.mockRejectedValue(
+      "playlist revision conflict: expected 1, found 2"
+    );
+    playlistApi.readPlaylists.mockResolvedValue([
+      { ...work(), name: "Other", revision: 2 },
     ]);
-    await store.flushSave();
+
+    await store.renamePlaylist("pl_a", "Mine");
+
+    expect(playlistApi.readPlaylists).toHaveBeenCalledTimes(2);
+    expect(store.getPlaylist("pl_a")).toMatchObject({ name: "Other", revision: 2 });
+  });
+
+  it("merges playlists-changed events once and reloads on a revision gap", async () => {
+    playlistApi.readPlaylists.mockResolvedValue([work()]);
+    const store = usePlaylistStore();
+    await store.loadPlaylists();
+    await store.startEventListening();
+    const emit = (event: PlaylistsChangedEvent) => eventApi.handler?.({ payload: event });
+
+    const renamed = { kind: "renamed", id: "pl_a", name: "Focus", revision: 2 } as const;
+    emit({ changes: [renamed] });
+    emit({ changes: [renamed] });
+    expect(store.getPlaylist("pl_a")).toMatchObject({ name: "Focus", revision: 2 });
+    expect(playlistApi.readPlaylists).toHaveBeenCalledOnce();
+
+    emit({
+      changes: [{ kind: "items_removed", id: "pl_a", indices: [0], revision: 5 }],
+    });
+    await vi.waitFor(() => expect(store.getPlaylist("pl_a")?.revision).toBe(1));
+    expect(playlistApi.readPlaylists).toHaveBeenCalledTimes(2);
+
+    emit({ changes: [{ kind: "deleted", id: "pl_a" }] });
+    expect(store.getPlaylist("pl_a")).toBeUndefined();
+    store.stopEventListening();
+    expect(eventApi.handler).toBeNull();
   });
 });
@@ -1,99 +1,128 @@
-import { ref, watch } from "vue";
+import { ref } from "vue";
 import { defineStore } from "pinia";
 import { ElMessage } from "element-plus";
-import { PLAYLIST_SAVE_DEBOUNCE_MS } from "@/constants";
-import type { Playlist, PlaylistItem } from "@/types/model";
-import { readPlaylists, writePlaylists } from "@/api/commands/playlist";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type {
+  Playlist,
+  PlaylistChange,
+  PlaylistItem,
+  PlaylistMutationResult,
+  PlaylistsChangedEvent,
+} from "@/types/model";
+import {
+  addPlaylistItems,
+  createPlaylist as createPlaylistCommand,
+  deletePlaylist as deletePlaylistCommand,
+  readPlaylists,
+  removePlaylistItems,
+  renamePlaylist as renamePlaylistCommand,
+  reorderPlaylistItem,
+} from "@/api/commands/playlist";
 import { i18n } from "@/i18n";
+import { getSongProvider } from "@/utils/songUtils";
 
-function generateId(): string {
-  return `pl_${Date.now()}_${Math.random().toString(36).slice(2, 9)}`;
-}
-
-/** 防抖写入：避免连续多次写入 */
-let saveTimeout: ReturnType<typeof setTimeout> | null = null;
-
+/** 播放列表：修改走后端的单项命令并带上 revision，按返回和事件中的差异更新本地 */
 export const usePlaylistStore = defineStore("playlist", () => {
   const playlists = ref<Playlist[]>([]);
-  const hasLoadedPlaylists = ref(false);
-  let skipNextSave = false;
-  let hasPendingSave = false;
-  let savePromise: Promise<void> | null = null;
+  const unlisteners: UnlistenFn[] = [];
+  // 修改按顺序提交，后一次用的是前一次合并后的 revision，不会和自己冲突
+  let pendingMutation: Promise<unknown> = Promise.resolve();
 
-  /** 从 Rust 后端加载播放列表（应用启动时调用） */
+  /** 从 Rust 后端加载播放列表（应用启动、版本冲突时调用） */
   async function loadPlaylists() {
     try {
       const list = await readPlaylists();
-      skipNextSave = true;
       playlists.value = Array.isArray(list) ? list : [];
-      hasLoadedPlaylists.value = true;
     } catch (e) {
       console.error("[playlist] load failed:", e);
       ElMessage.error(`${i18n.global.t("errors.unknownError")}: ${e}`);
-      hasLoadedPlaylists.value = true;
     }
   }
 
-  /** 写入后端（防抖） */
-  function scheduleSave() {
-    if (!hasLoadedPlaylists.value) return;
-    if (skipNextSave) {
-      skipNextSave = false;
+  /**
+   * 合并后端差异；命令结果与事件会各到一次，已合并过的（revision 不大于本地）直接跳过，
+   * 中间漏掉版本时整表重新读取
+   */
+  function applyChange(change: PlaylistChange) {
+    if (change.kind === "created") {
+      if (!getPlaylist(change.playlist.id)) playlists.value.push(change.playlist);
+      return;
+    }
+    if (change.kind === "deleted") {
+      const { id } = change;
+      playlists.value = playlists.value.filter((p) => p.id !== id);
       return;
     }
-    hasPendingSave = true;
-    if (saveTimeout) clearTimeout(saveTimeout);
-    saveTimeout = setTimeout(() => {
-      void flushSave();
-    }, PLAYLIST_SAVE_DEBOUNCE_MS);
-  }
 
-  async function flushSave() {
-    if (saveTimeout) {
-      clearTimeout(saveTimeout);
-      saveTimeout = null;
+    const list = getPlaylist(change.id);
+    if (!list || change.revision > list.revision + 1) {
+      void loadPlaylists();
+      return;
+    }
+    if (change.revision <= list.revision) return;
+
+    switch (change.kind) {
+      case "renamed":
+        list.name = change.name;
+        break;
+      case "items_added":
+        list.items.splice(change.index, 0, ...change.items);
+        break;
+      case "items_removed":
+        for (const index of [...change.indices].sort((a, b) => b - a)) {
+          list.items.splice(index, 1);
+        }
+        break;
+      case "item_moved": {
+        const [item] = list.items.splice(change.from_index, 1);
+        list.items.splice(change.to_index, 0, item);
+        break;
+      }
     }
-    if (!hasLoadedPlaylists.value) return;
-    if (savePromise) await savePromise;
-    if (!hasPendingSave) return;
+    list.revision = change.revision;
+  }
 
-    hasPendingSave = false;
-    const currentSave = (async () => {
+  /** 排队执行一次修改；失败（包括版本冲突）时提示并重新读取，返回 null */
+  function mutate(
+    run: () => Promise<PlaylistMutationResult>
+  ): Promise<PlaylistMutationResult | null> {
+    const next = pendingMutation.then(async () => {
       try {
-        await writePlaylists(playlists.value);
+        const result = await run();
+        applyChange(result.change);
+        return result;
       } catch (e) {
-        hasPendingSave = true;
-        console.error("[playlist] save failed:", e);
+        console.error("[playlist] update failed:", e);
         ElMessage.error(`${i18n.global.t("errors.unknownError")}: ${e}`);
+        await loadPlaylists();
+        return null;
       }
-    })();
-
-    savePromise = currentSave;
-    await currentSave;
-    if (savePromise === currentSave) savePromise = null;
+    });
+    pendingMutation = next;
+    return next;
   }
 
-  watch(playlists, () => scheduleSave(), { deep: true });
+  /** 提交时本地的版本号，作为 expectedRevision */
+  function revisionOf(id: string): number | undefined {
+    return getPlaylist(id)?.revision;
+  }
 
-  function createPlaylist(name: string): Playlist {
-    const list: Playlist = {
-      id: generateId(),
-      name: name.trim() || "新建播放列表",
-      items: [],
-      createdAt: Date.now(),
-    };
-    playlists.value.push(list);
-    return list;
+  async function createPlaylist(name: string): Promise<Playlist | null> {
+    const result = await mutate(() => createPlaylistCommand(name));
+    return result?.playlist ?? null;
   }
 
-  function deletePlaylist(id: string) {
-    const idx = playlists.value.findIndex((p) => p.id === id);
-    if (idx !== -1) playlists.value.splice(idx, 1);
+  async function deletePlaylist(id: string) {
+    if (!getPlaylist(id)) return;
+    await mutate(() => deletePlaylistCommand({ id, expectedRevision: revisionOf(id) }));
   }
 
-  function renamePlaylist(id: string, name: string) {
-    const list = playlists.value.find((p) => p.id === id);
-    if (list) list.name = name.trim() || list.name;
+  async function renamePlaylist(id: string, name: string) {
+    const list = getPlaylist(id);
+    if (!list || !name.trim() || name.trim() === list.name) return;
+    await mutate(() =>
+      renamePlaylistCommand({ id, name, expectedRevision: revisionOf(id) })
+    );
   }
 
   function getPlaylist(id: string): Playlist | undefined {
@@ -104,45 +133,108 @@ export const usePlaylistStore = defineStore("playlist", () => {
   function isSamePlaylistItem(a: PlaylistItem, b: PlaylistItem): boolean {
     if (a.type !== b.type) return false;
     if (a.type === "local" && b.type === "local") return a.file_name === b.file_name;
-    if (a.type === "online" && b.type === "online") return a.song.id === b.song.id;
+    if (a.type === "online" && b.type === "online") {
+      return (
+        getSongProvider(a.song) === getSongProvider(b.song) && a.song.id === b.song.id
+      );
+    }
+    if (a.type === "remote" && b.type === "remote") {
+      return a.song.server_id === b.song.server_id && a.song.id === b.song.id;
+    }
     return false;
   }
 
+  /** 批量添加到播放列表末尾；已存在的歌曲由后端跳过。返回实际添加的数量。 */
+  async function addItemsToPlaylist(
+    playlistId: string,
+    items: PlaylistItem[]
+  ): Promise<number> {
+    if (!getPlaylist(playlistId) || items.length === 0) return 0;
+    const result = await mutate(() =>
+      addPlaylistItems({
+        id: playlistId,
+        items,
+        expectedRevision: revisionOf(playlistId),
+      })
+    );
+    return result?.change.kind === "items_added" ? result.change.items.length : 0;
+  }
+
   /** 添加到播放列表；若已存在相同歌曲则不再添加。返回 true 表示已添加，false 表示已存在。 */
-  function addToPlaylist(playlistId: string, item: PlaylistItem): boolean {
-    const list = playlists.value.find((p) => p.id === playlistId);
-    if (!list) return false;
-    const alreadyExists = list.items.some((existing) =>
-      isSamePlaylistItem(existing, item)
+  async function addToPlaylist(playlistId: string, item: PlaylistItem): Promise<boolean> {
+    const list = getPlaylist(playlistId);
+    if (!list || list.items.some((existing) => isSamePlaylistItem(existing, item))) {
+      return false;
+    }
+    return (await addItemsToPlaylist(playlistId, [item])) > 0;
+  }
+
+  /** 按当前下标批量移除，一次提交，避免逐条删除时下标错位 */
+  async function removeItemsFromPlaylist(playlistId: string, indices: number[]) {
+    const list = getPlaylist(playlistId);
+    if (!list) return;
+    const valid = indices.filter((index) => index >= 0 && index < list.items.length);
+    if (valid.length === 0) return;
+    await mutate(() =>
+      removePlaylistItems({
+        id: playlistId,
+        indices: valid,
+        expectedRevision: revisionOf(playlistId),
+      })
     );
-    if (alreadyExists) return false;
-    list.items.push(item);
-    return true;
   }
 
-  function removeFromPlaylist(playlistId: string, index: number) {
-    const list = playlists.value.find((p) => p.id === playlistId);
-    if (list && index >= 0 && index < list.items.length) list.items.splice(index, 1);
+  async function removeFromPlaylist(playlistId: string, index: number) {
+    await removeItemsFromPlaylist(playlistId, [index]);
   }
 
-  function reorderPlaylist(playlistId: string, fromIndex: number, toIndex: number) {
-    const list = playlists.value.find((p) => p.id === playlistId);
+  async function reorderPlaylist(playlistId: string, fromIndex: number, toIndex: number) {
+    const list = getPlaylist(playlistId);
     if (!list || fromIndex < 0 || fromIndex >= list.items.length) return;
-    const [item] = list.items.splice(fromIndex, 1);
-    const safeTo = Math.max(0, Math.min(toIndex, list.items.length));
-    list.items.splice(safeTo, 0, item);
+    await mutate(() =>
+      reorderPlaylistItem({
+        id: playlistId,
+        fromIndex,
+        toIndex: Math.max(0, toIndex),
+        expectedRevision: revisionOf(playlistId),
+      })
+    );
+  }
+
+  /** 监听其他窗口或后端发出的修改 */
+  async function startEventListening() {
+    stopEventListening();
+    try {
+      unlisteners.push(
+        await listen<PlaylistsChangedEvent>("playlists-changed", (event) => {
+          for (const change of event.payload.changes) applyChange(change);
+        })
+      );
+    } catch (error) {
+      stopEventListening();
+      throw error;
+    }
+  }
+
+  function stopEventListening() {
+    while (unlisteners.length > 0) {
+      unlisteners.pop()?.();
+    }
   }
 
   return {
     playlists,
     loadPlaylists,
-    flushSave,
     createPlaylist,
     deletePlaylist,
     renamePlaylist,
     getPlaylist,
     addToPlaylist,
+    addItemsToPlaylist,
     removeFromPlaylist,
+    removeItemsFromPlaylist,
     reorderPlaylist,
+    startEventListening,
+    stopEventListening,
   };
 });
@@ -0,0 +1,173 @@
+import { ref } from "vue";
+import { defineStore } from "pinia";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type {
+  PodcastDownloadProgressEvent,
+  PodcastEpisode,
+  PodcastEpisodeUpdatedEvent,
+  PodcastFeed,
+  PodcastRefreshResult,
+  PodcastSettings,
+} from "@/types/model";
+import {
+  deletePodcastDownload,
+  downloadPodcastEpisode,
+  getPodcasts,
+  getPodcastSettings,
+  refreshPodcasts,
+  setPodcastEpisodePlayed,
+  setPodcastPlaybackRate,
+  setPodcastSettings,
+  subscribePodcast,
+  unsubscribePodcast,
+} from "@/api/commands/podcast";
+
+function downloadKey(feedId: string, episodeId: string) {
+  return `${feedId}:${episodeId}`;
+}
+
+/** 播客订阅与单集状态；后台刷新、收听进度与下载进度通过事件同步 */
+export const usePodcastStore = defineStore("podcast", () => {
+  const feeds = ref<PodcastFeed[]>([]);
+  const settings = ref<PodcastSettings>({ refresh_interval_minutes: 60 });
+  const isRefreshing = ref(false);
+  // 下载中的单集：键为 feedId:episodeId，值为 0-1 的进度（总大小未知时为 null）
+  const downloads = ref<Record<string, number | null>>({});
+  const unlisteners: UnlistenFn[] = [];
+
+  function getFeed(feedId: string) {
+    return feeds.value.find((feed) => feed.id === feedId) ?? null;
+  }
+
+  function replaceFeed(next: PodcastFeed) {
+    const index = feeds.value.findIndex((feed) => feed.id === next.id);
+    if (index >= 0) feeds.value.splice(index, 1, next);
+    else feeds.value.push(next);
+  }
+
+  function replaceEpisode(feedId: string, episode: PodcastEpisode) {
+    const feed = getFeed(feedId);
+    if (!feed) return;
+    const index = feed.episodes.findIndex((item) => item.id === episode.id);
+    if (index >= 0) feed.episodes.splice(index, 1, episode);
+  }
+
+  async function load() {
+    const [nextFeeds, nextSettings] = await Promise.all([getPodcasts(), getPodcastSettings()]);
+    feeds.value = nextFeeds;
+    settings.value = nextSettings;
+  }
+
+  async function subscribe(url: string) {
+    const feed = await subscribePodcast(url);
+    replaceFeed(feed);
+    return feed;
+  }
+
+  async function unsubscribe(feedId: string) {
+    await unsubscribePodcast(feedId);
+    feeds.value = feeds.value.filter((feed) => feed.id !== feedId);
+  }
+
+  async function refresh(feedId?: string): Promise<PodcastRefreshResult[]> {
+    isRefreshing.value = true;
+    try {
+      const results = await refreshPodcasts(feedId);
+      feeds.value = await getPodcasts();
+      return results;
+    } finally {
+      isRefreshing.value = false;
+    }
+  }
+
+  async function updateSettings(next: PodcastSettings) {
+    settings.value = await setPodcastSettings(next);
+  }
+
+  async function markPlayed(feedId: string, episodeId: string, played: boolean) {
+    replaceEpisode(feedId, await setPodcastEpisodePlayed({ feedId, episodeId, played }));
+  }
+
+  async function setPlaybackRate(feedId: string, rate: number | null) {
+    replaceFeed(await setPodcastPlaybackRate(feedId, rate));
+  }
+
+  async function download(feedId: string, episodeId: string) {
+    const key = downloadKey(feedId, episodeId);
+    downloads.value = { ...downloads.value, [key]: 0 };
+    try {
+      replaceEpisode(feedId, await downloadPodcastEpisode({ feedId, episodeId }));
+    } finally {
+      const next = { ...downloads.value };
+      delete next[key];
+      downloads.value = next;
+    }
+  }
+
+  async function deleteDownload(feedId: string, episodeId: string) {
+    replaceEpisode(feedId, await deletePodcastDownload({ feedId, episodeId }));
+  }
+
+  function getDownloadProgress(feedId: string, episodeId: string) {
+    return downloads.value[downloadKey(feedId, episodeId)];
+  }
+
+  async function startEventListening() {
+    stopEventListening();
+    try {
+      unlisteners.push(
+        await listen<PodcastRefreshResult[]>("podcasts-updated", () => {
+          void getPodcasts().then((next) => {
+            feeds.value = next;
+          });
+        })
+      );
+      unlisteners.push(
+        await listen<PodcastEpisodeUpdatedEvent>("podcast-episode-updated", (event) =>
+          replaceEpisode(event.payload.feed_id, event.payload.episode)
+        )
+      );
+      unlisteners.push(
+        await listen<PodcastDownloadProgressEvent>("podcast-download-progress", (event) => {
+          const { feed_id, episode_id, downloaded_bytes, total_bytes } = event.payload;
+          const key = downloadKey(feed_id, episode_id);
+          if (!(key in downloads.value)) return;
+          downloads.value = {
+            ...downloads.value,
+            [key]: total_bytes ? Math.min(1, downloaded_bytes / total_bytes) : null,
+          };
+        })
+      );
+    } catch (error) {
+      stopEventListening();
+      throw error;
+    }
+  }
+
+  function stopEventListening() {
+    while (unlisteners.length > 0) {
+      unlisteners.pop()?.();
+    }
+  }
+
+  return {
+    feeds,
+    settings,
+    isRefreshing,
+    downloads,
+    getFeed,
+    load,
+    subscribe,
+    unsubscribe,
+    refresh,
+    updateSettings,
+    markPlayed,
+    setPlaybackRate,
+    download,
+    deleteDownload,
+    getDownloadProgress,
+    replaceEpisode,
+    startEventListening,
+    stopEventListening,
+  };
+});
@@ -11,6 +11,29 @@ export interface MusicFile {
   artist?: string | null;
   album?: string | null;
   duration_ms?: number;
+  // CUE 表拆出的虚拟分轨；file_name 为 `原路径#分轨号`
+  cue?: CueRange | null;
+  // 库外文件（命令行、打开方式传入）的绝对路径
+  path?: string | null;
+}
+
+export interface CueRange {
+  // 实际音频文件的相对路径
+  source_path: string;
+  track: number;
+  start_ms: number;
+  end_ms: number | null;
+}
+
+// 命令行 / 打开方式传入的文件
+export interface OpenFilesRequest {
+  mode: "play" | "enqueue";
+  files: MusicFile[];
+}
+
+export interface PlaybackRange {
+  start_ms: number;
+  end_ms: number | null;
 }
 
 // 在线音乐信息模型
@@ -22,6 +45,149 @@ export interface SongInfo {
   duration: number; // 持续时间（毫秒）
   pic_url: string; // 图片URL
   file_hash: string; // 文件哈希值，用于播放
+  provider?: string; // 在线来源 id，缺省为网易云
+}
+
+// 远程 Subsonic 服务器上的歌曲：SongInfo 加所属服务器 id
+export type RemoteSong = SongInfo & { server_id: string };
+
+// 远程 Subsonic 服务器配置（Navidrome、Airsonic 等）
+export interface RemoteServer {
+  id: string; // 新建时留空，由后端生成
+  name: string;
+  base_url: string;
+  username: string;
+  password: string;
+  legacy_auth: boolean; // 服务器不支持 token 认证时使用明文（hex）密码
+}
+
+// 网络电台（Shoutcast / Icecast）；url 可以是直播流或 .pls / .m3u 地址
+export interface RadioStation {
+  id: string;
+  name: string;
+  url: string;
+  added_at: number;
+}
+
+// radio-metadata 事件：title 为 ICY StreamTitle，通常是“歌手 - 歌名”
+export interface RadioMetadataEvent {
+  url: string;
+  station_name: string | null;
+  title: string | null;
+}
+
+export interface RadioReconnectingEvent {
+  url: string;
+  attempt: number;
+  error: string;
+}
+
+// 播客单集；show_notes 为 HTML，position_ms / played / download_path 是本地收听状态
+export interface PodcastEpisode {
+  id: string;
+  guid: string;
+  title: string;
+  show_notes: string;
+  audio_url: string;
+  mime_type: string | null;
+  size_bytes: number | null;
+  duration_ms: number;
+  published_at: number | null;
+  image_url: string | null;
+  link: string | null;
+  position_ms: number;
+  played: boolean;
+  download_path: string | null;
+}
+
+export interface PodcastFeed {
+  id: string;
+  url: string;
+  title: string;
+  description: string;
+  author: string | null;
+  image_url: string | null;
+  link: string | null;
+  playback_rate: number | null; // 该节目的默认播放速度
+  etag: string | null;
+  last_modified: string | null;
+  last_checked_at: number;
+  last_error: string | null;
+  subscribed_at: number;
+  episodes: PodcastEpisode[]; // 按发布时间倒序
+}
+
+export interface PodcastSettings {
+  refresh_interval_minutes: number; // 0 表示只手动刷新
+}
+
+export interface PodcastRefreshResult {
+  feed_id: string;
+  new_episodes: number;
+  not_modified: boolean;
+  error: string | null;
+}
+
+export interface PodcastEpisodeUpdatedEvent {
+  feed_id: string;
+  episode: PodcastEpisode;
+}
+
+export interface PodcastDownloadProgressEvent {
+  feed_id: string;
+  episode_id: string;
+  downloaded_bytes: number;
+  total_bytes: number | null;
+}
+
+// 局域网里的 DLNA 渲染器（UPnP MediaRenderer）；id 为设备 UDN
+export interface DlnaRenderer {
+  id: string;
+  name: string;
+  model: string | null;
+  location: string;
+}
+
+// dlna-renderer-error 事件：渲染器连续多次无响应，本地已暂停
+export interface DlnaRendererErrorEvent {
+  renderer_id: string;
+  name: string;
+  message: string;
+}
+
+export interface RemoteAlbum {
+  server_id: string;
+  id: string;
+  name: string;
+  artist: string;
+  artist_id: string;
+  song_count: number;
+  duration: number; // 毫秒
+  pic_url: string;
+}
+
+export interface RemoteArtist {
+  server_id: string;
+  id: string;
+  name: string;
+  album_count: number;
+  pic_url: string;
+}
+
+export interface RemoteSearchResult {
+  artists: RemoteArtist[];
+  albums: RemoteAlbum[];
+  songs: RemoteSong[];
+}
+
+export interface RemoteAlbumDetail {
+  album: RemoteAlbum;
+  songs: RemoteSong[];
+}
+
+export interface RemoteArtistDetail {
+  artist: RemoteArtist;
+  albums: RemoteAlbum[];
 }
 
 // 搜索结果模型
@@ -50,6 +216,20 @@ export interface ArtistSongsResult {
   total: number;
 }
 
+export interface AlbumInfo {
+  id: string;
+  name: string;
+  artists: string[];
+  pic_url: string;
+  provider: string;
+}
+
+export interface AlbumResult {
+  album: AlbumInfo;
+  songs: SongInfo[];
+  total: number;
+}
+
 // 歌词信息模型
 export interface LyricInfo {
   id: string;
@@ -74,8 +254,17 @@ export interface PlaySongResult {
 }
 
 export type PlaybackSource =
-  | { type: "local"; path: string }
-  | { type: "online"; url: string; cache_key: string };
+  | { type: "local"; path: string; range?: PlaybackRange | null }
+  | { type: "online"; url: string; cache_key: string }
+  | { type: "remote"; server_id: string; song_id: string }
+  | { type: "radio"; url: string }
+  | { type: "podcast"; feed_id: string; episode_id: string };
+
+// 随 play_track 传给后端的曲目元数据（播放历史等功能使用）
+export type PlaybackTrack =
+  | { type: "local"; file: MusicFile }
+  | { type: "online"; song: SongInfo }
+  | { type: "remote"; song: RemoteSong };
 
 export interface PlayStartResult {
   position_ms: number;
@@ -87,6 +276,49 @@ export interface PlayStartResult {
 
 export type PlaybackPhase = "idle" | "resolving" | "buffering";
 
+// 播放会话持久化：队列与播放模式由前端同步给后端，启动时恢复
+export interface SessionQueue {
+  play_mode: PlayMode;
+  playlist_id: string | null;
+  local_queue: MusicFile[];
+  online_queue: SongInfo[];
+}
+
+export interface SessionTrack {
+  source: PlaybackSource;
+  track: PlaybackTrack | null;
+  position_ms: number;
+  duration_ms: number;
+}
+
+export interface PlaybackSession {
+  current: SessionTrack | null;
+  volume: number | null;
+  queue: SessionQueue;
+  saved_at_ms: number;
+}
+
+export interface RestoredSession {
+  session: PlaybackSession;
+  // 为 null 表示音频未能载入（文件不存在或在线音频未缓存），需要重新播放
+  playback: PlayStartResult | null;
+}
+
+// 睡眠定时器：按时间、当前曲目结束或播完指定曲目数后淡出并暂停
+export type SleepTimerMode =
+  | { mode: "duration"; minutes: number }
+  | { mode: "end_of_track" }
+  | { mode: "tracks"; count: number };
+
+export interface SleepTimerStatus {
+  active: boolean;
+  mode: SleepTimerMode | null;
+  // 按曲目计时且还有多首未播时为 null
+  remaining_ms: number | null;
+  tracks_remaining: number | null;
+  fading: boolean;
+}
+
 export interface PlaybackQueueItem {
   key: string;
   title: string;
@@ -100,6 +332,154 @@ export interface OnlineServiceStatus {
   available: boolean;
   status_code: number | null;
   message: string;
+  endpoints: OnlineEndpointStatus[]; // 每个已配置端点的探测结果
+}
+
+// 网易云 API 端点的健康状况与延迟统计
+export interface OnlineEndpointStatus {
+  url: string;
+  bundled: boolean; // 是否为内置服务
+  available: boolean;
+  status_code: number | null;
+  latency_ms: number | null; // 本次探测延迟
+  average_latency_ms: number | null;
+  successes: number;
+  failures: number;
+  last_error: string | null;
+}
+
+// 内置服务意外退出事件（online-service-exited），restart_in_ms 后自动重启
+export interface OnlineServiceExitEvent {
+  code: number | null;
+  signal: number | null;
+  restart_in_ms: number;
+}
+
+// 网易云 API 端点设置：内置服务排在自建实例之前，请求失败时按顺序切换
+export interface OnlineApiSettings {
+  use_bundled_service: boolean;
+  endpoints: string[];
+}
+
+export type HistoryItem =
+  | {
+      source: "local";
+      key: string;
+      file_name: string;
+      title: string | null;
+      artist: string | null;
+      album: string | null;
+    }
+  | { source: "online"; song: SongInfo }
+  | { source: "remote"; song: RemoteSong };
+
+export type PlayHistoryEntry = HistoryItem & {
+  played_at_ms: number;
+  listened_ms: number;
+  duration_ms: number;
+};
+
+export interface HistoryRange {
+  from_ms?: number | null;
+  to_ms?: number | null;
+}
+
+export type TrackStat = HistoryItem & {
+  play_count: number;
+  listened_ms: number;
+  last_played_ms: number;
+};
+
+export interface ArtistStat {
+  artist: string;
+  play_count: number;
+  listened_ms: number;
+}
+
+export interface AlbumStat {
+  album: string;
+  artist: string;
+  play_count: number;
+  listened_ms: number;
+}
+
+export interface ListeningTotals {
+  play_count: number;
+  listened_ms: number;
+}
+
+// 曲目提交（scrobble）设置
+export interface ScrobbleSourceFlags {
+  local: boolean;
+  online: boolean;
+}
+
+export interface ListenBrainzSettings {
+  enabled: boolean;
+  api_base: string;
+  token: string;
+  sources: ScrobbleSourceFlags;
+}
+
+export interface LastfmSettings {
+  enabled: boolean;
+  api_base: string;
+  api_key: string;
+  api_secret: string;
+  session_key: string;
+  username: string;
+  sources: ScrobbleSourceFlags;
+}
+
+export interface ScrobblerSettings {
+  listenbrainz: ListenBrainzSettings;
+  lastfm: LastfmSettings;
+}
+
+// 用户曲目元数据：本地曲目以 MusicFile.key、在线曲目以来源 + 歌曲 id、远程曲目以服务器 id + 曲目 id 为键
+export type TrackRef =
+  | { source: "local"; key: string; path?: string }
+  | { source: "online"; id: string; provider: string }
+  | { source: "remote"; server_id: string; id: string };
+
+export interface TrackUserMetadata {
+  rating: number; // 0-5，0 表示未评分
+  loved: boolean;
+  play_count: number;
+  skip_count: number;
+  last_played_ms: number | null;
+  tags: string[];
+  path?: string;
+  content_hash?: string;
+}
+
+export interface TrackUserMetadataUpdate {
+  rating?: number;
+  loved?: boolean;
+  tags?: string[];
+}
+
+export interface TrackUserMetadataResult {
+  track: TrackRef;
+  metadata: TrackUserMetadata;
+  tag_sync_error: string | null;
+}
+
+export interface UserMetadataSettings {
+  sync_rating_tags: boolean;
+}
+
+export interface UserMetadataStore {
+  settings: UserMetadataSettings;
+  local: Record<string, TrackUserMetadata>;
+  online: Record<string, TrackUserMetadata>;
+}
+
+export interface ScrobbleStatus {
+  pending: number;
+  pending_listenbrainz: number;
+  pending_lastfm: number;
+  last_error: string | null;
 }
 
 // 播放模式
@@ -118,10 +498,11 @@ export enum ViewMode {
 
 export type SearchScope = "local" | "online" | "playlist";
 
-// 播放列表单项（本地或在线）
+// 播放列表单项（本地、在线或远程服务器）
 export type PlaylistItem =
   | { type: "local"; file_name: string }
-  | { type: "online"; song: SongInfo };
+  | { type: "online"; song: SongInfo }
+  | { type: "remote"; song: RemoteSong };
 
 // 播放列表
 export interface Playlist {
@@ -129,4 +510,131 @@ export interface Playlist {
   name: string;
   items: PlaylistItem[];
   createdAt: number;
+  /** 后端维护的乐观并发版本号 */
+  revision: number;
+}
+
+// 播放列表差异（后端 playlists-changed 事件）
+export type PlaylistChange =
+  | { kind: "created"; playlist: Playlist }
+  | { kind: "renamed"; id: string; name: string; revision: number }
+  | { kind: "deleted"; id: string }
+  | {
+      kind: "items_added";
+      id: string;
+      index: number;
+      items: PlaylistItem[];
+      revision: number;
+    }
+  | { kind: "items_removed"; id: string; indices: number[]; revision: number }
+  | {
+      kind: "item_moved";
+      id: string;
+      from_index: number;
+      to_index: number;
+      revision: number;
+    };
+
+export interface PlaylistsChangedEvent {
+  changes: PlaylistChange[];
+}
+
+export interface PlaylistMutationResult {
+  playlist: Playlist | null;
+  change: PlaylistChange;
+}
+
+// A-B 循环：只对设置时的曲目（track_id）生效
+export type LoopPoint = "a" | "b";
+
+export interface AbLoopStatus {
+  track_id: number;
+  a_ms: number | null;
+  b_ms: number | null;
+  active: boolean;
+}
+
+// 曲目书签，按 TrackRef 的键保存，按位置排序
+export interface Bookmark {
+  id: string;
+  name: string;
+  position_ms: number;
+  created_ms: number;
+}
+
+// 音频输出设备
+export interface OutputConfigRange {
+  channels: number;
+  min_sample_rate: number;
+  max_sample_rate: number;
+}
+
+export interface OutputDeviceInfo {
+  name: string;
+  is_default: boolean;
+  default_sample_rate: number | null;
+  default_channels: number | null;
+  configs: OutputConfigRange[];
+}
+
+export interface OutputDeviceStatus {
+  // 为 null 表示跟随系统默认设备
+  selected: string | null;
+  // 为 null 表示没有可用设备
+  active: string | null;
+  fallback: boolean;
+}
+
+// 声道处理：balance 为 -1（全左）到 1（全右）
+export interface ChannelOptions {
+  balance: number;
+  mono: boolean;
+  swap_channels: boolean;
+}
+
+// 可视化数据帧，约 30 fps 推送
+export interface VisualizerFrame {
+  // 对数分布的频段能量，0-1
+  bands: number[];
+  rms: number;
+  peak: number;
+}
+
+// 波形峰值来源：本地文件或已完整缓存的在线曲目
+export type WaveformSource =
+  | { type: "local"; path: string; range?: PlaybackRange | null }
+  | { type: "online"; cache_key: string };
+
+export interface WaveformPeaks {
+  duration_ms: number;
+  // 每个桶的 [min, max]，按 -127..127 量化
+  peaks: [number, number][];
+}
+
+// 局域网远程控制 API，默认关闭；token 为空时保存会自动生成
+export interface RemoteApiSettings {
+  enabled: boolean;
+  bind_address: string;
+  token: string;
+}
+
+export interface RemoteApiStatus {
+  running: boolean;
+  address: string | null;
+  last_error: string | null;
+}
+
+/** Subsonic 兼容服务设置；library_root 为空时使用应用数据目录。CUE 分轨不会提供给客户端 */
+export interface SubsonicServerSettings {
+  enabled: boolean;
+  bind_address: string;
+  username: string;
+  password: string;
+  library_root: string | null;
+}
+
+export interface SubsonicServerStatus {
+  running: boolean;
+  address: string | null;
+  last_error: string | null;
 }
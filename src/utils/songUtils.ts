@@ -2,7 +2,7 @@
  * 歌曲/文件名解析与格式化工具（高内聚、可复用）
  */
 
-import type { MusicFile } from "@/types/model";
+import type { MusicFile, RemoteSong, SongInfo } from "@/types/model";
 
 /** 从路径取文件名（含扩展名） */
 export function getFileName(path: string): string {
@@ -40,6 +40,17 @@ export function getLocalMusicDisplayInfo(file: MusicFile, unknownArtist = "") {
   };
 }
 
+/** 在线来源 id，旧数据缺省为网易云 */
+export function getSongProvider(song: Pick<SongInfo, "provider">): string {
+  return song.provider || "netease";
+}
+
+/** 在线音频缓存键（与后端 provider::song_cache_key 一致）：网易云为裸 id，其他来源为 "来源:id" */
+export function getOnlineCacheKey(song: Pick<SongInfo, "id" | "provider">): string {
+  const provider = getSongProvider(song);
+  return provider === "netease" ? song.id : `${provider}:${song.id}`;
+}
+
 /** 格式化艺术家列表 */
 export function formatArtists(artists: string[]): string {
   return artists?.join(", ") ?? "";
@@ -93,3 +104,8 @@ export function getLocalFileNameForSong(
   );
   return found ? found.file_name : null;
 }
+
+/** 远程 Subsonic 服务器的歌曲带 server_id，其余在线歌曲来自网易云 */
+export function isRemoteSong(song: SongInfo): song is RemoteSong {
+  return typeof (song as Partial<RemoteSong>).server_id === "string";
+}